@@ -0,0 +1,188 @@
+use crate::bus::{InboundAck, InboundMessage};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Most messages folded into one turn; a full batch is sent without waiting
+/// out the window.
+const MAX_COALESCED_MESSAGES: usize = 8;
+
+/// One turn's worth of inbound messages: the merged message and the acks of
+/// every message folded into it.
+pub(crate) type Batch = (InboundMessage, Vec<InboundAck>);
+
+/// Coalesces a sender's rapid consecutive messages in a chat into one turn.
+/// Each message restarts the window; the batch is released once the sender
+/// has been quiet for the whole window or `MAX_COALESCED_MESSAGES` pile up.
+/// Cron turns and `/` commands are never merged, but release the sender's
+/// pending batch first so order is kept.
+#[derive(Clone)]
+pub(crate) struct Debouncer {
+    window: Duration,
+    pending: Arc<DashMap<String, Pending>>,
+    next_seq: Arc<AtomicU64>,
+    out: mpsc::UnboundedSender<Batch>,
+}
+
+struct Pending {
+    /// Sequence number of the newest message; only its timer may flush.
+    seq: u64,
+    messages: Vec<InboundMessage>,
+    acks: Vec<InboundAck>,
+}
+
+impl Debouncer {
+    pub(crate) fn new(window: Duration) -> (Self, mpsc::UnboundedReceiver<Batch>) {
+        let (out, rx) = mpsc::unbounded_channel();
+        let debouncer = Self {
+            window,
+            pending: Arc::new(DashMap::new()),
+            next_seq: Arc::new(AtomicU64::new(0)),
+            out,
+        };
+        (debouncer, rx)
+    }
+
+    pub(crate) fn push(&self, msg: InboundMessage, ack: InboundAck) {
+        let key = format!("{}:{}:{}", msg.channel, msg.chat_id, msg.sender_id);
+        if msg.sender_id == "cron" || msg.content.trim_start().starts_with('/') {
+            self.flush(&key, None);
+            let _ = self.out.send((msg, vec![ack]));
+            return;
+        }
+
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let full = {
+            let mut entry = self.pending.entry(key.clone()).or_insert_with(|| Pending {
+                seq,
+                messages: Vec::new(),
+                acks: Vec::new(),
+            });
+            entry.seq = seq;
+            entry.messages.push(msg);
+            entry.acks.push(ack);
+            entry.messages.len() >= MAX_COALESCED_MESSAGES
+        };
+        if full {
+            self.flush(&key, None);
+            return;
+        }
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(this.window).await;
+            this.flush(&key, Some(seq));
+        });
+    }
+
+    /// Send `key`'s pending batch, if any. With `seq`, only when no newer
+    /// message has arrived since.
+    fn flush(&self, key: &str, seq: Option<u64>) {
+        let Some((_, pending)) = self
+            .pending
+            .remove_if(key, |_, pending| seq.is_none_or(|seq| pending.seq == seq))
+        else {
+            return;
+        };
+        if let Some(msg) = merge(pending.messages) {
+            let _ = self.out.send((msg, pending.acks));
+        }
+    }
+}
+
+/// One message carrying the text and attachments of `messages`, in order,
+/// replying to the newest of them.
+fn merge(messages: Vec<InboundMessage>) -> Option<InboundMessage> {
+    let mut messages = messages.into_iter();
+    let mut merged = messages.next()?;
+    for msg in messages {
+        if !msg.content.trim().is_empty() {
+            if !merged.content.trim().is_empty() {
+                merged.content.push('\n');
+            }
+            merged.content.push_str(&msg.content);
+        }
+        merged.attachments.extend(msg.attachments);
+        merged.message_id = msg.message_id.or(merged.message_id);
+        merged.mention = msg.mention.or(merged.mention);
+    }
+    Some(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+
+    fn message(content: &str, id: &str) -> InboundMessage {
+        InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            sender_id: "7".to_string(),
+            content: content.to_string(),
+            message_id: Some(id.to_string()),
+            mention: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Lets spawned debounce timers start and the due ones fire.
+    async fn settle() {
+        for _ in 0..4 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn messages_within_the_window_become_one_turn() {
+        // Acks come from the bus; a plain in-memory bus gives no-op ones.
+        let bus = MessageBus::new();
+        let ack = || {
+            let bus = bus.clone();
+            async move {
+                bus.publish_inbound(message("", "0")).await;
+                bus.consume_inbound().await.unwrap().1
+            }
+        };
+        let (debouncer, mut rx) = Debouncer::new(Duration::from_millis(150));
+
+        debouncer.push(message("book a table for friday", "1"), ack().await);
+        settle().await;
+        tokio::time::advance(Duration::from_millis(100)).await;
+        settle().await;
+        assert!(rx.try_recv().is_err());
+        debouncer.push(message("actually make it saturday", "2"), ack().await);
+        settle().await;
+        // The second message restarted the window.
+        tokio::time::advance(Duration::from_millis(100)).await;
+        settle().await;
+        assert!(rx.try_recv().is_err());
+        tokio::time::advance(Duration::from_millis(50)).await;
+        settle().await;
+
+        let (merged, acks) = rx.try_recv().unwrap();
+        assert_eq!(
+            merged.content,
+            "book a table for friday\nactually make it saturday"
+        );
+        assert_eq!(merged.message_id.as_deref(), Some("2"));
+        assert_eq!(acks.len(), 2);
+
+        // After the window has passed, the next message is its own turn.
+        debouncer.push(message("thanks", "3"), ack().await);
+        settle().await;
+        tokio::time::advance(Duration::from_millis(150)).await;
+        settle().await;
+        let (single, acks) = rx.try_recv().unwrap();
+        assert_eq!(single.content, "thanks");
+        assert_eq!(acks.len(), 1);
+
+        // A command releases the pending batch ahead of itself.
+        debouncer.push(message("one more thing", "4"), ack().await);
+        debouncer.push(message("/stop off", "5"), ack().await);
+        assert_eq!(rx.try_recv().unwrap().0.content, "one more thing");
+        assert_eq!(rx.try_recv().unwrap().0.content, "/stop off");
+    }
+}
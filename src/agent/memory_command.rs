@@ -0,0 +1,453 @@
+use crate::config::{MemoryConfig, MemoryMode};
+use crate::memory::simple::file_store::MemoryStore;
+use crate::memory::smart::vector_store::{MemoryItem, VectorMemoryStore};
+
+use super::truncate_memory_snippet;
+
+const LIST_LIMIT: usize = 20;
+const SEARCH_LIMIT: usize = 5;
+/// Matches shown when `forget <text>` is ambiguous.
+const FORGET_CANDIDATES: usize = 5;
+const SNIPPET_CHARS: usize = 160;
+const USAGE: &str = "Usage: /memory list | search <query> | forget <id or text> | stats";
+
+/// `/memory` chat commands. They run against the stores directly so the
+/// answers don't depend on the model.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum MemoryCommand {
+    List,
+    Search(String),
+    Forget(String),
+    Stats,
+    Help,
+}
+
+impl MemoryCommand {
+    /// Parse a `/memory` message; `None` for anything else.
+    pub(crate) fn parse(content: &str) -> Option<Self> {
+        let content = content.trim();
+        let head = content
+            .get(..7)
+            .filter(|head| head.eq_ignore_ascii_case("/memory"))?;
+        let rest = &content[head.len()..];
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let rest = rest.trim();
+        let (sub, arg) = rest
+            .split_once(char::is_whitespace)
+            .map(|(sub, arg)| (sub, arg.trim()))
+            .unwrap_or((rest, ""));
+        let command = match (sub.to_ascii_lowercase().as_str(), arg.is_empty()) {
+            ("list" | "ls", _) => Self::List,
+            ("stats", _) => Self::Stats,
+            ("search" | "find", false) => Self::Search(arg.to_string()),
+            ("forget" | "delete" | "rm", false) => Self::Forget(arg.to_string()),
+            _ => Self::Help,
+        };
+        Some(command)
+    }
+
+    /// Why this sender may not run the command, if they may not. Listing
+    /// and forgetting are admin-only, and forgetting is a write that safe
+    /// mode refuses.
+    pub(crate) fn refusal(&self, admin: bool, safe_mode: bool) -> Option<&'static str> {
+        match self {
+            Self::List | Self::Forget(_) if !admin => {
+                Some("Only admins can list or forget memories.")
+            }
+            Self::Forget(_) if safe_mode => Some("Safe mode is on; memories can't be forgotten."),
+            _ => None,
+        }
+    }
+}
+
+/// Run `command` against the session's vector namespace in Smart mode, or
+/// against MEMORY.md otherwise.
+pub(crate) async fn run(
+    command: MemoryCommand,
+    cfg: &MemoryConfig,
+    file: &MemoryStore,
+    vector: Option<&VectorMemoryStore>,
+    namespace: &str,
+) -> String {
+    if command == MemoryCommand::Help {
+        return USAGE.to_string();
+    }
+    match vector {
+        Some(store) => run_vector(command, cfg, store, namespace)
+            .await
+            .unwrap_or_else(|err| format!("Error: {err}")),
+        None if cfg.mode == MemoryMode::None => "Memory is disabled.".to_string(),
+        None => run_file(command, file),
+    }
+}
+
+async fn run_vector(
+    command: MemoryCommand,
+    cfg: &MemoryConfig,
+    store: &VectorMemoryStore,
+    namespace: &str,
+) -> anyhow::Result<String> {
+    let reply = match command {
+        MemoryCommand::List => {
+            let items = store.list(namespace, LIST_LIMIT).await?;
+            if items.is_empty() {
+                return Ok("No memories stored for this chat yet.".to_string());
+            }
+            format!("Memories (newest first):\n{}", render_items(&items))
+        }
+        MemoryCommand::Search(query) => {
+            let hits = store
+                .search(
+                    &query,
+                    SEARCH_LIMIT,
+                    cfg.recall.threshold,
+                    Some(namespace),
+                    cfg.recall.priority_weight,
+                )
+                .await?;
+            if hits.is_empty() {
+                return Ok(format!("No memories match \"{query}\"."));
+            }
+            let lines: Vec<String> = hits
+                .iter()
+                .map(|(item, score)| format!("{} (score {score:.2})", render_item(item)))
+                .collect();
+            format!("Memories matching \"{query}\":\n{}", lines.join("\n"))
+        }
+        MemoryCommand::Forget(target) => {
+            let matches = match store.get(&target, Some(namespace)).await? {
+                Some(item) => vec![item],
+                None => {
+                    store
+                        .find_containing(namespace, &target, FORGET_CANDIDATES + 1)
+                        .await?
+                }
+            };
+            match matches.as_slice() {
+                [] => format!("No memory matches \"{target}\"."),
+                [item] => {
+                    store.delete(&item.id, Some(namespace)).await?;
+                    format!(
+                        "Forgot: {}",
+                        truncate_memory_snippet(&item.content, SNIPPET_CHARS)
+                    )
+                }
+                several => {
+                    let shown = &several[..several.len().min(FORGET_CANDIDATES)];
+                    format!(
+                        "Several memories match \"{target}\"; forget one by id:\n{}",
+                        render_items(shown)
+                    )
+                }
+            }
+        }
+        MemoryCommand::Stats => {
+            let stats = store.stats(namespace).await?;
+            let mut out = format!("Memories in this chat: {} (cap {})", stats.count, stats.cap);
+            if !stats.kinds.is_empty() {
+                let kinds: Vec<String> = stats
+                    .kinds
+                    .iter()
+                    .map(|(kind, count)| format!("{kind} {count}"))
+                    .collect();
+                out.push_str(&format!("\nBy kind: {}", kinds.join(", ")));
+            }
+            if let (Some(oldest), Some(newest)) = (stats.oldest, stats.newest) {
+                out.push_str(&format!(
+                    "\nOldest: {}, last updated: {}",
+                    oldest.format("%Y-%m-%d"),
+                    newest.format("%Y-%m-%d")
+                ));
+            }
+            out
+        }
+        MemoryCommand::Help => USAGE.to_string(),
+    };
+    Ok(reply)
+}
+
+fn render_item(item: &MemoryItem) -> String {
+    format!(
+        "- `{}`{} {}",
+        item.id,
+        if item.is_pinned() { " (pinned)" } else { "" },
+        truncate_memory_snippet(&item.content, SNIPPET_CHARS)
+    )
+}
+
+fn render_items(items: &[MemoryItem]) -> String {
+    items.iter().map(render_item).collect::<Vec<_>>().join("\n")
+}
+
+/// MEMORY.md is shared by every chat, so these act on the whole file.
+fn run_file(command: MemoryCommand, store: &MemoryStore) -> String {
+    let entries = store.entries();
+    let matching = |needle: &str| -> Vec<(String, String)> {
+        let needle = needle.to_lowercase();
+        entries
+            .iter()
+            .filter(|(_, entry)| entry.to_lowercase().contains(&needle))
+            .cloned()
+            .collect()
+    };
+    match command {
+        MemoryCommand::List => {
+            if entries.is_empty() {
+                return "MEMORY.md has no entries yet.".to_string();
+            }
+            let start = entries.len().saturating_sub(LIST_LIMIT);
+            format!(
+                "MEMORY.md entries (latest {}):\n{}",
+                entries.len() - start,
+                render_entries(&entries[start..])
+            )
+        }
+        MemoryCommand::Search(query) => {
+            let hits = matching(&query);
+            if hits.is_empty() {
+                return format!("No memories match \"{query}\".");
+            }
+            let start = hits.len().saturating_sub(SEARCH_LIMIT);
+            format!(
+                "Memories matching \"{query}\":\n{}",
+                render_entries(&hits[start..])
+            )
+        }
+        MemoryCommand::Forget(target) => match matching(&target).as_slice() {
+            [] => format!("No memory matches \"{target}\"."),
+            [(_, entry)] => {
+                if store.remove_entry(entry) {
+                    let text = entry.trim_start_matches("- ");
+                    format!("Forgot: {}", truncate_memory_snippet(text, SNIPPET_CHARS))
+                } else {
+                    "Error: MEMORY.md changed; try again.".to_string()
+                }
+            }
+            several => format!(
+                "Several memories match \"{target}\"; be more specific:\n{}",
+                render_entries(&several[..several.len().min(FORGET_CANDIDATES)])
+            ),
+        },
+        MemoryCommand::Stats => {
+            if entries.is_empty() {
+                return "MEMORY.md has no entries yet.".to_string();
+            }
+            let mut sections: Vec<(String, usize)> = Vec::new();
+            for (section, _) in &entries {
+                match sections.iter_mut().find(|(name, _)| name == section) {
+                    Some((_, count)) => *count += 1,
+                    None => sections.push((section.clone(), 1)),
+                }
+            }
+            let sections: Vec<String> = sections
+                .iter()
+                .map(|(name, count)| format!("{name} {count}"))
+                .collect();
+            format!(
+                "MEMORY.md: {} entries, {} chars\nBy section: {}",
+                entries.len(),
+                store.read_long_term().chars().count(),
+                sections.join(", ")
+            )
+        }
+        MemoryCommand::Help => USAGE.to_string(),
+    }
+}
+
+fn render_entries(entries: &[(String, String)]) -> String {
+    entries
+        .iter()
+        .map(|(section, entry)| {
+            let text = entry.trim_start_matches("- ");
+            format!(
+                "- [{section}] {}",
+                truncate_memory_snippet(text, SNIPPET_CHARS)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    #[test]
+    fn parses_memory_subcommands() {
+        let cases = [
+            ("/memory list", Some(MemoryCommand::List)),
+            ("/MEMORY ls", Some(MemoryCommand::List)),
+            ("/memory stats", Some(MemoryCommand::Stats)),
+            (
+                "/memory search  tea preferences ",
+                Some(MemoryCommand::Search("tea preferences".to_string())),
+            ),
+            (
+                "/memory forget 1234-abcd",
+                Some(MemoryCommand::Forget("1234-abcd".to_string())),
+            ),
+            ("/memory", Some(MemoryCommand::Help)),
+            ("/memory search", Some(MemoryCommand::Help)),
+            ("/memory wipe", Some(MemoryCommand::Help)),
+            ("/memoryless", None),
+            ("what do you remember?", None),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(MemoryCommand::parse(raw), expected, "{raw}");
+        }
+    }
+
+    #[test]
+    fn list_and_forget_need_an_admin_and_forget_is_off_in_safe_mode() {
+        let forget = MemoryCommand::Forget("tea".to_string());
+        assert!(MemoryCommand::List.refusal(false, false).is_some());
+        assert!(forget.refusal(false, false).is_some());
+        assert!(MemoryCommand::Stats.refusal(false, true).is_none());
+        assert!(MemoryCommand::Search("tea".to_string())
+            .refusal(false, true)
+            .is_none());
+        assert!(MemoryCommand::List.refusal(true, true).is_none());
+        assert!(forget.refusal(true, false).is_none());
+        assert_eq!(
+            forget.refusal(true, true),
+            Some("Safe mode is on; memories can't be forgotten.")
+        );
+    }
+
+    #[tokio::test]
+    async fn smart_commands_use_the_session_namespace() {
+        let root = std::env::temp_dir().join(format!("lightclaw-memcmd-{}", Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults().memory;
+        cfg.mode = MemoryMode::Smart;
+        cfg.recall.threshold = 0.5;
+        let file = MemoryStore::new(root.join("workspace"));
+        let store = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        let ns = "telegram_1";
+        let kind = |kind: &str| HashMap::from([("kind".to_string(), kind.into())]);
+        let tea = store
+            .add(
+                "User prefers green tea",
+                kind("fact"),
+                Some(ns),
+                Some(vec![1.0, 0.0]),
+            )
+            .await
+            .unwrap();
+        store
+            .add(
+                "User drinks tea at 4pm",
+                kind("fact"),
+                Some(ns),
+                Some(vec![0.9, 0.1]),
+            )
+            .await
+            .unwrap();
+        store
+            .add(
+                "Project deadline is Friday",
+                kind("summary"),
+                Some(ns),
+                Some(vec![0.0, 1.0]),
+            )
+            .await
+            .unwrap();
+        store
+            .add(
+                "Other chat secret",
+                HashMap::new(),
+                Some("discord_2"),
+                Some(vec![1.0, 0.0]),
+            )
+            .await
+            .unwrap();
+        store.seed_embedding("drinks", vec![1.0, 0.0]).await;
+
+        let run = |command| run(command, &cfg, &file, Some(&store), ns);
+        let list = run(MemoryCommand::List).await;
+        assert!(
+            list.contains("green tea") && list.contains("deadline"),
+            "{list}"
+        );
+        assert!(!list.contains("secret"));
+
+        let search = run(MemoryCommand::Search("drinks".to_string())).await;
+        assert!(
+            search.contains("green tea") && search.contains("4pm"),
+            "{search}"
+        );
+        assert!(!search.contains("deadline") && !search.contains("secret"));
+
+        let stats = run(MemoryCommand::Stats).await;
+        assert!(
+            stats.contains("Memories in this chat: 3 (cap 100)"),
+            "{stats}"
+        );
+        assert!(stats.contains("fact 2, summary 1"), "{stats}");
+
+        let ambiguous = run(MemoryCommand::Forget("tea".to_string())).await;
+        assert!(
+            ambiguous.starts_with("Several memories match"),
+            "{ambiguous}"
+        );
+        let by_text = run(MemoryCommand::Forget("4PM".to_string())).await;
+        assert_eq!(by_text, "Forgot: User drinks tea at 4pm");
+        let by_id = run(MemoryCommand::Forget(tea.id.clone())).await;
+        assert_eq!(by_id, "Forgot: User prefers green tea");
+        assert!(store.get(&tea.id, Some(ns)).await.unwrap().is_none());
+        assert_eq!(store.stats("discord_2").await.unwrap().count, 1);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn simple_commands_act_on_memory_md() {
+        let root = std::env::temp_dir().join(format!("lightclaw-memcmd-{}", Uuid::new_v4()));
+        let cfg = AppConfig::defaults().memory;
+        let file = MemoryStore::new(root.clone());
+        file.append_remembered_fact("User prefers green tea");
+        file.append_remembered_fact("User drinks tea at 4pm");
+        file.append_user_observation("Lives in Lisbon");
+
+        let run = |command| run(command, &cfg, &file, None, "telegram_1");
+        let list = run(MemoryCommand::List).await;
+        assert!(
+            list.contains("[Remembered Facts]") && list.contains("Lisbon"),
+            "{list}"
+        );
+        let search = run(MemoryCommand::Search("LISBON".to_string())).await;
+        assert!(
+            search.contains("Lives in Lisbon") && !search.contains("tea"),
+            "{search}"
+        );
+        let stats = run(MemoryCommand::Stats).await;
+        assert!(stats.contains("3 entries"), "{stats}");
+        assert!(
+            stats.contains("Remembered Facts 2, User Observations 1"),
+            "{stats}"
+        );
+
+        let ambiguous = run(MemoryCommand::Forget("tea".to_string())).await;
+        assert!(
+            ambiguous.starts_with("Several memories match"),
+            "{ambiguous}"
+        );
+        let forgot = run(MemoryCommand::Forget("green".to_string())).await;
+        assert!(forgot.starts_with("Forgot:"), "{forgot}");
+        let content = file.read_long_term();
+        assert!(!content.contains("green tea") && content.contains("4pm"));
+        // Only the forgotten line goes; the blank line between sections stays.
+        assert!(content.contains("4pm\n\n## "), "{content}");
+
+        let mut disabled = cfg.clone();
+        disabled.mode = MemoryMode::None;
+        let reply = super::run(MemoryCommand::List, &disabled, &file, None, "telegram_1").await;
+        assert_eq!(reply, "Memory is disabled.");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,213 @@
+use rig::completion::message::{Message, UserContent};
+
+use super::messages_to_chat;
+
+const USAGE: &str = "Usage: /session dump [channel:chat_id] | trim <turns> [channel:chat_id]";
+/// Longest dump, in characters. Older messages are left out first.
+const MAX_DUMP_CHARS: usize = 12_000;
+
+/// `/session` chat commands for admins debugging context: show the history
+/// a session sends to the model, or cut it down to its latest turns.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SessionCommand {
+    /// `target` is another session's `channel:chat_id`; `None` is this chat.
+    Dump {
+        target: Option<String>,
+    },
+    Trim {
+        keep: usize,
+        target: Option<String>,
+    },
+    Help,
+}
+
+impl SessionCommand {
+    /// Parse a `/session` message; `None` for anything else.
+    pub(crate) fn parse(content: &str) -> Option<Self> {
+        let content = content.trim();
+        let head = content
+            .get(..8)
+            .filter(|head| head.eq_ignore_ascii_case("/session"))?;
+        let rest = &content[head.len()..];
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut args = rest.split_whitespace();
+        let command = match args.next().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("dump" | "show") => Self::Dump {
+                target: args.next().map(str::to_string),
+            },
+            Some("trim") => match args.next().and_then(|n| n.parse().ok()) {
+                Some(keep) => Self::Trim {
+                    keep,
+                    target: args.next().map(str::to_string),
+                },
+                None => Self::Help,
+            },
+            _ => Self::Help,
+        };
+        Some(command)
+    }
+
+    pub(crate) fn usage() -> &'static str {
+        USAGE
+    }
+}
+
+/// `history` as the readable role/content list the summarizer also sees,
+/// newest messages first to go in when it runs past `MAX_DUMP_CHARS`.
+pub(crate) fn dump(session_key: &str, history: &[Message]) -> String {
+    let chat = messages_to_chat(history);
+    if chat.is_empty() {
+        return format!("Session {session_key} has no history.");
+    }
+    let mut entries: Vec<String> = chat
+        .iter()
+        .enumerate()
+        .map(|(idx, message)| format!("\n\n#{} {}\n{}", idx + 1, message.role, message.content))
+        .collect();
+    let mut budget = MAX_DUMP_CHARS;
+    let mut start = entries.len();
+    while start > 0 {
+        let len = entries[start - 1].chars().count();
+        if len > budget {
+            break;
+        }
+        budget -= len;
+        start -= 1;
+    }
+    if start == entries.len() {
+        // The newest message alone is too long: show its beginning.
+        let last = entries.last_mut().expect("history is not empty");
+        *last = last.chars().take(MAX_DUMP_CHARS).collect::<String>() + "...";
+        start -= 1;
+    }
+    let mut out = format!(
+        "Session {session_key}: {} message(s) sent to the model",
+        chat.len()
+    );
+    if start > 0 {
+        out.push_str(&format!(" ({start} earlier one(s) left out)"));
+    }
+    for entry in &entries[start..] {
+        out.push_str(entry);
+    }
+    out
+}
+
+/// Drop everything before the last `keep` user turns. Returns how many
+/// messages were removed.
+pub(crate) fn trim(history: &mut Vec<Message>, keep: usize) -> usize {
+    let turn_starts: Vec<usize> = history
+        .iter()
+        .enumerate()
+        .filter(|(_, message)| is_user_turn(message))
+        .map(|(idx, _)| idx)
+        .collect();
+    let Some(skip) = turn_starts.len().checked_sub(keep).filter(|skip| *skip > 0) else {
+        return 0;
+    };
+    let cut = turn_starts.get(skip).copied().unwrap_or(history.len());
+    history.drain(..cut);
+    cut
+}
+
+/// A user message carrying text, as opposed to one holding tool results.
+fn is_user_turn(message: &Message) -> bool {
+    match message {
+        Message::User { content } => content
+            .iter()
+            .any(|part| matches!(part, UserContent::Text(_))),
+        Message::Assistant { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::append_text_history;
+    use super::*;
+
+    fn history(turns: usize) -> Vec<Message> {
+        let mut history = Vec::new();
+        for n in 1..=turns {
+            append_text_history(
+                &mut history,
+                &format!("question {n}"),
+                &format!("answer {n}"),
+            );
+        }
+        history
+    }
+
+    #[test]
+    fn dump_renders_history_and_trim_keeps_the_last_turns() {
+        let mut messages = history(4);
+        let out = dump("telegram:42", &messages);
+        assert!(out.starts_with("Session telegram:42: 8 message(s) sent to the model"));
+        assert!(out.contains("#1 user\nquestion 1"));
+        assert!(out.contains("#8 assistant\nanswer 4"));
+
+        assert_eq!(trim(&mut messages, 2), 4);
+        assert_eq!(messages.len(), 4);
+        let out = dump("telegram:42", &messages);
+        assert!(out.contains("#1 user\nquestion 3"), "{out}");
+        assert!(!out.contains("question 2"));
+
+        // Keeping more turns than exist changes nothing; zero clears it.
+        assert_eq!(trim(&mut messages, 10), 0);
+        assert_eq!(trim(&mut messages, 0), 4);
+        assert!(messages.is_empty());
+        assert_eq!(
+            dump("telegram:42", &messages),
+            "Session telegram:42 has no history."
+        );
+    }
+
+    #[test]
+    fn long_dumps_keep_the_newest_messages() {
+        let mut messages = Vec::new();
+        for n in 1..=4 {
+            append_text_history(&mut messages, &format!("q{n} {}", "x".repeat(5000)), "ok");
+        }
+        let out = dump("telegram:42", &messages);
+        assert!(out.chars().count() <= MAX_DUMP_CHARS + 100, "{}", out.len());
+        assert!(
+            out.contains("(3 earlier one(s) left out)"),
+            "{}",
+            &out[..80]
+        );
+        assert!(out.contains("#8 assistant\nok"));
+        assert!(!out.contains("q2 "));
+
+        let huge = vec![Message::user("y".repeat(MAX_DUMP_CHARS * 2))];
+        let out = dump("telegram:42", &huge);
+        assert!(out.ends_with("..."));
+        assert!(out.chars().count() <= MAX_DUMP_CHARS + 100);
+    }
+
+    #[test]
+    fn parses_dump_and_trim_with_optional_targets() {
+        assert_eq!(
+            SessionCommand::parse("/session"),
+            Some(SessionCommand::Dump { target: None })
+        );
+        assert_eq!(
+            SessionCommand::parse("/session dump discord:99"),
+            Some(SessionCommand::Dump {
+                target: Some("discord:99".to_string())
+            })
+        );
+        assert_eq!(
+            SessionCommand::parse("/SESSION trim 3"),
+            Some(SessionCommand::Trim {
+                keep: 3,
+                target: None
+            })
+        );
+        assert_eq!(
+            SessionCommand::parse("/session trim lots"),
+            Some(SessionCommand::Help)
+        );
+        assert_eq!(SessionCommand::parse("/sessions"), None);
+    }
+}
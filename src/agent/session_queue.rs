@@ -0,0 +1,117 @@
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+/// Orders turns within a session. `enqueue` is called in arrival order from
+/// the inbound loop; each turn then waits for the one before it in the same
+/// session, while turns in other sessions run freely.
+#[derive(Clone, Default)]
+pub(crate) struct SessionQueue {
+    /// Completion signal of the newest queued turn per session.
+    tails: Arc<DashMap<String, (u64, oneshot::Receiver<()>)>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+impl SessionQueue {
+    pub(crate) fn enqueue(&self, session_key: &str) -> SessionTurn {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let (done, done_rx) = oneshot::channel();
+        let previous = self
+            .tails
+            .insert(session_key.to_string(), (seq, done_rx))
+            .map(|(_, rx)| rx);
+        SessionTurn {
+            queue: self.clone(),
+            session_key: session_key.to_string(),
+            seq,
+            previous,
+            _done: done,
+        }
+    }
+}
+
+/// A queued turn. Await `ready` before touching the session; dropping the
+/// turn releases the next one.
+pub(crate) struct SessionTurn {
+    queue: SessionQueue,
+    session_key: String,
+    seq: u64,
+    previous: Option<oneshot::Receiver<()>>,
+    _done: oneshot::Sender<()>,
+}
+
+impl SessionTurn {
+    /// Wait until every earlier turn in this session has finished.
+    pub(crate) async fn ready(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            // Resolves (with an error) when the earlier turn is dropped.
+            let _ = previous.await;
+        }
+    }
+}
+
+impl Drop for SessionTurn {
+    fn drop(&mut self) {
+        // Forget the session once its newest turn is done.
+        self.queue
+            .tails
+            .remove_if(&self.session_key, |_, (seq, _)| *seq == self.seq);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::Mutex;
+
+    #[tokio::test]
+    async fn turns_in_one_session_run_in_arrival_order() {
+        let queue = SessionQueue::default();
+        let history = Arc::new(Mutex::new(Vec::new()));
+        let replies = Arc::new(Mutex::new(Vec::new()));
+
+        let turn = |mut ticket: SessionTurn, text: &'static str, work: Duration| {
+            let history = history.clone();
+            let replies = replies.clone();
+            async move {
+                ticket.ready().await;
+                let mut history = history.lock().await;
+                history.push(format!("user: {text}"));
+                tokio::time::sleep(work).await;
+                history.push(format!("assistant: re {text}"));
+                replies.lock().await.push(text);
+            }
+        };
+
+        let first = queue.enqueue("telegram:1");
+        let second = queue.enqueue("telegram:1");
+        let other = queue.enqueue("discord:9");
+        // The second message's task starts first and is quicker, yet must
+        // still wait for the first.
+        let second = tokio::spawn(turn(second, "second", Duration::ZERO));
+        tokio::task::yield_now().await;
+        let first = tokio::spawn(turn(first, "first", Duration::from_millis(50)));
+        // Another session isn't held up by either.
+        let mut other = other;
+        tokio::time::timeout(Duration::from_millis(20), other.ready())
+            .await
+            .expect("other session runs immediately");
+        drop(other);
+        first.await.unwrap();
+        second.await.unwrap();
+
+        assert_eq!(*replies.lock().await, vec!["first", "second"]);
+        assert_eq!(
+            *history.lock().await,
+            vec![
+                "user: first",
+                "assistant: re first",
+                "user: second",
+                "assistant: re second",
+            ]
+        );
+        assert!(queue.tails.is_empty());
+    }
+}
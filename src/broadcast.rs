@@ -0,0 +1,178 @@
+use crate::bus::{new_handle, MessageBus, OutboundAction, OutboundMessage, OutboundSource};
+use crate::channels;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Gap between the messages of one broadcast. Telegram allows about 30 sends
+/// a second per bot; staying under it keeps a large broadcast from flooding
+/// the forwarders' queues.
+const BROADCAST_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A chat the agent has heard from, as a bus channel plus chat id.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct KnownChat {
+    pub channel: String,
+    #[serde(rename = "chatId")]
+    pub chat_id: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct KnownChatsData {
+    chats: BTreeSet<KnownChat>,
+}
+
+/// Chats that have messaged the bot, persisted to `data_dir/chats.json` so
+/// a broadcast reaches them after a restart.
+#[derive(Clone)]
+pub struct KnownChats {
+    path: PathBuf,
+    chats: Arc<Mutex<Option<BTreeSet<KnownChat>>>>,
+}
+
+impl KnownChats {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join("chats.json"),
+            chats: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Remember a chat and return whether it is new. Only platform chats are
+    /// kept; local (`tui`) and scheduled (`cron`) turns have nobody to
+    /// broadcast to. The file is rewritten only when the chat is new.
+    pub async fn record(&self, channel: &str, chat_id: &str) -> Result<bool> {
+        if !matches!(channels::platform(channel), "telegram" | "discord") {
+            return Ok(false);
+        }
+        // Held across the write so concurrent first contacts can't save an
+        // older set over a newer one.
+        let mut guard = self.chats.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.read().await);
+        }
+        let Some(chats) = guard.as_mut() else {
+            return Ok(false);
+        };
+        let added = chats.insert(KnownChat {
+            channel: channel.to_string(),
+            chat_id: chat_id.to_string(),
+        });
+        if !added {
+            return Ok(false);
+        }
+        let content = serde_json::to_string_pretty(&KnownChatsData {
+            chats: chats.clone(),
+        })?;
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, content).await?;
+        Ok(true)
+    }
+
+    /// Every known chat, read fresh from disk so chats recorded by the
+    /// running service are visible to the CLI.
+    pub async fn list(&self) -> Vec<KnownChat> {
+        self.read().await.into_iter().collect()
+    }
+
+    async fn read(&self) -> BTreeSet<KnownChat> {
+        tokio::fs::read_to_string(&self.path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_str::<KnownChatsData>(&content).ok())
+            .map(|data| data.chats)
+            .unwrap_or_default()
+    }
+}
+
+/// Queue `text` for every chat in `chats` and return how many messages were
+/// queued. A background task publishes them `BROADCAST_INTERVAL` apart,
+/// waiting for room on the bus, so the caller doesn't block on a large
+/// broadcast and the forwarders never see more than they can pace.
+pub fn broadcast(bus: &MessageBus, chats: Vec<KnownChat>, text: &str) -> usize {
+    let queued = chats.len();
+    let bus = bus.clone();
+    let text = text.to_string();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(BROADCAST_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        for chat in chats {
+            ticker.tick().await;
+            bus.publish_outbound(OutboundMessage {
+                channel: chat.channel,
+                chat_id: chat.chat_id,
+                content: text.clone(),
+                reply_to: None,
+                handle: Some(new_handle()),
+                action: OutboundAction::Send,
+                source: OutboundSource::Broadcast,
+                attachments: Vec::new(),
+                mention: None,
+            })
+            .await;
+        }
+    });
+    queued
+}
+
+/// Whether `sender_id` on `channel` may run admin commands. Entries are a
+/// bare sender id or `<channel>:<sender_id>`; an empty list allows no one.
+pub fn is_admin(admins: &[String], channel: &str, sender_id: &str) -> bool {
+    let scoped = format!("{channel}:{sender_id}");
+    admins
+        .iter()
+        .map(|entry| entry.trim())
+        .any(|entry| entry == sender_id || entry == scoped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn broadcast_enqueues_one_outbound_per_known_chat() {
+        let dir = tempfile::tempdir().unwrap();
+        let chats = KnownChats::new(dir.path());
+        assert!(chats.record("telegram", "100").await.unwrap());
+        assert!(chats.record("discord.work", "200").await.unwrap());
+        assert!(!chats.record("telegram", "100").await.unwrap());
+        assert!(!chats.record("tui", "local").await.unwrap());
+        assert!(!chats.record("cron", "direct").await.unwrap());
+
+        // A fresh instance sees what the service recorded.
+        let known = KnownChats::new(dir.path()).list().await;
+        assert_eq!(known.len(), 2);
+
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let queued = broadcast(&bus, known, "maintenance at 9");
+        assert_eq!(queued, 2);
+
+        let mut sent = Vec::new();
+        for _ in 0..2 {
+            let out = tokio::time::timeout(std::time::Duration::from_secs(1), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(out.content, "maintenance at 9");
+            sent.push(format!("{}:{}", out.channel, out.chat_id));
+        }
+        sent.sort();
+        assert_eq!(sent, vec!["discord.work:200", "telegram:100"]);
+    }
+
+    #[test]
+    fn admin_entries_match_bare_or_channel_scoped_ids() {
+        let admins = vec!["42".to_string(), "discord:7".to_string()];
+        assert!(is_admin(&admins, "telegram", "42"));
+        assert!(is_admin(&admins, "discord", "7"));
+        assert!(!is_admin(&admins, "telegram", "7"));
+        assert!(!is_admin(&[], "telegram", "42"));
+    }
+}
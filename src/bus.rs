@@ -1,13 +1,73 @@
+use crate::plugins::{Direction, WasmPlugin};
+use crate::postprocess::Pipeline;
+use anyhow::{anyhow, Result};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc, Mutex};
-use tracing::warn;
+use tracing::{info, warn};
 
-#[derive(Clone, Debug)]
+/// Sent messages remembered for later edits/deletes.
+const MAX_SENT_MESSAGES: usize = 1000;
+/// Deliveries of a journaled inbound message before it is set aside as a dead
+/// letter. Each restart that finds it still un-acked counts as one more.
+const MAX_INBOUND_ATTEMPTS: i64 = 3;
+/// The outbound log is rotated past this size, keeping one previous file.
+const MAX_OUTBOUND_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InboundMessage {
     pub channel: String,
     pub chat_id: String,
     pub sender_id: String,
     pub content: String,
+    /// Platform id of the originating message, when the channel has one.
+    pub message_id: Option<String>,
+    /// User to @-mention in notifications sent during this turn.
+    pub mention: Option<String>,
+    /// Non-text parts of the message (documents, images, voice notes).
+    pub attachments: Vec<Attachment>,
+}
+
+impl InboundMessage {
+    /// Text the agent sees: `content` followed by each rendered attachment.
+    /// Plain text messages come back unchanged.
+    pub fn text(&self) -> String {
+        let mut parts = Vec::with_capacity(self.attachments.len() + 1);
+        if !self.content.trim().is_empty() {
+            parts.push(self.content.clone());
+        }
+        parts.extend(self.attachments.iter().map(Attachment::render));
+        parts.join("\n\n")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Attachment {
+    /// Inline text, e.g. the contents of a small text document.
+    Text { text: String },
+    /// Image saved locally.
+    Image { path: PathBuf },
+    /// Audio already converted to text.
+    Audio { transcript: String },
+    /// Any other file saved locally.
+    File { path: PathBuf, mime: Option<String> },
+}
+
+impl Attachment {
+    pub fn render(&self) -> String {
+        match self {
+            Attachment::Text { text } => format!("[Attached text]\n{text}"),
+            Attachment::Image { path } => format!("[Attached image: {}]", path.display()),
+            Attachment::Audio { transcript } => format!("[Voice message transcript]\n{transcript}"),
+            Attachment::File { path, mime } => match mime {
+                Some(mime) => format!("[Attached file: {} ({mime})]", path.display()),
+                None => format!("[Attached file: {}]", path.display()),
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -15,14 +75,508 @@ pub struct OutboundMessage {
     pub channel: String,
     pub chat_id: String,
     pub content: String,
+    /// Platform message id this is a reply to; channels that support
+    /// threading attach it as a reference.
+    pub reply_to: Option<String>,
+    /// Agent-visible id. On `Send`, forwarders record the platform ids they
+    /// deliver under it; `Edit`/`Delete` target the message recorded here.
+    pub handle: Option<String>,
+    pub action: OutboundAction,
+    /// What produced the message, for logs.
+    pub source: OutboundSource,
+    /// Files delivered after the text of a `Send`.
+    pub attachments: Vec<PathBuf>,
+    /// User the forwarder should @-mention ahead of the text of a `Send`.
+    pub mention: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutboundAction {
+    #[default]
+    Send,
+    /// Replace the text of the message recorded under `handle`.
+    Edit,
+    /// Remove the message recorded under `handle`.
+    Delete,
+}
+
+/// What produced an outbound message: a turn's reply, a tool such as
+/// `send_message`, a scheduled (cron) turn, or a broadcast.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutboundSource {
+    #[default]
+    Reply,
+    Tool,
+    Cron,
+    Broadcast,
+}
+
+impl OutboundSource {
+    /// Source for messages produced while handling a turn from `sender_id`:
+    /// cron turns are proactive, anything else answers someone.
+    pub fn for_turn(sender_id: &str, default: Self) -> Self {
+        if sender_id == "cron" {
+            Self::Cron
+        } else {
+            default
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Reply => "reply",
+            Self::Tool => "tool",
+            Self::Cron => "cron",
+            Self::Broadcast => "broadcast",
+        }
+    }
+}
+
+/// Short id handed to the agent for a message it sent.
+pub fn new_handle() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..8].to_string()
+}
+
+/// Where a handle was delivered; long replies may span several platform
+/// messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentRecord {
+    pub channel: String,
+    pub chat_id: String,
+    pub message_ids: Vec<String>,
+}
+
+#[derive(Default)]
+struct SentLog {
+    order: VecDeque<String>,
+    by_handle: HashMap<String, SentRecord>,
+    last_by_chat: HashMap<(String, String), String>,
+}
+
+/// Handle -> platform message ids, filled in by channel forwarders.
+#[derive(Clone, Default)]
+pub struct SentMessages {
+    inner: Arc<std::sync::Mutex<SentLog>>,
+}
+
+impl SentMessages {
+    pub fn record(&self, handle: &str, channel: &str, chat_id: &str, message_id: String) {
+        let Ok(mut log) = self.inner.lock() else {
+            return;
+        };
+        if !log.by_handle.contains_key(handle) {
+            if log.order.len() >= MAX_SENT_MESSAGES {
+                if let Some(oldest) = log.order.pop_front() {
+                    log.by_handle.remove(&oldest);
+                }
+            }
+            log.order.push_back(handle.to_string());
+        }
+        log.by_handle
+            .entry(handle.to_string())
+            .or_insert_with(|| SentRecord {
+                channel: channel.to_string(),
+                chat_id: chat_id.to_string(),
+                message_ids: Vec::new(),
+            })
+            .message_ids
+            .push(message_id);
+        log.last_by_chat.insert(
+            (channel.to_string(), chat_id.to_string()),
+            handle.to_string(),
+        );
+    }
+
+    /// Replace the platform ids behind `handle`, after an edit changed how
+    /// many messages the reply spans.
+    pub fn replace(&self, handle: &str, message_ids: Vec<String>) {
+        if let Ok(mut log) = self.inner.lock() {
+            if let Some(record) = log.by_handle.get_mut(handle) {
+                record.message_ids = message_ids;
+            }
+        }
+    }
+
+    pub fn lookup(&self, handle: &str) -> Option<SentRecord> {
+        self.inner.lock().ok()?.by_handle.get(handle).cloned()
+    }
+
+    /// Handle of the most recent message sent to a chat.
+    pub fn last(&self, channel: &str, chat_id: &str) -> Option<String> {
+        let log = self.inner.lock().ok()?;
+        let handle = log
+            .last_by_chat
+            .get(&(channel.to_string(), chat_id.to_string()))?;
+        log.by_handle.contains_key(handle).then(|| handle.clone())
+    }
+
+    pub fn forget(&self, handle: &str) {
+        if let Ok(mut log) = self.inner.lock() {
+            log.by_handle.remove(handle);
+            log.order.retain(|h| h != handle);
+        }
+    }
+}
+
+/// When each chat last received a message from a person (scheduled turns
+/// don't count). In memory only unless built with `persisted`.
+#[derive(Clone, Default)]
+pub struct SessionActivity {
+    inner: Arc<std::sync::Mutex<HashMap<(String, String), i64>>>,
+    /// Wakes the task that saves the map, when persisted.
+    save: Option<mpsc::UnboundedSender<()>>,
 }
 
+/// One chat's entry in the saved activity file.
+#[derive(Serialize, Deserialize)]
+struct ActivityEntry {
+    channel: String,
+    #[serde(rename = "chatId")]
+    chat_id: String,
+    #[serde(rename = "lastInboundMs")]
+    last_inbound_ms: i64,
+}
+
+impl SessionActivity {
+    /// Activity read from `path` and saved back to it after each change, so
+    /// a restart doesn't make every chat look idle. A background task does
+    /// the writing so publishing never waits on disk.
+    pub fn persisted(path: PathBuf) -> Self {
+        let seen: HashMap<(String, String), i64> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<ActivityEntry>>(&content).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| ((entry.channel, entry.chat_id), entry.last_inbound_ms))
+            .collect();
+        let inner = Arc::new(std::sync::Mutex::new(seen));
+        let (tx, mut rx) = mpsc::unbounded_channel::<()>();
+        let map = inner.clone();
+        tokio::spawn(async move {
+            while rx.recv().await.is_some() {
+                // One save covers every change queued meanwhile.
+                while rx.try_recv().is_ok() {}
+                let entries: Vec<ActivityEntry> = match map.lock() {
+                    Ok(seen) => seen
+                        .iter()
+                        .map(|((channel, chat_id), at_ms)| ActivityEntry {
+                            channel: channel.clone(),
+                            chat_id: chat_id.clone(),
+                            last_inbound_ms: *at_ms,
+                        })
+                        .collect(),
+                    Err(_) => continue,
+                };
+                if let Err(err) = save_activity(&path, &entries).await {
+                    warn!("Failed to save chat activity {}: {err}", path.display());
+                }
+            }
+        });
+        Self {
+            inner,
+            save: Some(tx),
+        }
+    }
+
+    pub fn touch(&self, channel: &str, chat_id: &str, at_ms: i64) {
+        if let Ok(mut seen) = self.inner.lock() {
+            seen.insert((channel.to_string(), chat_id.to_string()), at_ms);
+        }
+        if let Some(save) = &self.save {
+            let _ = save.send(());
+        }
+    }
+
+    /// Every `(channel, chat_id)` with recorded activity.
+    pub fn chats(&self) -> HashSet<(String, String)> {
+        self.inner
+            .lock()
+            .map(|seen| seen.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Unix millis of the last inbound message in a chat, if any was seen.
+    pub fn last_inbound_ms(&self, channel: &str, chat_id: &str) -> Option<i64> {
+        self.inner
+            .lock()
+            .ok()?
+            .get(&(channel.to_string(), chat_id.to_string()))
+            .copied()
+    }
+}
+
+/// Replace the activity file at `path` with `entries`, via a temp file so a
+/// crash mid-write leaves the previous one intact.
+async fn save_activity(path: &Path, entries: &[ActivityEntry]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_string_pretty(entries)?).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Turn lifecycle signals so channels can show activity while the agent works.
+#[derive(Clone, Debug)]
+pub struct ProgressEvent {
+    pub channel: String,
+    pub chat_id: String,
+    /// Sender of the turn's inbound message (`cron` for scheduled turns).
+    pub sender_id: String,
+    pub kind: ProgressKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgressKind {
+    Started,
+    ToolCall { name: String },
+    Finished,
+}
+
+/// Append-only record of inbound messages that have not finished processing.
+/// Rows are deleted when their turn completes; whatever is left on startup
+/// was interrupted and gets replayed, up to `MAX_INBOUND_ATTEMPTS` times
+/// before it moves to `inbound_dead_letter`. SQLite runs on the blocking
+/// pool so the async callers never stall a runtime worker.
+#[derive(Clone)]
+struct InboundJournal {
+    conn: Arc<std::sync::Mutex<Connection>>,
+}
+
+impl InboundJournal {
+    fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inbound_journal (\
+                id INTEGER PRIMARY KEY AUTOINCREMENT,\
+                payload TEXT NOT NULL,\
+                created_at TEXT NOT NULL,\
+                attempts INTEGER NOT NULL DEFAULT 1\
+            )",
+            [],
+        )?;
+        // Journals from before attempts were counted.
+        if conn
+            .prepare("SELECT attempts FROM inbound_journal LIMIT 0")
+            .is_err()
+        {
+            conn.execute(
+                "ALTER TABLE inbound_journal ADD COLUMN attempts INTEGER NOT NULL DEFAULT 1",
+                [],
+            )?;
+        }
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inbound_dead_letter (\
+                id INTEGER PRIMARY KEY,\
+                payload TEXT NOT NULL,\
+                created_at TEXT NOT NULL,\
+                attempts INTEGER NOT NULL,\
+                failed_at TEXT NOT NULL\
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: Arc::new(std::sync::Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` against the connection on the blocking pool.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|e| anyhow!("mutex poisoned: {e}"))?;
+            f(&conn)
+        })
+        .await?
+    }
+
+    async fn append(&self, msg: &InboundMessage) -> Result<i64> {
+        let payload = serde_json::to_string(msg)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO inbound_journal (payload, created_at) VALUES (?1, ?2)",
+                params![payload, chrono::Utc::now().to_rfc3339()],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })
+        .await
+    }
+
+    async fn ack(&self, id: i64) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM inbound_journal WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Un-acked entries to deliver again, oldest first, each counted as one
+    /// more attempt. Rows that no longer parse are dropped; rows out of
+    /// attempts become dead letters.
+    async fn pending(&self) -> Result<Vec<(i64, InboundMessage)>> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let rows = {
+                let mut stmt =
+                    tx.prepare("SELECT id, payload, attempts FROM inbound_journal ORDER BY id")?;
+                let rows = stmt
+                    .query_map([], |row| {
+                        Ok((
+                            row.get::<_, i64>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, i64>(2)?,
+                        ))
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            };
+            let mut pending = Vec::with_capacity(rows.len());
+            for (id, payload, attempts) in rows {
+                if attempts >= MAX_INBOUND_ATTEMPTS {
+                    warn!(
+                        "inbound message {id} was not handled after {attempts} attempt(s); \
+                         moving it to inbound_dead_letter"
+                    );
+                    tx.execute(
+                        "INSERT OR REPLACE INTO inbound_dead_letter \
+                         (id, payload, created_at, attempts, failed_at) \
+                         SELECT id, payload, created_at, attempts, ?2 \
+                         FROM inbound_journal WHERE id = ?1",
+                        params![id, chrono::Utc::now().to_rfc3339()],
+                    )?;
+                    tx.execute("DELETE FROM inbound_journal WHERE id = ?1", params![id])?;
+                    continue;
+                }
+                match serde_json::from_str(&payload) {
+                    Ok(msg) => {
+                        tx.execute(
+                            "UPDATE inbound_journal SET attempts = attempts + 1 WHERE id = ?1",
+                            params![id],
+                        )?;
+                        pending.push((id, msg));
+                    }
+                    Err(err) => {
+                        warn!("dropping unreadable inbound journal entry {id}: {err}");
+                        tx.execute("DELETE FROM inbound_journal WHERE id = ?1", params![id])?;
+                    }
+                }
+            }
+            tx.commit()?;
+            Ok(pending)
+        })
+        .await
+    }
+}
+
+/// One line of the outbound log. Message text is left out; `chars` is its
+/// length.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboundLogEntry {
+    pub at: String,
+    pub channel: String,
+    #[serde(rename = "chatId")]
+    pub chat_id: String,
+    pub action: String,
+    pub source: String,
+    pub chars: usize,
+}
+
+/// JSON-lines record of every outbound message and what produced it, for
+/// operators. A background task does the writing so publishing never waits
+/// on disk.
+#[derive(Clone)]
+struct OutboundLog {
+    tx: mpsc::UnboundedSender<OutboundLogEntry>,
+}
+
+impl OutboundLog {
+    fn open(path: PathBuf) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<OutboundLogEntry>();
+        tokio::spawn(async move {
+            while let Some(entry) = rx.recv().await {
+                if let Err(err) = append_outbound_entry(&path, &entry).await {
+                    warn!("Failed to write outbound log {}: {err}", path.display());
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    fn record(&self, msg: &OutboundMessage) {
+        let _ = self.tx.send(OutboundLogEntry {
+            at: chrono::Utc::now().to_rfc3339(),
+            channel: msg.channel.clone(),
+            chat_id: msg.chat_id.clone(),
+            action: format!("{:?}", msg.action).to_ascii_lowercase(),
+            source: msg.source.as_str().to_string(),
+            chars: msg.content.chars().count(),
+        });
+    }
+}
+
+async fn append_outbound_entry(path: &Path, entry: &OutboundLogEntry) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let full = tokio::fs::metadata(path)
+        .await
+        .is_ok_and(|meta| meta.len() >= MAX_OUTBOUND_LOG_BYTES);
+    if full {
+        tokio::fs::rename(path, path.with_extension("jsonl.1")).await?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await
+}
+
+/// Acknowledges a consumed inbound message once its turn is done. Dropping
+/// it without calling `ack` leaves a durable message to be replayed.
+pub struct InboundAck {
+    entry: Option<(InboundJournal, i64)>,
+}
+
+impl InboundAck {
+    pub async fn ack(self) {
+        let Some((journal, id)) = self.entry else {
+            return;
+        };
+        if let Err(err) = journal.ack(id).await {
+            warn!("Failed to ack inbound message {id}: {err}");
+        }
+    }
+}
+
+type Queued = (Option<i64>, InboundMessage);
+
 #[derive(Clone)]
 pub struct MessageBus {
-    inbound_tx: mpsc::Sender<InboundMessage>,
+    inbound_tx: mpsc::Sender<Queued>,
     outbound_tx: mpsc::Sender<OutboundMessage>,
-    inbound_rx: Arc<Mutex<mpsc::Receiver<InboundMessage>>>,
+    inbound_rx: Arc<Mutex<mpsc::Receiver<Queued>>>,
     outbound_broadcast_tx: broadcast::Sender<OutboundMessage>,
+    progress_tx: broadcast::Sender<ProgressEvent>,
+    sent: SentMessages,
+    activity: SessionActivity,
+    journal: Option<InboundJournal>,
+    /// `outbound.post_process` stages, run on the text of every send and edit.
+    post_process: Arc<Pipeline>,
+    /// `plugins.wasm`, run on the text of every inbound and outbound message.
+    plugin: Option<Arc<WasmPlugin>>,
+    outbound_log: Option<OutboundLog>,
 }
 
 impl MessageBus {
@@ -30,6 +584,7 @@ impl MessageBus {
         let (inbound_tx, inbound_rx) = mpsc::channel(100);
         let (outbound_tx, mut outbound_rx) = mpsc::channel(100);
         let (outbound_broadcast_tx, _) = broadcast::channel(100);
+        let (progress_tx, _) = broadcast::channel(100);
 
         let inbound_rx = Arc::new(Mutex::new(inbound_rx));
 
@@ -38,6 +593,13 @@ impl MessageBus {
             outbound_tx,
             inbound_rx: inbound_rx.clone(),
             outbound_broadcast_tx: outbound_broadcast_tx.clone(),
+            progress_tx,
+            sent: SentMessages::default(),
+            activity: SessionActivity::default(),
+            journal: None,
+            post_process: Arc::new(Pipeline::default()),
+            plugin: None,
+            outbound_log: None,
         };
 
         tokio::spawn(async move {
@@ -51,24 +613,402 @@ impl MessageBus {
         bus
     }
 
-    pub async fn publish_inbound(&self, msg: InboundMessage) {
-        if let Err(e) = self.inbound_tx.send(msg).await {
+    /// A bus whose inbound messages are journaled at `path` until acked.
+    /// Entries left over from a previous run are queued again first.
+    pub async fn durable(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+        let journal = tokio::task::spawn_blocking(move || InboundJournal::open(&path)).await??;
+        let pending = journal.pending().await?;
+        let mut bus = Self::new();
+        bus.journal = Some(journal);
+        if !pending.is_empty() {
+            info!("replaying {} un-acked inbound message(s)", pending.len());
+            let tx = bus.inbound_tx.clone();
+            tokio::spawn(async move {
+                for (id, msg) in pending {
+                    if tx.send((Some(id), msg)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(bus)
+    }
+
+    /// Run `pipeline` on every outbound message, whatever produced it.
+    pub fn with_post_process(mut self, pipeline: Pipeline) -> Self {
+        self.post_process = Arc::new(pipeline);
+        self
+    }
+
+    /// Run `plugin` on every inbound message before it is queued and every
+    /// outbound one before the post-process stages.
+    pub fn with_plugin(mut self, plugin: Option<WasmPlugin>) -> Self {
+        self.plugin = plugin.map(Arc::new);
+        self
+    }
+
+    /// Append an entry for every outbound message to the JSON-lines file at
+    /// `path`.
+    pub fn with_outbound_log(mut self, path: PathBuf) -> Self {
+        self.outbound_log = Some(OutboundLog::open(path));
+        self
+    }
+
+    /// Remember when each chat was last active in the file at `path`.
+    pub fn with_activity_file(mut self, path: PathBuf) -> Self {
+        self.activity = SessionActivity::persisted(path);
+        self
+    }
+
+    /// Text as it would be delivered: through the plugin, then the
+    /// post-process stages.
+    pub async fn outbound_text(&self, mut text: String) -> String {
+        if let Some(plugin) = &self.plugin {
+            text = plugin.transform(Direction::Outbound, text).await;
+        }
+        self.post_process.apply(text)
+    }
+
+    /// Run the plugin on `msg`'s text and on any text or transcript it
+    /// carries as attachments.
+    pub async fn transform_inbound(&self, msg: &mut InboundMessage) {
+        let Some(plugin) = &self.plugin else {
+            return;
+        };
+        if !msg.content.is_empty() {
+            let content = std::mem::take(&mut msg.content);
+            msg.content = plugin.transform(Direction::Inbound, content).await;
+        }
+        for attachment in &mut msg.attachments {
+            if let Attachment::Text { text: body } | Attachment::Audio { transcript: body } =
+                attachment
+            {
+                let text = std::mem::take(body);
+                *body = plugin.transform(Direction::Inbound, text).await;
+            }
+        }
+    }
+
+    pub async fn publish_inbound(&self, mut msg: InboundMessage) {
+        self.transform_inbound(&mut msg).await;
+        if msg.sender_id != "cron" {
+            self.activity.touch(
+                &msg.channel,
+                &msg.chat_id,
+                chrono::Utc::now().timestamp_millis(),
+            );
+        }
+        let id = match &self.journal {
+            Some(journal) => match journal.append(&msg).await {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    warn!("Failed to journal inbound message: {e}");
+                    None
+                }
+            },
+            None => None,
+        };
+        if let Err(e) = self.inbound_tx.send((id, msg)).await {
             warn!("Failed to publish inbound message: {e}");
         }
     }
 
-    pub async fn publish_outbound(&self, msg: OutboundMessage) {
+    pub async fn publish_outbound(&self, mut msg: OutboundMessage) {
+        if msg.action != OutboundAction::Delete {
+            msg.content = self.outbound_text(msg.content).await;
+        }
+        info!(
+            "outbound {} message: channel={} chat_id={} action={:?} len={}",
+            msg.source.as_str(),
+            msg.channel,
+            msg.chat_id,
+            msg.action,
+            msg.content.len()
+        );
+        if let Some(log) = &self.outbound_log {
+            log.record(&msg);
+        }
         if let Err(e) = self.outbound_tx.send(msg).await {
             warn!("Failed to publish outbound message: {e}");
         }
     }
 
-    pub async fn consume_inbound(&self) -> Option<InboundMessage> {
-        let mut rx = self.inbound_rx.lock().await;
-        rx.recv().await
+    /// Next inbound message and the ack to call once it has been handled.
+    pub async fn consume_inbound(&self) -> Option<(InboundMessage, InboundAck)> {
+        let (id, msg) = self.inbound_rx.lock().await.recv().await?;
+        let entry = self.journal.clone().zip(id);
+        Some((msg, InboundAck { entry }))
     }
 
     pub fn subscribe_outbound(&self) -> broadcast::Receiver<OutboundMessage> {
         self.outbound_broadcast_tx.subscribe()
     }
+
+    /// Best-effort: progress is dropped when no channel is listening.
+    pub fn publish_progress(&self, event: ProgressEvent) {
+        let _ = self.progress_tx.send(event);
+    }
+
+    pub fn progress_sender(&self) -> broadcast::Sender<ProgressEvent> {
+        self.progress_tx.clone()
+    }
+
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.progress_tx.subscribe()
+    }
+
+    pub fn sent_messages(&self) -> SentMessages {
+        self.sent.clone()
+    }
+
+    pub fn activity(&self) -> SessionActivity {
+        self.activity.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inbound(content: &str, attachments: Vec<Attachment>) -> InboundMessage {
+        InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            sender_id: "2".to_string(),
+            content: content.to_string(),
+            message_id: None,
+            mention: None,
+            attachments,
+        }
+    }
+
+    #[test]
+    fn inbound_text_renders_attachments_after_content() {
+        assert_eq!(inbound("just text", Vec::new()).text(), "just text");
+
+        let msg = inbound(
+            "What's in these?",
+            vec![
+                Attachment::Image {
+                    path: PathBuf::from("/ws/inbox/cat.jpg"),
+                },
+                Attachment::File {
+                    path: PathBuf::from("/ws/inbox/report.pdf"),
+                    mime: Some("application/pdf".to_string()),
+                },
+                Attachment::Text {
+                    text: "line one".to_string(),
+                },
+            ],
+        );
+        assert_eq!(
+            msg.text(),
+            "What's in these?\n\n[Attached image: /ws/inbox/cat.jpg]\n\n\
+             [Attached file: /ws/inbox/report.pdf (application/pdf)]\n\n\
+             [Attached text]\nline one"
+        );
+
+        let voice = inbound(
+            "",
+            vec![Attachment::Audio {
+                transcript: "call me back".to_string(),
+            }],
+        );
+        assert_eq!(voice.text(), "[Voice message transcript]\ncall me back");
+    }
+
+    #[tokio::test]
+    async fn durable_bus_replays_unacked_messages_after_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("lightclaw-journal-{}", uuid::Uuid::new_v4()))
+            .join("inbound_journal.db");
+        let next = |bus: &MessageBus| {
+            let bus = bus.clone();
+            async move {
+                tokio::time::timeout(std::time::Duration::from_secs(1), bus.consume_inbound())
+                    .await
+                    .expect("inbound queued")
+                    .expect("bus open")
+            }
+        };
+
+        let bus = MessageBus::durable(&path).await.unwrap();
+        bus.publish_inbound(inbound("first", Vec::new())).await;
+        bus.publish_inbound(inbound("second", Vec::new())).await;
+        let (msg, ack) = next(&bus).await;
+        assert_eq!(msg.content, "first");
+        ack.ack().await;
+        let (msg, _unacked) = next(&bus).await;
+        assert_eq!(msg.content, "second");
+        drop(bus);
+
+        let bus = MessageBus::durable(&path).await.unwrap();
+        let (msg, ack) = next(&bus).await;
+        assert_eq!(msg.content, "second");
+        ack.ack().await;
+        drop(bus);
+
+        let bus = MessageBus::durable(&path).await.unwrap();
+        assert!(
+            tokio::time::timeout(std::time::Duration::from_millis(100), bus.consume_inbound())
+                .await
+                .is_err()
+        );
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn inbound_messages_that_keep_failing_become_dead_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("inbound_journal.db");
+        let consume = |bus: MessageBus| async move {
+            tokio::time::timeout(std::time::Duration::from_millis(200), bus.consume_inbound())
+                .await
+                .ok()
+                .flatten()
+                .map(|(msg, _unacked)| msg.content)
+        };
+
+        let bus = MessageBus::durable(&path).await.unwrap();
+        bus.publish_inbound(inbound("poison", Vec::new())).await;
+        assert_eq!(consume(bus).await.as_deref(), Some("poison"));
+        // Each restart that finds it un-acked delivers it again...
+        for _ in 1..MAX_INBOUND_ATTEMPTS {
+            let bus = MessageBus::durable(&path).await.unwrap();
+            assert_eq!(consume(bus).await.as_deref(), Some("poison"));
+        }
+        // ...until it runs out of attempts and is set aside.
+        let bus = MessageBus::durable(&path).await.unwrap();
+        assert_eq!(consume(bus).await, None);
+
+        let conn = Connection::open(&path).unwrap();
+        let (payload, attempts): (String, i64) = conn
+            .query_row(
+                "SELECT payload, attempts FROM inbound_dead_letter",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(payload.contains("poison"));
+        assert_eq!(attempts, MAX_INBOUND_ATTEMPTS);
+        let left: i64 = conn
+            .query_row("SELECT COUNT(*) FROM inbound_journal", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+
+    #[tokio::test]
+    async fn post_process_runs_on_every_send_and_edit() {
+        let pipeline = Pipeline::from_config(&crate::config::OutboundConfig {
+            post_process: vec!["redact_pii".into(), "footer".into()],
+            footer: "— lightclaw".to_string(),
+            max_chars: 0,
+        });
+        let bus = MessageBus::new().with_post_process(pipeline);
+        let mut rx = bus.subscribe_outbound();
+        let outbound = |action: OutboundAction, source: OutboundSource| OutboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            content: "mail ana@example.com".to_string(),
+            reply_to: None,
+            handle: Some("h1".to_string()),
+            action,
+            source,
+            attachments: Vec::new(),
+            mention: None,
+        };
+
+        for (action, source) in [
+            (OutboundAction::Send, OutboundSource::Tool),
+            (OutboundAction::Send, OutboundSource::Broadcast),
+            (OutboundAction::Edit, OutboundSource::Tool),
+        ] {
+            bus.publish_outbound(outbound(action, source)).await;
+            let msg = rx.recv().await.unwrap();
+            assert_eq!(msg.content, "mail [email]\n\n— lightclaw");
+        }
+        bus.publish_outbound(outbound(OutboundAction::Delete, OutboundSource::Tool))
+            .await;
+        assert_eq!(rx.recv().await.unwrap().content, "mail ana@example.com");
+    }
+
+    #[tokio::test]
+    async fn outbound_log_records_each_message_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("outbound.jsonl");
+        let bus = MessageBus::new().with_outbound_log(path.clone());
+        let outbound = |source: OutboundSource| OutboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            content: "héllo".to_string(),
+            reply_to: None,
+            handle: None,
+            action: OutboundAction::Send,
+            source,
+            attachments: Vec::new(),
+            mention: None,
+        };
+        bus.publish_outbound(outbound(OutboundSource::Reply)).await;
+        bus.publish_outbound(outbound(OutboundSource::Cron)).await;
+
+        let mut entries = Vec::new();
+        for _ in 0..100 {
+            entries = std::fs::read_to_string(&path)
+                .unwrap_or_default()
+                .lines()
+                .map(|line| serde_json::from_str::<OutboundLogEntry>(line).unwrap())
+                .collect();
+            if entries.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let sources: Vec<_> = entries.iter().map(|e| e.source.as_str()).collect();
+        assert_eq!(sources, ["reply", "cron"]);
+        assert_eq!(entries[0].chat_id, "42");
+        assert_eq!(entries[0].action, "send");
+        assert_eq!(entries[0].chars, 5);
+    }
+
+    #[tokio::test]
+    async fn chat_activity_survives_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("activity.json");
+        let bus = MessageBus::new().with_activity_file(path.clone());
+        bus.publish_inbound(inbound("hi", Vec::new())).await;
+        let seen = bus.activity().last_inbound_ms("telegram", "1").unwrap();
+
+        for _ in 0..100 {
+            if path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let restarted = SessionActivity::persisted(path);
+        assert_eq!(restarted.last_inbound_ms("telegram", "1"), Some(seen));
+        assert_eq!(restarted.last_inbound_ms("telegram", "2"), None);
+    }
+
+    #[test]
+    fn sent_messages_track_handles_and_last_per_chat() {
+        let sent = SentMessages::default();
+        sent.record("a1", "discord", "42", "1001".to_string());
+        sent.record("a1", "discord", "42", "1002".to_string());
+        sent.record("b2", "telegram", "7", "55".to_string());
+
+        let record = sent.lookup("a1").unwrap();
+        assert_eq!(record.chat_id, "42");
+        assert_eq!(record.message_ids, vec!["1001", "1002"]);
+        assert_eq!(sent.last("discord", "42").as_deref(), Some("a1"));
+        assert_eq!(sent.last("telegram", "7").as_deref(), Some("b2"));
+
+        sent.replace("a1", vec!["1001".to_string()]);
+        assert_eq!(sent.lookup("a1").unwrap().message_ids, vec!["1001"]);
+
+        sent.forget("a1");
+        assert!(sent.lookup("a1").is_none());
+        assert!(sent.last("discord", "42").is_none());
+    }
 }
@@ -1,92 +1,275 @@
-use crate::bus::{InboundMessage, MessageBus, OutboundMessage};
-use crate::config::AppConfig;
+use crate::bus::{InboundMessage, MessageBus, OutboundAction, OutboundMessage, SentMessages};
+use crate::channels::render::split_message;
+use crate::channels::{
+    deliver_per_chat, outbound_text, queue_outbound, should_show_typing, supervise,
+    FatalChannelError, RateLimiter, SenderAllowlist,
+};
+use crate::config::{AllowMatchMode, AppConfig, BotConfig, GuildResponseMode, ReconnectPolicy};
 use anyhow::{anyhow, Result};
 use serenity::async_trait;
+use serenity::builder::{CreateAttachment, CreateMessage, EditMessage};
+use serenity::gateway::GatewayError;
 use serenity::http::Http;
 use serenity::model::channel::Message as DiscordMessage;
 use serenity::model::gateway::Ready;
-use serenity::model::id::ChannelId;
+use serenity::model::id::{ChannelId, MessageId};
 use serenity::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{info, warn};
+use tracing::{debug, info, warn};
 
 const DISCORD_MESSAGE_LIMIT: usize = 2000;
 
 pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
+    let mut bots = Vec::new();
     let token = cfg.channels.discord.bot_token.trim().to_string();
-    if token.is_empty() {
+    if !token.is_empty() {
+        bots.push((token, DiscordHandler::new(&cfg, bus.clone())));
+    }
+    for bot in &cfg.channels.discord.bots {
+        let handler = DiscordHandler::for_bot(&cfg, bot, bus.clone());
+        bots.push((bot.bot_token.trim().to_string(), handler));
+    }
+    if bots.is_empty() {
         return Err(anyhow!("discord token is missing"));
     }
+    let policy = cfg.channels.reconnect;
+    if bots.len() == 1 {
+        let (token, handler) = bots.remove(0);
+        return run_client(token, handler, bus, policy).await;
+    }
 
+    let mut clients = tokio::task::JoinSet::new();
+    for (token, handler) in bots {
+        let bus = bus.clone();
+        clients.spawn(async move {
+            let channel = handler.channel.clone();
+            if let Err(err) = run_client(token, handler, bus, policy).await {
+                warn!("{channel} disabled: {err}");
+            }
+        });
+    }
+    while clients.join_next().await.is_some() {}
+    Ok(())
+}
+
+async fn run_client(
+    token: String,
+    handler: DiscordHandler,
+    bus: MessageBus,
+    policy: ReconnectPolicy,
+) -> Result<()> {
+    let channel = handler.channel.clone();
+    // One forwarder for the life of the bot: messages published while the
+    // gateway reconnects wait in its queue and go out over plain HTTP.
+    let forwarder = spawn_outbound_forwarder(
+        Arc::new(Http::new(&token)),
+        channel.clone(),
+        queue_outbound(channel.clone(), bus.subscribe_outbound()),
+        bus.sent_messages(),
+    );
+    let result = supervise(&channel, policy, || connect(token.clone(), handler.clone())).await;
+    forwarder.abort();
+    result
+}
+
+/// One gateway session: build the client and dispatch events until the
+/// connection ends.
+async fn connect(token: String, handler: DiscordHandler) -> Result<()> {
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
-    let handler = DiscordHandler::new(&cfg, bus.clone());
+    let channel = handler.channel.clone();
     let mut client = Client::builder(token, intents)
         .event_handler(handler)
         .await
-        .map_err(|err| anyhow!("discord client initialization failed: {err}"))?;
+        .map_err(|err| {
+            FatalChannelError(format!("{channel} client initialization failed: {err}"))
+        })?;
 
-    spawn_outbound_forwarder(client.http.clone(), bus.subscribe_outbound());
-
-    client
-        .start()
-        .await
-        .map_err(|err| anyhow!("discord runtime error: {err}"))?;
-    Ok(())
+    match client.start().await {
+        Ok(()) => Ok(()),
+        Err(serenity::Error::Gateway(
+            err @ (GatewayError::InvalidAuthentication
+            | GatewayError::InvalidGatewayIntents
+            | GatewayError::DisallowedGatewayIntents),
+        )) => Err(FatalChannelError(format!("{channel} runtime error: {err}")).into()),
+        Err(err) => Err(anyhow!("{channel} runtime error: {err}")),
+    }
 }
 
+#[derive(Clone)]
 struct DiscordHandler {
     bus: MessageBus,
+    /// Bus channel for this bot's traffic (`discord` or `discord.<name>`).
+    channel: String,
     allowed_channels: HashSet<u64>,
-    allow_from: Vec<String>,
+    allow_from: SenderAllowlist,
+    /// Guild members holding any of these roles pass even when `allow_from`
+    /// doesn't match them.
+    allowed_roles: HashSet<u64>,
+    guild_response_mode: GuildResponseMode,
+    guild_prefix: String,
+    /// Per-guild overrides of `guild_prefix`, keyed by guild id.
+    guild_prefixes: HashMap<u64, String>,
+    show_typing: bool,
 }
 
 impl DiscordHandler {
     fn new(cfg: &AppConfig, bus: MessageBus) -> Self {
-        let allowed_channels = cfg
-            .channels
-            .discord
-            .allowed_channels
-            .iter()
-            .filter_map(|raw| raw.trim().parse::<u64>().ok())
-            .collect::<HashSet<_>>();
-        let allow_from = cfg
-            .channels
-            .discord
-            .allow_from
+        let discord = &cfg.channels.discord;
+        Self::with_lists(
+            cfg,
+            bus,
+            "discord".to_string(),
+            &discord.allowed_channels,
+            &discord.allow_from,
+            &discord.match_mode,
+            &discord.allowed_roles,
+        )
+    }
+
+    /// Handler for an extra bot: its own allowlists, shared response rules.
+    fn for_bot(cfg: &AppConfig, bot: &BotConfig, bus: MessageBus) -> Self {
+        Self::with_lists(
+            cfg,
+            bus,
+            format!("discord.{}", bot.name),
+            &bot.allowed_channels,
+            &bot.allow_from,
+            &bot.match_mode,
+            &bot.allowed_roles,
+        )
+    }
+
+    fn with_lists(
+        cfg: &AppConfig,
+        bus: MessageBus,
+        channel: String,
+        allowed_channels: &[String],
+        allow_from: &[String],
+        match_mode: &AllowMatchMode,
+        allowed_roles: &[String],
+    ) -> Self {
+        let allowed_channels = parse_ids(allowed_channels);
+        let allowed_roles = parse_ids(allowed_roles);
+        let allow_from = allow_from
             .iter()
-            .map(|entry| entry.trim().to_ascii_lowercase())
+            .map(|entry| match match_mode {
+                AllowMatchMode::Exact => entry.trim().to_ascii_lowercase(),
+                // Patterns already ignore case; lowercasing would break `\D`.
+                AllowMatchMode::Regex => entry.trim().to_string(),
+            })
             .filter(|entry| !entry.is_empty())
             .collect::<Vec<_>>();
+        let allow_from = SenderAllowlist::new(allow_from, match_mode);
         Self {
             bus,
+            channel,
             allowed_channels,
             allow_from,
+            allowed_roles,
+            guild_response_mode: cfg.channels.discord.guild_response_mode.clone(),
+            guild_prefix: cfg.channels.discord.guild_prefix.clone(),
+            guild_prefixes: cfg
+                .channels
+                .discord
+                .guild_prefixes
+                .iter()
+                .filter_map(|(guild, prefix)| Some((guild.parse().ok()?, prefix.clone())))
+                .collect(),
+            show_typing: cfg.channels.discord.show_typing,
         }
     }
 
-    fn is_channel_allowed(&self, msg: &DiscordMessage) -> bool {
-        if self.allowed_channels.is_empty() || msg.guild_id.is_none() {
+    fn is_sender_allowed(&self, msg: &DiscordMessage) -> bool {
+        let roles = msg
+            .member
+            .as_ref()
+            .map(|member| {
+                member
+                    .roles
+                    .iter()
+                    .map(|role| role.get())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.sender_allowed(msg.author.id.get(), &msg.author.name, &roles)
+    }
+
+    /// Open when neither `allow_from` nor `allowed_roles` is set; otherwise
+    /// the sender must match `allow_from` or hold one of the roles.
+    fn sender_allowed(&self, user_id: u64, username: &str, roles: &[u64]) -> bool {
+        if self.allow_from.is_empty() && self.allowed_roles.is_empty() {
             return true;
         }
-        self.allowed_channels.contains(&msg.channel_id.get())
+        let uid = user_id.to_string();
+        let uname = username.to_ascii_lowercase();
+        let (at_name, mention) = (format!("@{uname}"), format!("<@{uid}>"));
+        self.allow_from
+            .matches(&[uid.as_str(), uname.as_str(), &at_name, &mention])
+            || roles.iter().any(|role| self.allowed_roles.contains(role))
     }
 
-    fn is_sender_allowed(&self, msg: &DiscordMessage) -> bool {
-        if self.allow_from.is_empty() {
+    fn is_channel_allowed(&self, in_guild: bool, channel_id: u64) -> bool {
+        if self.allowed_channels.is_empty() || !in_guild {
             return true;
         }
-        let uid = msg.author.id.get().to_string();
-        let uname = msg.author.name.to_ascii_lowercase();
-        let mention = format!("<@{}>", msg.author.id.get());
-        self.allow_from.iter().any(|allowed| {
-            allowed == &uid
-                || allowed == &uname
-                || allowed == &format!("@{uname}")
-                || allowed == &mention
+        self.allowed_channels.contains(&channel_id)
+    }
+
+    /// The `prefix`-mode trigger in `guild_id`.
+    fn prefix_for(&self, guild_id: u64) -> &str {
+        self.guild_prefixes
+            .get(&guild_id)
+            .unwrap_or(&self.guild_prefix)
+    }
+
+    /// Content to hand to the agent for a message in `guild_id`, or `None`
+    /// when the configured response mode says to stay quiet.
+    fn guild_content(&self, guild_id: u64, text: &str, mentioned: bool) -> Option<String> {
+        match self.guild_response_mode {
+            GuildResponseMode::All => Some(text.to_string()),
+            GuildResponseMode::Mention => mentioned.then(|| text.to_string()),
+            GuildResponseMode::Prefix => match text.strip_prefix(self.prefix_for(guild_id)) {
+                Some(rest) if !rest.trim().is_empty() => Some(rest.trim().to_string()),
+                _ => mentioned.then(|| text.to_string()),
+            },
+        }
+    }
+
+    /// Apply channel and response-mode rules; `None` means the message is
+    /// not for the bot.
+    fn inbound_for(
+        &self,
+        guild_id: Option<u64>,
+        channel_id: u64,
+        sender_id: u64,
+        message_id: u64,
+        mentioned: bool,
+        text: &str,
+    ) -> Option<InboundMessage> {
+        if !self.is_channel_allowed(guild_id.is_some(), channel_id) {
+            return None;
+        }
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let content = match guild_id {
+            Some(guild_id) => self.guild_content(guild_id, text, mentioned)?,
+            None => text.to_string(),
+        };
+        Some(InboundMessage {
+            channel: self.channel.clone(),
+            chat_id: channel_id.to_string(),
+            sender_id: sender_id.to_string(),
+            content,
+            message_id: Some(message_id.to_string()),
+            mention: None,
+            attachments: Vec::new(),
         })
     }
 }
@@ -97,101 +280,452 @@ impl EventHandler for DiscordHandler {
         if msg.author.bot {
             return;
         }
-        if !self.is_channel_allowed(&msg) || !self.is_sender_allowed(&msg) {
-            return;
-        }
-
-        let text = msg.content.trim().to_string();
-        if text.is_empty() {
+        if !self.is_sender_allowed(&msg) {
             return;
         }
 
-        if msg.guild_id.is_some() {
+        let guild_id = msg.guild_id.map(|id| id.get());
+        let mentioned = guild_id.is_some() && {
             let bot_id = ctx.cache.current_user().id;
-            let mentioned = msg.mentions.iter().any(|user| user.id == bot_id);
-            if !mentioned {
-                return;
-            }
-        }
-
-        let _typing = msg.channel_id.start_typing(&ctx.http);
+            msg.mentions.iter().any(|user| user.id == bot_id)
+        };
+        let Some(inbound) = self.inbound_for(
+            guild_id,
+            msg.channel_id.get(),
+            msg.author.id.get(),
+            msg.id.get(),
+            mentioned,
+            &msg.content,
+        ) else {
+            return;
+        };
 
-        self.bus
-            .publish_inbound(InboundMessage {
-                channel: "discord".to_string(),
-                chat_id: msg.channel_id.get().to_string(),
-                sender_id: msg.author.id.get().to_string(),
-                content: text,
-            })
-            .await;
+        let _typing = should_show_typing(self.show_typing, &inbound.sender_id)
+            .then(|| msg.channel_id.start_typing(&ctx.http));
+        self.bus.publish_inbound(inbound).await;
     }
 
     async fn ready(&self, _ctx: Context, ready: Ready) {
-        info!("discord connected as {}", ready.user.name);
+        info!("{} connected as {}", self.channel, ready.user.name);
     }
 }
 
 fn spawn_outbound_forwarder(
     http: Arc<Http>,
-    mut rx: tokio::sync::broadcast::Receiver<OutboundMessage>,
-) {
+    channel: String,
+    queue: tokio::sync::mpsc::Receiver<OutboundMessage>,
+    sent: SentMessages,
+) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        loop {
-            let msg = match rx.recv().await {
-                Ok(msg) => msg,
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                    info!("outbound channel closed, discord forwarder shutting down");
-                    break;
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                    warn!("discord outbound lagged, skipped {skipped} message(s)");
-                    continue;
+        let limiter = Arc::new(RateLimiter::discord());
+        let forwarder = channel.clone();
+        deliver_per_chat(&channel, queue, move |msg| {
+            let (http, channel, limiter, sent) = (
+                http.clone(),
+                forwarder.clone(),
+                limiter.clone(),
+                sent.clone(),
+            );
+            async move { deliver_outbound(&http, &channel, &limiter, &sent, msg).await }
+        })
+        .await;
+        info!("outbound channel closed, {channel} forwarder shutting down");
+    })
+}
+
+/// Send, edit or delete one outbound message, pacing each API call by the
+/// channel's rate limit.
+async fn deliver_outbound(
+    http: &Http,
+    channel: &str,
+    limiter: &RateLimiter,
+    sent: &SentMessages,
+    msg: OutboundMessage,
+) {
+    let Ok(raw_channel_id) = msg.chat_id.parse::<u64>() else {
+        warn!("invalid discord chat_id: {}", msg.chat_id);
+        return;
+    };
+
+    let channel_id = ChannelId::new(raw_channel_id);
+    match msg.action {
+        OutboundAction::Send => {
+            debug!(
+                "discord sending {} message to channel {}",
+                msg.source.as_str(),
+                msg.chat_id
+            );
+            let reply_to = msg
+                .reply_to
+                .as_deref()
+                .and_then(|raw| raw.parse::<u64>().ok())
+                .filter(|id| *id != 0)
+                .map(MessageId::new);
+            let mut ids = Vec::new();
+            let result = send_discord_message(
+                http,
+                limiter,
+                channel_id,
+                &outbound_text(&msg),
+                reply_to,
+                &msg.attachments,
+                &mut ids,
+            )
+            .await;
+            // Keep what did go out, so a later edit or delete reaches it.
+            if let Some(handle) = &msg.handle {
+                for id in ids {
+                    sent.record(handle, channel, &msg.chat_id, id.get().to_string());
                 }
+            }
+            if let Err(err) = result {
+                warn!(
+                    "discord send failed for {} message to channel {}: {err}",
+                    msg.source.as_str(),
+                    msg.chat_id
+                )
+            }
+        }
+        OutboundAction::Edit | OutboundAction::Delete => {
+            let Some(handle) = msg.handle.as_deref() else {
+                return;
             };
-
-            if msg.channel != "discord" {
-                continue;
+            let Some(record) = sent.lookup(handle) else {
+                warn!(
+                    "discord {:?} for unknown message handle {handle}",
+                    msg.action
+                );
+                return;
+            };
+            let mut ids: Vec<MessageId> = record
+                .message_ids
+                .iter()
+                .filter_map(|id| id.parse::<u64>().ok())
+                .filter(|id| *id != 0)
+                .map(MessageId::new)
+                .collect();
+            let content = match msg.action {
+                OutboundAction::Edit => Some(msg.content.as_str()),
+                _ => None,
+            };
+            if let Err(err) = apply_change(http, limiter, channel_id, &mut ids, content).await {
+                warn!(
+                    "discord {:?} failed for channel {}: {err}",
+                    msg.action, msg.chat_id
+                );
             }
+            if ids.is_empty() {
+                sent.forget(handle);
+            } else {
+                sent.replace(handle, ids.iter().map(|id| id.get().to_string()).collect());
+            }
+        }
+    }
+}
 
-            let Ok(raw_channel_id) = msg.chat_id.parse::<u64>() else {
-                warn!("invalid discord chat_id: {}", msg.chat_id);
-                continue;
-            };
+/// One API call in rewriting a sent reply.
+#[derive(Debug, PartialEq)]
+enum Change {
+    Edit(MessageId, String),
+    Send(String),
+    Delete(MessageId),
+}
+
+/// Calls that turn the reply made of `ids` into `content`, split like a fresh
+/// send: existing messages are edited in place, extra chunks follow them and
+/// leftover messages are deleted. No content deletes the whole reply.
+fn plan_change(ids: &[MessageId], content: Option<&str>) -> Vec<Change> {
+    let chunks = content.map_or_else(Vec::new, |text| split_message(text, DISCORD_MESSAGE_LIMIT));
+    let mut changes = Vec::new();
+    for (idx, chunk) in chunks.iter().enumerate() {
+        changes.push(match ids.get(idx) {
+            Some(id) => Change::Edit(*id, chunk.clone()),
+            None => Change::Send(chunk.clone()),
+        });
+    }
+    changes.extend(ids.iter().skip(chunks.len()).map(|id| Change::Delete(*id)));
+    changes
+}
 
-            if let Err(err) =
-                send_discord_message(&http, ChannelId::new(raw_channel_id), &msg.content).await
-            {
-                warn!("discord send failed for channel {}: {err}", msg.chat_id);
+/// Edit or delete a previously sent reply, keeping `ids` in step with the
+/// messages that still make it up, so a call that fails part way leaves an
+/// accurate record for the next attempt.
+async fn apply_change(
+    http: &Http,
+    limiter: &RateLimiter,
+    channel_id: ChannelId,
+    ids: &mut Vec<MessageId>,
+    content: Option<&str>,
+) -> serenity::Result<()> {
+    let chat_id = channel_id.get().to_string();
+    for change in plan_change(ids, content) {
+        limiter.acquire(&chat_id).await;
+        match change {
+            Change::Edit(id, text) => {
+                channel_id
+                    .edit_message(http, id, EditMessage::new().content(text))
+                    .await?;
+            }
+            Change::Send(text) => {
+                let id = channel_id
+                    .send_message(http, CreateMessage::new().content(text))
+                    .await?
+                    .id;
+                ids.push(id);
+            }
+            Change::Delete(id) => {
+                channel_id.delete_message(http, id).await?;
+                ids.retain(|kept| *kept != id);
             }
         }
-    });
+    }
+    Ok(())
 }
 
+/// Send a reply and its attachments, pushing each delivered message onto
+/// `sent` as it goes so a failure part way still reports what went out.
 async fn send_discord_message(
     http: &Http,
+    limiter: &RateLimiter,
     channel_id: ChannelId,
     text: &str,
+    reply_to: Option<MessageId>,
+    attachments: &[PathBuf],
+    sent: &mut Vec<MessageId>,
 ) -> serenity::Result<()> {
-    if text.len() <= DISCORD_MESSAGE_LIMIT {
-        channel_id.say(http, text).await?;
-        return Ok(());
-    }
-
-    let mut remaining = text;
-    while !remaining.is_empty() {
-        let chunk_len = if remaining.len() <= DISCORD_MESSAGE_LIMIT {
-            remaining.len()
-        } else {
-            remaining[..DISCORD_MESSAGE_LIMIT]
-                .rfind('\n')
-                .unwrap_or(DISCORD_MESSAGE_LIMIT)
-        };
-        let chunk = &remaining[..chunk_len];
-        channel_id.say(http, chunk).await?;
-        remaining = &remaining[chunk_len..];
-        if remaining.starts_with('\n') {
-            remaining = &remaining[1..];
+    let chat_id = channel_id.get().to_string();
+    if !text.trim().is_empty() || attachments.is_empty() {
+        for message in build_messages(channel_id, text, reply_to) {
+            limiter.acquire(&chat_id).await;
+            sent.push(channel_id.send_message(http, message).await?.id);
         }
     }
+    for path in attachments {
+        let file = CreateAttachment::path(path).await?;
+        limiter.acquire(&chat_id).await;
+        sent.push(
+            channel_id
+                .send_message(http, CreateMessage::new().add_file(file))
+                .await?
+                .id,
+        );
+    }
     Ok(())
 }
+
+/// Split `text` into Discord-sized messages. Only the first one references
+/// `reply_to`, so a long answer threads under the user's message once.
+fn build_messages(
+    channel_id: ChannelId,
+    text: &str,
+    reply_to: Option<MessageId>,
+) -> Vec<CreateMessage> {
+    split_message(text, DISCORD_MESSAGE_LIMIT)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, chunk)| {
+            let message = CreateMessage::new().content(chunk);
+            match reply_to {
+                Some(id) if idx == 0 => message.reference_message((channel_id, id)),
+                _ => message,
+            }
+        })
+        .collect()
+}
+
+fn parse_ids(raw: &[String]) -> HashSet<u64> {
+    raw.iter()
+        .filter_map(|id| id.trim().parse::<u64>().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn handler(mode: GuildResponseMode, bus: MessageBus) -> DiscordHandler {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.discord.allowed_channels = vec!["42".to_string()];
+        cfg.channels.discord.guild_response_mode = mode;
+        DiscordHandler::new(&cfg, bus)
+    }
+
+    #[tokio::test]
+    async fn all_mode_publishes_guild_messages_without_mention() {
+        let bus = MessageBus::new();
+        let handler = handler(GuildResponseMode::All, bus.clone());
+
+        let inbound = handler
+            .inbound_for(Some(1), 42, 7, 1001, false, "hello there")
+            .expect("all mode accepts unmentioned guild messages");
+        handler.bus.publish_inbound(inbound).await;
+        let (inbound, _) = tokio::time::timeout(Duration::from_secs(1), bus.consume_inbound())
+            .await
+            .expect("inbound published")
+            .expect("bus open");
+        assert_eq!(inbound.chat_id, "42");
+        assert_eq!(inbound.sender_id, "7");
+        assert_eq!(inbound.content, "hello there");
+
+        assert!(handler
+            .inbound_for(Some(1), 99, 7, 1002, false, "wrong channel")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn extra_bots_tag_inbound_and_keep_their_own_allowlists() {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.discord.bot_token = "primary-token".to_string();
+        cfg.channels.discord.allowed_channels = vec!["42".to_string()];
+        cfg.channels.discord.guild_response_mode = GuildResponseMode::All;
+        let work = BotConfig {
+            name: "work".to_string(),
+            bot_token: "work-token".to_string(),
+            allow_from: vec!["Alice".to_string()],
+            match_mode: AllowMatchMode::Exact,
+            allowed_channels: vec!["77".to_string()],
+            allowed_roles: Vec::new(),
+            persona: None,
+        };
+        let primary = DiscordHandler::new(&cfg, MessageBus::new());
+        let extra = DiscordHandler::for_bot(&cfg, &work, MessageBus::new());
+
+        let inbound = primary.inbound_for(Some(1), 42, 7, 1, false, "hi").unwrap();
+        assert_eq!(inbound.channel, "discord");
+        assert!(primary
+            .inbound_for(Some(1), 77, 7, 2, false, "hi")
+            .is_none());
+        assert!(primary.allow_from.is_empty());
+
+        let inbound = extra.inbound_for(Some(1), 77, 7, 3, false, "hi").unwrap();
+        assert_eq!(inbound.channel, "discord.work");
+        assert!(extra.inbound_for(Some(1), 42, 7, 4, false, "hi").is_none());
+        assert_eq!(extra.allow_from.entries(), ["alice".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn allowed_roles_admit_members_outside_allow_from() {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.discord.allow_from = vec!["alice".to_string()];
+        cfg.channels.discord.allowed_roles = vec!["500".to_string()];
+        let handler = DiscordHandler::new(&cfg, MessageBus::new());
+
+        assert!(handler.sender_allowed(1, "Alice", &[]));
+        assert!(handler.sender_allowed(2, "bob", &[400, 500]));
+        assert!(!handler.sender_allowed(2, "bob", &[400]));
+        // DMs carry no member, so roles can't help there.
+        assert!(!handler.sender_allowed(2, "bob", &[]));
+
+        cfg.channels.discord.allow_from.clear();
+        let roles_only = DiscordHandler::new(&cfg, MessageBus::new());
+        assert!(roles_only.sender_allowed(3, "carol", &[500]));
+        assert!(!roles_only.sender_allowed(3, "carol", &[]));
+    }
+
+    #[tokio::test]
+    async fn regex_allow_from_matches_discord_usernames() {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.discord.allow_from = vec![r"mod_\w+".to_string()];
+        cfg.channels.discord.match_mode = AllowMatchMode::Regex;
+        let handler = DiscordHandler::new(&cfg, MessageBus::new());
+
+        assert!(handler.sender_allowed(1, "Mod_Dana", &[]));
+        assert!(!handler.sender_allowed(1, "dana", &[]));
+    }
+
+    #[tokio::test]
+    async fn mention_and_prefix_modes_filter_guild_messages() {
+        let mention = handler(GuildResponseMode::Mention, MessageBus::new());
+        assert_eq!(mention.guild_content(1, "hi", false), None);
+        assert_eq!(mention.guild_content(1, "hi", true).as_deref(), Some("hi"));
+
+        let prefix = handler(GuildResponseMode::Prefix, MessageBus::new());
+        assert_eq!(
+            prefix.guild_content(1, "! status", false).as_deref(),
+            Some("status")
+        );
+        assert_eq!(prefix.guild_content(1, "status", false), None);
+        assert_eq!(prefix.guild_content(1, "!", false), None);
+        assert!(prefix.guild_content(1, "status", true).is_some());
+    }
+
+    #[tokio::test]
+    async fn guild_prefixes_are_looked_up_by_guild_id() {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.discord.guild_response_mode = GuildResponseMode::Prefix;
+        cfg.channels.discord.guild_prefixes = HashMap::from([("900".to_string(), "?".to_string())]);
+        let handler = DiscordHandler::new(&cfg, MessageBus::new());
+
+        // Channel 42 sits in guild 900, which has its own prefix.
+        let inbound = handler
+            .inbound_for(Some(900), 42, 7, 1, false, "?weather")
+            .expect("guild prefix");
+        assert_eq!(inbound.content, "weather");
+        assert_eq!(inbound.chat_id, "42");
+        assert!(handler
+            .inbound_for(Some(900), 42, 7, 2, false, "!weather")
+            .is_none());
+        // Other guilds keep the default.
+        assert!(handler
+            .inbound_for(Some(901), 42, 7, 3, false, "!weather")
+            .is_some());
+        assert!(handler
+            .inbound_for(Some(901), 42, 7, 4, false, "?weather")
+            .is_none());
+    }
+
+    #[test]
+    fn forwarder_threads_first_chunk_under_reply_to() {
+        let channel = ChannelId::new(42);
+        let reply_to = MessageId::new(555);
+
+        let messages = build_messages(channel, "short answer", Some(reply_to));
+        assert_eq!(messages.len(), 1);
+        let json = serde_json::to_value(&messages[0]).unwrap();
+        assert_eq!(
+            json["message_reference"]["message_id"],
+            serde_json::to_value(reply_to).unwrap()
+        );
+
+        let long = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+        let messages = build_messages(channel, &long, Some(reply_to));
+        assert_eq!(messages.len(), 2);
+        let second = serde_json::to_value(&messages[1]).unwrap();
+        assert!(second
+            .get("message_reference")
+            .map_or(true, |v| v.is_null()));
+
+        let plain = serde_json::to_value(&build_messages(channel, "hi", None)[0]).unwrap();
+        assert!(plain["message_reference"].is_null());
+    }
+
+    #[test]
+    fn edits_split_long_text_across_the_reply() {
+        let ids = [MessageId::new(1), MessageId::new(2), MessageId::new(3)];
+        let long = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+
+        // Two chunks over a three-message reply: both edited, the third goes.
+        assert_eq!(
+            plan_change(&ids, Some(&long)),
+            vec![
+                Change::Edit(ids[0], "a".repeat(1500)),
+                Change::Edit(ids[1], "b".repeat(1500)),
+                Change::Delete(ids[2]),
+            ]
+        );
+        // A longer rewrite of a one-message reply sends the extra chunk.
+        assert_eq!(
+            plan_change(&ids[..1], Some(&long)),
+            vec![
+                Change::Edit(ids[0], "a".repeat(1500)),
+                Change::Send("b".repeat(1500)),
+            ]
+        );
+        // A delete removes every message.
+        assert_eq!(
+            plan_change(&ids, None),
+            ids.iter().map(|id| Change::Delete(*id)).collect::<Vec<_>>()
+        );
+    }
+}
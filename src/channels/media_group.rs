@@ -0,0 +1,235 @@
+use crate::bus::{InboundMessage, MessageBus};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long an album waits for its next part. Telegram sends the parts of a
+/// media group as separate updates, usually well within a second.
+pub(crate) const MEDIA_GROUP_WAIT: Duration = Duration::from_millis(1500);
+
+/// Collects the parts of a media group (a Telegram album) into one inbound
+/// message, published once no new part has arrived for the wait. Parts
+/// reserve their place before downloading, so an album over the attachment
+/// cap never downloads the extras and a slow download isn't left behind.
+#[derive(Clone)]
+pub(crate) struct MediaGroups {
+    wait: Duration,
+    max_attachments: usize,
+    pending: Arc<DashMap<String, Album>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+struct Album {
+    /// Sequence number of the latest settled part; only its timer publishes.
+    last: u64,
+    /// Parts in arrival order.
+    parts: Vec<Part>,
+    /// Parts turned away because the album was already full.
+    dropped: usize,
+}
+
+enum Part {
+    Downloading,
+    Ready(InboundMessage),
+    Failed,
+}
+
+/// A part's place in its album, held while the part downloads.
+pub(crate) struct AlbumSlot {
+    key: String,
+    index: usize,
+}
+
+impl MediaGroups {
+    /// `max_attachments` of 0 keeps every part.
+    pub(crate) fn new(wait: Duration, max_attachments: usize) -> Self {
+        Self {
+            wait,
+            max_attachments,
+            pending: Arc::new(DashMap::new()),
+            next_seq: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Reserve a place for the next part of group `group_id` before
+    /// downloading it. `None` once the album already holds
+    /// `max_attachments` parts: the part is skipped and noted in the text.
+    pub(crate) fn reserve(
+        &self,
+        channel: &str,
+        chat_id: &str,
+        group_id: &str,
+    ) -> Option<AlbumSlot> {
+        let key = format!("{channel}:{chat_id}:{group_id}");
+        let mut album = self.pending.entry(key.clone()).or_insert_with(|| Album {
+            last: 0,
+            parts: Vec::new(),
+            dropped: 0,
+        });
+        if self.max_attachments > 0 && album.parts.len() >= self.max_attachments {
+            album.dropped += 1;
+            return None;
+        }
+        album.parts.push(Part::Downloading);
+        let index = album.parts.len() - 1;
+        Some(AlbumSlot { key, index })
+    }
+
+    /// Fill `slot` with its downloaded part; the assembled message goes to
+    /// `bus` after the wait.
+    pub(crate) fn fill(&self, slot: AlbumSlot, part: InboundMessage, bus: MessageBus) {
+        self.settle(slot, Part::Ready(part), bus);
+    }
+
+    /// Give up on `slot` (the download failed or was refused) so the rest of
+    /// the album still goes out.
+    pub(crate) fn abandon(&self, slot: AlbumSlot, bus: MessageBus) {
+        self.settle(slot, Part::Failed, bus);
+    }
+
+    fn settle(&self, slot: AlbumSlot, part: Part, bus: MessageBus) {
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.pending.get_mut(&slot.key) {
+            Some(mut album) => {
+                album.parts[slot.index] = part;
+                album.last = seq;
+            }
+            None => return,
+        }
+
+        let this = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(this.wait).await;
+            // Wait for parts still downloading; the last of them to settle
+            // publishes.
+            let Some((_, album)) = this.pending.remove_if(&slot.key, |_, album| {
+                album.last == seq
+                    && !album
+                        .parts
+                        .iter()
+                        .any(|part| matches!(part, Part::Downloading))
+            }) else {
+                return;
+            };
+            if let Some(message) = assemble(album, this.max_attachments) {
+                bus.publish_inbound(message).await;
+            }
+        });
+    }
+}
+
+/// Fold the album's downloaded parts into one message, noting any that were
+/// left out so the model doesn't assume it saw them all.
+fn assemble(album: Album, max: usize) -> Option<InboundMessage> {
+    let mut parts = album.parts.into_iter().filter_map(|part| match part {
+        Part::Ready(message) => Some(message),
+        Part::Downloading | Part::Failed => None,
+    });
+    let mut message = parts.next()?;
+    for part in parts {
+        append_part(&mut message, part);
+    }
+    if album.dropped > 0 {
+        if !message.content.is_empty() {
+            message.content.push_str("\n\n");
+        }
+        message.content.push_str(&format!(
+            "[{} more attachment(s) not included; at most {max} per message]",
+            album.dropped
+        ));
+    }
+    Some(message)
+}
+
+/// Fold `part` into `message`: captions join in arrival order and the
+/// attachments are appended. The first part's message id is kept as the
+/// one to reply to.
+fn append_part(message: &mut InboundMessage, part: InboundMessage) {
+    if !part.content.trim().is_empty() {
+        if !message.content.trim().is_empty() {
+            message.content.push('\n');
+        }
+        message.content.push_str(&part.content);
+    }
+    message.attachments.extend(part.attachments);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Attachment;
+    use std::path::PathBuf;
+
+    fn photo(id: &str, caption: &str) -> InboundMessage {
+        InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            sender_id: "7".to_string(),
+            content: caption.to_string(),
+            message_id: Some(id.to_string()),
+            mention: None,
+            attachments: vec![Attachment::Image {
+                path: PathBuf::from(format!("/ws/inbox/{id}.jpg")),
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn album_parts_are_assembled_into_one_capped_message() {
+        let bus = MessageBus::new();
+        let groups = MediaGroups::new(Duration::from_millis(100), 3);
+
+        let first = groups.reserve("telegram", "42", "album").expect("room");
+        let second = groups.reserve("telegram", "42", "album").expect("room");
+        let third = groups.reserve("telegram", "42", "album").expect("room");
+        // Over the cap: refused before anything is downloaded.
+        assert!(groups.reserve("telegram", "42", "album").is_none());
+
+        // Downloads finish out of order, and one is slower than the wait;
+        // the album still goes out once, in arrival order.
+        groups.fill(second, photo("2", ""), bus.clone());
+        groups.fill(first, photo("1", "which of these is best?"), bus.clone());
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        groups.fill(third, photo("3", ""), bus.clone());
+
+        // A different album in the same chat stays separate.
+        let other = groups.reserve("telegram", "42", "other").expect("room");
+        groups.fill(other, photo("9", "and this one"), bus.clone());
+        let failed = groups.reserve("telegram", "42", "other").expect("room");
+        groups.abandon(failed, bus.clone());
+
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let (msg, _) = tokio::time::timeout(Duration::from_secs(2), bus.consume_inbound())
+                .await
+                .unwrap()
+                .unwrap();
+            received.push(msg);
+        }
+        let album = received
+            .iter()
+            .find(|msg| msg.message_id.as_deref() == Some("1"))
+            .expect("album delivered");
+        assert_eq!(album.attachments.len(), 3);
+        assert_eq!(
+            album.content,
+            "which of these is best?\n\n[1 more attachment(s) not included; at most 3 per message]"
+        );
+        assert!(matches!(
+            &album.attachments[0],
+            Attachment::Image { path } if path.ends_with("1.jpg")
+        ));
+        assert!(matches!(
+            &album.attachments[2],
+            Attachment::Image { path } if path.ends_with("3.jpg")
+        ));
+
+        let other = received
+            .iter()
+            .find(|msg| msg.message_id.as_deref() == Some("9"))
+            .expect("second album delivered");
+        assert_eq!(other.attachments.len(), 1);
+        assert_eq!(other.content, "and this one");
+    }
+}
@@ -1,2 +1,710 @@
 pub mod discord;
+mod media_group;
+mod render;
 pub mod telegram;
+mod telegram_webhook;
+
+use crate::bus::OutboundMessage;
+use crate::config::{AllowMatchMode, AttachmentLimits, ReconnectPolicy};
+use dashmap::DashMap;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Platform behind a bus channel. Extra bots use `<platform>.<name>`, so
+/// `telegram.work` is still Telegram.
+pub fn platform(channel: &str) -> &str {
+    channel
+        .split_once('.')
+        .map_or(channel, |(platform, _)| platform)
+}
+
+/// Platform markup that pings `user_id`, for channels that support it.
+pub fn mention_token(channel: &str, user_id: &str) -> Option<String> {
+    let user_id = user_id.trim();
+    if user_id.is_empty() || !user_id.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    match platform(channel) {
+        "discord" => Some(format!("<@{user_id}>")),
+        // Rendered as a MarkdownV2 inline mention by the Telegram forwarder.
+        "telegram" => Some(format!("[@user](tg://user?id={user_id})")),
+        _ => None,
+    }
+}
+
+/// Text a forwarder delivers for a `Send`: the content, led by the
+/// platform's mention markup when the message pings someone.
+pub(crate) fn outbound_text(msg: &OutboundMessage) -> String {
+    match msg
+        .mention
+        .as_deref()
+        .and_then(|user| mention_token(&msg.channel, user))
+    {
+        Some(token) => format!("{token} {}", msg.content).trim_end().to_string(),
+        None => msg.content.clone(),
+    }
+}
+
+/// Where a chat's received files wait for its turn: a folder of its own
+/// under the workspace inbox, emptied once the turn is done.
+pub(crate) fn inbox_dir(workspace_dir: &Path, channel: &str, chat_id: &str) -> PathBuf {
+    let folder: String = format!("{channel}_{chat_id}")
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    workspace_dir.join("inbox").join(folder)
+}
+
+/// Whether to show a typing indicator for a turn from `sender_id`. Scheduled
+/// (cron) turns never type: nobody is waiting on a reply.
+pub fn should_show_typing(show_typing: bool, sender_id: &str) -> bool {
+    show_typing && sender_id != "cron"
+}
+
+/// A channel's `allow_from` list, compiled for its `match_mode`.
+#[derive(Clone, Debug)]
+pub(crate) struct SenderAllowlist {
+    entries: Vec<String>,
+    /// Compiled entries in regex mode; empty in exact mode.
+    patterns: Vec<Regex>,
+    mode: AllowMatchMode,
+}
+
+impl SenderAllowlist {
+    pub(crate) fn new(entries: Vec<String>, mode: &AllowMatchMode) -> Self {
+        let patterns = match mode {
+            AllowMatchMode::Exact => Vec::new(),
+            AllowMatchMode::Regex => entries
+                .iter()
+                .filter_map(
+                    |entry| match Regex::new(&format!("(?i)^(?:{})$", entry.trim())) {
+                        Ok(re) => Some(re),
+                        Err(err) => {
+                            warn!("ignoring invalid allow_from pattern {entry:?}: {err}");
+                            None
+                        }
+                    },
+                )
+                .collect(),
+        };
+        Self {
+            entries,
+            patterns,
+            mode: mode.clone(),
+        }
+    }
+
+    /// An empty list allows everyone. A list whose patterns all failed to
+    /// compile is not empty: it allows no one.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Whether any entry matches one of the sender's identifiers (id,
+    /// username, `@username`, ...).
+    pub(crate) fn matches(&self, candidates: &[&str]) -> bool {
+        match self.mode {
+            AllowMatchMode::Exact => self
+                .entries
+                .iter()
+                .any(|entry| candidates.contains(&entry.as_str())),
+            AllowMatchMode::Regex => self
+                .patterns
+                .iter()
+                .any(|re| candidates.iter().any(|c| re.is_match(c))),
+        }
+    }
+}
+
+/// Token bucket that lets callers go into debt, so reservations queue up in
+/// order instead of being dropped.
+#[derive(Clone, Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, per: Duration, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec: capacity as f64 / per.as_secs_f64(),
+            last: now,
+        }
+    }
+
+    /// Take one token and return how long to wait before using it.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.refill_per_sec)
+        }
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens + elapsed * self.refill_per_sec >= self.capacity
+    }
+}
+
+/// Chats tracked before idle per-chat buckets are dropped.
+const MAX_TRACKED_CHATS: usize = 1024;
+
+/// Outbound send pacing for one platform: an optional global bucket plus one
+/// bucket per chat. Forwarders await `acquire` before each API call; the
+/// limiter is shared by every chat's delivery task.
+pub struct RateLimiter {
+    per_chat: (u32, Duration),
+    buckets: Mutex<Buckets>,
+}
+
+struct Buckets {
+    global: Option<TokenBucket>,
+    chats: HashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(global: Option<(u32, Duration)>, per_chat: (u32, Duration)) -> Self {
+        let now = Instant::now();
+        Self {
+            per_chat,
+            buckets: Mutex::new(Buckets {
+                global: global.map(|(n, per)| TokenBucket::new(n, per, now)),
+                chats: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Telegram: ~30 messages/second overall and 1/second per chat.
+    pub fn telegram() -> Self {
+        Self::new(
+            Some((30, Duration::from_secs(1))),
+            (1, Duration::from_secs(1)),
+        )
+    }
+
+    /// Discord: 5 messages per 5 seconds per channel.
+    pub fn discord() -> Self {
+        Self::new(None, (5, Duration::from_secs(5)))
+    }
+
+    /// Wait until a message may be sent to `chat_id`.
+    pub async fn acquire(&self, chat_id: &str) {
+        let wait = self.reserve(chat_id, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn reserve(&self, chat_id: &str, now: Instant) -> Duration {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.chats.len() >= MAX_TRACKED_CHATS {
+            buckets.chats.retain(|_, bucket| !bucket.is_idle(now));
+        }
+        let (n, per) = self.per_chat;
+        let chat_wait = buckets
+            .chats
+            .entry(chat_id.to_string())
+            .or_insert_with(|| TokenBucket::new(n, per, now))
+            .reserve(now);
+        let global_wait = buckets
+            .global
+            .as_mut()
+            .map(|bucket| bucket.reserve(now))
+            .unwrap_or(Duration::ZERO);
+        chat_wait.max(global_wait)
+    }
+}
+
+/// Messages a forwarder may have waiting for a send slot before new ones are
+/// dropped.
+const MAX_QUEUED_OUTBOUND: usize = 1000;
+
+/// Drain `channel`'s outbound messages off the bus into a local queue as they
+/// arrive. Forwarders pace their sends from the queue, so a rate-limited chat
+/// never leaves the broadcast receiver lagging and skipping messages.
+pub(crate) fn queue_outbound(
+    channel: String,
+    mut rx: broadcast::Receiver<OutboundMessage>,
+) -> mpsc::Receiver<OutboundMessage> {
+    let (tx, queue) = mpsc::channel(MAX_QUEUED_OUTBOUND);
+    tokio::spawn(async move {
+        loop {
+            let msg = match rx.recv().await {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Closed) => break,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("{channel} outbound lagged, skipped {skipped} message(s)");
+                    continue;
+                }
+            };
+            if msg.channel != channel {
+                continue;
+            }
+            match tx.try_send(msg) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(msg)) => warn!(
+                    "{channel} outbound queue is full, dropping {} message to chat {}",
+                    msg.source.as_str(),
+                    msg.chat_id
+                ),
+                // The forwarder is gone.
+                Err(mpsc::error::TrySendError::Closed(_)) => break,
+            }
+        }
+    });
+    queue
+}
+
+/// Messages one chat may have waiting behind its rate limit before new ones
+/// are dropped.
+const MAX_QUEUED_PER_CHAT: usize = 100;
+
+/// One chat's delivery task and how many messages it has yet to finish.
+struct ChatLane {
+    tx: mpsc::Sender<OutboundMessage>,
+    pending: Arc<AtomicUsize>,
+}
+
+/// Hand each queued message to `deliver` on a task of its own chat, so a chat
+/// waiting out its rate limit never holds up delivery to the others. Messages
+/// for one chat are delivered one at a time in queue order, so an edit or
+/// delete always runs after the send it refers to. Returns once `queue` closes.
+pub(crate) async fn deliver_per_chat<F, Fut>(
+    channel: &str,
+    mut queue: mpsc::Receiver<OutboundMessage>,
+    deliver: F,
+) where
+    F: Fn(OutboundMessage) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut lanes: HashMap<String, ChatLane> = HashMap::new();
+    while let Some(msg) = queue.recv().await {
+        if lanes.len() >= MAX_TRACKED_CHATS {
+            // Dropping an idle lane's sender ends its task; nothing is in
+            // flight there, so a later message can't overtake it.
+            lanes.retain(|_, lane| lane.pending.load(Ordering::Acquire) > 0);
+        }
+        let lane = lanes.entry(msg.chat_id.clone()).or_insert_with(|| {
+            let (tx, mut rx) = mpsc::channel::<OutboundMessage>(MAX_QUEUED_PER_CHAT);
+            let pending = Arc::new(AtomicUsize::new(0));
+            let deliver = deliver.clone();
+            let done = pending.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    deliver(msg).await;
+                    done.fetch_sub(1, Ordering::AcqRel);
+                }
+            });
+            ChatLane { tx, pending }
+        });
+        lane.pending.fetch_add(1, Ordering::AcqRel);
+        if let Err(err) = lane.tx.try_send(msg) {
+            lane.pending.fetch_sub(1, Ordering::AcqRel);
+            let msg = match err {
+                mpsc::error::TrySendError::Full(msg) | mpsc::error::TrySendError::Closed(msg) => {
+                    msg
+                }
+            };
+            warn!(
+                "{channel} outbound queue for chat {} is full, dropping {} message",
+                msg.chat_id,
+                msg.source.as_str()
+            );
+        }
+    }
+}
+
+/// Per-sender attachment usage within `attachment_limits.window_secs`, so a
+/// flood of voice notes or files can't exhaust transcription quota or disk.
+#[derive(Clone)]
+pub(crate) struct AttachmentGuard {
+    limits: AttachmentLimits,
+    /// Arrival time and size of each accepted attachment, keyed by
+    /// `<channel>:<sender_id>`.
+    usage: Arc<DashMap<String, Vec<(Instant, u64)>>>,
+    last_sweep: Arc<Mutex<Instant>>,
+}
+
+impl AttachmentGuard {
+    pub(crate) fn new(limits: AttachmentLimits) -> Self {
+        Self {
+            limits,
+            usage: Arc::new(DashMap::new()),
+            last_sweep: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Record an attachment of `bytes` from `sender`, or return the message
+    /// to reply with when it would exceed the sender's budget.
+    pub(crate) fn admit(&self, sender: &str, bytes: u64) -> Result<(), String> {
+        self.admit_at(sender, bytes, Instant::now())
+    }
+
+    fn admit_at(&self, sender: &str, bytes: u64, now: Instant) -> Result<(), String> {
+        let AttachmentLimits {
+            max_count,
+            max_bytes,
+            window_secs,
+        } = self.limits;
+        if window_secs == 0 || (max_count == 0 && max_bytes == 0) {
+            return Ok(());
+        }
+        let window = Duration::from_secs(window_secs);
+        self.sweep(now, window);
+
+        let mut entry = self.usage.entry(sender.to_string()).or_default();
+        entry.retain(|(at, _)| now.saturating_duration_since(*at) < window);
+        let minutes = window_secs.div_ceil(60);
+        if max_count > 0 && entry.len() >= max_count as usize {
+            return Err(format!(
+                "You've sent a lot of attachments recently (limit {max_count} per {minutes} min). Please wait a bit before sending more."
+            ));
+        }
+        let used = entry.iter().map(|(_, size)| size).sum::<u64>();
+        if max_bytes > 0 && used + bytes > max_bytes {
+            return Err(format!(
+                "That would go over your attachment allowance ({} MB per {minutes} min). Please wait a bit before sending more.",
+                max_bytes / (1024 * 1024)
+            ));
+        }
+        entry.push((now, bytes));
+        Ok(())
+    }
+
+    /// Once per window, drop senders with no attachments left in it.
+    fn sweep(&self, now: Instant, window: Duration) {
+        let mut last = self.last_sweep.lock().unwrap_or_else(|e| e.into_inner());
+        if now.saturating_duration_since(*last) < window {
+            return;
+        }
+        *last = now;
+        self.usage.retain(|_, entries| {
+            entries
+                .iter()
+                .any(|(at, _)| now.saturating_duration_since(*at) < window)
+        });
+    }
+}
+
+/// A connection that stayed up at least this long was healthy, so the next
+/// drop starts the backoff over.
+const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+
+/// A channel error reconnecting can't fix, such as a rejected token.
+/// `supervise` gives up on it right away.
+#[derive(Debug)]
+pub(crate) struct FatalChannelError(pub(crate) String);
+
+impl fmt::Display for FatalChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FatalChannelError {}
+
+/// Wait before restarting after `failures` consecutive failed runs, or
+/// `None` to give up.
+fn reconnect_delay(policy: &ReconnectPolicy, failures: u32, fatal: bool) -> Option<Duration> {
+    if fatal || (policy.max_attempts > 0 && failures > policy.max_attempts) {
+        return None;
+    }
+    let factor = 1u64
+        .checked_shl(failures.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    let delay = policy
+        .base_delay_ms
+        .saturating_mul(factor)
+        .min(policy.max_delay_ms);
+    Some(Duration::from_millis(delay))
+}
+
+/// Run a channel connection until it ends cleanly, restarting it with
+/// exponential backoff when it fails. Each call to `connect` sets up the
+/// connection (and its bus subscriptions) from scratch.
+pub(crate) async fn supervise<F, Fut>(
+    channel: &str,
+    policy: ReconnectPolicy,
+    mut connect: F,
+) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let mut failures = 0;
+    loop {
+        let started = Instant::now();
+        let err = match connect().await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        if started.elapsed() >= STABLE_CONNECTION {
+            failures = 0;
+        }
+        failures += 1;
+        let fatal = err.downcast_ref::<FatalChannelError>().is_some();
+        let Some(delay) = reconnect_delay(&policy, failures, fatal) else {
+            return Err(err);
+        };
+        warn!(
+            "{channel} disconnected: {err}; reconnecting in {}ms (attempt {failures})",
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_backs_off_exponentially_and_gives_up() {
+        let policy = ReconnectPolicy {
+            max_attempts: 4,
+            base_delay_ms: 500,
+            max_delay_ms: 3000,
+        };
+        let delays: Vec<_> = (1..=5)
+            .map(|n| reconnect_delay(&policy, n, false).map(|d| d.as_millis()))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![Some(500), Some(1000), Some(2000), Some(3000), None]
+        );
+
+        // Fatal errors stop at once; 0 attempts means retry forever.
+        assert_eq!(reconnect_delay(&policy, 1, true), None);
+        let forever = ReconnectPolicy {
+            max_attempts: 0,
+            ..policy
+        };
+        assert_eq!(
+            reconnect_delay(&forever, 500, false),
+            Some(Duration::from_millis(3000))
+        );
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_until_a_fatal_error() {
+        let policy = ReconnectPolicy {
+            max_attempts: 10,
+            base_delay_ms: 1,
+            max_delay_ms: 1,
+        };
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = supervise("discord", policy, || {
+            let n = attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            async move {
+                match n {
+                    1 | 2 => Err(anyhow::anyhow!("gateway closed")),
+                    _ => Err(anyhow::Error::new(FatalChannelError("bad token".into()))),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "bad token");
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn regex_allowlist_matches_whole_usernames_ignoring_case() {
+        let list = SenderAllowlist::new(
+            vec!["ops_.*".to_string(), "[".to_string()],
+            &AllowMatchMode::Regex,
+        );
+        assert!(list.matches(&["123", "OPS_alice", "@OPS_alice"]));
+        assert!(!list.matches(&["123", "devops_bob", "@devops_bob"]));
+
+        let exact = SenderAllowlist::new(vec!["ops_.*".to_string()], &AllowMatchMode::Exact);
+        assert!(!exact.matches(&["123", "ops_alice"]));
+        assert!(exact.matches(&["ops_.*"]));
+
+        // A list of only broken patterns denies instead of opening up.
+        let broken = SenderAllowlist::new(vec!["(".to_string()], &AllowMatchMode::Regex);
+        assert!(!broken.is_empty());
+        assert!(!broken.matches(&["anyone"]));
+    }
+
+    #[test]
+    fn attachments_over_the_window_budget_are_rejected() {
+        let guard = AttachmentGuard::new(AttachmentLimits {
+            max_count: 3,
+            max_bytes: 10 * 1024 * 1024,
+            window_secs: 600,
+        });
+        let start = Instant::now();
+        let big = 3 * 1024 * 1024;
+        for i in 0..3 {
+            assert!(guard
+                .admit_at("telegram:7", big, start + Duration::from_secs(i))
+                .is_ok());
+        }
+        // The fourth large file in the window goes over both limits.
+        let err = guard
+            .admit_at("telegram:7", big, start + Duration::from_secs(3))
+            .unwrap_err();
+        assert!(err.contains("limit 3 per 10 min"), "{err}");
+        // Other senders have their own budget.
+        assert!(guard.admit_at("telegram:8", big, start).is_ok());
+
+        // Byte budget alone: 9 MB used, another 3 MB is too much.
+        let bytes_only = AttachmentGuard::new(AttachmentLimits {
+            max_count: 0,
+            max_bytes: 10 * 1024 * 1024,
+            window_secs: 600,
+        });
+        for _ in 0..3 {
+            bytes_only.admit_at("discord:1", big, start).unwrap();
+        }
+        assert!(bytes_only.admit_at("discord:1", big, start).is_err());
+
+        // Once the window has passed the sender can send again.
+        let later = start + Duration::from_secs(601);
+        assert!(guard.admit_at("telegram:7", big, later).is_ok());
+        assert!(!guard.usage.contains_key("telegram:8"));
+    }
+
+    #[tokio::test]
+    async fn sends_over_the_per_chat_rate_are_spaced_out() {
+        let interval = Duration::from_millis(40);
+        let limiter = RateLimiter::new(None, (1, interval));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire("100").await;
+        }
+        // The first send goes out immediately; the next two each wait a slot.
+        assert!(start.elapsed() >= interval * 2 - Duration::from_millis(2));
+
+        // Another chat has its own budget.
+        let before = Instant::now();
+        limiter.acquire("200").await;
+        assert!(before.elapsed() < interval);
+    }
+
+    #[tokio::test]
+    async fn queued_outbound_keeps_up_with_a_burst() {
+        let (tx, rx) = broadcast::channel(16);
+        let mut queue = queue_outbound("discord".to_string(), rx);
+        let message = |channel: &str, n: usize| OutboundMessage {
+            channel: channel.to_string(),
+            chat_id: "42".to_string(),
+            content: format!("message {n}"),
+            reply_to: None,
+            handle: None,
+            action: Default::default(),
+            source: Default::default(),
+            attachments: Vec::new(),
+            mention: None,
+        };
+        for n in 0..100 {
+            tx.send(message("discord", n)).unwrap();
+            tx.send(message("telegram", n)).unwrap();
+            tokio::task::yield_now().await;
+        }
+
+        // Far more than the broadcast buffer, and nothing has been sent yet,
+        // but every message for this channel is waiting in order.
+        for n in 0..100 {
+            let msg = queue.recv().await.expect("queued");
+            assert_eq!(msg.content, format!("message {n}"));
+            assert_eq!(msg.channel, "discord");
+        }
+        assert!(queue.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn a_throttled_chat_does_not_hold_up_other_chats() {
+        let (tx, queue) = mpsc::channel(16);
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+        let message = |chat_id: &str, content: &str| OutboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: chat_id.to_string(),
+            content: content.to_string(),
+            reply_to: None,
+            handle: None,
+            action: Default::default(),
+            source: Default::default(),
+            attachments: Vec::new(),
+            mention: None,
+        };
+        tokio::spawn(deliver_per_chat("telegram", queue, move |msg| {
+            let done_tx = done_tx.clone();
+            async move {
+                if msg.chat_id == "slow" {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                }
+                let _ = done_tx.send(format!("{}:{}", msg.chat_id, msg.content));
+            }
+        }));
+        tx.send(message("slow", "first")).await.unwrap();
+        tx.send(message("slow", "second")).await.unwrap();
+        tx.send(message("fast", "hello")).await.unwrap();
+
+        assert_eq!(done.recv().await.unwrap(), "fast:hello");
+        // The slow chat still gets its messages, in order.
+        assert_eq!(done.recv().await.unwrap(), "slow:first");
+        assert_eq!(done.recv().await.unwrap(), "slow:second");
+    }
+
+    #[test]
+    fn each_chat_has_its_own_inbox() {
+        let ws = Path::new("/ws");
+        assert_eq!(
+            inbox_dir(ws, "telegram", "-100123"),
+            Path::new("/ws/inbox/telegram_-100123")
+        );
+        assert_eq!(
+            inbox_dir(ws, "telegram.work", "../7"),
+            Path::new("/ws/inbox/telegram_work____7")
+        );
+    }
+
+    #[test]
+    fn typing_is_suppressed_for_cron_turns_and_when_disabled() {
+        assert!(should_show_typing(true, "12345"));
+        assert!(!should_show_typing(true, "cron"));
+        assert!(!should_show_typing(false, "12345"));
+        assert!(!should_show_typing(false, "cron"));
+    }
+
+    #[test]
+    fn mention_tokens_match_platform_syntax() {
+        assert_eq!(mention_token("discord", "42").as_deref(), Some("<@42>"));
+        assert!(mention_token("telegram", "42")
+            .unwrap()
+            .contains("tg://user?id=42"));
+        assert_eq!(mention_token("tui", "42"), None);
+        assert_eq!(mention_token("discord", "<@42>"), None);
+        assert_eq!(
+            mention_token("discord.work", "42").as_deref(),
+            Some("<@42>")
+        );
+    }
+}
@@ -0,0 +1,125 @@
+const FENCE: &str = "```";
+
+/// Split `text` into chunks of at most `limit` bytes for platforms with a
+/// message size cap. Cuts fall on line breaks where possible; a line longer
+/// than a chunk is hard-split on a character boundary. A code block cut in
+/// two is closed at the end of one chunk and reopened, with its language
+/// tag, at the start of the next.
+pub(crate) fn split_message(text: &str, limit: usize) -> Vec<String> {
+    if text.len() <= limit {
+        return vec![text.to_string()];
+    }
+    // Room for the "\n```" that closes a block cut mid-way.
+    let budget = limit.saturating_sub(FENCE.len() + 1).max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    // Opening line ("```rust") of the code block `current` is inside.
+    let mut open: Option<String> = None;
+
+    for line in text.split_inclusive('\n') {
+        let reopen_len = open.as_ref().map_or(0, |fence| fence.len() + 1);
+        let piece_max = budget.saturating_sub(reopen_len).max(1);
+        for piece in hard_split(line, piece_max) {
+            if current.len() + piece.len() > budget && !current.trim().is_empty() {
+                flush(&mut chunks, &mut current, open.as_deref());
+            }
+            current.push_str(piece);
+        }
+        if line.trim_start().starts_with(FENCE) {
+            open = match open {
+                Some(_) => None,
+                None => Some(line.trim().to_string()),
+            };
+        }
+    }
+    let last = current.trim_end_matches('\n');
+    if !last.trim().is_empty() {
+        chunks.push(last.to_string());
+    }
+    chunks
+}
+
+/// End the chunk in progress, closing an open code block, and start the
+/// next one (reopening the block).
+fn flush(chunks: &mut Vec<String>, current: &mut String, open: Option<&str>) {
+    let mut chunk = std::mem::take(current);
+    if open.is_some() {
+        if !chunk.ends_with('\n') {
+            chunk.push('\n');
+        }
+        chunk.push_str(FENCE);
+    }
+    let chunk = chunk.trim_end_matches('\n');
+    if !chunk.trim().is_empty() {
+        chunks.push(chunk.to_string());
+    }
+    if let Some(fence) = open {
+        current.push_str(fence);
+        current.push('\n');
+    }
+}
+
+/// Cut `line` into pieces of at most `max` bytes on character boundaries.
+fn hard_split(line: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.len() > max {
+        let mut cut = max;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        if cut == 0 {
+            // A single character wider than `max`; keep it whole.
+            cut = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+        pieces.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_code_blocks_keep_balanced_fences_across_chunks() {
+        let code = (0..150)
+            .map(|i| format!("    let value_{i:03} = compute({i});"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert!(code.len() > 3000);
+        let text = format!("Here is the change:\n```rust\n{code}\n```\nThat's all.");
+
+        let chunks = split_message(&text, 2000);
+        assert!(chunks.len() >= 2);
+        for (idx, chunk) in chunks.iter().enumerate() {
+            assert!(chunk.len() <= 2000, "chunk {idx} is {} bytes", chunk.len());
+            let fences = chunk
+                .lines()
+                .filter(|line| line.trim_start().starts_with(FENCE))
+                .count();
+            assert_eq!(fences % 2, 0, "chunk {idx} has unbalanced fences");
+            if idx > 0 {
+                assert!(
+                    chunk.starts_with("```rust\n"),
+                    "chunk {idx} lost its language tag"
+                );
+            }
+        }
+        assert!(chunks.last().unwrap().ends_with("That's all."));
+    }
+
+    #[test]
+    fn single_long_lines_are_hard_split_on_char_boundaries() {
+        let text = "é".repeat(1500);
+        let chunks = split_message(&text, 2000);
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 2000));
+        assert_eq!(chunks.concat(), text);
+
+        assert_eq!(split_message("short", 2000), vec!["short"]);
+    }
+}
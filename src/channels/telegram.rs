@@ -1,28 +1,141 @@
-use crate::bus::{InboundMessage, MessageBus};
-use crate::config::AppConfig;
-use crate::transcription::Transcriber;
+use crate::bus::{
+    Attachment, InboundMessage, MessageBus, OutboundAction, ProgressEvent, ProgressKind,
+    SentMessages,
+};
+use crate::channels::media_group::{MediaGroups, MEDIA_GROUP_WAIT};
+use crate::channels::render::split_message;
+use crate::channels::telegram_webhook;
+use crate::channels::{
+    deliver_per_chat, inbox_dir, outbound_text, queue_outbound, should_show_typing, supervise,
+    AttachmentGuard, FatalChannelError, RateLimiter, SenderAllowlist,
+};
+use crate::config::{AllowMatchMode, AppConfig, TelegramConfig, TelegramMode};
+use crate::transcription::{AudioTooLong, Transcriber};
 use anyhow::{anyhow, Result};
 use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use teloxide::dispatching::UpdateHandler;
 use teloxide::net::Download;
 use teloxide::prelude::*;
-use teloxide::types::{ChatAction, FileId, ParseMode};
-use tracing::{info, warn};
+use teloxide::types::{ChatAction, FileId, InputFile, MessageId, ParseMode};
+use teloxide::{ApiError, RequestError};
+use tracing::{debug, info, warn};
+
+/// One bot the process runs: the primary `channels.telegram` token or an
+/// entry from `channels.telegram.bots`.
+#[derive(Clone, Debug, PartialEq)]
+struct BotSetup {
+    /// Bus channel for this bot's traffic (`telegram` or `telegram.<name>`).
+    channel: String,
+    token: String,
+    allow_from: Vec<String>,
+    match_mode: AllowMatchMode,
+    /// Only the primary bot can take updates over the webhook listener.
+    webhook: bool,
+}
+
+fn bot_setups(cfg: &TelegramConfig) -> Vec<BotSetup> {
+    let mut setups = Vec::new();
+    if !cfg.bot_token.trim().is_empty() {
+        setups.push(BotSetup {
+            channel: "telegram".to_string(),
+            token: cfg.bot_token.clone(),
+            allow_from: cfg.allow_from.clone(),
+            match_mode: cfg.match_mode.clone(),
+            webhook: cfg.mode == TelegramMode::Webhook,
+        });
+    }
+    setups.extend(cfg.bots.iter().map(|bot| BotSetup {
+        channel: format!("telegram.{}", bot.name),
+        token: bot.bot_token.clone(),
+        allow_from: bot.allow_from.clone(),
+        match_mode: bot.match_mode.clone(),
+        webhook: false,
+    }));
+    setups
+}
 
 pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
-    let bot = Bot::new(cfg.channels.telegram.bot_token.clone());
-    bot.get_me()
-        .await
-        .map_err(|err| anyhow!("telegram authentication failed: {err}"))?;
+    let mut setups = bot_setups(&cfg.channels.telegram);
+    if setups.len() == 1 {
+        return supervise_bot(cfg, setups.remove(0), bus).await;
+    }
+    let mut bots = tokio::task::JoinSet::new();
+    for setup in setups {
+        let channel = setup.channel.clone();
+        let (cfg, bus) = (cfg.clone(), bus.clone());
+        bots.spawn(async move {
+            if let Err(err) = supervise_bot(cfg, setup, bus).await {
+                warn!("{channel} disabled: {err}");
+            }
+        });
+    }
+    while bots.join_next().await.is_some() {}
+    Ok(())
+}
 
-    spawn_outbound_forwarder(bot.clone(), bus.subscribe_outbound());
+/// Run a bot, reconnecting per `channels.reconnect` when it fails.
+async fn supervise_bot(cfg: AppConfig, setup: BotSetup, bus: MessageBus) -> Result<()> {
+    if setup.webhook {
+        // Fail on a bad URL/secret before touching the Bot API.
+        telegram_webhook::validate(&cfg.channels.telegram)?;
+    }
+    let channel = setup.channel.clone();
+    // Forwarders live across reconnects: messages published while the bot
+    // is reconnecting wait in their queue instead of being missed.
+    let bot = Bot::new(setup.token.clone());
+    let outbound = spawn_outbound_forwarder(
+        bot.clone(),
+        channel.clone(),
+        queue_outbound(channel.clone(), bus.subscribe_outbound()),
+        bus.sent_messages(),
+    );
+    let progress = spawn_progress_forwarder(
+        bot,
+        channel.clone(),
+        bus.subscribe_progress(),
+        cfg.channels.telegram.show_typing,
+    );
+    let policy = cfg.channels.reconnect;
+    let result = supervise(&channel, policy, || {
+        run_bot(cfg.clone(), setup.clone(), bus.clone())
+    })
+    .await;
+    outbound.abort();
+    progress.abort();
+    result
+}
 
-    let allowlist = cfg.channels.telegram.allow_from.clone();
+async fn run_bot(cfg: AppConfig, setup: BotSetup, bus: MessageBus) -> Result<()> {
+    let bot = Bot::new(setup.token.clone());
+    bot.get_me().await.map_err(|err| match err {
+        RequestError::Api(ApiError::InvalidToken) => {
+            anyhow!(FatalChannelError(format!(
+                "{} authentication failed: {err}",
+                setup.channel
+            )))
+        }
+        err => anyhow!("{} authentication failed: {err}", setup.channel),
+    })?;
+
+    let show_typing = cfg.channels.telegram.show_typing;
+    let allowlist = SenderAllowlist::new(setup.allow_from.clone(), &setup.match_mode);
+    let channel = setup.channel.clone();
     let transcriber = Transcriber::from_config(&cfg);
+    let attachment_guard = AttachmentGuard::new(cfg.channels.attachment_limits);
+    let media_groups = MediaGroups::new(MEDIA_GROUP_WAIT, cfg.channels.max_attachments_per_message);
+    let workspace_dir = cfg.workspace_dir.clone();
     let handler: UpdateHandler<anyhow::Error> =
         Update::filter_message().endpoint(move |bot: Bot, msg: Message, bus: MessageBus| {
             let allowlist = allowlist.clone();
+            let channel = channel.clone();
             let transcriber = transcriber.clone();
+            let attachment_guard = attachment_guard.clone();
+            let media_groups = media_groups.clone();
+            let workspace_dir = workspace_dir.clone();
             async move {
                 if !is_allowed(&msg, &allowlist) {
                     return Ok(());
@@ -37,13 +150,79 @@ pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
 
                 if let Some(text) = msg.text() {
                     let inbound = InboundMessage {
-                        channel: "telegram".to_string(),
+                        channel: channel.clone(),
                         chat_id,
                         sender_id,
                         content: text.to_string(),
+                        message_id: Some(msg.id.0.to_string()),
+                        mention: None,
+                        attachments: Vec::new(),
                     };
                     bus.publish_inbound(inbound).await;
-                    bot.send_chat_action(msg.chat.id, ChatAction::Typing).await?;
+                    if show_typing {
+                        bot.send_chat_action(msg.chat.id, ChatAction::Typing).await?;
+                    }
+                    return Ok(());
+                }
+
+                // Sizes come smallest first; keep the full-resolution one.
+                if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+                    // Album parts arrive as separate updates and go on as one
+                    // message. Claim a place first so parts over the cap are
+                    // never downloaded.
+                    let slot = match msg.media_group_id() {
+                        Some(group) => match media_groups.reserve(&channel, &chat_id, &group.0) {
+                            Some(slot) => Some(slot),
+                            None => return Ok(()),
+                        },
+                        None => None,
+                    };
+                    if let Err(reply) = attachment_guard
+                        .admit(&format!("{channel}:{sender_id}"), photo.file.size as u64)
+                    {
+                        if let Some(slot) = slot {
+                            media_groups.abandon(slot, bus);
+                        }
+                        bot.send_message(msg.chat.id, reply).await?;
+                        return Ok(());
+                    }
+                    let filename = format!("telegram_{}.jpg", photo.file.unique_id.0);
+                    let saved = match download_telegram_file(&bot, photo.file.id.clone()).await {
+                        Ok(data) => save_to_inbox(
+                            &inbox_dir(&workspace_dir, &channel, &chat_id),
+                            &filename,
+                            &data,
+                        ),
+                        Err(err) => Err(err),
+                    };
+                    let path = match saved {
+                        Ok(path) => path,
+                        Err(err) => {
+                            warn!("photo download failed: {err}");
+                            if let Some(slot) = slot {
+                                media_groups.abandon(slot, bus);
+                            }
+                            bot.send_message(
+                                msg.chat.id,
+                                "I couldn't download that photo from Telegram.",
+                            )
+                            .await?;
+                            return Ok(());
+                        }
+                    };
+                    let inbound = InboundMessage {
+                        channel: channel.clone(),
+                        chat_id,
+                        sender_id,
+                        content: msg.caption().unwrap_or_default().to_string(),
+                        message_id: Some(msg.id.0.to_string()),
+                        mention: None,
+                        attachments: vec![Attachment::Image { path }],
+                    };
+                    match slot {
+                        Some(slot) => media_groups.fill(slot, inbound, bus),
+                        None => bus.publish_inbound(inbound).await,
+                    }
                     return Ok(());
                 }
 
@@ -52,18 +231,24 @@ pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
                         voice.file.id.clone(),
                         format!("voice_{}.ogg", voice.file.unique_id.0),
                         voice.file.size as usize,
+                        voice.duration.seconds(),
                     ))
                 } else if let Some(audio) = msg.audio() {
                     let filename = audio
                         .file_name
                         .clone()
                         .unwrap_or_else(|| format!("audio_{}.mp3", audio.file.unique_id.0));
-                    Some((audio.file.id.clone(), filename, audio.file.size as usize))
+                    Some((
+                        audio.file.id.clone(),
+                        filename,
+                        audio.file.size as usize,
+                        audio.duration.seconds(),
+                    ))
                 } else {
                     None
                 };
 
-                if let Some((file_id, filename, file_size)) = media {
+                if let Some((file_id, filename, file_size, duration_secs)) = media {
                     let Some(transcriber) = transcriber.clone() else {
                         bot.send_message(
                             msg.chat.id,
@@ -85,15 +270,33 @@ pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
                         return Ok(());
                     }
 
-                    bot.send_chat_action(msg.chat.id, ChatAction::Typing).await?;
+                    // Telegram reports the duration up front, so long clips are
+                    // rejected before downloading them.
+                    if let Err(err) = transcriber.check_duration(Some(duration_secs as f64)) {
+                        bot.send_message(msg.chat.id, err.user_message()).await?;
+                        return Ok(());
+                    }
+                    if let Err(reply) =
+                        attachment_guard.admit(&format!("{channel}:{sender_id}"), file_size as u64)
+                    {
+                        bot.send_message(msg.chat.id, reply).await?;
+                        return Ok(());
+                    }
+
+                    if show_typing {
+                        bot.send_chat_action(msg.chat.id, ChatAction::Typing).await?;
+                    }
                     match download_telegram_file(&bot, file_id).await {
                         Ok(data) => match transcriber.transcribe_bytes(filename, data).await {
                             Ok(transcript) if !transcript.is_empty() => {
                                 let inbound = InboundMessage {
-                                    channel: "telegram".to_string(),
+                                    channel: channel.clone(),
                                     chat_id,
                                     sender_id,
-                                    content: transcript,
+                                    content: String::new(),
+                                    message_id: Some(msg.id.0.to_string()),
+                                    mention: None,
+                                    attachments: vec![Attachment::Audio { transcript }],
                                 };
                                 bus.publish_inbound(inbound).await;
                             }
@@ -105,12 +308,14 @@ pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
                                 .await?;
                             }
                             Err(err) => {
-                                warn!("audio transcription failed: {err}");
-                                bot.send_message(
-                                    msg.chat.id,
-                                    "I couldn't transcribe that audio message. Please retry or send text.",
-                                )
-                                .await?;
+                                let reply = match err.downcast_ref::<AudioTooLong>() {
+                                    Some(too_long) => too_long.user_message(),
+                                    None => {
+                                        warn!("audio transcription failed: {err}");
+                                        "I couldn't transcribe that audio message. Please retry or send text.".to_string()
+                                    }
+                                };
+                                bot.send_message(msg.chat.id, reply).await?;
                             }
                         },
                         Err(err) => {
@@ -128,17 +333,20 @@ pub async fn start(cfg: AppConfig, bus: MessageBus) -> Result<()> {
             }
         });
 
-    Dispatcher::builder(bot, handler)
-        .dependencies(dptree::deps![bus])
-        .enable_ctrlc_handler()
-        .build()
-        .dispatch()
-        .await;
-
-    Ok(())
+    if setup.webhook {
+        telegram_webhook::run(bot, handler, bus, &cfg.channels.telegram).await
+    } else {
+        Dispatcher::builder(bot, handler)
+            .dependencies(dptree::deps![bus])
+            .enable_ctrlc_handler()
+            .build()
+            .dispatch()
+            .await;
+        Ok(())
+    }
 }
 
-fn is_allowed(msg: &Message, allowlist: &[String]) -> bool {
+fn is_allowed(msg: &Message, allowlist: &SenderAllowlist) -> bool {
     if allowlist.is_empty() {
         return true;
     }
@@ -147,53 +355,278 @@ fn is_allowed(msg: &Message, allowlist: &[String]) -> bool {
         None => return false,
     };
     let uid = user.id.0.to_string();
-    let uname = user.username.as_ref().map(|u| u.to_string());
-    allowlist.iter().any(|allowed| {
-        if allowed.starts_with('@') {
-            uname
-                .as_ref()
-                .map(|u| format!("@{u}"))
-                .as_ref()
-                .map(|u| u == allowed)
-                .unwrap_or(false)
-        } else {
-            allowed == &uid || uname.as_ref().map(|u| u == allowed).unwrap_or(false)
-        }
-    })
+    match user.username.as_deref() {
+        Some(uname) => allowlist.matches(&[uid.as_str(), uname, &format!("@{uname}")]),
+        None => allowlist.matches(&[&uid]),
+    }
 }
 
+/// Long replies go out in chunks of at most this many bytes, which leaves
+/// room for MarkdownV2 escapes under Telegram's 4096-character message cap.
+const TELEGRAM_CHUNK_BYTES: usize = 3000;
+
 fn spawn_outbound_forwarder(
     bot: Bot,
-    mut outbound_rx: tokio::sync::broadcast::Receiver<crate::bus::OutboundMessage>,
-) {
+    channel: String,
+    queue: tokio::sync::mpsc::Receiver<crate::bus::OutboundMessage>,
+    sent: SentMessages,
+) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        loop {
-            let msg = match outbound_rx.recv().await {
-                Ok(msg) => msg,
-                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                    info!("outbound channel closed, telegram forwarder shutting down");
-                    break;
-                }
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
-                    warn!("telegram outbound lagged, skipped {skipped} message(s)");
-                    continue;
+        let limiter = Arc::new(RateLimiter::telegram());
+        let forwarder = channel.clone();
+        deliver_per_chat(&channel, queue, move |msg| {
+            let (bot, channel, limiter, sent) = (
+                bot.clone(),
+                forwarder.clone(),
+                limiter.clone(),
+                sent.clone(),
+            );
+            async move { deliver_outbound(&bot, &channel, &limiter, &sent, msg).await }
+        })
+        .await;
+        info!("outbound channel closed, {channel} forwarder shutting down");
+    })
+}
+
+/// Send, edit or delete one outbound message, after waiting out the chat's
+/// rate limit.
+async fn deliver_outbound(
+    bot: &Bot,
+    channel: &str,
+    limiter: &RateLimiter,
+    sent: &SentMessages,
+    msg: crate::bus::OutboundMessage,
+) {
+    let Ok(chat_id) = msg.chat_id.parse::<i64>() else {
+        return;
+    };
+    let chat = ChatId(chat_id);
+    limiter.acquire(&msg.chat_id).await;
+
+    if msg.action == OutboundAction::Send {
+        debug!(
+            "telegram sending {} message to chat {chat_id}",
+            msg.source.as_str()
+        );
+        let text = outbound_text(&msg);
+        if !text.trim().is_empty() || msg.attachments.is_empty() {
+            for (idx, chunk) in split_message(&text, TELEGRAM_CHUNK_BYTES)
+                .iter()
+                .enumerate()
+            {
+                if idx > 0 {
+                    limiter.acquire(&msg.chat_id).await;
                 }
-            };
-            if msg.channel != "telegram" {
-                continue;
-            }
-            if let Ok(chat_id) = msg.chat_id.parse::<i64>() {
-                let rendered = markdown_to_telegram_markdown_v2(&msg.content);
-                if let Err(e) = bot
-                    .send_message(ChatId(chat_id), rendered)
+                let rendered = markdown_to_telegram_markdown_v2(chunk);
+                match bot
+                    .send_message(chat, rendered)
                     .parse_mode(ParseMode::MarkdownV2)
                     .await
                 {
-                    warn!("Failed to send Telegram message to chat {chat_id}: {e}");
+                    Ok(delivered) => {
+                        if let Some(handle) = &msg.handle {
+                            sent.record(handle, channel, &msg.chat_id, delivered.id.0.to_string());
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to send Telegram {} message to chat {chat_id}: {e}",
+                            msg.source.as_str()
+                        );
+                        break;
+                    }
+                }
+            }
+        }
+        for path in &msg.attachments {
+            limiter.acquire(&msg.chat_id).await;
+            match bot.send_document(chat, InputFile::file(path.clone())).await {
+                Ok(delivered) => {
+                    if let Some(handle) = &msg.handle {
+                        sent.record(handle, channel, &msg.chat_id, delivered.id.0.to_string());
+                    }
+                }
+                Err(e) => warn!(
+                    "Failed to send {} to Telegram chat {chat_id}: {e}",
+                    path.display()
+                ),
+            }
+        }
+        return;
+    }
+
+    let Some((handle, record)) = msg
+        .handle
+        .as_deref()
+        .and_then(|h| sent.lookup(h).map(|record| (h, record)))
+    else {
+        warn!("telegram {:?} for unknown message handle", msg.action);
+        return;
+    };
+    let mut ids = record
+        .message_ids
+        .iter()
+        .filter_map(|id| id.parse::<i32>().ok())
+        .map(MessageId);
+    if msg.action == OutboundAction::Edit {
+        // Edits rewrite the first message of a reply.
+        let Some(id) = ids.next() else {
+            return;
+        };
+        let rendered = markdown_to_telegram_markdown_v2(&msg.content);
+        if let Err(e) = bot
+            .edit_message_text(chat, id, rendered)
+            .parse_mode(ParseMode::MarkdownV2)
+            .await
+        {
+            warn!(
+                "Failed to edit Telegram message {} in chat {chat_id}: {e}",
+                id.0
+            );
+        }
+    } else {
+        for id in ids {
+            if let Err(e) = bot.delete_message(chat, id).await {
+                warn!(
+                    "Failed to delete Telegram message {} in chat {chat_id}: {e}",
+                    id.0
+                );
+            }
+        }
+        sent.forget(handle);
+    }
+}
+
+/// Telegram clears a chat action after ~5s, so re-send slightly sooner.
+const TYPING_REFRESH: Duration = Duration::from_secs(4);
+/// Give up on a turn that never reported completion.
+const MAX_TURN_TYPING: Duration = Duration::from_secs(10 * 60);
+
+/// Rate-limits typing actions for a chat with turns in flight.
+struct TypingThrottle {
+    started: Instant,
+    last_sent: Option<Instant>,
+    /// Turns started in this chat that haven't reported completion yet.
+    turns: usize,
+}
+
+impl TypingThrottle {
+    fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            last_sent: None,
+            turns: 0,
+        }
+    }
+
+    fn begin_turn(&mut self, now: Instant) {
+        self.turns += 1;
+        self.started = now;
+    }
+
+    /// Record one finished turn; true once none remain in flight.
+    fn finish_turn(&mut self) -> bool {
+        self.turns = self.turns.saturating_sub(1);
+        self.turns == 0
+    }
+
+    /// Whether a typing action is due, recording the send when it is.
+    fn poll(&mut self, now: Instant) -> bool {
+        let due = match self.last_sent {
+            Some(last) => now.duration_since(last) >= TYPING_REFRESH,
+            None => true,
+        };
+        if due {
+            self.last_sent = Some(now);
+        }
+        due
+    }
+
+    fn expired(&self, now: Instant) -> bool {
+        now.duration_since(self.started) >= MAX_TURN_TYPING
+    }
+}
+
+/// Keep the typing indicator alive while the agent works on a chat's turns,
+/// refreshed on a timer and on each tool call, until every turn started in
+/// that chat reports completion.
+fn spawn_progress_forwarder(
+    bot: Bot,
+    channel: String,
+    mut progress_rx: tokio::sync::broadcast::Receiver<ProgressEvent>,
+    show_typing: bool,
+) -> tokio::task::JoinHandle<()> {
+    fn send_typing(bot: &Bot, chat_id: i64) {
+        let bot = bot.clone();
+        tokio::spawn(async move {
+            let _ = bot
+                .send_chat_action(ChatId(chat_id), ChatAction::Typing)
+                .await;
+        });
+    }
+
+    tokio::spawn(async move {
+        let mut active: HashMap<i64, TypingThrottle> = HashMap::new();
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                event = progress_rx.recv() => {
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    };
+                    if event.channel != channel {
+                        continue;
+                    }
+                    let Ok(chat_id) = event.chat_id.parse::<i64>() else {
+                        continue;
+                    };
+                    if let ProgressKind::ToolCall { name } = &event.kind {
+                        debug!("{channel} chat {chat_id}: running tool {name}");
+                    }
+                    let now = Instant::now();
+                    match event.kind {
+                        ProgressKind::Started | ProgressKind::ToolCall { .. } => {
+                            if !should_show_typing(show_typing, &event.sender_id) {
+                                continue;
+                            }
+                            let throttle = active
+                                .entry(chat_id)
+                                .or_insert_with(|| TypingThrottle::new(now));
+                            if matches!(event.kind, ProgressKind::Started) {
+                                throttle.begin_turn(now);
+                            } else {
+                                // A tool call whose `Started` was lagged away
+                                // still belongs to one running turn.
+                                throttle.turns = throttle.turns.max(1);
+                            }
+                            if throttle.poll(now) {
+                                send_typing(&bot, chat_id);
+                            }
+                        }
+                        ProgressKind::Finished => {
+                            if active
+                                .get_mut(&chat_id)
+                                .is_some_and(|throttle| throttle.finish_turn())
+                            {
+                                active.remove(&chat_id);
+                            }
+                        }
+                    }
+                }
+                _ = ticker.tick() => {
+                    let now = Instant::now();
+                    active.retain(|_, throttle| !throttle.expired(now));
+                    for (chat_id, throttle) in active.iter_mut() {
+                        if throttle.poll(now) {
+                            send_typing(&bot, *chat_id);
+                        }
+                    }
                 }
             }
         }
-    });
+    })
 }
 
 fn markdown_to_telegram_markdown_v2(input: &str) -> String {
@@ -434,9 +867,95 @@ async fn download_telegram_file(bot: &Bot, file_id: FileId) -> Result<Vec<u8>> {
     Ok(data)
 }
 
+/// Write a received file to the chat's inbox and return its path. The agent
+/// removes it once the turn that carries it is done.
+fn save_to_inbox(inbox: &Path, filename: &str, data: &[u8]) -> Result<PathBuf> {
+    std::fs::create_dir_all(inbox)?;
+    let path = inbox.join(filename);
+    std::fs::write(&path, data)?;
+    Ok(path)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::markdown_to_telegram_markdown_v2;
+    use super::{
+        bot_setups, markdown_to_telegram_markdown_v2, BotSetup, TypingThrottle, TYPING_REFRESH,
+    };
+    use crate::config::{AllowMatchMode, AppConfig, BotConfig, TelegramMode};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn each_bot_token_gets_its_own_setup_and_allowlist() {
+        let mut cfg = AppConfig::defaults().channels.telegram;
+        cfg.bot_token = "111:primary".to_string();
+        cfg.allow_from = vec!["@owner".to_string()];
+        cfg.mode = TelegramMode::Webhook;
+        cfg.bots = vec![BotConfig {
+            name: "work".to_string(),
+            bot_token: "222:work".to_string(),
+            allow_from: vec!["42".to_string()],
+            match_mode: AllowMatchMode::Regex,
+            allowed_channels: Vec::new(),
+            allowed_roles: Vec::new(),
+            persona: Some("Be terse.".to_string()),
+        }];
+
+        let setups = bot_setups(&cfg);
+        assert_eq!(
+            setups,
+            vec![
+                BotSetup {
+                    channel: "telegram".to_string(),
+                    token: "111:primary".to_string(),
+                    allow_from: vec!["@owner".to_string()],
+                    match_mode: AllowMatchMode::Exact,
+                    webhook: true,
+                },
+                BotSetup {
+                    channel: "telegram.work".to_string(),
+                    token: "222:work".to_string(),
+                    allow_from: vec!["42".to_string()],
+                    match_mode: AllowMatchMode::Regex,
+                    webhook: false,
+                },
+            ]
+        );
+
+        // Extra bots still run without a primary token.
+        cfg.bot_token.clear();
+        let setups = bot_setups(&cfg);
+        assert_eq!(setups.len(), 1);
+        assert_eq!(setups[0].channel, "telegram.work");
+    }
+
+    #[test]
+    fn typing_throttle_refreshes_at_most_once_per_interval() {
+        let start = Instant::now();
+        let mut throttle = TypingThrottle::new(start);
+
+        assert!(throttle.poll(start));
+        assert!(!throttle.poll(start + Duration::from_secs(1)));
+        assert!(!throttle.poll(start + TYPING_REFRESH - Duration::from_millis(1)));
+        assert!(throttle.poll(start + TYPING_REFRESH));
+        assert!(!throttle.poll(start + TYPING_REFRESH + Duration::from_secs(2)));
+        assert!(throttle.poll(start + TYPING_REFRESH * 2));
+
+        assert!(!throttle.expired(start + Duration::from_secs(60)));
+        assert!(throttle.expired(start + super::MAX_TURN_TYPING));
+    }
+
+    #[test]
+    fn typing_stops_only_after_every_turn_in_the_chat_finishes() {
+        let start = Instant::now();
+        let mut throttle = TypingThrottle::new(start);
+        throttle.begin_turn(start);
+        throttle.begin_turn(start + Duration::from_secs(1));
+
+        assert!(!throttle.finish_turn());
+        assert!(throttle.finish_turn());
+        // A stray extra `Finished` doesn't underflow.
+        assert!(throttle.finish_turn());
+    }
 
     #[test]
     fn renders_multiline_blockquote_lines() {
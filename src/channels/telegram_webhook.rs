@@ -0,0 +1,128 @@
+use crate::bus::MessageBus;
+use crate::config::TelegramConfig;
+use anyhow::{anyhow, bail, Result};
+use std::net::SocketAddr;
+use teloxide::dispatching::UpdateHandler;
+use teloxide::prelude::*;
+use teloxide::update_listeners::webhooks::{self, Options};
+use tracing::info;
+use url::Url;
+
+/// Check the public URL, secret token and listen address for webhook mode.
+pub(crate) fn validate(telegram: &TelegramConfig) -> Result<Url> {
+    let raw = telegram.webhook_url.trim();
+    if raw.is_empty() {
+        bail!("channels.telegram.webhook.url is required in webhook mode");
+    }
+    let url = Url::parse(raw)
+        .map_err(|err| anyhow!("invalid channels.telegram.webhook.url '{raw}': {err}"))?;
+    if url.scheme() != "https" {
+        bail!("channels.telegram.webhook.url must use https (Telegram rejects plain http)");
+    }
+    if url.host_str().unwrap_or_default().is_empty() {
+        bail!("channels.telegram.webhook.url '{raw}' has no host");
+    }
+    let secret = &telegram.webhook_secret;
+    let secret_ok = (1..=256).contains(&secret.len())
+        && secret
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !secret_ok {
+        bail!(
+            "channels.telegram.webhook.secret_token must be 1-256 characters of A-Z, a-z, 0-9, _ or -"
+        );
+    }
+    listen_address(telegram)?;
+    Ok(url)
+}
+
+fn listen_address(telegram: &TelegramConfig) -> Result<SocketAddr> {
+    let listen = telegram.webhook_listen.trim();
+    listen.parse().map_err(|err| {
+        anyhow!("invalid channels.telegram.webhook.listen '{listen}' (expected ip:port): {err}")
+    })
+}
+
+/// Webhook options for `telegram`: Telegram posts to `url`, the receiver
+/// serves the URL's path on `listen` and checks the secret token header.
+pub(crate) fn options(telegram: &TelegramConfig) -> Result<Options> {
+    let url = validate(telegram)?;
+    Ok(Options::new(listen_address(telegram)?, url).secret_token(telegram.webhook_secret.clone()))
+}
+
+/// Register the webhook and run `handler` over the updates Telegram pushes
+/// to `webhook_listen` until ctrl-c, which removes the webhook again. As with
+/// polling, updates for one chat are handled one at a time in arrival order.
+pub(crate) async fn run(
+    bot: Bot,
+    handler: UpdateHandler<anyhow::Error>,
+    bus: MessageBus,
+    telegram: &TelegramConfig,
+) -> Result<()> {
+    let options = options(telegram)?;
+    let url = options.url.clone();
+    let listener = webhooks::axum(bot.clone(), options)
+        .await
+        .map_err(|err| anyhow!("telegram setWebhook failed: {err}"))?;
+    info!(
+        "telegram webhook registered for {url}; listening on {}",
+        telegram.webhook_listen
+    );
+    Dispatcher::builder(bot, handler)
+        .dependencies(dptree::deps![bus])
+        .enable_ctrlc_handler()
+        .build()
+        .dispatch_with_listener(
+            listener,
+            LoggingErrorHandler::with_custom_text("telegram webhook listener failed"),
+        )
+        .await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AppConfig, TelegramMode};
+
+    fn webhook_config() -> TelegramConfig {
+        let mut cfg = AppConfig::defaults().channels.telegram;
+        cfg.mode = TelegramMode::Webhook;
+        cfg.webhook_url = "https://bot.example.com/telegram/hook".to_string();
+        cfg.webhook_secret = "s3cret_token-1".to_string();
+        cfg
+    }
+
+    #[test]
+    fn options_serve_the_url_path_with_the_secret() {
+        let mut options = options(&webhook_config()).unwrap();
+        assert_eq!(
+            options.url.as_str(),
+            "https://bot.example.com/telegram/hook"
+        );
+        assert_eq!(options.path, "/telegram/hook");
+        assert_eq!(options.address, SocketAddr::from(([0, 0, 0, 0], 8443)));
+        assert_eq!(options.get_or_gen_secret_token(), "s3cret_token-1");
+    }
+
+    #[test]
+    fn webhook_settings_are_validated() {
+        let mut cfg = webhook_config();
+        cfg.webhook_url = "http://bot.example.com/hook".to_string();
+        assert!(validate(&cfg).unwrap_err().to_string().contains("https"));
+
+        let mut cfg = webhook_config();
+        cfg.webhook_url = String::new();
+        assert!(validate(&cfg).is_err());
+
+        for secret in ["", "has space", "semi;colon"] {
+            let mut cfg = webhook_config();
+            cfg.webhook_secret = secret.to_string();
+            assert!(validate(&cfg).is_err(), "{secret:?}");
+        }
+
+        let mut cfg = webhook_config();
+        cfg.webhook_listen = "localhost".to_string();
+        assert!(validate(&cfg).unwrap_err().to_string().contains("listen"));
+    }
+}
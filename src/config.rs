@@ -2,7 +2,7 @@ use anyhow::{anyhow, Result};
 use etcetera::{choose_base_strategy, BaseStrategy};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -11,6 +11,7 @@ pub enum ProviderKind {
     OpenRouter,
     OpenAI,
     Ollama,
+    Azure,
 }
 
 impl ProviderKind {
@@ -19,6 +20,7 @@ impl ProviderKind {
             "openrouter" => Some(Self::OpenRouter),
             "openai" => Some(Self::OpenAI),
             "ollama" => Some(Self::Ollama),
+            "azure" | "azure_openai" | "azure-openai" => Some(Self::Azure),
             _ => None,
         }
     }
@@ -28,6 +30,21 @@ impl ProviderKind {
             Self::OpenRouter => "openrouter",
             Self::OpenAI => "openai",
             Self::Ollama => "ollama",
+            Self::Azure => "azure",
+        }
+    }
+
+    pub fn requires_api_key(&self) -> bool {
+        !matches!(self, Self::Ollama)
+    }
+
+    /// Environment variable read for this provider's API key.
+    pub fn api_key_env(&self) -> &'static str {
+        match self {
+            Self::OpenRouter => "OPENROUTER_API_KEY",
+            Self::OpenAI => "OPENAI_API_KEY",
+            Self::Ollama => "OLLAMA_API_KEY",
+            Self::Azure => "AZURE_OPENAI_API_KEY",
         }
     }
 }
@@ -54,6 +71,20 @@ pub struct OpenRouterEntry {
     pub extra_headers: Vec<(String, String)>,
 }
 
+/// Ollama entry: credentials plus model residency settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OllamaEntry {
+    pub api_key: String,
+    pub base_url: String,
+    pub extra_headers: Vec<(String, String)>,
+    /// How long Ollama keeps the model loaded after a request, forwarded as
+    /// `keep_alive` (`"30m"`, `"24h"`, seconds, or `-1` for forever).
+    pub keep_alive: Option<String>,
+    /// Send a tiny warm-up request at startup so the first reply doesn't
+    /// wait for the model to load.
+    pub preload: bool,
+}
+
 /// Mistral provider entry (api key + base URL only).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MistralEntry {
@@ -61,12 +92,24 @@ pub struct MistralEntry {
     pub base_url: String,
 }
 
+/// Azure OpenAI entry (resource endpoint, deployment and API version).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AzureEntry {
+    pub api_key: String,
+    /// Resource endpoint, e.g. `https://my-resource.openai.azure.com`.
+    pub endpoint: String,
+    pub deployment: String,
+    pub api_version: String,
+    pub extra_headers: Vec<(String, String)>,
+}
+
 /// All provider credentials.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProvidersConfig {
     pub openrouter: OpenRouterEntry,
     pub openai: ProviderEntry,
-    pub ollama: ProviderEntry,
+    pub ollama: OllamaEntry,
+    pub azure: AzureEntry,
     pub mistral: MistralEntry,
 }
 
@@ -76,6 +119,72 @@ pub struct ModelConfig {
     pub model: String,
     pub fallbacks: Vec<String>,
     pub max_tool_turns: usize,
+    /// Per-route overrides keyed by `provider/model` or bare `model`.
+    pub max_tool_turns_by_model: HashMap<String, usize>,
+    /// Generation stops at the first of these; sessions may override with `/stop`.
+    pub stop_sequences: Vec<String>,
+    /// Hard cap on tokens generated per completion.
+    pub max_response_tokens: u64,
+    /// Extra "continue" rounds when a reply stops at `max_response_tokens`.
+    pub max_continuations: usize,
+    /// Session history is compacted once it holds this many tokens.
+    pub compaction_max_tokens: usize,
+    /// Detect the language of each message and ask for a reply in it.
+    pub match_user_language: bool,
+    /// Put the current date and time in each turn's conversation context.
+    pub inject_time_context: bool,
+    /// Extra attempts on the same route after a transient failure.
+    pub max_retries: usize,
+    /// Backoff before retry `n` (1-based) is `n * retry_base_delay_ms`.
+    pub retry_base_delay_ms: u64,
+    /// Routes (primary plus fallbacks) tried per request; `None` tries all.
+    pub max_routes_attempted: Option<usize>,
+    /// Reproducible replies for evals: temperature 0, a fixed seed where the
+    /// provider takes one, and routes tried strictly in configured order.
+    pub deterministic: bool,
+    /// Log the reasoning that is stripped from replies (at info level).
+    pub log_reasoning: bool,
+    /// Reply sent when no provider route can be built; empty uses the
+    /// built-in setup instructions.
+    pub unconfigured_reply: String,
+    /// Quiet period that folds a sender's rapid messages in a chat into one
+    /// turn; 0 handles every message on its own.
+    pub inbound_debounce_ms: u64,
+}
+
+/// Seed sent with every request in deterministic mode.
+pub const DETERMINISTIC_SEED: u64 = 42;
+
+/// Upper bound on `max_continuations`, so a misbehaving model can't loop.
+pub const MAX_CONTINUATIONS_LIMIT: usize = 10;
+
+/// Upper bound on `max_retries`, so a dead route can't stall a turn.
+pub const MAX_RETRIES_LIMIT: usize = 10;
+
+/// Most stop sequences a provider request accepts.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+/// Longest single stop sequence, in characters.
+pub const MAX_STOP_SEQUENCE_CHARS: usize = 64;
+
+/// Check a stop-sequence list against provider limits.
+pub fn validate_stop_sequences(list: &[String]) -> Result<Vec<String>, String> {
+    if list.len() > MAX_STOP_SEQUENCES {
+        return Err(format!(
+            "at most {MAX_STOP_SEQUENCES} stop sequences are allowed (got {})",
+            list.len()
+        ));
+    }
+    for stop in list {
+        if stop.is_empty() {
+            return Err("stop sequences must not be empty".to_string());
+        }
+        if stop.chars().count() > MAX_STOP_SEQUENCE_CHARS {
+            return Err(format!(
+                "stop sequence '{stop}' is longer than {MAX_STOP_SEQUENCE_CHARS} characters"
+            ));
+        }
+    }
+    Ok(list.to_vec())
 }
 
 /// Telegram channel settings.
@@ -83,6 +192,75 @@ pub struct ModelConfig {
 pub struct TelegramConfig {
     pub bot_token: String,
     pub allow_from: Vec<String>,
+    /// How `allow_from` entries are compared with senders.
+    pub match_mode: AllowMatchMode,
+    /// How updates are received from Telegram.
+    pub mode: TelegramMode,
+    /// Public HTTPS URL Telegram posts updates to in webhook mode.
+    pub webhook_url: String,
+    /// Sent back by Telegram in `X-Telegram-Bot-Api-Secret-Token`.
+    pub webhook_secret: String,
+    /// Local address the webhook receiver binds (behind a TLS proxy).
+    pub webhook_listen: String,
+    /// Send "typing…" while a turn runs (never for cron-triggered turns).
+    pub show_typing: bool,
+    /// Additional bots run by the same process (always long polling).
+    pub bots: Vec<BotConfig>,
+}
+
+/// An additional bot on a platform. Its traffic uses the bus channel
+/// `<platform>.<name>`, so replies go back out through the same bot.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BotConfig {
+    pub name: String,
+    pub bot_token: String,
+    pub allow_from: Vec<String>,
+    pub match_mode: AllowMatchMode,
+    /// Discord only: channel ids the bot answers in (empty means all).
+    pub allowed_channels: Vec<String>,
+    /// Discord only: role ids whose members are allowed in guilds.
+    pub allowed_roles: Vec<String>,
+    /// Extra instructions for turns handled by this bot.
+    pub persona: Option<String>,
+}
+
+/// How allowlist entries are compared with a sender's id and username.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AllowMatchMode {
+    /// The entry must equal the id, username or `@username`.
+    Exact,
+    /// The entry is a regex that must match one of them in full, ignoring case.
+    Regex,
+}
+
+impl AllowMatchMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "exact" => Some(Self::Exact),
+            "regex" | "pattern" => Some(Self::Regex),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TelegramMode {
+    /// Long-poll `getUpdates`; works without a public URL.
+    Polling,
+    /// Register `webhook_url` with Telegram and receive pushed updates.
+    Webhook,
+}
+
+impl TelegramMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "polling" | "poll" | "long_polling" => Some(Self::Polling),
+            "webhook" | "webhooks" => Some(Self::Webhook),
+            _ => None,
+        }
+    }
 }
 
 /// Discord channel settings.
@@ -90,7 +268,44 @@ pub struct TelegramConfig {
 pub struct DiscordConfig {
     pub bot_token: String,
     pub allow_from: Vec<String>,
+    pub match_mode: AllowMatchMode,
     pub allowed_channels: Vec<String>,
+    /// Role ids whose members may talk to the bot in guilds, in addition to
+    /// `allow_from`.
+    pub allowed_roles: Vec<String>,
+    /// Which guild messages get a reply; DMs are always answered.
+    pub guild_response_mode: GuildResponseMode,
+    /// Leading text that triggers a reply in `prefix` mode, for guilds
+    /// without an entry in `guild_prefixes`.
+    pub guild_prefix: String,
+    /// `prefix`-mode triggers keyed by guild id.
+    pub guild_prefixes: HashMap<String, String>,
+    /// Show the typing indicator while a reply is being prepared.
+    pub show_typing: bool,
+    /// Additional bots run by the same process.
+    pub bots: Vec<BotConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GuildResponseMode {
+    /// Only messages that @-mention the bot.
+    Mention,
+    /// Every message in an allowed channel.
+    All,
+    /// Messages starting with `guild_prefix` (mentions still work).
+    Prefix,
+}
+
+impl GuildResponseMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "mention" | "mentions" => Some(Self::Mention),
+            "all" | "always" => Some(Self::All),
+            "prefix" => Some(Self::Prefix),
+            _ => None,
+        }
+    }
 }
 
 /// All channel settings.
@@ -98,6 +313,79 @@ pub struct DiscordConfig {
 pub struct ChannelsConfig {
     pub telegram: TelegramConfig,
     pub discord: DiscordConfig,
+    /// Senders allowed to run admin chat commands such as `/broadcast`, as a
+    /// bare sender id or `<channel>:<sender_id>`.
+    pub admins: Vec<String>,
+    pub attachment_limits: AttachmentLimits,
+    /// Most attachments one inbound message (e.g. a Telegram album) carries
+    /// to the agent; 0 disables the cap.
+    pub max_attachments_per_message: usize,
+    pub reconnect: ReconnectPolicy,
+    /// Sent once to each new Telegram or Discord chat, followed by the chat
+    /// commands; empty disables it.
+    pub greeting: String,
+    /// IANA timezone (`Europe/Lisbon`) for chats without an entry in
+    /// `timezones`; empty means the server's local time.
+    pub timezone: String,
+    /// Timezones keyed by `<channel>:<chat_id>` or by a whole channel.
+    pub timezones: HashMap<String, String>,
+}
+
+impl ChannelsConfig {
+    /// A chat's timezone: its own entry, then its channel's, then
+    /// `timezone`. `None` means the server's local time.
+    pub fn timezone_for(&self, channel: &str, chat_id: &str) -> Option<chrono_tz::Tz> {
+        let name = self
+            .timezones
+            .get(&format!("{channel}:{chat_id}"))
+            .or_else(|| self.timezones.get(channel))
+            .unwrap_or(&self.timezone);
+        name.trim().parse().ok()
+    }
+
+    /// `at` on the chat's wall clock.
+    pub fn chat_time(
+        &self,
+        channel: &str,
+        chat_id: &str,
+        at: chrono::DateTime<chrono::Utc>,
+    ) -> chrono::DateTime<chrono::FixedOffset> {
+        match self.timezone_for(channel, chat_id) {
+            Some(tz) => at.with_timezone(&tz).fixed_offset(),
+            None => at.with_timezone(&chrono::Local).fixed_offset(),
+        }
+    }
+}
+
+/// `name` if it is a known IANA timezone; otherwise warn about `key` and
+/// drop it.
+fn known_timezone(key: &str, name: &str) -> Option<String> {
+    let name = name.trim();
+    if name.parse::<chrono_tz::Tz>().is_ok() {
+        return Some(name.to_string());
+    }
+    tracing::warn!("ignoring {key}: unknown timezone '{name}'");
+    None
+}
+
+/// How a channel restarts after its connection drops: exponential backoff
+/// from `base_delay_ms`, capped at `max_delay_ms`, giving up after
+/// `max_attempts` consecutive failures (0 retries forever).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+/// Per-sender budget for inbound attachments (voice notes, audio, files)
+/// over a sliding window, on top of the per-file size checks. 0 disables a
+/// limit.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AttachmentLimits {
+    pub max_count: u32,
+    pub max_bytes: u64,
+    pub window_secs: u64,
 }
 
 /// Transcription (speech-to-text) settings.
@@ -108,9 +396,15 @@ pub struct TranscriptionConfig {
     pub model: String,
     pub language: Option<String>,
     pub max_bytes: usize,
+    /// Longest clip accepted for transcription; 0 disables the check.
+    pub max_duration_secs: u64,
     pub mistral_diarize: bool,
     pub mistral_context_bias: Option<String>,
     pub mistral_timestamp_granularities: Vec<String>,
+    /// Per-attempt HTTP timeout for the provider call.
+    pub timeout_secs: u64,
+    /// Extra attempts after a transient failure (timeouts, 429, 5xx).
+    pub max_retries: u32,
 }
 
 /// Memory mode: none, simple (file-based), or smart (vector + file).
@@ -138,13 +432,122 @@ impl MemoryMode {
     }
 }
 
+/// How a message is mapped to its memory namespace.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NamespaceStrategy {
+    /// One namespace per chat (`telegram_42`).
+    #[default]
+    #[serde(rename = "per_chat")]
+    Chat,
+    /// One namespace per sender on a platform, shared by all their chats.
+    #[serde(rename = "per_sender")]
+    Sender,
+    /// Senders listed in `memory.users` share one namespace across
+    /// platforms; anyone else falls back to `Sender`.
+    #[serde(rename = "per_user")]
+    User,
+}
+
+impl NamespaceStrategy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "per_chat" | "chat" => Some(Self::Chat),
+            "per_sender" | "sender" => Some(Self::Sender),
+            "per_user" | "user" => Some(Self::User),
+            _ => None,
+        }
+    }
+}
+
+/// Vector recall tuning shared by prompt injection and `memory_search`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RecallConfig {
+    pub top_k: usize,
+    /// Minimum cosine similarity in `0.0..=1.0`.
+    pub threshold: f32,
+    /// Weight of stored priority vs. similarity in `0.0..=1.0`.
+    pub priority_weight: f32,
+    /// Longest wait for the query embedding before per-turn recall is
+    /// skipped; 0 waits indefinitely.
+    pub embed_timeout_ms: u64,
+}
+
+/// When and over how much history Smart mode summarizes conversations.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SummaryConfig {
+    /// New user turns since the last summary before another one runs.
+    pub trigger_user_turns: usize,
+    /// Already-summarized messages included ahead of the new ones.
+    pub context_messages: usize,
+    /// Upper bound on messages sent to the summarizer.
+    pub max_window_messages: usize,
+    /// Upper bound on the summarizer window's estimated size in tokens.
+    pub max_window_tokens: usize,
+}
+
+impl SummaryConfig {
+    fn is_valid(&self) -> bool {
+        self.trigger_user_turns >= 1
+            && self.max_window_messages >= self.context_messages
+            && self.max_window_tokens > 0
+    }
+}
+
 /// Memory (vector store for Smart mode) settings.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MemoryConfig {
     pub mode: MemoryMode,
     pub embedding_model: String,
+    /// Model for background summaries (`provider/model` or a bare model on
+    /// the main provider); the chat model when `None`.
+    pub summary_model: Option<String>,
 
     pub max_memories: usize,
+    /// Per-namespace prune caps keyed by the exact namespace or by its
+    /// channel prefix (`telegram`, `discord`).
+    pub max_memories_by_namespace: HashMap<String, usize>,
+    pub recall: RecallConfig,
+    pub summary: SummaryConfig,
+    /// Namespace readable/writable from every session (disabled when `None`).
+    pub shared_namespace: Option<String>,
+    pub namespace_strategy: NamespaceStrategy,
+    /// Logical user for `channel:sender_id` pairs, from `memory.users`
+    /// (`{"alice": ["telegram:123", "discord:456"]}`). Used by `per_user`.
+    pub users: HashMap<String, String>,
+    /// Embed each user/assistant turn verbatim (Smart mode), in a
+    /// `<namespace>__turns` namespace of their own so they never crowd out
+    /// facts. Growth is bounded by `max_memories` pruning.
+    pub store_raw_turns: bool,
+    /// Turns shorter than this are not embedded as raw turns.
+    pub raw_turn_min_chars: usize,
+    /// Keep the embedding cache on disk so restarts don't re-embed.
+    pub persist_embedding_cache: bool,
+    /// MEMORY.md size (chars) above which the oldest entries are moved to a
+    /// monthly archive file; 0 (the default) disables archival.
+    pub max_file_chars: usize,
+    /// Periodically re-fetch URL-sourced grounded facts and flag stale ones.
+    pub verify_grounded_facts: bool,
+    /// Keep tool calls and their (truncated) results in `/export` transcripts.
+    pub transcript_tool_results: bool,
+    /// Recall with the previous user turn prepended when the message looks
+    /// like a follow-up ("what about that?"), not the raw message alone.
+    pub context_aware_recall: bool,
+    /// Copy trigger-phrase observations ("my name is", "i prefer") from
+    /// user messages and summaries into MEMORY.md. Off means only explicit
+    /// `remember` calls write memory.
+    pub auto_extract: bool,
+    /// Simple mode: ask `summary_model` for durable user facts after each
+    /// turn. Trigger phrases are matched instead when that call fails.
+    pub llm_extract: bool,
+    /// Smart mode: `remember` writes MEMORY.md and returns, embedding the
+    /// memory on a background task. Vector failures are only logged.
+    pub async_remember: bool,
+    /// Characters of memory (file notes plus session recall) injected into
+    /// each prompt.
+    pub context_budget_chars: usize,
+    /// Share of `context_budget_chars` given to file memory (0.0–1.0); the
+    /// rest goes to session vector recall.
+    pub context_file_share: f32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -152,6 +555,8 @@ pub struct MemoryConfig {
 pub enum WebSearchProvider {
     Brave,
     Firecrawl,
+    /// A self-hosted SearXNG instance at `tools.web.search.searxng_base_url`.
+    Searxng,
 }
 
 impl WebSearchProvider {
@@ -159,9 +564,18 @@ impl WebSearchProvider {
         match raw.trim().to_ascii_lowercase().as_str() {
             "brave" => Some(Self::Brave),
             "firecrawl" => Some(Self::Firecrawl),
+            "searxng" | "searx" => Some(Self::Searxng),
             _ => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Brave => "brave",
+            Self::Firecrawl => "firecrawl",
+            Self::Searxng => "searxng",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -185,11 +599,89 @@ impl WebFetchProvider {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ToolsConfig {
     pub exec_timeout_secs: u64,
+    /// Longest any tool call may run before the model gets a timeout
+    /// error; 0 disables the bound.
+    pub timeout_secs: u64,
+    /// Bound for `web_search` and `web_fetch`; `timeout_secs` when unset.
+    pub web_timeout_secs: Option<u64>,
+    /// Bound for `web_fetch` alone; overrides `web_timeout_secs`.
+    pub web_fetch_timeout_secs: Option<u64>,
+    /// Longest tool result, in characters, the model is shown; 0 disables
+    /// the cap.
+    pub max_output_chars: usize,
+    /// Per-tool overrides of `max_output_chars`, keyed by tool name.
+    pub max_output_chars_by_tool: HashMap<String, usize>,
+    /// Regexes for commands `exec` only runs with `confirm: true`.
+    pub exec_confirm_patterns: Vec<String>,
+    /// Confine file tools and exec working dirs to the workspace (default on).
     pub restrict_to_workspace: bool,
     pub web_search_provider: WebSearchProvider,
+    /// Providers `web_search` tries, in order, when the primary is down or
+    /// out of quota.
+    pub web_search_fallbacks: Vec<WebSearchProvider>,
     pub web_fetch_provider: WebFetchProvider,
+    /// Response bytes `web_fetch` reads before giving up on the body.
+    pub web_fetch_max_download_bytes: usize,
+    pub web_fetch_max_redirects: usize,
+    /// Let `web_fetch` reach loopback, private and link-local addresses.
+    pub web_fetch_allow_internal_hosts: bool,
     pub brave_api_key: Option<String>,
     pub firecrawl_api_key: Option<String>,
+    /// Root URL of a SearXNG instance with the JSON format enabled.
+    pub searxng_base_url: Option<String>,
+    /// Allowlist of tool names; `None` enables every tool.
+    pub enabled: Option<Vec<String>>,
+    /// Tool names removed after applying `enabled`.
+    pub disabled: Vec<String>,
+    /// Read-only agent: mutating tools refuse and memory is never written.
+    pub safe_mode: bool,
+    /// Let `send_message` target chats other than the one the turn came from.
+    /// Scheduled (cron) turns may always send elsewhere.
+    pub send_allow_cross_session: bool,
+}
+
+/// Log output settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Scrub configured secrets and token-shaped strings from log output.
+    pub redact: bool,
+    /// Also replace message bodies (prompts, completions, content fields).
+    pub redact_content: bool,
+}
+
+/// Scheduled job settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CronConfig {
+    /// Deliver a cron turn's final text to its chat instead of suppressing
+    /// it and relying on `send_message`.
+    pub auto_reply: bool,
+}
+
+/// Message bus settings.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BusConfig {
+    /// Journal inbound messages to disk and replay un-acked ones on startup,
+    /// for at-least-once processing across crashes.
+    pub durable: bool,
+}
+
+/// Post-processing applied to replies before channels render them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OutboundConfig {
+    /// Stage names run in order: `redact_pii`, `footer`, `trim`.
+    pub post_process: Vec<String>,
+    /// Text the `footer` stage appends.
+    pub footer: String,
+    /// Character limit for the `trim` stage; 0 disables it.
+    pub max_chars: usize,
+}
+
+/// Operator-supplied message transforms.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// WebAssembly module (`.wasm` or `.wat`) run on the text of every
+    /// inbound and outbound message.
+    pub wasm: Option<PathBuf>,
 }
 
 // ---------------------------------------------------------------------------
@@ -205,13 +697,19 @@ pub struct AppConfig {
     pub transcription: TranscriptionConfig,
     pub memory: MemoryConfig,
     pub tools: ToolsConfig,
+    pub cron: CronConfig,
+    pub bus: BusConfig,
+    pub outbound: OutboundConfig,
+    pub plugins: PluginsConfig,
+    pub logging: LoggingConfig,
     pub data_dir: PathBuf,
     pub workspace_dir: PathBuf,
 }
 
 impl AppConfig {
     pub fn load() -> Result<Self> {
-        let cfg = Self::load_relaxed();
+        let (cfg, unresolved) = Self::load_with_secrets();
+        cfg.check_unresolved(&unresolved)?;
 
         if cfg.provider_requires_api_key() && cfg.provider_api_key().trim().is_empty() {
             return Err(anyhow!(
@@ -220,22 +718,53 @@ impl AppConfig {
                 cfg.provider.as_str()
             ));
         }
+        if let Some(problem) = cfg.smart_memory_problem() {
+            return Err(anyhow!(problem));
+        }
 
         Ok(cfg)
     }
 
+    /// Like `load`, but never fails: unresolved secret references are
+    /// logged and left empty, and missing keys are not checked.
     pub fn load_relaxed() -> Self {
+        let (cfg, unresolved) = Self::load_with_secrets();
+        for err in unresolved {
+            tracing::warn!("{err}");
+        }
+        cfg
+    }
+
+    /// Fails for an unresolved reference in the active provider's API key,
+    /// which the service can't run without; the rest are only warned about.
+    fn check_unresolved(&self, unresolved: &[UnresolvedSecret]) -> Result<()> {
+        for err in unresolved {
+            if err.is_api_key_of(&self.provider) && self.provider_api_key().trim().is_empty() {
+                return Err(anyhow!("{err}"));
+            }
+        }
+        for err in unresolved {
+            tracing::warn!("{err}");
+        }
+        Ok(())
+    }
+
+    /// Config with `${VAR}`/`file:` references resolved, plus each
+    /// reference that couldn't be.
+    fn load_with_secrets() -> (Self, Vec<UnresolvedSecret>) {
         let mut cfg = Self::defaults();
+        let mut unresolved = Vec::new();
 
-        if let Some(lightclaw) = load_lightclaw_config() {
+        if let Some(mut lightclaw) = load_lightclaw_config() {
+            unresolved = resolve_secret_refs(&mut lightclaw);
             apply_lightclaw_config(&mut cfg, &lightclaw);
         }
 
         apply_env_overrides(&mut cfg);
-        cfg
+        (cfg, unresolved)
     }
 
-    fn defaults() -> Self {
+    pub(crate) fn defaults() -> Self {
         Self {
             provider: ProviderKind::OpenRouter,
             providers: ProvidersConfig {
@@ -251,10 +780,19 @@ impl AppConfig {
                     base_url: "https://api.openai.com/v1".to_string(),
                     extra_headers: Vec::new(),
                 },
-                ollama: ProviderEntry {
+                ollama: OllamaEntry {
                     api_key: String::new(),
                     base_url: "http://127.0.0.1:11434/v1".to_string(),
                     extra_headers: Vec::new(),
+                    keep_alive: None,
+                    preload: false,
+                },
+                azure: AzureEntry {
+                    api_key: String::new(),
+                    endpoint: String::new(),
+                    deployment: String::new(),
+                    api_version: "2024-10-21".to_string(),
+                    extra_headers: Vec::new(),
                 },
                 mistral: MistralEntry {
                     api_key: String::new(),
@@ -265,16 +803,59 @@ impl AppConfig {
                 model: "anthropic/claude-opus-4-5".to_string(),
                 fallbacks: Vec::new(),
                 max_tool_turns: 20,
+                max_tool_turns_by_model: HashMap::new(),
+                stop_sequences: Vec::new(),
+                max_response_tokens: 4096,
+                compaction_max_tokens: crate::session_compaction::DEFAULT_COMPACTION_MAX_TOKENS,
+                max_continuations: 2,
+                match_user_language: false,
+                inject_time_context: true,
+                max_retries: 2,
+                retry_base_delay_ms: 400,
+                max_routes_attempted: None,
+                deterministic: false,
+                log_reasoning: false,
+                unconfigured_reply: String::new(),
+                inbound_debounce_ms: 0,
             },
             channels: ChannelsConfig {
                 telegram: TelegramConfig {
                     bot_token: String::new(),
                     allow_from: Vec::new(),
+                    match_mode: AllowMatchMode::Exact,
+                    mode: TelegramMode::Polling,
+                    webhook_url: String::new(),
+                    webhook_secret: String::new(),
+                    webhook_listen: "0.0.0.0:8443".to_string(),
+                    show_typing: true,
+                    bots: Vec::new(),
                 },
                 discord: DiscordConfig {
                     bot_token: String::new(),
                     allow_from: Vec::new(),
+                    match_mode: AllowMatchMode::Exact,
                     allowed_channels: Vec::new(),
+                    allowed_roles: Vec::new(),
+                    guild_response_mode: GuildResponseMode::Mention,
+                    guild_prefix: "!".to_string(),
+                    guild_prefixes: HashMap::new(),
+                    show_typing: true,
+                    bots: Vec::new(),
+                },
+                admins: Vec::new(),
+                attachment_limits: AttachmentLimits {
+                    max_count: 30,
+                    max_bytes: 200 * 1024 * 1024,
+                    window_secs: 3600,
+                },
+                max_attachments_per_message: 10,
+                greeting: String::new(),
+                timezone: String::new(),
+                timezones: HashMap::new(),
+                reconnect: ReconnectPolicy {
+                    max_attempts: 10,
+                    base_delay_ms: 1000,
+                    max_delay_ms: 5 * 60 * 1000,
                 },
             },
             transcription: TranscriptionConfig {
@@ -283,22 +864,77 @@ impl AppConfig {
                 model: "whisper-1".to_string(),
                 language: None,
                 max_bytes: 20 * 1024 * 1024,
+                max_duration_secs: 900,
                 mistral_diarize: false,
                 mistral_context_bias: None,
                 mistral_timestamp_granularities: Vec::new(),
+                timeout_secs: 60,
+                max_retries: 2,
             },
             memory: MemoryConfig {
                 mode: MemoryMode::Simple,
                 embedding_model: "text-embedding-3-small".to_string(),
+                summary_model: None,
                 max_memories: 1000,
+                max_memories_by_namespace: HashMap::new(),
+                recall: RecallConfig {
+                    top_k: 3,
+                    threshold: 0.08,
+                    priority_weight: 0.3,
+                    embed_timeout_ms: 3000,
+                },
+                summary: SummaryConfig {
+                    trigger_user_turns: 3,
+                    context_messages: 6,
+                    max_window_messages: 18,
+                    max_window_tokens: 6_000,
+                },
+                shared_namespace: None,
+                namespace_strategy: NamespaceStrategy::Chat,
+                users: HashMap::new(),
+                store_raw_turns: false,
+                raw_turn_min_chars: 40,
+                persist_embedding_cache: false,
+                max_file_chars: 0,
+                verify_grounded_facts: false,
+                transcript_tool_results: false,
+                context_aware_recall: false,
+                auto_extract: true,
+                llm_extract: false,
+                async_remember: false,
+                context_budget_chars: 10_000,
+                context_file_share: 0.8,
             },
             tools: ToolsConfig {
                 exec_timeout_secs: 60,
-                restrict_to_workspace: false,
+                timeout_secs: 120,
+                web_timeout_secs: None,
+                web_fetch_timeout_secs: None,
+                max_output_chars: 50_000,
+                max_output_chars_by_tool: HashMap::new(),
+                exec_confirm_patterns: default_exec_confirm_patterns(),
+                restrict_to_workspace: true,
                 web_search_provider: WebSearchProvider::Brave,
+                web_search_fallbacks: Vec::new(),
                 web_fetch_provider: WebFetchProvider::Native,
+                web_fetch_max_download_bytes: 5 * 1024 * 1024,
+                web_fetch_max_redirects: 5,
+                web_fetch_allow_internal_hosts: false,
                 brave_api_key: None,
                 firecrawl_api_key: None,
+                searxng_base_url: None,
+                enabled: None,
+                disabled: Vec::new(),
+                safe_mode: false,
+                send_allow_cross_session: false,
+            },
+            cron: CronConfig { auto_reply: false },
+            bus: BusConfig { durable: false },
+            outbound: OutboundConfig::default(),
+            plugins: PluginsConfig::default(),
+            logging: LoggingConfig {
+                redact: true,
+                redact_content: false,
             },
             data_dir: default_data_dir(),
             workspace_dir: default_workspace_dir(),
@@ -306,35 +942,149 @@ impl AppConfig {
     }
 
     pub fn provider_api_key(&self) -> &str {
-        match self.provider {
+        self.api_key_for(&self.provider)
+    }
+
+    pub fn provider_requires_api_key(&self) -> bool {
+        self.provider.requires_api_key()
+    }
+
+    fn api_key_for(&self, provider: &ProviderKind) -> &str {
+        match provider {
             ProviderKind::OpenRouter => &self.providers.openrouter.api_key,
             ProviderKind::OpenAI => &self.providers.openai.api_key,
             ProviderKind::Ollama => &self.providers.ollama.api_key,
+            ProviderKind::Azure => &self.providers.azure.api_key,
         }
     }
 
-    pub fn provider_requires_api_key(&self) -> bool {
+    /// Route for a one-off `--model` override (`provider/model` or a bare
+    /// model on the main provider), checked for the provider's API key.
+    pub fn route_override(&self, raw: &str) -> Result<ModelRoute> {
+        let route = parse_model_route(raw, &self.provider)
+            .ok_or_else(|| anyhow!("invalid model '{raw}' (expected provider/model)"))?;
+        if route.provider.requires_api_key() && self.api_key_for(&route.provider).trim().is_empty()
+        {
+            return Err(anyhow!(
+                "missing API key for provider '{}' (set env var or providers.{}.apiKey in ~/.lightclaw/config.json)",
+                route.provider.as_str(),
+                route.provider.as_str()
+            ));
+        }
+        Ok(route)
+    }
+
+    /// Embedding model id to request from the active provider. OpenRouter
+    /// wants vendor-prefixed ids, so a bare OpenAI model such as the default
+    /// `text-embedding-3-small` is sent as `openai/text-embedding-3-small`.
+    pub fn embedding_model(&self) -> String {
+        let model = self.memory.embedding_model.trim();
+        match self.provider {
+            ProviderKind::OpenRouter
+                if !model.contains('/') && model.starts_with("text-embedding-") =>
+            {
+                format!("openai/{model}")
+            }
+            _ => model.to_string(),
+        }
+    }
+
+    /// Why Smart memory can't get embeddings from the active provider, if it
+    /// can't. Only catches what is knowable without a network call; `doctor`
+    /// probes the model itself.
+    pub fn smart_memory_problem(&self) -> Option<String> {
+        if self.memory.mode != MemoryMode::Smart {
+            return None;
+        }
+        let model = self.embedding_model();
+        let provider = self.provider.as_str();
+        if model.is_empty() {
+            return Some(format!(
+                "memory.mode is smart but memory.embedding_model is empty; set an embedding model served by '{provider}' or switch memory.mode to simple"
+            ));
+        }
         match self.provider {
-            ProviderKind::OpenRouter | ProviderKind::OpenAI => true,
-            ProviderKind::Ollama => false,
+            ProviderKind::Ollama if model.starts_with("text-embedding-") => Some(format!(
+                "memory.mode is smart but Ollama does not serve the OpenAI embedding model '{model}'; pull a local one (e.g. `ollama pull nomic-embed-text`) and set memory.embedding_model, or switch memory.mode to simple"
+            )),
+            ProviderKind::OpenRouter if !model.contains('/') => Some(format!(
+                "memory.mode is smart but '{model}' is not an OpenRouter embedding model id; use the vendor-prefixed form (e.g. openai/{model}) in memory.embedding_model, or switch memory.mode to simple"
+            )),
+            _ => None,
         }
     }
 
     pub fn telegram_enabled(&self) -> bool {
         !self.channels.telegram.bot_token.trim().is_empty()
+            || !self.channels.telegram.bots.is_empty()
     }
 
     pub fn discord_enabled(&self) -> bool {
-        !self.channels.discord.bot_token.trim().is_empty()
+        !self.channels.discord.bot_token.trim().is_empty() || !self.channels.discord.bots.is_empty()
+    }
+
+    /// Persona of the extra bot behind bus channel `channel`, if any.
+    pub fn bot_persona(&self, channel: &str) -> Option<&str> {
+        let (platform, name) = channel.split_once('.')?;
+        let bots = match platform {
+            "telegram" => &self.channels.telegram.bots,
+            "discord" => &self.channels.discord.bots,
+            _ => return None,
+        };
+        bots.iter()
+            .find(|bot| bot.name == name)
+            .and_then(|bot| bot.persona.as_deref())
+    }
+
+    /// Tool-turn cap for `route`: an exact `provider/model` override, then a
+    /// bare `model` override, then the global `max_tool_turns`.
+    pub fn max_tool_turns_for(&self, route: &ModelRoute) -> usize {
+        let overrides = &self.model.max_tool_turns_by_model;
+        overrides
+            .get(&format!("{}/{}", route.provider.as_str(), route.model))
+            .or_else(|| overrides.get(&route.model))
+            .copied()
+            .unwrap_or(self.model.max_tool_turns)
+    }
+
+    /// Route used by the Smart-mode summarizer.
+    pub fn summary_route(&self) -> ModelRoute {
+        self.memory
+            .summary_model
+            .as_deref()
+            .and_then(|raw| parse_model_route(raw, &self.provider))
+            .unwrap_or_else(|| ModelRoute {
+                provider: self.provider.clone(),
+                model: self.model.model.clone(),
+            })
+    }
+
+    /// Search providers in the order `web_search` tries them: the primary,
+    /// then each fallback not already listed.
+    pub fn web_search_chain(&self) -> Vec<WebSearchProvider> {
+        let mut chain = vec![self.tools.web_search_provider.clone()];
+        for provider in &self.tools.web_search_fallbacks {
+            if !chain.contains(provider) {
+                chain.push(provider.clone());
+            }
+        }
+        chain
     }
 
     pub fn model_routes(&self) -> Vec<ModelRoute> {
         let mut routes = Vec::new();
         let mut seen = HashSet::new();
 
+        // Azure routes address a deployment, which stands in for the model name.
+        let primary_model = match self.provider {
+            ProviderKind::Azure if !self.providers.azure.deployment.trim().is_empty() => {
+                self.providers.azure.deployment.trim()
+            }
+            _ => self.model.model.trim(),
+        };
         let primary = ModelRoute {
             provider: self.provider.clone(),
-            model: self.model.model.trim().to_string(),
+            model: primary_model.to_string(),
         };
         if !primary.model.is_empty() {
             let key = format!("{}/{}", primary.provider.as_str(), primary.model);
@@ -388,6 +1138,21 @@ fn default_config_path() -> Option<PathBuf> {
     legacy
 }
 
+fn default_exec_confirm_patterns() -> Vec<String> {
+    [
+        r"\brm\s+-[a-z]*(r[a-z]*f|f[a-z]*r)\b",
+        r"\bgit\s+push\b.*\s(--force|-f)\b",
+        // A `+` refspec force-pushes that one branch.
+        r"\bgit\s+push\b[^;&|]*\s\+\S",
+        r"\bdd\s+(if|of)=",
+        r"\bmkfs(\.[a-z0-9_+-]+)?\b",
+        r":\(\)\s*\{",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
 fn default_data_dir() -> PathBuf {
     let legacy = dirs::home_dir().map(|p| p.join(".lightclaw").join("data"));
     if let Some(ref p) = legacy {
@@ -427,7 +1192,146 @@ fn load_lightclaw_config() -> Option<Value> {
     serde_json::from_str::<Value>(&content).ok()
 }
 
-fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
+/// How many references a secret may pass through (`${A}` holding `file:...`
+/// holding `${B}`, ...) before it counts as a loop.
+const MAX_SECRET_REF_DEPTH: usize = 4;
+
+/// Looks up an environment variable; injectable so tests don't touch the
+/// process environment.
+type EnvLookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// A `${VAR}` or `file:` reference that couldn't be resolved.
+#[derive(Debug)]
+pub(crate) struct UnresolvedSecret {
+    /// Dotted path of the config value, e.g. `providers.openai.apiKey`.
+    pub path: String,
+    pub reason: String,
+}
+
+impl UnresolvedSecret {
+    /// Whether this is `provider`'s API key.
+    fn is_api_key_of(&self, provider: &ProviderKind) -> bool {
+        let parts: Vec<&str> = self.path.split('.').collect();
+        match parts.as_slice() {
+            ["providers", name, "apiKey" | "api_key"] => {
+                ProviderKind::parse(name).as_ref() == Some(provider)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for UnresolvedSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "config value {}: {}", self.path, self.reason)
+    }
+}
+
+/// Replace every string in `value` that is a whole `${VAR}` or `file:/path`
+/// reference with what it points to, so secrets can live outside
+/// config.json. Unresolvable references become empty strings; the returned
+/// list says which and why.
+pub(crate) fn resolve_secret_refs(value: &mut Value) -> Vec<UnresolvedSecret> {
+    resolve_secret_refs_with(value, &|var| std::env::var(var).ok())
+}
+
+/// `resolve_secret_refs` reading `${VAR}` references through `env`.
+fn resolve_secret_refs_with(value: &mut Value, env: EnvLookup<'_>) -> Vec<UnresolvedSecret> {
+    let mut errors = Vec::new();
+    resolve_refs_at(value, "", env, &mut errors);
+    errors
+}
+
+fn resolve_refs_at(
+    value: &mut Value,
+    path: &str,
+    env: EnvLookup<'_>,
+    errors: &mut Vec<UnresolvedSecret>,
+) {
+    match value {
+        Value::String(raw) => {
+            if secret_ref(raw).is_none() {
+                return;
+            }
+            match resolve_secret(raw, env) {
+                Ok(resolved) => *raw = resolved,
+                Err(err) => {
+                    errors.push(UnresolvedSecret {
+                        path: path.to_string(),
+                        reason: err.to_string(),
+                    });
+                    raw.clear();
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (idx, item) in items.iter_mut().enumerate() {
+                resolve_refs_at(item, &format!("{path}[{idx}]"), env, errors);
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                resolve_refs_at(item, &path, env, errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+enum SecretRef<'a> {
+    Env(&'a str),
+    File(&'a str),
+}
+
+fn secret_ref(raw: &str) -> Option<SecretRef<'_>> {
+    let raw = raw.trim();
+    if let Some(var) = raw
+        .strip_prefix("${")
+        .and_then(|rest| rest.strip_suffix('}'))
+    {
+        return Some(SecretRef::Env(var.trim()));
+    }
+    raw.strip_prefix("file:")
+        .map(|path| SecretRef::File(path.trim()))
+}
+
+fn resolve_secret(raw: &str, env: EnvLookup<'_>) -> Result<String> {
+    let mut current = raw.to_string();
+    for _ in 0..MAX_SECRET_REF_DEPTH {
+        let next = match secret_ref(&current) {
+            None => return Ok(current),
+            Some(SecretRef::Env(var)) => env(var)
+                .filter(|val| !val.trim().is_empty())
+                .ok_or_else(|| anyhow!("environment variable {var} is not set"))?,
+            Some(SecretRef::File(path)) => {
+                let path = match path.strip_prefix("~/") {
+                    Some(rest) => dirs::home_dir()
+                        .ok_or_else(|| anyhow!("no home directory for {path}"))?
+                        .join(rest),
+                    None => PathBuf::from(path),
+                };
+                std::fs::read_to_string(&path)
+                    .map_err(|err| anyhow!("cannot read {}: {err}", path.display()))?
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string()
+            }
+        };
+        current = next;
+    }
+    match secret_ref(&current) {
+        None => Ok(current),
+        Some(_) => Err(anyhow!(
+            "more than {MAX_SECRET_REF_DEPTH} nested secret references"
+        )),
+    }
+}
+
+pub(crate) fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     if let Some(provider) = get_str(value, &["agents", "defaults", "provider"])
         .or_else(|| get_str(value, &["llm", "provider"]))
     {
@@ -439,6 +1343,19 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     apply_provider_config(cfg, value, &["openrouter"], ProviderKind::OpenRouter);
     apply_provider_config(cfg, value, &["openai"], ProviderKind::OpenAI);
     apply_provider_config(cfg, value, &["ollama"], ProviderKind::Ollama);
+    if let Some(obj) = get_provider_object(value, &["ollama"]) {
+        match obj.get("keep_alive").or_else(|| obj.get("keepAlive")) {
+            Some(Value::String(v)) if !v.trim().is_empty() => {
+                cfg.providers.ollama.keep_alive = Some(v.trim().to_string());
+            }
+            Some(Value::Number(n)) => cfg.providers.ollama.keep_alive = Some(n.to_string()),
+            _ => {}
+        }
+        if let Some(v) = obj.get("preload").and_then(Value::as_bool) {
+            cfg.providers.ollama.preload = v;
+        }
+    }
+    apply_provider_config(cfg, value, &["azure", "azure_openai"], ProviderKind::Azure);
     if let Some(obj) = get_provider_object(value, &["mistral"]) {
         if let Some(v) = obj
             .get("apiKey")
@@ -470,19 +1387,72 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     if let Some(timeout) = get_u64(value, &["tools", "exec", "timeout"]) {
         cfg.tools.exec_timeout_secs = timeout;
     }
+    if let Some(timeout) = get_u64(value, &["tools", "timeout_secs"]) {
+        cfg.tools.timeout_secs = timeout;
+    }
+    if let Some(timeout) = get_u64(value, &["tools", "web", "timeout_secs"]) {
+        cfg.tools.web_timeout_secs = Some(timeout);
+    }
+    if let Some(timeout) = get_u64(value, &["tools", "web", "fetch", "timeout_secs"])
+        .or_else(|| get_u64(value, &["tools", "fetch", "timeout_secs"]))
+    {
+        cfg.tools.web_fetch_timeout_secs = Some(timeout);
+    }
+    if let Some(max) = get_u64(value, &["tools", "max_output_chars"]) {
+        cfg.tools.max_output_chars = max as usize;
+    }
+    if let Some(map) = value
+        .pointer("/tools/max_output_chars_by_tool")
+        .and_then(Value::as_object)
+    {
+        cfg.tools.max_output_chars_by_tool = map
+            .iter()
+            .filter_map(|(tool, max)| Some((tool.trim().to_string(), max.as_u64()? as usize)))
+            .filter(|(tool, _)| !tool.is_empty())
+            .collect();
+    }
+    if let Some(patterns) = get_array(value, &["tools", "exec", "confirm_patterns"]) {
+        cfg.tools.exec_confirm_patterns = patterns;
+    }
     if let Some(restrict) = get_bool(value, &["tools", "restrict_to_workspace"]) {
         cfg.tools.restrict_to_workspace = restrict;
     }
+    if let Some(list) = get_array(value, &["tools", "enabled"]) {
+        cfg.tools.enabled = Some(list);
+    }
+    if let Some(list) = get_array(value, &["tools", "disabled"]) {
+        cfg.tools.disabled = list;
+    }
+    if let Some(safe) = get_bool(value, &["tools", "safe_mode"]) {
+        cfg.tools.safe_mode = safe;
+    }
+    if let Some(allow) = get_bool(value, &["tools", "send", "allow_cross_session"]) {
+        cfg.tools.send_allow_cross_session = allow;
+    }
     if let Some(provider) = get_str(value, &["tools", "web", "search", "provider"]) {
         if let Some(parsed) = WebSearchProvider::parse(provider) {
             cfg.tools.web_search_provider = parsed;
         }
     }
+    if let Some(list) = get_array(value, &["tools", "web", "search", "fallbacks"]) {
+        cfg.tools.web_search_fallbacks = parse_search_providers(&list);
+    }
     if let Some(provider) = get_str(value, &["tools", "web", "fetch", "provider"]) {
         if let Some(parsed) = WebFetchProvider::parse(provider) {
             cfg.tools.web_fetch_provider = parsed;
         }
     }
+    if let Some(max) = get_u64(value, &["tools", "web", "fetch", "max_download_bytes"]) {
+        if max > 0 {
+            cfg.tools.web_fetch_max_download_bytes = max as usize;
+        }
+    }
+    if let Some(max) = get_u64(value, &["tools", "web", "fetch", "max_redirects"]) {
+        cfg.tools.web_fetch_max_redirects = max as usize;
+    }
+    if let Some(allow) = get_bool(value, &["tools", "web", "fetch", "allow_internal_hosts"]) {
+        cfg.tools.web_fetch_allow_internal_hosts = allow;
+    }
     if let Some(legacy_key) = get_str(value, &["tools", "web", "search", "api_key"])
         .or_else(|| get_str(value, &["tools", "web", "search", "apiKey"]))
     {
@@ -504,41 +1474,156 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     {
         cfg.tools.firecrawl_api_key = Some(firecrawl.to_string());
     }
+    if let Some(url) = get_str(value, &["tools", "web", "search", "searxng_base_url"])
+        .or_else(|| get_str(value, &["tools", "web", "search", "searxngBaseUrl"]))
+    {
+        cfg.tools.searxng_base_url = non_empty(url);
+    }
     if let Some(token) = get_str(value, &["channels", "telegram", "token"]) {
         cfg.channels.telegram.bot_token = token.to_string();
     }
     if let Some(list) = get_array(value, &["channels", "telegram", "allow_from"]) {
         cfg.channels.telegram.allow_from = list;
     }
+    if let Some(mode) = get_str(value, &["channels", "telegram", "match_mode"]) {
+        if let Some(parsed) = AllowMatchMode::parse(mode) {
+            cfg.channels.telegram.match_mode = parsed;
+        }
+    }
+    if let Some(show) = get_bool(value, &["channels", "telegram", "show_typing"]) {
+        cfg.channels.telegram.show_typing = show;
+    }
+    if let Some(mode) = get_str(value, &["channels", "telegram", "mode"]) {
+        if let Some(parsed) = TelegramMode::parse(mode) {
+            cfg.channels.telegram.mode = parsed;
+        }
+    }
+    if let Some(url) = get_str(value, &["channels", "telegram", "webhook", "url"]) {
+        cfg.channels.telegram.webhook_url = url.trim().to_string();
+    }
+    if let Some(secret) = get_str(value, &["channels", "telegram", "webhook", "secret_token"]) {
+        cfg.channels.telegram.webhook_secret = secret.trim().to_string();
+    }
+    if let Some(listen) = get_str(value, &["channels", "telegram", "webhook", "listen"]) {
+        if let Some(listen) = non_empty(listen) {
+            cfg.channels.telegram.webhook_listen = listen;
+        }
+    }
+    if let Some(bots) = get_bots(value, "telegram") {
+        cfg.channels.telegram.bots = bots;
+    }
     if let Some(token) = get_str(value, &["channels", "discord", "token"]) {
         cfg.channels.discord.bot_token = token.to_string();
     }
+    if let Some(bots) = get_bots(value, "discord") {
+        cfg.channels.discord.bots = bots;
+    }
     if let Some(list) = get_array(value, &["channels", "discord", "allow_from"]) {
         cfg.channels.discord.allow_from = list;
     }
     if let Some(list) = get_array(value, &["channels", "discord", "allowed_channels"]) {
         cfg.channels.discord.allowed_channels = list;
     }
-    if let Some(enabled) = get_bool(value, &["channels", "telegram", "transcription", "enabled"]) {
-        cfg.transcription.enabled = enabled;
-    }
-    if let Some(provider) = get_str(
-        value,
-        &["channels", "telegram", "transcription", "provider"],
-    ) {
-        if !provider.trim().is_empty() {
-            cfg.transcription.provider = provider.to_string();
+    if let Some(mode) = get_str(value, &["channels", "discord", "match_mode"]) {
+        if let Some(parsed) = AllowMatchMode::parse(mode) {
+            cfg.channels.discord.match_mode = parsed;
         }
     }
-    if let Some(model) = get_str(value, &["channels", "telegram", "transcription", "model"]) {
-        if !model.trim().is_empty() {
-            cfg.transcription.model = model.to_string();
-        }
+    if let Some(list) = get_array(value, &["channels", "admins"]) {
+        cfg.channels.admins = list;
     }
-    if let Some(language) = get_str(
-        value,
-        &["channels", "telegram", "transcription", "language"],
-    ) {
+    if let Some(n) = get_u64(value, &["channels", "attachment_limits", "max_count"]) {
+        cfg.channels.attachment_limits.max_count = n as u32;
+    }
+    if let Some(n) = get_u64(value, &["channels", "attachment_limits", "max_bytes"]) {
+        cfg.channels.attachment_limits.max_bytes = n;
+    }
+    if let Some(n) = get_u64(value, &["channels", "attachment_limits", "window_secs"]) {
+        cfg.channels.attachment_limits.window_secs = n;
+    }
+    if let Some(n) = get_u64(value, &["channels", "max_attachments_per_message"]) {
+        cfg.channels.max_attachments_per_message = n as usize;
+    }
+    if let Some(greeting) = get_str(value, &["channels", "greeting"]) {
+        cfg.channels.greeting = greeting.to_string();
+    }
+    if let Some(tz) = get_str(value, &["channels", "timezone"]) {
+        if let Some(tz) = known_timezone("channels.timezone", tz) {
+            cfg.channels.timezone = tz;
+        }
+    }
+    if let Some(map) = value
+        .pointer("/channels/timezones")
+        .and_then(Value::as_object)
+    {
+        cfg.channels.timezones = map
+            .iter()
+            .filter_map(|(chat, tz)| {
+                let key = format!("channels.timezones.{chat}");
+                Some((chat.trim().to_string(), known_timezone(&key, tz.as_str()?)?))
+            })
+            .filter(|(chat, _)| !chat.is_empty())
+            .collect();
+    }
+    if let Some(n) = get_u64(value, &["channels", "reconnect", "max_attempts"]) {
+        cfg.channels.reconnect.max_attempts = n as u32;
+    }
+    if let Some(n) = get_u64(value, &["channels", "reconnect", "base_delay_ms"]) {
+        cfg.channels.reconnect.base_delay_ms = n;
+    }
+    if let Some(n) = get_u64(value, &["channels", "reconnect", "max_delay_ms"]) {
+        cfg.channels.reconnect.max_delay_ms = n;
+    }
+    if let Some(list) = get_array(value, &["channels", "discord", "allowed_roles"]) {
+        cfg.channels.discord.allowed_roles = list;
+    }
+    if let Some(show) = get_bool(value, &["channels", "discord", "show_typing"]) {
+        cfg.channels.discord.show_typing = show;
+    }
+    if let Some(mode) = get_str(value, &["channels", "discord", "guild_response_mode"]) {
+        if let Some(parsed) = GuildResponseMode::parse(mode) {
+            cfg.channels.discord.guild_response_mode = parsed;
+        }
+    }
+    for key in ["prefix", "guild_prefix"] {
+        if let Some(prefix) = get_str(value, &["channels", "discord", key]) {
+            if let Some(prefix) = non_empty(prefix) {
+                cfg.channels.discord.guild_prefix = prefix;
+            }
+        }
+    }
+    if let Some(map) = value
+        .pointer("/channels/discord/guild_prefixes")
+        .and_then(Value::as_object)
+    {
+        cfg.channels.discord.guild_prefixes = map
+            .iter()
+            .filter_map(|(guild, prefix)| {
+                Some((guild.trim().to_string(), non_empty(prefix.as_str()?)?))
+            })
+            .filter(|(guild, _)| !guild.is_empty())
+            .collect();
+    }
+    if let Some(enabled) = get_bool(value, &["channels", "telegram", "transcription", "enabled"]) {
+        cfg.transcription.enabled = enabled;
+    }
+    if let Some(provider) = get_str(
+        value,
+        &["channels", "telegram", "transcription", "provider"],
+    ) {
+        if !provider.trim().is_empty() {
+            cfg.transcription.provider = provider.to_string();
+        }
+    }
+    if let Some(model) = get_str(value, &["channels", "telegram", "transcription", "model"]) {
+        if !model.trim().is_empty() {
+            cfg.transcription.model = model.to_string();
+        }
+    }
+    if let Some(language) = get_str(
+        value,
+        &["channels", "telegram", "transcription", "language"],
+    ) {
         if language.trim().is_empty() {
             cfg.transcription.language = None;
         } else {
@@ -551,6 +1636,12 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     ) {
         cfg.transcription.max_bytes = max_bytes as usize;
     }
+    if let Some(secs) = get_u64(
+        value,
+        &["channels", "telegram", "transcription", "max_duration_secs"],
+    ) {
+        cfg.transcription.max_duration_secs = secs;
+    }
     if let Some(diarize) = get_bool(value, &["channels", "telegram", "transcription", "diarize"]) {
         cfg.transcription.mistral_diarize = diarize;
     }
@@ -575,9 +1666,86 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     ) {
         cfg.transcription.mistral_timestamp_granularities = grans;
     }
+    if let Some(secs) = get_u64(
+        value,
+        &["channels", "telegram", "transcription", "timeout_secs"],
+    ) {
+        cfg.transcription.timeout_secs = secs.max(1);
+    }
+    if let Some(retries) = get_u64(
+        value,
+        &["channels", "telegram", "transcription", "max_retries"],
+    ) {
+        cfg.transcription.max_retries = retries.min(10) as u32;
+    }
     if let Some(turns) = get_u64(value, &["agents", "defaults", "max_tool_iterations"]) {
         cfg.model.max_tool_turns = turns as usize;
     }
+    if let Some(map) = value
+        .pointer("/agents/defaults/max_tool_turns_by_model")
+        .and_then(Value::as_object)
+    {
+        cfg.model.max_tool_turns_by_model = map
+            .iter()
+            .filter_map(|(route, turns)| Some((route.trim().to_string(), turns.as_u64()? as usize)))
+            .filter(|(route, _)| !route.is_empty())
+            .collect();
+    }
+    if let Some(stops) = value
+        .pointer("/agents/defaults/stop_sequences")
+        .and_then(Value::as_array)
+    {
+        let stops: Vec<String> = stops
+            .iter()
+            .filter_map(|stop| stop.as_str().map(str::to_string))
+            .collect();
+        match validate_stop_sequences(&stops) {
+            Ok(stops) => cfg.model.stop_sequences = stops,
+            Err(err) => tracing::warn!("ignoring agents.defaults.stop_sequences: {err}"),
+        }
+    }
+    if let Some(tokens) = get_u64(value, &["agents", "defaults", "max_response_tokens"]) {
+        if tokens > 0 {
+            cfg.model.max_response_tokens = tokens;
+        }
+    }
+    if let Some(tokens) = get_u64(value, &["agents", "defaults", "compaction_max_tokens"]) {
+        if tokens > 0 {
+            cfg.model.compaction_max_tokens = tokens as usize;
+        }
+    }
+    if let Some(enabled) = get_bool(value, &["agents", "defaults", "match_user_language"]) {
+        cfg.model.match_user_language = enabled;
+    }
+    if let Some(enabled) = get_bool(value, &["agents", "defaults", "inject_time_context"]) {
+        cfg.model.inject_time_context = enabled;
+    }
+    if let Some(rounds) = get_u64(value, &["agents", "defaults", "max_continuations"]) {
+        cfg.model.max_continuations = (rounds as usize).min(MAX_CONTINUATIONS_LIMIT);
+    }
+    // Negative values aren't u64 and are ignored, keeping the default.
+    if let Some(retries) = get_u64(value, &["agents", "defaults", "max_retries"]) {
+        cfg.model.max_retries = (retries as usize).min(MAX_RETRIES_LIMIT);
+    }
+    if let Some(delay) = get_u64(value, &["agents", "defaults", "retry_base_delay_ms"]) {
+        cfg.model.retry_base_delay_ms = delay;
+    }
+    // 0 means no cap, same as leaving it unset.
+    if let Some(cap) = get_u64(value, &["agents", "defaults", "max_routes_attempted"]) {
+        cfg.model.max_routes_attempted = (cap > 0).then_some(cap as usize);
+    }
+    if let Some(enabled) = get_bool(value, &["agents", "defaults", "deterministic"]) {
+        cfg.model.deterministic = enabled;
+    }
+    if let Some(enabled) = get_bool(value, &["agents", "defaults", "log_reasoning"]) {
+        cfg.model.log_reasoning = enabled;
+    }
+    if let Some(ms) = get_u64(value, &["agents", "defaults", "inbound_debounce_ms"]) {
+        cfg.model.inbound_debounce_ms = ms;
+    }
+    if let Some(reply) = get_str(value, &["agents", "defaults", "unconfigured_reply"]) {
+        cfg.model.unconfigured_reply = reply.to_string();
+    }
     // New "mode" key takes priority over legacy booleans.
     if let Some(mode_str) = get_str(value, &["memory", "mode"]) {
         if let Some(mode) = MemoryMode::parse(mode_str) {
@@ -599,10 +1767,150 @@ fn apply_lightclaw_config(cfg: &mut AppConfig, value: &Value) {
     if let Some(model) = get_str(value, &["memory", "embedding_model"]) {
         cfg.memory.embedding_model = model.to_string();
     }
+    if let Some(model) = get_str(value, &["memory", "summary_model"]) {
+        cfg.memory.summary_model = non_empty(model);
+    }
 
     if let Some(max) = get_u64(value, &["memory", "max_memories"]) {
         cfg.memory.max_memories = max as usize;
     }
+    if let Some(map) = value
+        .pointer("/memory/max_memories_by_namespace")
+        .and_then(Value::as_object)
+    {
+        cfg.memory.max_memories_by_namespace = map
+            .iter()
+            .filter_map(|(ns, max)| Some((ns.trim().to_string(), max.as_u64()? as usize)))
+            .filter(|(ns, _)| !ns.is_empty())
+            .collect();
+    }
+    if let Some(top_k) = get_u64(value, &["memory", "recall_top_k"]) {
+        if top_k > 0 {
+            cfg.memory.recall.top_k = top_k as usize;
+        }
+    }
+    if let Some(threshold) = get_f64(value, &["memory", "recall_threshold"]).and_then(unit_interval)
+    {
+        cfg.memory.recall.threshold = threshold;
+    }
+    if let Some(weight) = get_f64(value, &["memory", "priority_weight"]).and_then(unit_interval) {
+        cfg.memory.recall.priority_weight = weight;
+    }
+    if let Some(ms) = get_u64(value, &["memory", "recall_embed_timeout_ms"]) {
+        cfg.memory.recall.embed_timeout_ms = ms;
+    }
+    if let Some(shared) = get_str(value, &["memory", "shared_namespace"]) {
+        cfg.memory.shared_namespace = non_empty(shared);
+    }
+    if let Some(strategy) =
+        get_str(value, &["memory", "namespace_strategy"]).and_then(NamespaceStrategy::parse)
+    {
+        cfg.memory.namespace_strategy = strategy;
+    }
+    if let Some(users) = value.pointer("/memory/users").and_then(Value::as_object) {
+        cfg.memory.users = users
+            .iter()
+            .filter(|(user, _)| !user.trim().is_empty())
+            .flat_map(|(user, ids)| {
+                ids.as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|id| (id.trim().to_string(), user.trim().to_string()))
+            })
+            .filter(|(id, _)| id.contains(':'))
+            .collect();
+    }
+    // Fields are validated together; an inconsistent section keeps defaults.
+    let mut summary = cfg.memory.summary;
+    if let Some(n) = get_u64(value, &["memory", "summary", "trigger_user_turns"]) {
+        summary.trigger_user_turns = n as usize;
+    }
+    if let Some(n) = get_u64(value, &["memory", "summary", "context_messages"]) {
+        summary.context_messages = n as usize;
+    }
+    if let Some(n) = get_u64(value, &["memory", "summary", "max_window_messages"]) {
+        summary.max_window_messages = n as usize;
+    }
+    if let Some(n) = get_u64(value, &["memory", "summary", "max_window_tokens"]) {
+        summary.max_window_tokens = n as usize;
+    }
+    if summary.is_valid() {
+        cfg.memory.summary = summary;
+    }
+    if let Some(raw) = get_bool(value, &["memory", "store_raw_turns"]) {
+        cfg.memory.store_raw_turns = raw;
+    }
+    if let Some(tools) = get_bool(value, &["memory", "transcript_tool_results"]) {
+        cfg.memory.transcript_tool_results = tools;
+    }
+    if let Some(aware) = get_bool(value, &["memory", "context_aware_recall"]) {
+        cfg.memory.context_aware_recall = aware;
+    }
+    if let Some(extract) = get_bool(value, &["memory", "auto_extract"]) {
+        cfg.memory.auto_extract = extract;
+    }
+    if let Some(extract) = get_bool(value, &["memory", "llm_extract"]) {
+        cfg.memory.llm_extract = extract;
+    }
+    if let Some(background) = get_bool(value, &["memory", "async_remember"]) {
+        cfg.memory.async_remember = background;
+    }
+    if let Some(budget) = get_u64(value, &["memory", "context_budget_chars"]) {
+        cfg.memory.context_budget_chars = budget as usize;
+    }
+    if let Some(share) = get_f64(value, &["memory", "context_file_share"]).and_then(unit_interval) {
+        cfg.memory.context_file_share = share;
+    }
+    if let Some(min) = get_u64(value, &["memory", "raw_turn_min_chars"]) {
+        cfg.memory.raw_turn_min_chars = min as usize;
+    }
+    if let Some(persist) = get_bool(value, &["memory", "persist_embedding_cache"]) {
+        cfg.memory.persist_embedding_cache = persist;
+    }
+    if let Some(max) = get_u64(value, &["memory", "max_file_chars"]) {
+        cfg.memory.max_file_chars = max as usize;
+    }
+    if let Some(verify) = get_bool(value, &["memory", "verify_grounded_facts"]) {
+        cfg.memory.verify_grounded_facts = verify;
+    }
+
+    if let Some(auto_reply) = get_bool(value, &["cron", "auto_reply"]) {
+        cfg.cron.auto_reply = auto_reply;
+    }
+    if let Some(durable) = get_bool(value, &["bus", "durable"]) {
+        cfg.bus.durable = durable;
+    }
+    if let Some(stages) = get_array(value, &["outbound", "post_process"]) {
+        cfg.outbound.post_process = stages;
+    }
+    if let Some(footer) = get_str(value, &["outbound", "footer"]) {
+        cfg.outbound.footer = footer.to_string();
+    }
+    if let Some(max) = get_u64(value, &["outbound", "max_chars"]) {
+        cfg.outbound.max_chars = max as usize;
+    }
+    if let Some(path) = get_str(value, &["plugins", "wasm"]) {
+        cfg.plugins.wasm = plugin_path(path);
+    }
+    if let Some(redact) = get_bool(value, &["logging", "redact"]) {
+        cfg.logging.redact = redact;
+    }
+    if let Some(redact) = get_bool(value, &["logging", "redact_content"]) {
+        cfg.logging.redact_content = redact;
+    }
+}
+
+/// A configured plugin path with `~/` expanded; empty means none.
+fn plugin_path(raw: &str) -> Option<PathBuf> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    match raw.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)),
+        None => Some(PathBuf::from(raw)),
+    }
 }
 
 fn apply_provider_config(
@@ -662,6 +1970,31 @@ fn apply_provider_config(
                 cfg.providers.ollama.extra_headers = v;
             }
         }
+        ProviderKind::Azure => {
+            if let Some(v) = api_key {
+                cfg.providers.azure.api_key = v.to_string();
+            }
+            if let Some(v) = provider_obj
+                .get("endpoint")
+                .and_then(Value::as_str)
+                .or(base_url)
+            {
+                cfg.providers.azure.endpoint = v.to_string();
+            }
+            if let Some(v) = provider_obj.get("deployment").and_then(Value::as_str) {
+                cfg.providers.azure.deployment = v.to_string();
+            }
+            if let Some(v) = provider_obj
+                .get("apiVersion")
+                .and_then(Value::as_str)
+                .or_else(|| provider_obj.get("api_version").and_then(Value::as_str))
+            {
+                cfg.providers.azure.api_version = v.to_string();
+            }
+            if let Some(v) = extra_headers {
+                cfg.providers.azure.extra_headers = v;
+            }
+        }
     }
 }
 
@@ -722,6 +2055,29 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
     if let Ok(base) = std::env::var("OLLAMA_BASE_URL") {
         cfg.providers.ollama.base_url = base;
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_OLLAMA_KEEP_ALIVE") {
+        let val = val.trim();
+        cfg.providers.ollama.keep_alive = (!val.is_empty()).then(|| val.to_string());
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_OLLAMA_PRELOAD") {
+        if let Some(enabled) = parse_bool(&val) {
+            cfg.providers.ollama.preload = enabled;
+        }
+    }
+    if let Ok(key) = std::env::var("AZURE_OPENAI_API_KEY") {
+        cfg.providers.azure.api_key = key;
+    }
+    if let Ok(endpoint) = std::env::var("AZURE_OPENAI_ENDPOINT") {
+        cfg.providers.azure.endpoint = endpoint;
+    }
+    if let Ok(deployment) = std::env::var("AZURE_OPENAI_DEPLOYMENT") {
+        cfg.providers.azure.deployment = deployment;
+    }
+    if let Ok(version) = std::env::var("AZURE_OPENAI_API_VERSION") {
+        if !version.trim().is_empty() {
+            cfg.providers.azure.api_version = version;
+        }
+    }
     if let Ok(key) = std::env::var("MISTRAL_API_KEY") {
         cfg.providers.mistral.api_key = key;
     }
@@ -745,6 +2101,57 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             .map(|s| s.to_string())
             .collect();
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MAX_ATTACHMENTS_PER_MESSAGE") {
+        if let Ok(n) = val.trim().parse::<usize>() {
+            cfg.channels.max_attachments_per_message = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_GREETING") {
+        cfg.channels.greeting = val;
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TIMEZONE") {
+        if let Some(tz) = known_timezone("LIGHTCLAW_TIMEZONE", &val) {
+            cfg.channels.timezone = tz;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_ATTACHMENT_MAX_COUNT") {
+        if let Ok(n) = val.parse::<u32>() {
+            cfg.channels.attachment_limits.max_count = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_ATTACHMENT_MAX_BYTES") {
+        if let Ok(n) = val.parse::<u64>() {
+            cfg.channels.attachment_limits.max_bytes = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_ATTACHMENT_WINDOW_SECS") {
+        if let Ok(n) = val.parse::<u64>() {
+            cfg.channels.attachment_limits.window_secs = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECONNECT_MAX_ATTEMPTS") {
+        if let Ok(n) = val.parse::<u32>() {
+            cfg.channels.reconnect.max_attempts = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECONNECT_BASE_DELAY_MS") {
+        if let Ok(n) = val.parse::<u64>() {
+            cfg.channels.reconnect.base_delay_ms = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECONNECT_MAX_DELAY_MS") {
+        if let Ok(n) = val.parse::<u64>() {
+            cfg.channels.reconnect.max_delay_ms = n;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_ADMINS") {
+        cfg.channels.admins = val
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_DISCORD_ALLOWED_CHANNELS") {
         cfg.channels.discord.allowed_channels = val
             .split(',')
@@ -753,16 +2160,68 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             .map(|s| s.to_string())
             .collect();
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TELEGRAM_SHOW_TYPING") {
+        if let Some(show) = parse_bool(&val) {
+            cfg.channels.telegram.show_typing = show;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_DISCORD_SHOW_TYPING") {
+        if let Some(show) = parse_bool(&val) {
+            cfg.channels.discord.show_typing = show;
+        }
+    }
+    if let Ok(mode) = std::env::var("LIGHTCLAW_TELEGRAM_MODE") {
+        if let Some(parsed) = TelegramMode::parse(&mode) {
+            cfg.channels.telegram.mode = parsed;
+        }
+    }
+    if let Ok(url) = std::env::var("LIGHTCLAW_TELEGRAM_WEBHOOK_URL") {
+        cfg.channels.telegram.webhook_url = url.trim().to_string();
+    }
+    if let Ok(secret) = std::env::var("LIGHTCLAW_TELEGRAM_WEBHOOK_SECRET") {
+        cfg.channels.telegram.webhook_secret = secret.trim().to_string();
+    }
+    if let Ok(mode) = std::env::var("LIGHTCLAW_DISCORD_GUILD_RESPONSE_MODE") {
+        if let Some(parsed) = GuildResponseMode::parse(&mode) {
+            cfg.channels.discord.guild_response_mode = parsed;
+        }
+    }
+    if let Ok(prefix) = std::env::var("LIGHTCLAW_DISCORD_PREFIX") {
+        if let Some(prefix) = non_empty(&prefix) {
+            cfg.channels.discord.guild_prefix = prefix;
+        }
+    }
     if let Ok(provider) = std::env::var("LIGHTCLAW_WEB_SEARCH_PROVIDER") {
         if let Some(parsed) = WebSearchProvider::parse(&provider) {
             cfg.tools.web_search_provider = parsed;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_SEARCH_FALLBACKS") {
+        let list = val.split(',').map(str::to_string).collect::<Vec<_>>();
+        cfg.tools.web_search_fallbacks = parse_search_providers(&list);
+    }
     if let Ok(provider) = std::env::var("LIGHTCLAW_WEB_FETCH_PROVIDER") {
         if let Some(parsed) = WebFetchProvider::parse(&provider) {
             cfg.tools.web_fetch_provider = parsed;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_FETCH_MAX_DOWNLOAD_BYTES") {
+        if let Ok(num) = val.parse::<usize>() {
+            if num > 0 {
+                cfg.tools.web_fetch_max_download_bytes = num;
+            }
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_FETCH_MAX_REDIRECTS") {
+        if let Ok(num) = val.parse::<usize>() {
+            cfg.tools.web_fetch_max_redirects = num;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_FETCH_ALLOW_INTERNAL_HOSTS") {
+        if let Some(allow) = parse_bool(&val) {
+            cfg.tools.web_fetch_allow_internal_hosts = allow;
+        }
+    }
     if let Ok(brave) = std::env::var("LIGHTCLAW_BRAVE_API_KEY") {
         cfg.tools.brave_api_key = Some(brave);
     }
@@ -775,6 +2234,9 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
     if let Ok(firecrawl) = std::env::var("FIRECRAWL_API_KEY") {
         cfg.tools.firecrawl_api_key = Some(firecrawl);
     }
+    if let Ok(url) = std::env::var("LIGHTCLAW_SEARXNG_BASE_URL") {
+        cfg.tools.searxng_base_url = non_empty(&url);
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPTION_ENABLED") {
         if let Some(flag) = parse_bool(&val) {
             cfg.transcription.enabled = flag;
@@ -802,6 +2264,11 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             cfg.transcription.max_bytes = num;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPTION_MAX_DURATION_SECS") {
+        if let Ok(num) = val.parse::<u64>() {
+            cfg.transcription.max_duration_secs = num;
+        }
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPTION_DIARIZE") {
         if let Some(flag) = parse_bool(&val) {
             cfg.transcription.mistral_diarize = flag;
@@ -823,6 +2290,19 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             .collect::<Vec<_>>();
         cfg.transcription.mistral_timestamp_granularities = parsed;
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPTION_TIMEOUT_SECS") {
+        if let Ok(num) = val.parse::<u64>() {
+            cfg.transcription.timeout_secs = num.max(1);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPTION_MAX_RETRIES") {
+        if let Ok(num) = val.parse::<u32>() {
+            cfg.transcription.max_retries = num.min(10);
+        }
+    }
+    if let Ok(path) = std::env::var("LIGHTCLAW_WASM_PLUGIN") {
+        cfg.plugins.wasm = plugin_path(&path);
+    }
     if let Ok(path) =
         std::env::var("LIGHTCLAW_DATA_DIR").or_else(|_| std::env::var("RUSTBOT_DATA_DIR"))
     {
@@ -839,6 +2319,19 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
         cfg.tools.restrict_to_workspace =
             parse_bool(&val).unwrap_or(cfg.tools.restrict_to_workspace);
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TOOLS_ENABLED") {
+        cfg.tools.enabled = Some(split_list(&val));
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TOOLS_DISABLED") {
+        cfg.tools.disabled = split_list(&val);
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_SAFE_MODE") {
+        cfg.tools.safe_mode = parse_bool(&val).unwrap_or(cfg.tools.safe_mode);
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_SEND_ALLOW_CROSS_SESSION") {
+        cfg.tools.send_allow_cross_session =
+            parse_bool(&val).unwrap_or(cfg.tools.send_allow_cross_session);
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_EXEC_TIMEOUT_SECS")
         .or_else(|_| std::env::var("RUSTBOT_EXEC_TIMEOUT_SECS"))
     {
@@ -846,6 +2339,26 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             cfg.tools.exec_timeout_secs = num;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TOOL_TIMEOUT_SECS") {
+        if let Ok(num) = val.parse::<u64>() {
+            cfg.tools.timeout_secs = num;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_TIMEOUT_SECS") {
+        if let Ok(num) = val.parse::<u64>() {
+            cfg.tools.web_timeout_secs = Some(num);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_WEB_FETCH_TIMEOUT_SECS") {
+        if let Ok(num) = val.parse::<u64>() {
+            cfg.tools.web_fetch_timeout_secs = Some(num);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TOOL_MAX_OUTPUT_CHARS") {
+        if let Ok(num) = val.trim().parse::<usize>() {
+            cfg.tools.max_output_chars = num;
+        }
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_MAX_TOOL_TURNS")
         .or_else(|_| std::env::var("RUSTBOT_MAX_TOOL_TURNS"))
     {
@@ -878,12 +2391,195 @@ fn apply_env_overrides(cfg: &mut AppConfig) {
             cfg.memory.embedding_model = val;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_SUMMARY_MODEL") {
+        if let Some(model) = non_empty(&val) {
+            cfg.memory.summary_model = Some(model);
+        }
+    }
 
     if let Ok(val) = std::env::var("LIGHTCLAW_MAX_MEMORIES") {
         if let Ok(num) = val.parse::<usize>() {
             cfg.memory.max_memories = num;
         }
     }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECALL_TOP_K") {
+        if let Ok(num) = val.parse::<usize>() {
+            if num > 0 {
+                cfg.memory.recall.top_k = num;
+            }
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECALL_THRESHOLD") {
+        if let Some(threshold) = val.trim().parse::<f64>().ok().and_then(unit_interval) {
+            cfg.memory.recall.threshold = threshold;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_PRIORITY_WEIGHT") {
+        if let Some(weight) = val.trim().parse::<f64>().ok().and_then(unit_interval) {
+            cfg.memory.recall.priority_weight = weight;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RECALL_EMBED_TIMEOUT_MS") {
+        if let Ok(num) = val.trim().parse::<u64>() {
+            cfg.memory.recall.embed_timeout_ms = num;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_SHARED_NAMESPACE") {
+        cfg.memory.shared_namespace = non_empty(&val);
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_NAMESPACE_STRATEGY") {
+        if let Some(strategy) = NamespaceStrategy::parse(&val) {
+            cfg.memory.namespace_strategy = strategy;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_STORE_RAW_TURNS") {
+        if let Some(raw) = parse_bool(&val) {
+            cfg.memory.store_raw_turns = raw;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_TRANSCRIPT_TOOL_RESULTS") {
+        if let Some(tools) = parse_bool(&val) {
+            cfg.memory.transcript_tool_results = tools;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_CONTEXT_AWARE_RECALL") {
+        if let Some(aware) = parse_bool(&val) {
+            cfg.memory.context_aware_recall = aware;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_AUTO_EXTRACT") {
+        if let Some(extract) = parse_bool(&val) {
+            cfg.memory.auto_extract = extract;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_ASYNC_REMEMBER") {
+        if let Some(background) = parse_bool(&val) {
+            cfg.memory.async_remember = background;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_CONTEXT_BUDGET_CHARS") {
+        if let Ok(budget) = val.trim().parse::<usize>() {
+            cfg.memory.context_budget_chars = budget;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_CONTEXT_FILE_SHARE") {
+        if let Some(share) = val.trim().parse::<f64>().ok().and_then(unit_interval) {
+            cfg.memory.context_file_share = share;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_LLM_EXTRACT") {
+        if let Some(extract) = parse_bool(&val) {
+            cfg.memory.llm_extract = extract;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_VERIFY_GROUNDED_FACTS") {
+        if let Some(verify) = parse_bool(&val) {
+            cfg.memory.verify_grounded_facts = verify;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_CRON_AUTO_REPLY") {
+        if let Some(auto_reply) = parse_bool(&val) {
+            cfg.cron.auto_reply = auto_reply;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_LOG_REDACT") {
+        if let Some(redact) = parse_bool(&val) {
+            cfg.logging.redact = redact;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_LOG_REDACT_CONTENT") {
+        if let Some(redact) = parse_bool(&val) {
+            cfg.logging.redact_content = redact;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_BUS_DURABLE") {
+        if let Some(durable) = parse_bool(&val) {
+            cfg.bus.durable = durable;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_OUTBOUND_POST_PROCESS") {
+        cfg.outbound.post_process = val
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_OUTBOUND_FOOTER") {
+        cfg.outbound.footer = val;
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_OUTBOUND_MAX_CHARS") {
+        if let Ok(max) = val.trim().parse::<usize>() {
+            cfg.outbound.max_chars = max;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MEMORY_MAX_FILE_CHARS") {
+        if let Ok(max) = val.trim().parse::<usize>() {
+            cfg.memory.max_file_chars = max;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MAX_RESPONSE_TOKENS") {
+        if let Ok(num) = val.parse::<u64>() {
+            if num > 0 {
+                cfg.model.max_response_tokens = num;
+            }
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_COMPACTION_MAX_TOKENS") {
+        if let Ok(num) = val.trim().parse::<usize>() {
+            if num > 0 {
+                cfg.model.compaction_max_tokens = num;
+            }
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MATCH_USER_LANGUAGE") {
+        if let Some(enabled) = parse_bool(&val) {
+            cfg.model.match_user_language = enabled;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_INJECT_TIME_CONTEXT") {
+        if let Some(enabled) = parse_bool(&val) {
+            cfg.model.inject_time_context = enabled;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MAX_CONTINUATIONS") {
+        if let Ok(num) = val.parse::<usize>() {
+            cfg.model.max_continuations = num.min(MAX_CONTINUATIONS_LIMIT);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MAX_RETRIES") {
+        if let Ok(num) = val.trim().parse::<usize>() {
+            cfg.model.max_retries = num.min(MAX_RETRIES_LIMIT);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_RETRY_BASE_DELAY_MS") {
+        if let Ok(num) = val.trim().parse::<u64>() {
+            cfg.model.retry_base_delay_ms = num;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_MAX_ROUTES_ATTEMPTED") {
+        if let Ok(num) = val.trim().parse::<usize>() {
+            cfg.model.max_routes_attempted = (num > 0).then_some(num);
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_LOG_REASONING") {
+        if let Some(enabled) = parse_bool(&val) {
+            cfg.model.log_reasoning = enabled;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_INBOUND_DEBOUNCE_MS") {
+        if let Ok(ms) = val.trim().parse::<u64>() {
+            cfg.model.inbound_debounce_ms = ms;
+        }
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_UNCONFIGURED_REPLY") {
+        cfg.model.unconfigured_reply = val;
+    }
+    if let Ok(val) = std::env::var("LIGHTCLAW_DETERMINISTIC") {
+        if let Some(enabled) = parse_bool(&val) {
+            cfg.model.deterministic = enabled;
+        }
+    }
     if let Ok(val) = std::env::var("LIGHTCLAW_MODEL_FALLBACKS") {
         let parsed = val
             .split(',')
@@ -913,6 +2609,14 @@ fn get_u64(value: &Value, path: &[&str]) -> Option<u64> {
     cur.as_u64()
 }
 
+fn get_f64(value: &Value, path: &[&str]) -> Option<f64> {
+    let mut cur = value;
+    for key in path {
+        cur = cur.get(*key)?;
+    }
+    cur.as_f64()
+}
+
 fn get_bool(value: &Value, path: &[&str]) -> Option<bool> {
     let mut cur = value;
     for key in path {
@@ -921,6 +2625,14 @@ fn get_bool(value: &Value, path: &[&str]) -> Option<bool> {
     cur.as_bool()
 }
 
+/// Known search providers from `names`; unknown names are skipped.
+fn parse_search_providers(names: &[String]) -> Vec<WebSearchProvider> {
+    names
+        .iter()
+        .filter_map(|name| WebSearchProvider::parse(name))
+        .collect()
+}
+
 fn get_array(value: &Value, path: &[&str]) -> Option<Vec<String>> {
     let mut cur = value;
     for key in path {
@@ -944,6 +2656,75 @@ fn parse_bool(value: &str) -> Option<bool> {
     }
 }
 
+/// Read `channels.<platform>.bots`. Entries need a token and a unique name
+/// of lowercase letters, digits, `-` or `_`; others are skipped.
+fn get_bots(value: &Value, platform: &str) -> Option<Vec<BotConfig>> {
+    let entries = value
+        .get("channels")?
+        .get(platform)?
+        .get("bots")?
+        .as_array()?;
+    let mut bots: Vec<BotConfig> = Vec::new();
+    for entry in entries {
+        let name = get_str(entry, &["name"])
+            .map(|name| name.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        let token = get_str(entry, &["token"])
+            .or_else(|| get_str(entry, &["bot_token"]))
+            .and_then(non_empty);
+        let valid_name = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        let Some(bot_token) = token.filter(|_| valid_name) else {
+            continue;
+        };
+        if bots.iter().any(|bot| bot.name == name) {
+            continue;
+        }
+        bots.push(BotConfig {
+            name,
+            bot_token,
+            allow_from: get_array(entry, &["allow_from"]).unwrap_or_default(),
+            match_mode: get_str(entry, &["match_mode"])
+                .and_then(AllowMatchMode::parse)
+                .unwrap_or(AllowMatchMode::Exact),
+            allowed_channels: get_array(entry, &["allowed_channels"]).unwrap_or_default(),
+            allowed_roles: get_array(entry, &["allowed_roles"]).unwrap_or_default(),
+            persona: get_str(entry, &["persona"]).and_then(non_empty),
+        });
+    }
+    Some(bots)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+/// Split a comma-separated env value, dropping blanks.
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Accept only values in `0.0..=1.0`; out-of-range settings keep the default.
+fn unit_interval(value: f64) -> Option<f32> {
+    if (0.0..=1.0).contains(&value) {
+        Some(value as f32)
+    } else {
+        None
+    }
+}
+
 fn parse_model_route(raw: &str, default_provider: &ProviderKind) -> Option<ModelRoute> {
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -968,3 +2749,443 @@ fn parse_model_route(raw: &str, default_provider: &ProviderKind) -> Option<Model
         model: trimmed.to_string(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_tool_turn_overrides_fall_back_to_global() {
+        let mut cfg = AppConfig::defaults();
+        cfg.provider = ProviderKind::OpenRouter;
+        cfg.model.model = "anthropic/claude-sonnet-4".to_string();
+        cfg.model.fallbacks = vec![
+            "openai/gpt-4o-mini".to_string(),
+            "ollama/llama3".to_string(),
+        ];
+        let value = serde_json::json!({
+            "agents": {
+                "defaults": {
+                    "max_tool_iterations": 12,
+                    "max_tool_turns_by_model": {
+                        "openai/gpt-4o-mini": 3,
+                        "llama3": 5
+                    }
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+
+        let turns: Vec<usize> = cfg
+            .model_routes()
+            .iter()
+            .map(|route| cfg.max_tool_turns_for(route))
+            .collect();
+        assert_eq!(turns, vec![12, 3, 5]);
+    }
+
+    #[test]
+    fn chat_timezones_fall_back_to_the_channel_then_the_default() {
+        let mut cfg = AppConfig::defaults();
+        assert!(cfg.channels.timezone_for("telegram", "1").is_none());
+        let value = serde_json::json!({
+            "channels": {
+                "timezone": "Europe/Lisbon",
+                "timezones": {
+                    "telegram:42": "Asia/Tokyo",
+                    "discord": "America/New_York",
+                    "telegram:7": "Mars/Olympus_Mons"
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+
+        let tz = |channel: &str, chat: &str| cfg.channels.timezone_for(channel, chat);
+        assert_eq!(tz("telegram", "42"), Some(chrono_tz::Asia::Tokyo));
+        assert_eq!(tz("discord", "5"), Some(chrono_tz::America::New_York));
+        assert_eq!(tz("telegram", "1"), Some(chrono_tz::Europe::Lisbon));
+        // Unknown names are dropped, so that chat gets the default.
+        assert_eq!(tz("telegram", "7"), Some(chrono_tz::Europe::Lisbon));
+
+        let noon_utc = chrono::DateTime::parse_from_rfc3339("2026-01-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let tokyo = cfg.channels.chat_time("telegram", "42", noon_utc);
+        assert_eq!(tokyo.to_rfc3339(), "2026-01-15T21:00:00+09:00");
+    }
+
+    #[test]
+    fn discord_prefixes_are_parsed_per_guild() {
+        let mut cfg = AppConfig::defaults();
+        let value = serde_json::json!({
+            "channels": {
+                "discord": {
+                    "guild_prefix": "$",
+                    "guild_prefixes": { "900": "?", " ": "!", "901": "  " }
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.channels.discord.guild_prefix, "$");
+        assert_eq!(
+            cfg.channels.discord.guild_prefixes,
+            HashMap::from([("900".to_string(), "?".to_string())])
+        );
+    }
+
+    #[test]
+    fn namespace_memory_caps_are_parsed() {
+        let mut cfg = AppConfig::defaults();
+        let value = serde_json::json!({
+            "memory": {
+                "max_memories": 200,
+                "max_memories_by_namespace": {
+                    "telegram": 5000,
+                    "discord_42": 50,
+                    " ": 1,
+                    "bad": "many"
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+
+        assert_eq!(cfg.memory.max_memories, 200);
+        assert_eq!(cfg.memory.max_memories_by_namespace.len(), 2);
+        assert_eq!(cfg.memory.max_memories_by_namespace["telegram"], 5000);
+        assert_eq!(cfg.memory.max_memories_by_namespace["discord_42"], 50);
+    }
+
+    #[test]
+    fn namespace_strategy_and_user_map_are_parsed() {
+        let mut cfg = AppConfig::defaults();
+        let value = serde_json::json!({
+            "memory": {
+                "namespace_strategy": "per-user",
+                "users": {
+                    "alice": ["telegram:123", "discord:456", "no-channel"],
+                    " ": ["telegram:1"]
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+
+        assert_eq!(cfg.memory.namespace_strategy, NamespaceStrategy::User);
+        assert_eq!(cfg.memory.users.len(), 2);
+        assert_eq!(cfg.memory.users["discord:456"], "alice");
+        assert_eq!(
+            NamespaceStrategy::parse("sender"),
+            Some(NamespaceStrategy::Sender)
+        );
+        assert_eq!(NamespaceStrategy::parse("per_team"), None);
+    }
+
+    #[test]
+    fn recall_settings_parse_and_validate_ranges() {
+        let mut cfg = AppConfig::defaults();
+        let value = serde_json::json!({
+            "memory": {
+                "recall_top_k": 7,
+                "recall_threshold": 0.25,
+                "priority_weight": 1.5
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+
+        assert_eq!(cfg.memory.recall.top_k, 7);
+        assert!((cfg.memory.recall.threshold - 0.25).abs() < f32::EPSILON);
+        // Out-of-range weight is ignored and the default kept.
+        assert!((cfg.memory.recall.priority_weight - 0.3).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn secret_references_resolve_from_env_and_files() {
+        let key_file = std::env::temp_dir().join(format!(
+            "lightclaw-secret-{}-{}",
+            std::process::id(),
+            line!()
+        ));
+        std::fs::write(&key_file, "sk-from-file\n").unwrap();
+        let vars = HashMap::from([
+            ("LIGHTCLAW_TEST_SECRET_REF", "sk-from-env".to_string()),
+            (
+                "LIGHTCLAW_TEST_SECRET_NESTED",
+                format!("file:{}", key_file.display()),
+            ),
+        ]);
+        let env = |var: &str| vars.get(var).cloned();
+
+        let mut value = serde_json::json!({
+            "providers": {
+                "openrouter": { "apiKey": "${LIGHTCLAW_TEST_SECRET_REF}" },
+                "openai": { "apiKey": format!("file:{}", key_file.display()) },
+                "azure": { "apiKey": "${LIGHTCLAW_TEST_SECRET_NESTED}" }
+            },
+            "outbound": { "footer": "costs ${5} a month" }
+        });
+        assert!(resolve_secret_refs_with(&mut value, &env).is_empty());
+        let mut cfg = AppConfig::defaults();
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.providers.openrouter.api_key, "sk-from-env");
+        assert_eq!(cfg.providers.openai.api_key, "sk-from-file");
+        assert_eq!(cfg.providers.azure.api_key, "sk-from-file");
+        // Only whole-value references are resolved.
+        assert_eq!(value["outbound"]["footer"], "costs ${5} a month");
+
+        let _ = std::fs::remove_file(key_file);
+    }
+
+    #[test]
+    fn unresolved_secret_references_are_reported() {
+        let mut value = serde_json::json!({
+            "providers": {
+                "openrouter": { "apiKey": "${LIGHTCLAW_TEST_SECRET_UNSET}" },
+                "openai": { "apiKey": "file:/nonexistent/lightclaw/key" }
+            }
+        });
+        let no_env = |_: &str| None::<String>;
+        let errors = resolve_secret_refs_with(&mut value, &no_env);
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors.iter().any(|err| err
+            .contains("providers.openrouter.apiKey: environment variable LIGHTCLAW_TEST_SECRET_UNSET is not set")));
+        assert!(errors
+            .iter()
+            .any(|err| err.contains("providers.openai.apiKey: cannot read")));
+        assert_eq!(value["providers"]["openrouter"]["apiKey"], "");
+
+        let looping = |_: &str| Some("${LIGHTCLAW_TEST_SECRET_LOOP}".to_string());
+        let mut looped = serde_json::json!({ "key": "${LIGHTCLAW_TEST_SECRET_LOOP}" });
+        let errors = resolve_secret_refs_with(&mut looped, &looping);
+        assert!(errors[0].to_string().contains("nested secret references"));
+    }
+
+    #[test]
+    fn only_the_active_providers_unresolved_key_is_fatal() {
+        let mut value = serde_json::json!({
+            "agents": { "defaults": { "provider": "openai" } },
+            "providers": {
+                "openrouter": { "apiKey": "${LIGHTCLAW_TEST_SECRET_UNSET}" },
+                "openai": { "api_key": "${LIGHTCLAW_TEST_SECRET_UNSET}" }
+            },
+            "tools": { "web": { "search": { "apiKey": "${LIGHTCLAW_TEST_SECRET_UNSET}" } } }
+        });
+        let unresolved = resolve_secret_refs_with(&mut value, &|_| None);
+        assert_eq!(unresolved.len(), 3);
+        let mut cfg = AppConfig::defaults();
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.provider, ProviderKind::OpenAI);
+
+        let err = cfg.check_unresolved(&unresolved).unwrap_err();
+        assert!(
+            err.to_string().contains("providers.openai.api_key"),
+            "{err}"
+        );
+
+        // Another provider's key or a tool's key only warns.
+        cfg.provider = ProviderKind::OpenRouter;
+        cfg.providers.openrouter.api_key = "sk-or-from-env".to_string();
+        assert!(cfg.check_unresolved(&unresolved).is_ok());
+        cfg.provider = ProviderKind::Ollama;
+        assert!(cfg.check_unresolved(&unresolved).is_ok());
+    }
+
+    #[test]
+    fn summary_settings_parse_and_reject_inconsistent_windows() {
+        let mut cfg = AppConfig::defaults();
+        let value = serde_json::json!({
+            "memory": {
+                "summary": {
+                    "trigger_user_turns": 5,
+                    "context_messages": 4,
+                    "max_window_messages": 30,
+                    "max_window_tokens": 2000
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.memory.summary.trigger_user_turns, 5);
+        assert_eq!(cfg.memory.summary.context_messages, 4);
+        assert_eq!(cfg.memory.summary.max_window_messages, 30);
+        assert_eq!(cfg.memory.summary.max_window_tokens, 2000);
+
+        for bad in [
+            serde_json::json!({ "memory": { "summary": { "trigger_user_turns": 0 } } }),
+            serde_json::json!({ "memory": { "summary": { "context_messages": 40 } } }),
+            serde_json::json!({ "memory": { "summary": { "max_window_tokens": 0 } } }),
+        ] {
+            let mut cfg = AppConfig::defaults();
+            apply_lightclaw_config(&mut cfg, &bad);
+            assert_eq!(cfg.memory.summary, AppConfig::defaults().memory.summary);
+        }
+    }
+
+    #[test]
+    fn workspace_restriction_is_on_by_default() {
+        let mut cfg = AppConfig::defaults();
+        assert!(cfg.tools.restrict_to_workspace);
+
+        let value = serde_json::json!({ "tools": { "restrict_to_workspace": false } });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert!(!cfg.tools.restrict_to_workspace);
+    }
+
+    #[test]
+    fn telegram_mode_defaults_to_polling_and_reads_webhook_settings() {
+        let mut cfg = AppConfig::defaults();
+        assert_eq!(cfg.channels.telegram.mode, TelegramMode::Polling);
+
+        let value = serde_json::json!({
+            "channels": {
+                "telegram": {
+                    "mode": "webhook",
+                    "webhook": {
+                        "url": "https://bot.example.com/hook",
+                        "secret_token": "abc_123",
+                        "listen": "127.0.0.1:9000"
+                    }
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.channels.telegram.mode, TelegramMode::Webhook);
+        assert_eq!(
+            cfg.channels.telegram.webhook_url,
+            "https://bot.example.com/hook"
+        );
+        assert_eq!(cfg.channels.telegram.webhook_secret, "abc_123");
+        assert_eq!(cfg.channels.telegram.webhook_listen, "127.0.0.1:9000");
+
+        // Unknown modes keep the current setting.
+        apply_lightclaw_config(
+            &mut cfg,
+            &serde_json::json!({ "channels": { "telegram": { "mode": "carrier-pigeon" } } }),
+        );
+        assert_eq!(cfg.channels.telegram.mode, TelegramMode::Webhook);
+    }
+
+    #[test]
+    fn channel_bots_are_parsed_with_their_own_allowlists() {
+        let mut cfg = AppConfig::defaults();
+        assert!(!cfg.discord_enabled());
+        let value = serde_json::json!({
+            "channels": {
+                "discord": {
+                    "bots": [
+                        { "name": "Work", "token": "work-token", "allow_from": ["alice"],
+                          "allowed_channels": ["77"], "persona": "Be formal." },
+                        { "name": "work", "token": "duplicate" },
+                        { "name": "bad name", "token": "x" },
+                        { "name": "notoken" }
+                    ]
+                }
+            }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        let bots = &cfg.channels.discord.bots;
+        assert_eq!(bots.len(), 1);
+        assert_eq!(bots[0].name, "work");
+        assert_eq!(bots[0].bot_token, "work-token");
+        assert_eq!(bots[0].allowed_channels, vec!["77".to_string()]);
+        assert!(cfg.discord_enabled());
+        assert_eq!(cfg.bot_persona("discord.work"), Some("Be formal."));
+        assert_eq!(cfg.bot_persona("discord"), None);
+        assert_eq!(cfg.bot_persona("telegram.work"), None);
+    }
+
+    #[test]
+    fn stop_sequences_are_validated() {
+        let mut cfg = AppConfig::defaults();
+        assert!(cfg.model.stop_sequences.is_empty());
+        assert_eq!(cfg.model.max_response_tokens, 4096);
+
+        let value = serde_json::json!({
+            "agents": { "defaults": { "stop_sequences": ["###", "\nEND"], "max_response_tokens": 512 } }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(
+            cfg.model.stop_sequences,
+            vec!["###".to_string(), "\nEND".to_string()]
+        );
+        assert_eq!(cfg.model.max_response_tokens, 512);
+
+        let value =
+            serde_json::json!({ "agents": { "defaults": { "compaction_max_tokens": 8000 } } });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.model.compaction_max_tokens, 8000);
+
+        // Over-long lists and empty entries are rejected as a whole.
+        for bad in [
+            serde_json::json!(["a", "b", "c", "d", "e"]),
+            serde_json::json!(["ok", ""]),
+        ] {
+            let value = serde_json::json!({ "agents": { "defaults": { "stop_sequences": bad } } });
+            apply_lightclaw_config(&mut cfg, &value);
+            assert_eq!(cfg.model.stop_sequences.len(), 2);
+        }
+        assert!(validate_stop_sequences(&["x".repeat(MAX_STOP_SEQUENCE_CHARS + 1)]).is_err());
+
+        assert_eq!(cfg.model.max_continuations, 2);
+        let value = serde_json::json!({ "agents": { "defaults": { "max_continuations": 500 } } });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.model.max_continuations, MAX_CONTINUATIONS_LIMIT);
+    }
+
+    #[test]
+    fn retry_settings_parse_and_ignore_negatives() {
+        let mut cfg = AppConfig::defaults();
+        assert_eq!(
+            (cfg.model.max_retries, cfg.model.retry_base_delay_ms),
+            (2, 400)
+        );
+
+        let value = serde_json::json!({
+            "agents": { "defaults": { "max_retries": 5, "retry_base_delay_ms": 1000 } }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(
+            (cfg.model.max_retries, cfg.model.retry_base_delay_ms),
+            (5, 1000)
+        );
+
+        let value = serde_json::json!({
+            "agents": { "defaults": { "max_retries": -1, "retry_base_delay_ms": -50 } }
+        });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(
+            (cfg.model.max_retries, cfg.model.retry_base_delay_ms),
+            (5, 1000)
+        );
+
+        let value = serde_json::json!({ "agents": { "defaults": { "max_retries": 1_000_000 } } });
+        apply_lightclaw_config(&mut cfg, &value);
+        assert_eq!(cfg.model.max_retries, MAX_RETRIES_LIMIT);
+    }
+
+    #[test]
+    fn smart_memory_without_embedding_model_is_a_config_error() {
+        let mut cfg = AppConfig::defaults();
+        assert_eq!(cfg.smart_memory_problem(), None);
+
+        cfg.memory.mode = MemoryMode::Smart;
+        cfg.provider = ProviderKind::Ollama;
+        let problem = cfg.smart_memory_problem().expect("OpenAI model on Ollama");
+        assert!(problem.contains("memory.embedding_model"), "{problem}");
+        cfg.memory.embedding_model = "nomic-embed-text".to_string();
+        assert_eq!(cfg.smart_memory_problem(), None);
+
+        // The default OpenAI model works on OpenRouter under its vendor prefix.
+        cfg.provider = ProviderKind::OpenRouter;
+        cfg.memory.embedding_model = "text-embedding-3-small".to_string();
+        assert_eq!(cfg.smart_memory_problem(), None);
+        assert_eq!(cfg.embedding_model(), "openai/text-embedding-3-small");
+        cfg.memory.embedding_model = "openai/text-embedding-3-small".to_string();
+        assert_eq!(cfg.smart_memory_problem(), None);
+        assert_eq!(cfg.embedding_model(), "openai/text-embedding-3-small");
+        cfg.memory.embedding_model = "mxbai-embed-large".to_string();
+        assert!(cfg.smart_memory_problem().is_some());
+
+        cfg.provider = ProviderKind::OpenAI;
+        cfg.memory.embedding_model = "  ".to_string();
+        assert!(cfg.smart_memory_problem().is_some());
+    }
+}
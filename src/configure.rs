@@ -3,6 +3,7 @@ use anyhow::{anyhow, Result};
 use cliclack::{confirm, input, intro, log, outro, outro_cancel, password, select};
 use serde_json::{Map, Value};
 use std::fs;
+use std::io::{IsTerminal, Read};
 use std::path::PathBuf;
 use std::process;
 
@@ -39,6 +40,11 @@ pub fn run() -> Result<()> {
                 "Memory",
                 "Memory mode and extraction settings",
             )
+            .item(
+                MenuAction::Agent,
+                "Agent",
+                "Tool turns, retries, fallbacks and timeouts",
+            )
             .item(MenuAction::ShowPath, "Show config path", "")
             .item(MenuAction::SaveAndExit, "Save and exit", "")
             .item(MenuAction::ExitWithoutSaving, "Exit without saving", "")
@@ -80,6 +86,10 @@ pub fn run() -> Result<()> {
                 configure_memory(&mut root)?;
                 dirty = root != initial_root;
             }
+            MenuAction::Agent => {
+                configure_agent(&mut root)?;
+                dirty = root != initial_root;
+            }
             MenuAction::ShowPath => {
                 log::info(&format!("Config path: {}", path.display()))?;
             }
@@ -110,6 +120,160 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// Apply a JSON merge patch from stdin and/or `--set key=value` pairs without
+/// prompting, then save and sync the background service.
+pub fn run_non_interactive(sets: &[String], skip_service: bool) -> Result<()> {
+    let path = crate::config::config_path();
+    let mut root = load_config_value(&path)?;
+
+    let stdin = std::io::stdin();
+    let patch = if stdin.is_terminal() {
+        None
+    } else {
+        let mut raw = String::new();
+        stdin.lock().read_to_string(&mut raw)?;
+        if raw.trim().is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::from_str::<Value>(&raw)
+                    .map_err(|e| anyhow!("invalid JSON patch on stdin: {e}"))?,
+            )
+        }
+    };
+
+    let changed = apply_non_interactive(&mut root, patch.as_ref(), sets)?;
+    if changed.is_empty() {
+        println!("No changes to save.");
+    } else {
+        println!("Changes to save:");
+        for path in &changed {
+            println!("  - {path}");
+        }
+        save_config_value(&path, &root)?;
+        println!("Configuration saved to {}", path.display());
+    }
+    if !skip_service {
+        match service::query_status(Scope::User) {
+            Ok(status) if !service_needs_sync(!changed.is_empty(), &status) => {
+                println!("Configuration unchanged; background service left as is.");
+            }
+            _ => apply_service_lifecycle_after_save(),
+        }
+    }
+    Ok(())
+}
+
+/// Whether a save should touch the background service: always when it isn't
+/// installed yet, otherwise only when the configuration it runs with changed.
+fn service_needs_sync(config_changed: bool, status: &RuntimeStatus) -> bool {
+    config_changed || *status == RuntimeStatus::NotInstalled
+}
+
+/// Merge `patch` and `sets` into `root`, validate the result, and return the
+/// changed config paths.
+fn apply_non_interactive(
+    root: &mut Value,
+    patch: Option<&Value>,
+    sets: &[String],
+) -> Result<Vec<String>> {
+    let before = root.clone();
+    if let Some(patch) = patch {
+        if !patch.is_object() {
+            return Err(anyhow!("JSON patch must be an object"));
+        }
+        merge_patch(root, patch, &mut Vec::new())?;
+    }
+    for assignment in sets {
+        let (key, raw) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid --set '{assignment}': expected key=value"))?;
+        let path = key
+            .split('.')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        if path.is_empty() {
+            return Err(anyhow!("invalid --set '{assignment}': empty key"));
+        }
+        // Accept JSON literals (numbers, booleans, arrays); anything else is a string.
+        let value =
+            serde_json::from_str::<Value>(raw).unwrap_or_else(|_| Value::String(raw.to_string()));
+        set_path(root, &path, value)?;
+    }
+    validate_config_value(root)?;
+
+    let mut changed = Vec::new();
+    collect_changed_paths(&before, root, String::new(), &mut changed);
+    Ok(changed)
+}
+
+/// RFC 7386-style merge: objects merge recursively, `null` removes a key.
+fn merge_patch(root: &mut Value, patch: &Value, prefix: &mut Vec<String>) -> Result<()> {
+    let Some(obj) = patch.as_object() else {
+        return Ok(());
+    };
+    for (key, value) in obj {
+        prefix.push(key.clone());
+        let path = prefix.iter().map(String::as_str).collect::<Vec<_>>();
+        match value {
+            Value::Object(_) => {
+                let existing_is_object = get_value_at(root, &path).is_some_and(Value::is_object);
+                if !existing_is_object {
+                    set_path(root, &path, Value::Object(Map::new()))?;
+                }
+                merge_patch(root, value, prefix)?;
+            }
+            Value::Null => remove_path(root, &path),
+            _ => set_path(root, &path, value.clone())?,
+        }
+        prefix.pop();
+    }
+    Ok(())
+}
+
+fn remove_path(root: &mut Value, path: &[&str]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut cur = root;
+    for key in parents {
+        match cur.get_mut(*key) {
+            Some(next) => cur = next,
+            None => return,
+        }
+    }
+    if let Some(obj) = cur.as_object_mut() {
+        obj.remove(*last);
+    }
+}
+
+fn validate_config_value(root: &Value) -> Result<()> {
+    use crate::config::{MemoryMode, ProviderKind, WebFetchProvider, WebSearchProvider};
+
+    if let Some(provider) = get_str_at(root, &["agents", "defaults", "provider"]) {
+        if ProviderKind::parse(provider).is_none() {
+            return Err(anyhow!("unknown provider '{provider}'"));
+        }
+    }
+    if let Some(mode) = get_str_at(root, &["memory", "mode"]) {
+        if MemoryMode::parse(mode).is_none() {
+            return Err(anyhow!("unknown memory mode '{mode}'"));
+        }
+    }
+    if let Some(provider) = get_str_at(root, &["tools", "web", "search", "provider"]) {
+        if WebSearchProvider::parse(provider).is_none() {
+            return Err(anyhow!("unknown web search provider '{provider}'"));
+        }
+    }
+    if let Some(provider) = get_str_at(root, &["tools", "web", "fetch", "provider"]) {
+        if WebFetchProvider::parse(provider).is_none() {
+            return Err(anyhow!("unknown web fetch provider '{provider}'"));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum MenuAction {
     Provider,
@@ -118,6 +282,7 @@ enum MenuAction {
     Web,
     Transcription,
     Memory,
+    Agent,
     ShowPath,
     SaveAndExit,
     ExitWithoutSaving,
@@ -173,6 +338,7 @@ fn configure_provider(root: &mut Value) -> Result<bool> {
         .item("openrouter", "OpenRouter", "openrouter.ai")
         .item("openai", "OpenAI", "api.openai.com")
         .item("ollama", "Ollama", "local")
+        .item("azure", "Azure OpenAI", "*.openai.azure.com deployments")
         .initial_value(&current_provider)
         .interact()?;
 
@@ -227,6 +393,38 @@ fn configure_provider(root: &mut Value) -> Result<bool> {
                 Value::String(base),
             )?;
         }
+        "azure" => {
+            let current_key = get_str_at(root, &["providers", "azure", "apiKey"]).unwrap_or("");
+            let current_endpoint =
+                get_str_at(root, &["providers", "azure", "endpoint"]).unwrap_or("");
+            let current_deployment =
+                get_str_at(root, &["providers", "azure", "deployment"]).unwrap_or("");
+            let current_version =
+                get_str_at(root, &["providers", "azure", "apiVersion"]).unwrap_or("2024-10-21");
+            let key = prompt_secret("Azure OpenAI API key", current_key)?;
+            let endpoint = prompt_str(
+                "Azure OpenAI endpoint (https://<resource>.openai.azure.com)",
+                current_endpoint,
+            )?;
+            let deployment = prompt_str("Deployment name", current_deployment)?;
+            let version = prompt_str("API version (or v1)", current_version)?;
+            set_path(root, &["providers", "azure", "apiKey"], Value::String(key))?;
+            set_path(
+                root,
+                &["providers", "azure", "endpoint"],
+                Value::String(endpoint),
+            )?;
+            set_path(
+                root,
+                &["providers", "azure", "deployment"],
+                Value::String(deployment),
+            )?;
+            set_path(
+                root,
+                &["providers", "azure", "apiVersion"],
+                Value::String(version),
+            )?;
+        }
         _ => {}
     }
 
@@ -358,6 +556,7 @@ fn configure_web_search(root: &mut Value) -> Result<bool> {
             "Firecrawl Search API",
         )
         .item("brave", "Brave", "Brave Search API")
+        .item("searxng", "SearXNG", "Self-hosted meta-search, no API key")
         .initial_value(&current_provider)
         .interact()?;
     set_path(
@@ -366,6 +565,18 @@ fn configure_web_search(root: &mut Value) -> Result<bool> {
         Value::String(provider.to_string()),
     )?;
 
+    if provider == "searxng" {
+        let current_url =
+            get_str_at(root, &["tools", "web", "search", "searxng_base_url"]).unwrap_or("");
+        let url = prompt_str("SearXNG base URL (e.g. http://localhost:8888)", current_url)?;
+        set_path(
+            root,
+            &["tools", "web", "search", "searxng_base_url"],
+            Value::String(url.trim().to_string()),
+        )?;
+        return Ok(root != &before);
+    }
+
     let current_brave = get_str_at(root, &["tools", "web", "search", "braveApiKey"])
         .or_else(|| get_str_at(root, &["tools", "web", "search", "apiKey"]))
         .unwrap_or("");
@@ -673,6 +884,109 @@ fn configure_memory(root: &mut Value) -> Result<bool> {
     Ok(root != &before)
 }
 
+/// Agent runtime limits edited by the "Agent" menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AgentLimits {
+    max_tool_iterations: u64,
+    max_continuations: u64,
+    max_retries: u64,
+    retry_base_delay_ms: u64,
+    /// 0 tries every route.
+    max_routes_attempted: u64,
+    exec_timeout_secs: u64,
+}
+
+impl AgentLimits {
+    fn current(root: &Value) -> Self {
+        let defaults = |key: &str| get_u64_at(root, &["agents", "defaults", key]);
+        Self {
+            max_tool_iterations: defaults("max_tool_iterations").unwrap_or(20),
+            max_continuations: defaults("max_continuations").unwrap_or(2),
+            max_retries: defaults("max_retries").unwrap_or(2),
+            retry_base_delay_ms: defaults("retry_base_delay_ms").unwrap_or(400),
+            max_routes_attempted: defaults("max_routes_attempted").unwrap_or(0),
+            exec_timeout_secs: get_u64_at(root, &["tools", "exec", "timeout"]).unwrap_or(60),
+        }
+    }
+
+    fn write(&self, root: &mut Value) -> Result<()> {
+        let number = |n: u64| Value::Number(serde_json::Number::from(n));
+        for (key, value) in [
+            ("max_tool_iterations", self.max_tool_iterations),
+            ("max_continuations", self.max_continuations),
+            ("max_retries", self.max_retries),
+            ("retry_base_delay_ms", self.retry_base_delay_ms),
+            ("max_routes_attempted", self.max_routes_attempted),
+        ] {
+            set_path(root, &["agents", "defaults", key], number(value))?;
+        }
+        set_path(
+            root,
+            &["tools", "exec", "timeout"],
+            number(self.exec_timeout_secs),
+        )
+    }
+}
+
+fn prompt_u64(label: &str, current: u64, min: u64, max: u64) -> Result<u64> {
+    Ok(input(label)
+        .default_input(&current.to_string())
+        .required(false)
+        .validate(move |s: &String| match s.trim().parse::<u64>() {
+            Ok(n) if (min..=max).contains(&n) => Ok(()),
+            Ok(_) => Err(format!("Enter a value between {min} and {max}")),
+            Err(_) => Err("Enter a non-negative integer".to_string()),
+        })
+        .interact()?)
+}
+
+fn configure_agent(root: &mut Value) -> Result<bool> {
+    let before = root.clone();
+    let current = AgentLimits::current(root);
+
+    let limits = AgentLimits {
+        max_tool_iterations: prompt_u64(
+            "Max tool turns per message",
+            current.max_tool_iterations,
+            1,
+            1000,
+        )?,
+        max_continuations: prompt_u64(
+            "Continuation rounds after a cut-off reply (0 = off)",
+            current.max_continuations,
+            0,
+            crate::config::MAX_CONTINUATIONS_LIMIT as u64,
+        )?,
+        max_retries: prompt_u64(
+            "Retries on the same model after a transient error",
+            current.max_retries,
+            0,
+            10,
+        )?,
+        retry_base_delay_ms: prompt_u64(
+            "Base retry delay (ms)",
+            current.retry_base_delay_ms,
+            0,
+            60_000,
+        )?,
+        max_routes_attempted: prompt_u64(
+            "Max models tried per message, fallbacks included (0 = all)",
+            current.max_routes_attempted,
+            0,
+            100,
+        )?,
+        exec_timeout_secs: prompt_u64(
+            "Shell command timeout (seconds)",
+            current.exec_timeout_secs,
+            1,
+            86_400,
+        )?,
+    };
+    limits.write(root)?;
+
+    Ok(root != &before)
+}
+
 fn parse_comma_list(input: &str, fallback: &[String]) -> Vec<String> {
     if input.trim().is_empty() {
         return fallback.to_vec();
@@ -815,6 +1129,14 @@ fn get_str_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a str> {
     cur.as_str()
 }
 
+fn get_value_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    let mut cur = value;
+    for key in path {
+        cur = cur.get(*key)?;
+    }
+    Some(cur)
+}
+
 fn get_array_at(value: &Value, path: &[&str]) -> Vec<String> {
     let mut cur = value;
     for key in path {
@@ -877,3 +1199,103 @@ fn apply_service_lifecycle_after_save() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_interactive_patch_merges_and_reports_changes() {
+        let mut root = serde_json::json!({
+            "agents": { "defaults": { "provider": "openrouter", "model": "a/b" } },
+            "providers": { "openrouter": { "apiKey": "old" } },
+            "memory": { "mode": "simple" }
+        });
+        let patch = serde_json::json!({
+            "providers": { "openrouter": { "apiKey": "new" } },
+            "memory": { "mode": null },
+            "channels": { "telegram": { "token": "123:abc" } }
+        });
+        let sets = vec![
+            "agents.defaults.max_tool_iterations=12".to_string(),
+            "tools.restrict_to_workspace=true".to_string(),
+        ];
+
+        let changed = apply_non_interactive(&mut root, Some(&patch), &sets).unwrap();
+
+        assert_eq!(root["providers"]["openrouter"]["apiKey"], "new");
+        assert_eq!(root["agents"]["defaults"]["model"], "a/b");
+        assert_eq!(root["agents"]["defaults"]["max_tool_iterations"], 12);
+        assert_eq!(root["tools"]["restrict_to_workspace"], true);
+        assert_eq!(root["channels"]["telegram"]["token"], "123:abc");
+        assert!(root["memory"].get("mode").is_none());
+        assert_eq!(
+            changed,
+            vec![
+                "agents.defaults.max_tool_iterations",
+                "channels",
+                "memory.mode",
+                "providers.openrouter.apiKey",
+                "tools",
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_config_leaves_an_installed_service_alone() {
+        assert!(!service_needs_sync(false, &RuntimeStatus::Running));
+        assert!(!service_needs_sync(false, &RuntimeStatus::Stopped(None)));
+        assert!(service_needs_sync(true, &RuntimeStatus::Running));
+        assert!(service_needs_sync(false, &RuntimeStatus::NotInstalled));
+
+        let mut root = serde_json::json!({ "agents": { "defaults": { "model": "a/b" } } });
+        let sets = vec!["agents.defaults.model=a/b".to_string()];
+        assert!(apply_non_interactive(&mut root, None, &sets)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn agent_limits_write_runtime_config_keys() {
+        let mut root = serde_json::json!({
+            "agents": { "defaults": { "model": "a/b", "max_retries": 5 } }
+        });
+        let current = AgentLimits::current(&root);
+        assert_eq!(current.max_retries, 5);
+        assert_eq!(current.max_tool_iterations, 20);
+
+        let limits = AgentLimits {
+            max_tool_iterations: 8,
+            max_continuations: 0,
+            max_retries: 1,
+            retry_base_delay_ms: 250,
+            max_routes_attempted: 2,
+            exec_timeout_secs: 30,
+        };
+        limits.write(&mut root).unwrap();
+
+        let defaults = &root["agents"]["defaults"];
+        assert_eq!(defaults["model"], "a/b");
+        assert_eq!(defaults["max_tool_iterations"], 8);
+        assert_eq!(defaults["max_continuations"], 0);
+        assert_eq!(defaults["max_retries"], 1);
+        assert_eq!(defaults["retry_base_delay_ms"], 250);
+        assert_eq!(defaults["max_routes_attempted"], 2);
+        assert_eq!(root["tools"]["exec"]["timeout"], 30);
+        assert_eq!(AgentLimits::current(&root), limits);
+
+        let mut cfg = crate::config::AppConfig::defaults();
+        crate::config::apply_lightclaw_config(&mut cfg, &root);
+        assert_eq!(cfg.model.max_tool_turns, 8);
+        assert_eq!(cfg.model.max_routes_attempted, Some(2));
+        assert_eq!(cfg.tools.exec_timeout_secs, 30);
+    }
+
+    #[test]
+    fn non_interactive_rejects_invalid_values() {
+        let mut root = serde_json::json!({});
+        let sets = vec!["memory.mode=bogus".to_string()];
+        assert!(apply_non_interactive(&mut root, None, &sets).is_err());
+        assert!(apply_non_interactive(&mut root, None, &["novalue".to_string()]).is_err());
+    }
+}
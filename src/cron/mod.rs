@@ -1,6 +1,7 @@
 pub mod store;
 pub mod types;
 
+use crate::broadcast::{self, KnownChats};
 use crate::bus::{InboundMessage, MessageBus};
 use crate::config::AppConfig;
 use anyhow::Result;
@@ -16,6 +17,7 @@ use types::{CronJob, CronSchedule};
 struct CronInner {
     store: Mutex<store::CronStore>,
     bus: MessageBus,
+    chats: KnownChats,
     notify: Notify,
 }
 
@@ -37,6 +39,7 @@ impl CronService {
             inner: Arc::new(CronInner {
                 store: Mutex::new(store),
                 bus,
+                chats: KnownChats::new(&cfg.data_dir),
                 notify: Notify::new(),
             }),
         }
@@ -147,28 +150,45 @@ impl CronService {
             }
         }
 
+        let activity = self.inner.bus.activity();
         for idx in jobs_to_run {
             let job = &mut store.jobs[idx];
+            let (channel, chat_id) = job_target(job);
+            if job
+                .payload
+                .skip_for_activity(activity.last_inbound_ms(&channel, &chat_id), now)
+            {
+                info!(
+                    "Skipping check-in job {} ({}): {channel}:{chat_id} was active recently",
+                    job.name, job.id
+                );
+                job.state.last_run_at_ms = Some(now);
+                job.state.last_status = Some("skipped_active".to_string());
+                job.updated_at_ms = now;
+                job.state.next_run_at_ms = compute_next_run(&job.schedule, now);
+                continue;
+            }
             info!("Executing cron job: {} ({})", job.name, job.id);
 
-            // Send message to bus
-            let msg = InboundMessage {
-                channel: job
-                    .payload
-                    .channel
-                    .clone()
-                    .unwrap_or_else(|| "cron".to_string()),
-                chat_id: job
-                    .payload
-                    .to
-                    .clone()
-                    .unwrap_or_else(|| "direct".to_string()),
-                sender_id: "cron".to_string(),
-                content: job.payload.message.clone(),
-                // TODO: Propagate job.payload.model when InboundMessage supports it
-                // For now, we just ensure the field exists in CronPayload
-            };
-            self.inner.bus.publish_inbound(msg).await;
+            if job.payload.kind == "broadcast" {
+                let chats = self.inner.chats.list().await;
+                let queued = broadcast::broadcast(&self.inner.bus, chats, &job.payload.message);
+                info!("Broadcast job {} queued {queued} message(s)", job.id);
+            } else {
+                // Send message to bus
+                let msg = InboundMessage {
+                    channel,
+                    chat_id,
+                    sender_id: "cron".to_string(),
+                    content: job.payload.message.clone(),
+                    message_id: None,
+                    mention: job.payload.mention_user.clone(),
+                    attachments: Vec::new(),
+                    // TODO: Propagate job.payload.model when InboundMessage supports it
+                    // For now, we just ensure the field exists in CronPayload
+                };
+                self.inner.bus.publish_inbound(msg).await;
+            }
 
             // Update state
             job.state.last_run_at_ms = Some(now);
@@ -203,6 +223,50 @@ impl CronService {
         message: String,
         channel: Option<String>,
         to: Option<String>,
+        mention_user: Option<String>,
+    ) -> Result<()> {
+        let payload = types::CronPayload {
+            kind: "agent_turn".to_string(),
+            message,
+            deliver: false,
+            channel,
+            to,
+            model: None, // Default
+            mention_user,
+            idle_after_ms: None,
+        };
+        self.add_recurring(name, schedule, payload).await
+    }
+
+    /// Schedule a check-in: like `add_job`, but each run is skipped while
+    /// the chat has had an inbound message within `idle_after`.
+    pub async fn add_check_in(
+        &self,
+        name: String,
+        schedule: String,
+        message: String,
+        channel: String,
+        to: String,
+        idle_after: Duration,
+    ) -> Result<()> {
+        let payload = types::CronPayload {
+            kind: "agent_turn".to_string(),
+            message,
+            deliver: false,
+            channel: Some(channel),
+            to: Some(to),
+            model: None,
+            mention_user: None,
+            idle_after_ms: Some(idle_after.as_millis() as i64),
+        };
+        self.add_recurring(name, schedule, payload).await
+    }
+
+    async fn add_recurring(
+        &self,
+        name: String,
+        schedule: String,
+        payload: types::CronPayload,
     ) -> Result<()> {
         let mut store = self.inner.store.lock().await;
         store.load()?;
@@ -231,6 +295,58 @@ impl CronService {
 
         let next = compute_next_run(&sched, now);
 
+        let job = CronJob {
+            id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
+            name,
+            enabled: true,
+            schedule: sched,
+            payload,
+            state: types::CronState {
+                next_run_at_ms: next,
+                ..Default::default()
+            },
+            created_at_ms: now,
+            updated_at_ms: now,
+            delete_after_run: false,
+        };
+
+        store.add(job.clone())?;
+        info!("Added job: {}", job.id);
+
+        // Notify the loop to pick up the new job immediately
+        self.inner.notify.notify_one();
+
+        Ok(())
+    }
+
+    /// Schedule a job that fires once at `at` and is then disabled.
+    pub async fn add_one_shot(
+        &self,
+        name: String,
+        at: DateTime<Utc>,
+        message: String,
+        channel: Option<String>,
+        to: Option<String>,
+        mention_user: Option<String>,
+    ) -> Result<CronJob> {
+        let mut store = self.inner.store.lock().await;
+        store.load()?;
+        let now = Utc::now().timestamp_millis();
+
+        let sched = CronSchedule {
+            kind: "at".to_string(),
+            at_ms: Some(at.timestamp_millis()),
+            every_ms: None,
+            expr: None,
+            tz: None,
+        };
+        let Some(next) = compute_next_run(&sched, now) else {
+            return Err(anyhow::anyhow!(
+                "Reminder time {} is in the past",
+                at.to_rfc3339()
+            ));
+        };
+
         let job = CronJob {
             id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
             name,
@@ -242,24 +358,73 @@ impl CronService {
                 deliver: false,
                 channel,
                 to,
-                model: None, // Default
+                model: None,
+                mention_user,
+                idle_after_ms: None,
             },
             state: types::CronState {
-                next_run_at_ms: next,
+                next_run_at_ms: Some(next),
                 ..Default::default()
             },
             created_at_ms: now,
             updated_at_ms: now,
-            delete_after_run: false,
+            delete_after_run: true,
         };
 
         store.add(job.clone())?;
-        info!("Added job: {}", job.id);
+        info!("Added one-shot job: {}", job.id);
+        self.inner.notify.notify_one();
 
-        // Notify the loop to pick up the new job immediately
+        Ok(job)
+    }
+
+    /// Queue `text` for every known chat. The running service's scheduler
+    /// delivers it, so the CLI can broadcast without its own channel
+    /// connections.
+    pub async fn add_broadcast(&self, text: String) -> Result<CronJob> {
+        let mut store = self.inner.store.lock().await;
+        store.load()?;
+        let now = Utc::now().timestamp_millis();
+        let job = CronJob {
+            id: uuid::Uuid::new_v4().to_string()[..8].to_string(),
+            name: "broadcast".to_string(),
+            enabled: true,
+            schedule: CronSchedule {
+                kind: "at".to_string(),
+                at_ms: Some(now),
+                every_ms: None,
+                expr: None,
+                tz: None,
+            },
+            payload: types::CronPayload {
+                kind: "broadcast".to_string(),
+                message: text,
+                deliver: true,
+                channel: None,
+                to: None,
+                model: None,
+                mention_user: None,
+                idle_after_ms: None,
+            },
+            state: types::CronState {
+                next_run_at_ms: Some(now),
+                ..Default::default()
+            },
+            created_at_ms: now,
+            updated_at_ms: now,
+            delete_after_run: true,
+        };
+
+        store.add(job.clone())?;
+        info!("Added broadcast job: {}", job.id);
         self.inner.notify.notify_one();
 
-        Ok(())
+        Ok(job)
+    }
+
+    /// Chats a broadcast would reach.
+    pub async fn known_chats(&self) -> Vec<broadcast::KnownChat> {
+        self.inner.chats.list().await
     }
 
     pub async fn list_jobs(&self) -> Result<Vec<CronJob>> {
@@ -296,6 +461,20 @@ impl CronService {
     }
 }
 
+/// Bus channel and chat a job's turn is published to.
+fn job_target(job: &CronJob) -> (String, String) {
+    (
+        job.payload
+            .channel
+            .clone()
+            .unwrap_or_else(|| "cron".to_string()),
+        job.payload
+            .to
+            .clone()
+            .unwrap_or_else(|| "direct".to_string()),
+    )
+}
+
 fn compute_next_run(schedule: &CronSchedule, now_ms: i64) -> Option<i64> {
     match schedule.kind.as_str() {
         "at" => {
@@ -329,3 +508,74 @@ fn compute_next_run(schedule: &CronSchedule, now_ms: i64) -> Option<i64> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn check_in_is_skipped_while_the_chat_is_active() {
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir =
+            std::env::temp_dir().join(format!("lightclaw-cron-{}", uuid::Uuid::new_v4()));
+        let bus = MessageBus::new();
+        let service = CronService::new(&cfg, bus.clone());
+        service
+            .add_check_in(
+                "check-in".to_string(),
+                "3600".to_string(),
+                "Check in if they've gone quiet".to_string(),
+                "telegram".to_string(),
+                "42".to_string(),
+                Duration::from_secs(1800),
+            )
+            .await
+            .unwrap();
+        let make_due = || async {
+            let mut store = service.inner.store.lock().await;
+            store.load().unwrap();
+            store.jobs[0].state.next_run_at_ms = Some(0);
+            store.save().unwrap();
+        };
+
+        bus.publish_inbound(InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "42".to_string(),
+            sender_id: "7".to_string(),
+            content: "hi".to_string(),
+            message_id: None,
+            mention: None,
+            attachments: Vec::new(),
+        })
+        .await;
+        let _ = bus.consume_inbound().await.unwrap();
+
+        make_due().await;
+        service.process_due_jobs().await;
+        let job = service.list_jobs().await.unwrap().remove(0);
+        assert_eq!(job.state.last_status.as_deref(), Some("skipped_active"));
+        assert!(job.state.next_run_at_ms.unwrap() > 0);
+
+        // Idle for longer than the window: the check-in fires.
+        bus.activity().touch(
+            "telegram",
+            "42",
+            Utc::now().timestamp_millis() - 2 * 1800 * 1000,
+        );
+        make_due().await;
+        service.process_due_jobs().await;
+        let (fired, _) = tokio::time::timeout(Duration::from_secs(1), bus.consume_inbound())
+            .await
+            .expect("check-in published")
+            .unwrap();
+        assert_eq!(
+            (fired.channel.as_str(), fired.chat_id.as_str()),
+            ("telegram", "42")
+        );
+        assert_eq!(fired.sender_id, "cron");
+        let job = service.list_jobs().await.unwrap().remove(0);
+        assert_eq!(job.state.last_status.as_deref(), Some("ok"));
+
+        let _ = std::fs::remove_dir_all(&cfg.data_dir);
+    }
+}
@@ -30,13 +30,32 @@ pub struct CronSchedule {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CronPayload {
-    pub kind: String, // "agent_turn"
+    pub kind: String, // "agent_turn", "broadcast"
     pub message: String,
     #[serde(default)]
     pub deliver: bool,
     pub channel: Option<String>,
     pub to: Option<String>,
     pub model: Option<String>,
+    /// Id of the user who scheduled the job, pinged when it notifies.
+    #[serde(default)]
+    pub mention_user: Option<String>,
+    /// Check-in jobs only fire when the target chat has had no inbound
+    /// message for this long; otherwise the run is skipped.
+    #[serde(rename = "idleAfterMs", default)]
+    pub idle_after_ms: Option<i64>,
+}
+
+impl CronPayload {
+    /// Whether a due run should be skipped because the target chat was
+    /// active within the idle window. A chat with no recorded activity
+    /// counts as idle.
+    pub fn skip_for_activity(&self, last_inbound_ms: Option<i64>, now_ms: i64) -> bool {
+        match (self.idle_after_ms, last_inbound_ms) {
+            (Some(window), Some(last)) => now_ms - last < window,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -0,0 +1,434 @@
+use crate::config::{self, AppConfig, MemoryMode, ProviderKind};
+use crate::memory::smart::client::LlmClient;
+use anyhow::{anyhow, Result};
+use rusqlite::OpenFlags;
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(8);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Run every setup check, print a checklist, and fail if any check failed.
+pub async fn run() -> Result<()> {
+    let path = config::config_path();
+    let mut checks = vec![check_config_file(&path)];
+
+    let cfg = AppConfig::load_relaxed();
+    checks.push(check_provider_key(&cfg));
+    if checks.last().map(|c| c.status) == Some(Status::Pass) {
+        checks.push(check_provider_reachable(&cfg).await);
+    }
+    checks.push(check_channels_enabled(&cfg));
+    if cfg.telegram_enabled() {
+        checks.push(check_telegram_auth(&cfg).await);
+    }
+    if cfg.discord_enabled() {
+        checks.push(check_discord_auth(&cfg).await);
+    }
+    checks.push(check_dir_writable(
+        "Workspace directory",
+        &cfg.workspace_dir,
+    ));
+    checks.push(check_dir_writable("Data directory", &cfg.data_dir));
+    if cfg.memory.mode == MemoryMode::Smart {
+        checks.push(check_vector_db(&cfg));
+        checks.push(check_embedding_config(&cfg));
+        if checks.last().map(|c| c.status) == Some(Status::Pass) {
+            checks.push(check_embeddings(&cfg).await);
+        }
+    }
+
+    println!("lightclaw doctor\n");
+    for check in &checks {
+        let tag = match check.status {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        };
+        println!("[{tag}] {}: {}", check.name, check.detail);
+        if let Some(hint) = &check.hint {
+            println!("       hint: {hint}");
+        }
+    }
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    println!();
+    if failed == 0 {
+        println!("All checks passed.");
+        Ok(())
+    } else {
+        Err(anyhow!("{failed} check(s) failed"))
+    }
+}
+
+fn check_config_file(path: &Path) -> Check {
+    const NAME: &str = "Config file";
+    if !path.exists() {
+        return Check::warn(
+            NAME,
+            format!("{} not found; using defaults and env vars", path.display()),
+            "Run `lightclaw configure` to create it",
+        );
+    }
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) => {
+            return Check::fail(
+                NAME,
+                format!("cannot read {}: {err}", path.display()),
+                "Check file permissions",
+            )
+        }
+    };
+    match serde_json::from_str::<Value>(&content) {
+        Ok(value) if value.is_object() => Check::pass(NAME, path.display().to_string()),
+        Ok(_) => Check::fail(
+            NAME,
+            format!("{} is not a JSON object", path.display()),
+            "The config root must be an object; re-run `lightclaw configure`",
+        ),
+        Err(err) => Check::fail(
+            NAME,
+            format!("{} does not parse: {err}", path.display()),
+            "Fix the JSON syntax or move the file aside and re-run `lightclaw configure`",
+        ),
+    }
+}
+
+fn check_provider_key(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Provider API key";
+    let provider = cfg.provider.as_str();
+    if !cfg.provider_requires_api_key() {
+        return Check::pass(NAME, format!("{provider} does not require a key"));
+    }
+    if cfg.provider_api_key().trim().is_empty() {
+        return Check::fail(
+            NAME,
+            format!("no API key for active provider '{provider}'"),
+            format!(
+                "Set providers.{provider}.apiKey via `lightclaw configure` or the provider env var"
+            ),
+        );
+    }
+    Check::pass(NAME, format!("{provider} key configured"))
+}
+
+async fn check_provider_reachable(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Provider endpoint";
+    let base = provider_base_url(cfg);
+    let client = match reqwest::Client::builder().timeout(NETWORK_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(err) => return Check::fail(NAME, err.to_string(), "Check TLS/system setup"),
+    };
+    // Any HTTP response proves the endpoint is reachable; auth is checked on use.
+    match client.get(&base).send().await {
+        Ok(res) => Check::pass(NAME, format!("{base} responded ({})", res.status())),
+        Err(err) => Check::fail(
+            NAME,
+            format!("{base} unreachable: {err}"),
+            "Check the base URL, network access, or that the local server (Ollama) is running",
+        ),
+    }
+}
+
+fn provider_base_url(cfg: &AppConfig) -> String {
+    match cfg.provider {
+        ProviderKind::OpenRouter => cfg.providers.openrouter.base_url.clone(),
+        ProviderKind::OpenAI => cfg.providers.openai.base_url.clone(),
+        ProviderKind::Ollama => cfg.providers.ollama.base_url.clone(),
+        ProviderKind::Azure => crate::providers::azure_base_url(&cfg.providers.azure),
+    }
+}
+
+fn check_channels_enabled(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Chat channels";
+    let mut enabled = Vec::new();
+    if cfg.telegram_enabled() {
+        enabled.push("telegram");
+    }
+    if cfg.discord_enabled() {
+        enabled.push("discord");
+    }
+    if enabled.is_empty() {
+        return Check::fail(
+            NAME,
+            "no channel has a bot token",
+            "Configure Telegram or Discord with `lightclaw configure`, or use `lightclaw tui`",
+        );
+    }
+    Check::pass(NAME, enabled.join(", "))
+}
+
+async fn check_telegram_auth(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Telegram token";
+    let url = format!(
+        "https://api.telegram.org/bot{}/getMe",
+        cfg.channels.telegram.bot_token.trim()
+    );
+    match authenticated_get(&url, None).await {
+        Ok(body) => {
+            let username = body
+                .pointer("/result/username")
+                .and_then(Value::as_str)
+                .unwrap_or("?");
+            Check::pass(NAME, format!("authenticated as @{username}"))
+        }
+        Err(err) => Check::fail(
+            NAME,
+            err.to_string(),
+            "Verify the token with @BotFather and update channels.telegram.token",
+        ),
+    }
+}
+
+async fn check_discord_auth(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Discord token";
+    let auth = format!("Bot {}", cfg.channels.discord.bot_token.trim());
+    match authenticated_get("https://discord.com/api/v10/users/@me", Some(&auth)).await {
+        Ok(body) => {
+            let username = body.get("username").and_then(Value::as_str).unwrap_or("?");
+            Check::pass(NAME, format!("authenticated as {username}"))
+        }
+        Err(err) => Check::fail(
+            NAME,
+            err.to_string(),
+            "Regenerate the bot token in the Discord developer portal and update channels.discord.token",
+        ),
+    }
+}
+
+async fn authenticated_get(url: &str, authorization: Option<&str>) -> Result<Value> {
+    let client = reqwest::Client::builder()
+        .timeout(NETWORK_TIMEOUT)
+        .build()?;
+    let mut req = client.get(url);
+    if let Some(auth) = authorization {
+        req = req.header(reqwest::header::AUTHORIZATION, auth);
+    }
+    let res = req.send().await?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(anyhow!("request rejected with status {status}"));
+    }
+    Ok(res.json().await?)
+}
+
+fn check_dir_writable(name: &'static str, dir: &Path) -> Check {
+    if let Err(err) = std::fs::create_dir_all(dir) {
+        return Check::fail(
+            name,
+            format!("cannot create {}: {err}", dir.display()),
+            "Fix permissions or point LIGHTCLAW_DATA_DIR / LIGHTCLAW_WORKSPACE_DIR elsewhere",
+        );
+    }
+    let probe = dir.join(format!(".lightclaw-doctor-{}", std::process::id()));
+    let result = std::fs::write(&probe, b"ok");
+    let _ = std::fs::remove_file(&probe);
+    match result {
+        Ok(()) => Check::pass(name, format!("{} is writable", dir.display())),
+        Err(err) => Check::fail(
+            name,
+            format!("{} is not writable: {err}", dir.display()),
+            "Fix permissions or point LIGHTCLAW_DATA_DIR / LIGHTCLAW_WORKSPACE_DIR elsewhere",
+        ),
+    }
+}
+
+fn check_vector_db(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Vector store";
+    let path = crate::memory::cli::vector_db_path(cfg);
+    if !path.exists() {
+        return Check::warn(
+            NAME,
+            format!("{} is missing", path.display()),
+            "It is created on the first run with Smart memory",
+        );
+    }
+    // Read-only so the doctor never creates or modifies the database.
+    let opened = rusqlite::Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| conn.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)));
+    match opened {
+        Ok(result) if result == "ok" => Check::pass(NAME, path.display().to_string()),
+        Ok(result) => Check::fail(
+            NAME,
+            format!("{} integrity check: {result}", path.display()),
+            "Back up with `lightclaw memory export`, then remove the database to rebuild it",
+        ),
+        Err(err) => Check::fail(
+            NAME,
+            format!("cannot open {}: {err}", path.display()),
+            "Check permissions on the workspace memory directory",
+        ),
+    }
+}
+
+fn check_embedding_config(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Embedding config";
+    match cfg.smart_memory_problem() {
+        Some(problem) => Check::fail(
+            NAME,
+            problem,
+            "Smart memory needs an embedding model the active provider serves",
+        ),
+        None => Check::pass(
+            NAME,
+            format!("{} via {}", cfg.embedding_model(), cfg.provider.as_str()),
+        ),
+    }
+}
+
+async fn check_embeddings(cfg: &AppConfig) -> Check {
+    const NAME: &str = "Embeddings";
+    let client =
+        match LlmClient::from_config(cfg) {
+            Ok(client) => client,
+            Err(err) => return Check::fail(
+                NAME,
+                err.to_string(),
+                "Smart memory needs a provider key; configure one or switch memory.mode to simple",
+            ),
+        };
+    let model = cfg.embedding_model();
+    match tokio::time::timeout(
+        NETWORK_TIMEOUT,
+        client.embeddings(&model, "lightclaw doctor"),
+    )
+    .await
+    {
+        Ok(Ok(vector)) => Check::pass(NAME, format!("{model} returned {} dims", vector.len())),
+        Ok(Err(err)) => Check::fail(
+            NAME,
+            format!("{model} failed: {err}"),
+            "Pick an embedding model your provider serves (memory.embedding_model)",
+        ),
+        Err(_) => Check::fail(
+            NAME,
+            format!("{model} timed out"),
+            "Check provider connectivity or switch memory.mode to simple",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn config_file_check_reports_parse_errors() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-doctor-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.json");
+
+        assert_eq!(check_config_file(&path).status, Status::Warn);
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq!(check_config_file(&path).status, Status::Fail);
+        std::fs::write(&path, r#"{"agents":{}}"#).unwrap();
+        assert_eq!(check_config_file(&path).status, Status::Pass);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn provider_key_check_depends_on_provider() {
+        let mut cfg = AppConfig::defaults();
+        cfg.provider = ProviderKind::OpenRouter;
+        cfg.providers.openrouter.api_key.clear();
+        let check = check_provider_key(&cfg);
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.hint.unwrap().contains("providers.openrouter.apiKey"));
+
+        cfg.providers.openrouter.api_key = "sk-test".to_string();
+        assert_eq!(check_provider_key(&cfg).status, Status::Pass);
+
+        cfg.provider = ProviderKind::Ollama;
+        assert_eq!(check_provider_key(&cfg).status, Status::Pass);
+    }
+
+    #[test]
+    fn channels_check_fails_without_tokens() {
+        let mut cfg = AppConfig::defaults();
+        cfg.channels.telegram.bot_token.clear();
+        cfg.channels.discord.bot_token.clear();
+        assert_eq!(check_channels_enabled(&cfg).status, Status::Fail);
+
+        cfg.channels.discord.bot_token = "token".to_string();
+        let check = check_channels_enabled(&cfg);
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(check.detail, "discord");
+    }
+
+    #[test]
+    fn dir_check_detects_writable_directory() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-doctor-{}", Uuid::new_v4()));
+        assert_eq!(check_dir_writable("Workspace", &dir).status, Status::Pass);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn vector_db_check_reports_missing_without_creating_it() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-doctor-{}", Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.workspace_dir = dir.join("workspace");
+        cfg.data_dir = dir.join("data");
+        let path = crate::memory::cli::vector_db_path(&cfg);
+
+        let check = check_vector_db(&cfg);
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.contains("missing"));
+        assert!(!path.exists());
+
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x INTEGER)")
+            .unwrap();
+        assert_eq!(check_vector_db(&cfg).status, Status::Pass);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config::{self, AppConfig};
+use anyhow::Result;
+use serde_json::Value;
+
+pub(crate) const REDACTED: &str = "<redacted>";
+
+/// Names of secret-bearing keys; a key is secret when it is one of them or
+/// ends in one after an underscore (`bot_token`, `brave_api_key`).
+const SECRET_KEYS: &[&str] = &["api_key", "token", "secret", "password", "headers"];
+
+/// Print build and effective-config details for bug reports.
+pub fn run() -> Result<()> {
+    let cfg = AppConfig::load_relaxed();
+    print!("{}", render(&cfg)?);
+    Ok(())
+}
+
+fn render(cfg: &AppConfig) -> Result<String> {
+    let features = env!("LIGHTCLAW_FEATURES");
+    let mut channels = Vec::new();
+    if cfg.telegram_enabled() {
+        channels.push("telegram");
+    }
+    if cfg.discord_enabled() {
+        channels.push("discord");
+    }
+
+    let mut effective = serde_json::to_value(cfg)?;
+    redact(&mut effective);
+
+    let mut out = String::new();
+    out.push_str(&format!("lightclaw {}\n", env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!("git sha: {}\n", env!("LIGHTCLAW_GIT_SHA")));
+    out.push_str(&format!(
+        "target: {}-{}\n",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    ));
+    out.push_str(&format!(
+        "features: {}\n",
+        if features.is_empty() {
+            "none"
+        } else {
+            features
+        }
+    ));
+    out.push_str(&format!(
+        "config file: {}\n",
+        config::config_path().display()
+    ));
+    out.push_str(&format!("provider: {}\n", cfg.provider.as_str()));
+    out.push_str(&format!("model: {}\n", cfg.model.model));
+    let memory_mode = format!("{:?}", cfg.memory.mode).to_lowercase();
+    out.push_str(&format!("memory mode: {memory_mode}\n"));
+    out.push_str(&format!(
+        "channels: {}\n",
+        if channels.is_empty() {
+            "none".to_string()
+        } else {
+            channels.join(", ")
+        }
+    ));
+    out.push_str("\neffective config (secrets redacted):\n");
+    out.push_str(&serde_json::to_string_pretty(&effective)?);
+    out.push('\n');
+    Ok(out)
+}
+
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.iter().any(|name| {
+        key.strip_suffix(name)
+            .is_some_and(|rest| rest.is_empty() || rest.ends_with('_'))
+    })
+}
+
+/// Blank out secret-bearing fields, keeping empty ones visible as unset.
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if !is_secret_key(key) {
+                    redact(field);
+                    continue;
+                }
+                let unset = match field {
+                    Value::Null => true,
+                    Value::String(s) => s.trim().is_empty(),
+                    Value::Array(items) => items.is_empty(),
+                    Value::Object(map) => map.is_empty(),
+                    _ => false,
+                };
+                if !unset {
+                    *field = Value::String(REDACTED.to_string());
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendered_info_redacts_key_bearing_fields() {
+        let mut cfg = AppConfig::defaults();
+        cfg.providers.openrouter.api_key = "sk-or-secret-123".to_string();
+        cfg.providers.openai.extra_headers =
+            vec![("Authorization".to_string(), "Bearer hdr-secret".to_string())];
+        cfg.channels.telegram.bot_token = "123:tg-secret".to_string();
+        cfg.tools.brave_api_key = Some("brave-secret".to_string());
+
+        let out = render(&cfg).unwrap();
+        for secret in ["sk-or-secret", "hdr-secret", "tg-secret", "brave-secret"] {
+            assert!(!out.contains(secret), "leaked {secret}:\n{out}");
+        }
+        assert!(out.contains(REDACTED));
+        assert!(out.contains("channels: telegram\n"), "{out}");
+        assert!(out.contains(&format!("provider: {}", cfg.provider.as_str())));
+        // Non-secret settings stay readable.
+        assert!(out.contains(&cfg.model.model));
+    }
+
+    #[test]
+    fn secret_keys_match_whole_names_only() {
+        for key in [
+            "api_key",
+            "bot_token",
+            "secret_token",
+            "Password",
+            "extra_headers",
+        ] {
+            assert!(is_secret_key(key), "{key}");
+        }
+        for key in [
+            "max_tokens",
+            "max_response_tokens",
+            "keep_alive",
+            "secrets_dir",
+        ] {
+            assert!(!is_secret_key(key), "{key}");
+        }
+    }
+}
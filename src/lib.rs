@@ -1,20 +1,32 @@
 mod agent;
+mod broadcast;
 mod bus;
 mod channels;
 mod config;
 mod configure;
 mod cron;
+mod doctor;
+mod info;
+mod logging;
 mod memory;
+mod metrics;
+mod plugins;
+mod postprocess;
 mod providers;
+mod retry;
 mod service;
 mod session_compaction;
 mod skills;
+#[cfg(test)]
+mod test_http;
+mod tokenizer;
 mod tools;
 mod transcription;
 mod uninstall;
 
 use anyhow::{anyhow, Result};
 use clap::{CommandFactory, Parser, Subcommand};
+use std::sync::Arc;
 use tokio::io::{self, AsyncBufReadExt};
 use tracing::{info, warn};
 use tracing_appender::non_blocking::WorkerGuard;
@@ -30,14 +42,53 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    Run,
+    Run {
+        /// Answer QUESTION once and exit instead of starting the service
+        #[arg(long, default_value_t = false, requires = "question")]
+        once: bool,
+        /// Use this model (provider/model) instead of the configured routes
+        #[arg(long, requires = "once")]
+        model: Option<String>,
+        question: Option<String>,
+    },
     Tui,
-    Configure,
+    Configure {
+        /// Apply changes without prompts (JSON patch on stdin and/or --set)
+        #[arg(long, default_value_t = false)]
+        non_interactive: bool,
+        /// Set a config value by dotted path, e.g. --set agents.defaults.model=gpt-4o
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        set: Vec<String>,
+        /// Do not install or restart the background service after saving
+        #[arg(long, default_value_t = false)]
+        skip_service: bool,
+    },
     Uninstall,
+    /// Diagnose common setup problems
+    Doctor,
+    /// Print build details and the effective config (secrets redacted) for bug reports
+    Info,
+    /// Show turn latency and tool-call statistics from the running agent
+    Stats,
+    /// Send a message to every chat that has talked to the running agent
+    Broadcast {
+        #[arg(long)]
+        text: String,
+    },
     Skills {
         #[command(subcommand)]
         command: skills::cli::SkillsCommands,
     },
+    Tools {
+        /// Inspect the tools the agent is given
+        #[command(subcommand)]
+        command: tools::cli::ToolsCommands,
+    },
+    Memory {
+        /// Back up or migrate Smart-mode memories
+        #[command(subcommand)]
+        command: memory::cli::MemoryCommands,
+    },
     Cron {
         /// Admin cron operations (tool-driven scheduling is preferred)
         #[command(subcommand)]
@@ -103,6 +154,7 @@ enum ServiceCommands {
 }
 
 pub async fn run_cli() -> Result<()> {
+    service::load_env_file();
     let cli = Cli::parse();
     let Some(command) = cli.command else {
         let mut cmd = Cli::command();
@@ -110,19 +162,40 @@ pub async fn run_cli() -> Result<()> {
         println!();
         return Ok(());
     };
-    let write_runtime_logs = matches!(&command, Commands::Run | Commands::Tui);
+    let write_runtime_logs = matches!(&command, Commands::Run { once: false, .. } | Commands::Tui);
     init_logging(write_runtime_logs);
 
     match command {
-        Commands::Run => run().await,
+        Commands::Run {
+            once: true,
+            model,
+            question,
+        } => run_once(question.unwrap_or_default(), model).await,
+        Commands::Run { .. } => run().await,
         Commands::Tui => run_tui().await,
-        Commands::Configure => configure::run(),
+        Commands::Configure {
+            non_interactive,
+            set,
+            skip_service,
+        } => {
+            if non_interactive || !set.is_empty() {
+                configure::run_non_interactive(&set, skip_service)
+            } else {
+                configure::run()
+            }
+        }
         Commands::Uninstall => uninstall::run(),
+        Commands::Doctor => doctor::run().await,
+        Commands::Info => info::run(),
+        Commands::Stats => metrics::print_stats(),
         Commands::Skills { command } => {
             tokio::task::spawn_blocking(move || skills::cli::handle_skills(command))
                 .await
                 .map_err(|err| anyhow!("skills command task failed: {err}"))?
         }
+        Commands::Tools { command } => tools::cli::handle_tools(command).await,
+        Commands::Memory { command } => memory::cli::handle_memory(command).await,
+        Commands::Broadcast { text } => handle_broadcast(text).await,
         Commands::Cron { command } => handle_cron(command).await,
         Commands::Service { command } => handle_service(command).await,
     }
@@ -131,7 +204,15 @@ pub async fn run_cli() -> Result<()> {
 async fn run() -> Result<()> {
     let cfg = config::AppConfig::load()?;
 
-    let bus = bus::MessageBus::new();
+    let bus = if cfg.bus.durable {
+        bus::MessageBus::durable(&cfg.data_dir.join("inbound_journal.db")).await?
+    } else {
+        bus::MessageBus::new()
+    }
+    .with_post_process(postprocess::Pipeline::from_config(&cfg.outbound))
+    .with_plugin(plugins::WasmPlugin::from_config(&cfg.plugins))
+    .with_outbound_log(cfg.data_dir.join("outbound.jsonl"))
+    .with_activity_file(cfg.data_dir.join("activity.json"));
 
     // Start Cron Service
     let cron_service = cron::CronService::new(&cfg, bus.clone());
@@ -180,6 +261,33 @@ async fn run() -> Result<()> {
     Ok(())
 }
 
+/// `run --once`: answer one question on the local channel and print it.
+/// `--model` only needs credentials for that model's provider.
+async fn run_once(question: String, model: Option<String>) -> Result<()> {
+    let (cfg, route) = match model {
+        Some(raw) => {
+            let cfg = config::AppConfig::load_relaxed();
+            let route = cfg.route_override(&raw)?;
+            (cfg, Some(route))
+        }
+        None => (config::AppConfig::load()?, None),
+    };
+    let bus = bus::MessageBus::new()
+        .with_post_process(postprocess::Pipeline::from_config(&cfg.outbound))
+        .with_plugin(plugins::WasmPlugin::from_config(&cfg.plugins));
+    let cron_service = cron::CronService::new(&cfg, bus.clone());
+    let mut agent = agent::AgentLoop::new(cfg, bus, cron_service);
+    if let Some(route) = &route {
+        agent = agent.with_route_override(route)?;
+    }
+    let reply = agent
+        .run_once(question)
+        .await
+        .ok_or_else(|| anyhow!("no reply"))?;
+    println!("{}", reply.trim());
+    Ok(())
+}
+
 async fn wait_for_shutdown() -> Result<()> {
     tokio::signal::ctrl_c().await?;
     Ok(())
@@ -256,6 +364,28 @@ async fn handle_cron(cmd: CronCommands) -> Result<()> {
     Ok(())
 }
 
+async fn handle_broadcast(text: String) -> Result<()> {
+    if text.trim().is_empty() {
+        return Err(anyhow!("broadcast text is empty"));
+    }
+    let cfg = config::AppConfig::load()?;
+    // The running service owns the channel connections, so hand the
+    // broadcast to its scheduler through the cron store.
+    let service = cron::CronService::new(&cfg, bus::MessageBus::new());
+    let chats = service.known_chats().await;
+    if chats.is_empty() {
+        println!("No known chats yet; nothing to broadcast.");
+        return Ok(());
+    }
+    let job = service.add_broadcast(text).await?;
+    println!(
+        "Broadcast {} queued for {} chat(s); the running lightclaw service delivers it.",
+        job.id,
+        chats.len()
+    );
+    Ok(())
+}
+
 async fn handle_service(cmd: ServiceCommands) -> Result<()> {
     let scope = |system: bool| {
         if system {
@@ -278,7 +408,9 @@ async fn handle_service(cmd: ServiceCommands) -> Result<()> {
 
 async fn run_tui() -> Result<()> {
     let cfg = config::AppConfig::load()?;
-    let bus = bus::MessageBus::new();
+    let bus = bus::MessageBus::new()
+        .with_post_process(postprocess::Pipeline::from_config(&cfg.outbound))
+        .with_plugin(plugins::WasmPlugin::from_config(&cfg.plugins));
 
     let cron_service = cron::CronService::new(&cfg, bus.clone());
     cron_service.start().await;
@@ -301,6 +433,9 @@ async fn run_tui() -> Result<()> {
                 continue;
             }
             println!("\nassistant> {}\n", msg.content.trim());
+            for path in &msg.attachments {
+                println!("[attachment: {}]\n", path.display());
+            }
         }
     });
 
@@ -321,6 +456,9 @@ async fn run_tui() -> Result<()> {
             chat_id: "local".to_string(),
             sender_id: "local".to_string(),
             content,
+            message_id: None,
+            mention: None,
+            attachments: Vec::new(),
         })
         .await;
     }
@@ -330,9 +468,13 @@ async fn run_tui() -> Result<()> {
 
 fn init_logging(write_runtime_logs: bool) {
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let redactor = Arc::new(logging::Redactor::from_config(
+        &config::AppConfig::load_relaxed(),
+    ));
     let stdout_layer = tracing_subscriber::fmt::layer()
         .with_target(false)
-        .compact();
+        .compact()
+        .fmt_fields(logging::redacting_fields(redactor.clone()));
 
     if write_runtime_logs {
         let log_path = config::log_file_path();
@@ -356,6 +498,7 @@ fn init_logging(write_runtime_logs: bool) {
                             .with_ansi(false)
                             .with_target(true)
                             .compact()
+                            .fmt_fields(logging::redacting_fields(redactor))
                             .with_writer(non_blocking),
                     )
                     .init();
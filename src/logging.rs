@@ -0,0 +1,207 @@
+use crate::config::AppConfig;
+use crate::info::{is_secret_key, REDACTED};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::sync::{Arc, LazyLock};
+use tracing::field::Field;
+use tracing_subscriber::field::MakeExt;
+use tracing_subscriber::fmt::format::{self, FormatFields, Writer};
+
+/// Token shapes scrubbed even when they don't come from the config:
+/// Telegram bot tokens, Discord bot tokens, `sk-`/`fc-` style API keys and
+/// bearer credentials.
+static SECRET_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        r"\d{6,12}:[A-Za-z0-9_-]{30,}",
+        r"\b[A-Za-z0-9_-]{24,}\.[A-Za-z0-9_-]{6}\.[A-Za-z0-9_-]{27,}",
+        r"\b(?:sk|fc)-[A-Za-z0-9_-]{16,}",
+        r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]{16,}",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+/// Field names (last dotted segment) whose values are message bodies.
+const CONTENT_FIELDS: &[&str] = &[
+    "content",
+    "text",
+    "body",
+    "prompt",
+    "completion",
+    "preamble",
+    "messages",
+];
+
+/// Configured secrets shorter than this are too generic to scrub literally.
+const MIN_LITERAL_SECRET_CHARS: usize = 8;
+
+/// Scrubs secrets (and optionally message bodies) from log fields. With
+/// both switches off, fields are written verbatim.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Redactor {
+    redact_secrets: bool,
+    secrets: Vec<String>,
+    redact_content: bool,
+}
+
+impl Redactor {
+    pub(crate) fn from_config(cfg: &AppConfig) -> Self {
+        let mut secrets = Vec::new();
+        if cfg.logging.redact {
+            if let Ok(value) = serde_json::to_value(cfg) {
+                collect_secrets(&value, false, &mut secrets);
+            }
+        }
+        secrets.retain(|s| s.chars().count() >= MIN_LITERAL_SECRET_CHARS);
+        // Longest first so a secret containing another is replaced whole.
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.dedup();
+        Self {
+            redact_secrets: cfg.logging.redact,
+            secrets,
+            redact_content: cfg.logging.redact_content,
+        }
+    }
+
+    pub(crate) fn scrub(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for secret in &self.secrets {
+            if out.contains(secret.as_str()) {
+                out = out.replace(secret.as_str(), REDACTED);
+            }
+        }
+        for pattern in SECRET_PATTERNS.iter() {
+            if pattern.is_match(&out) {
+                out = pattern.replace_all(&out, REDACTED).into_owned();
+            }
+        }
+        out
+    }
+
+    fn field(&self, name: &str, raw: &str) -> String {
+        let last = name.rsplit('.').next().unwrap_or(name);
+        if self.redact_content && CONTENT_FIELDS.contains(&last) {
+            return format!("<redacted {} chars>", raw.chars().count());
+        }
+        if !self.redact_secrets {
+            return raw.to_string();
+        }
+        self.scrub(raw)
+    }
+}
+
+/// Field formatter for `fmt::layer().fmt_fields(..)` that writes fields the
+/// way the default formatter does, after passing each value through `redactor`.
+pub(crate) fn redacting_fields(
+    redactor: Arc<Redactor>,
+) -> impl for<'writer> FormatFields<'writer> + Send + Sync + 'static {
+    format::debug_fn(
+        move |writer: &mut Writer<'_>, field: &Field, value: &dyn std::fmt::Debug| {
+            let mut raw = String::new();
+            write!(raw, "{value:?}")?;
+            let shown = redactor.field(field.name(), &raw);
+            if field.name() == "message" {
+                write!(writer, "{shown}")
+            } else {
+                write!(writer, "{}={shown}", field.name())
+            }
+        },
+    )
+    .delimited(" ")
+}
+
+fn collect_secrets(value: &Value, secret: bool, out: &mut Vec<String>) {
+    match value {
+        Value::String(s) if secret && !s.trim().is_empty() => out.push(s.trim().to_string()),
+        // Header lists are `[name, value]` pairs; only the value is secret.
+        Value::Array(pair) if secret && pair.len() == 2 && pair.iter().all(Value::is_string) => {
+            collect_secrets(&pair[1], true, out)
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_secrets(v, secret, out)),
+        Value::Object(map) => {
+            for (key, field) in map {
+                collect_secrets(field, secret || is_secret_key(key), out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture_logs(redactor: Redactor, emit: impl FnOnce()) -> String {
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .fmt_fields(redacting_fields(Arc::new(redactor)))
+            .finish();
+        tracing::subscriber::with_default(subscriber, emit);
+        let bytes = capture.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn bot_token_shaped_strings_are_redacted_from_log_events() {
+        let token = "123456789:AAHdqTcvCH1vGWJxfSeofSAs0K5PALDsaw";
+        let mut cfg = AppConfig::defaults();
+        cfg.providers.openrouter.api_key = "or-configured-secret".to_string();
+        let redactor = Redactor::from_config(&cfg);
+
+        let out = capture_logs(redactor.clone(), || {
+            tracing::warn!("telegram request to /bot{token}/getMe failed");
+            tracing::info!(key = "or-configured-secret", "provider configured");
+            tracing::info!(content = "call me at 555", "inbound message");
+        });
+        assert!(!out.contains(token), "{out}");
+        assert!(!out.contains("or-configured-secret"), "{out}");
+        assert!(out.contains("telegram request to /bot<redacted>/getMe failed"));
+        // Bodies stay visible unless redact_content is on.
+        assert!(out.contains("call me at 555"));
+
+        let strict = Redactor {
+            redact_content: true,
+            ..redactor
+        };
+        let out = capture_logs(strict, || {
+            tracing::info!(content = "call me at 555", "inbound message");
+            tracing::warn!("telegram send failed for chat 42: timed out");
+        });
+        assert!(!out.contains("call me at 555"), "{out}");
+        assert!(out.contains("content=<redacted 16 chars>"), "{out}");
+        // Only the content fields go; the rest of the line stays readable.
+        assert!(out.contains("inbound message"), "{out}");
+        assert!(
+            out.contains("telegram send failed for chat 42: timed out"),
+            "{out}"
+        );
+
+        // With redaction off, logs are left untouched.
+        cfg.logging.redact = false;
+        let out = capture_logs(Redactor::from_config(&cfg), || {
+            tracing::warn!("telegram request to /bot{token}/getMe failed");
+        });
+        assert!(out.contains(token), "{out}");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::config::AppConfig;
+use crate::memory::smart::client::LlmClient;
+use crate::memory::smart::vector_store::{EmbeddingService, MemoryExportRecord, VectorMemoryStore};
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+#[derive(Subcommand, Debug)]
+pub enum MemoryCommands {
+    /// Export Smart-mode vector memories (all namespaces) to a JSONL file
+    Export {
+        /// Destination file
+        file: PathBuf,
+        /// Include raw embeddings so import can skip re-embedding
+        #[arg(long, default_value_t = false)]
+        with_embeddings: bool,
+    },
+    /// Import memories from a JSONL file produced by `memory export`
+    Import {
+        /// Source file
+        file: PathBuf,
+    },
+    /// Recompute priorities, rebuild indices and check embedding dimensions
+    /// after bulk edits
+    Rebuild,
+}
+
+pub async fn handle_memory(command: MemoryCommands) -> Result<()> {
+    let cfg = AppConfig::load()?;
+
+    match command {
+        MemoryCommands::Export {
+            file,
+            with_embeddings,
+        } => {
+            // Read-only, so exporting doesn't contend with a running service.
+            let store = open_vector_store_read_only(&cfg)?;
+            let records = store.export_all(with_embeddings).await?;
+            write_records(&file, &records)?;
+            println!("Exported {} memories to {}", records.len(), file.display());
+        }
+        MemoryCommands::Import { file } => {
+            let store = open_vector_store(&cfg)?;
+            let records = read_records(&file)?;
+            let total = records.len();
+            let imported = store.import_records(records).await?;
+            println!(
+                "Imported {imported} of {total} memories from {}",
+                file.display()
+            );
+        }
+        MemoryCommands::Rebuild => {
+            let store = open_vector_store(&cfg)?;
+            let report = store.rebuild().await?;
+            println!(
+                "Rebuilt {} memories: {} reprioritized",
+                report.rows, report.reprioritized
+            );
+            match report.dimension {
+                Some(dim) => println!("Embedding dimension: {dim}"),
+                None => println!("Embedding dimension: none stored"),
+            }
+            if !report.mismatched.is_empty() {
+                println!(
+                    "{} memories have a different dimension and never match a search:",
+                    report.mismatched.len()
+                );
+                for (id, namespace, width) in &report.mismatched {
+                    println!("  {namespace}/{id}: {width}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn open_vector_store(cfg: &AppConfig) -> Result<VectorMemoryStore> {
+    let client = LlmClient::from_config(cfg)?;
+    let embedder = embedding_service(cfg, client);
+    VectorMemoryStore::new(
+        vector_db_path(cfg),
+        embedder,
+        cfg.memory.max_memories,
+        "default".to_string(),
+    )
+    .map(|store| store.with_namespace_caps(cfg.memory.max_memories_by_namespace.clone()))
+}
+
+fn open_vector_store_read_only(cfg: &AppConfig) -> Result<VectorMemoryStore> {
+    let client = LlmClient::from_config(cfg)?;
+    VectorMemoryStore::open_read_only(
+        vector_db_path(cfg),
+        embedding_service(cfg, client),
+        cfg.memory.max_memories,
+        "default".to_string(),
+    )
+}
+
+pub(crate) fn vector_db_path(cfg: &AppConfig) -> PathBuf {
+    cfg.workspace_dir.join("memory").join("vectors.db")
+}
+
+/// Embedding service for the configured model, disk-backed when
+/// `memory.persist_embedding_cache` is on. Falls back to an in-memory cache
+/// if the cache file can't be opened.
+pub(crate) fn embedding_service(cfg: &AppConfig, client: LlmClient) -> EmbeddingService {
+    let service = EmbeddingService::new(client, cfg.embedding_model());
+    if !cfg.memory.persist_embedding_cache {
+        return service;
+    }
+    let path = cfg.workspace_dir.join("memory").join("embedding_cache.db");
+    match service.clone().with_disk_cache(path) {
+        Ok(persistent) => persistent,
+        Err(err) => {
+            warn!("embedding cache persistence disabled: {err}");
+            service
+        }
+    }
+}
+
+fn write_records(path: &Path, records: &[MemoryExportRecord]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn read_records(path: &Path) -> Result<Vec<MemoryExportRecord>> {
+    let file = std::fs::File::open(path)
+        .map_err(|err| anyhow!("failed to open {}: {err}", path.display()))?;
+    let mut records = Vec::new();
+    for (idx, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|err| anyhow!("invalid record on line {}: {err}", idx + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
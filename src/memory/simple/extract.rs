@@ -0,0 +1,94 @@
+use crate::memory::smart::client::{ChatMessage, LlmClient};
+use anyhow::{anyhow, Result};
+use std::future::Future;
+
+const EXTRACT_PROMPT: &str = r#"You extract long-term memory about the user from one chat message.
+
+List durable facts about the user that would help in future conversations:
+identity, preferences, constraints, ongoing projects, relationships, routines.
+
+Skip questions, requests for the current task, small talk and anything that
+will not matter next week. Write each fact as a short third-person statement
+("Prefers tea over coffee").
+
+Return ONLY a JSON array of strings, at most 5 items. Return [] when there is
+nothing worth keeping."#;
+
+/// Longest fact kept from an extraction reply, in characters.
+const MAX_FACT_CHARS: usize = 220;
+/// Most facts kept from one message.
+const MAX_FACTS: usize = 5;
+
+/// Pulls durable user facts out of a message.
+pub trait FactExtractor {
+    fn extract(&self, text: &str) -> impl Future<Output = Result<Vec<String>>> + Send;
+}
+
+/// Asks a (preferably cheap) chat model for the facts.
+#[derive(Clone)]
+pub struct LlmFactExtractor {
+    model: String,
+    client: LlmClient,
+}
+
+impl LlmFactExtractor {
+    pub fn new(model: String, client: LlmClient) -> Self {
+        Self { model, client }
+    }
+}
+
+impl FactExtractor for LlmFactExtractor {
+    fn extract(&self, text: &str) -> impl Future<Output = Result<Vec<String>>> + Send {
+        let prompt = format!("{EXTRACT_PROMPT}\n\n<message>\n{text}\n</message>");
+        async move {
+            let raw = self
+                .client
+                .chat_completion(
+                    &self.model,
+                    vec![ChatMessage {
+                        role: "user".to_string(),
+                        content: prompt,
+                    }],
+                    200,
+                    0.0,
+                    None,
+                )
+                .await?;
+            parse_facts(&raw)
+        }
+    }
+}
+
+/// Read a JSON array of strings, tolerating a surrounding code fence or
+/// prose. Anything else is an error so the caller can fall back.
+fn parse_facts(raw: &str) -> Result<Vec<String>> {
+    let start = raw
+        .find('[')
+        .ok_or_else(|| anyhow!("no JSON array in reply"))?;
+    let end = raw
+        .rfind(']')
+        .ok_or_else(|| anyhow!("no JSON array in reply"))?;
+    let items: Vec<String> = serde_json::from_str(raw.get(start..=end).unwrap_or_default())?;
+    Ok(items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty() && item.chars().count() <= MAX_FACT_CHARS)
+        .take(MAX_FACTS)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_are_parsed_as_fact_lists() {
+        let raw = "```json\n[\"Lives in Porto\", \" \", \"Prefers short answers\"]\n```";
+        assert_eq!(
+            parse_facts(raw).unwrap(),
+            vec!["Lives in Porto", "Prefers short answers"]
+        );
+        assert!(parse_facts("[]").unwrap().is_empty());
+        assert!(parse_facts("The user lives in Porto.").is_err());
+    }
+}
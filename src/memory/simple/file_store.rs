@@ -1,13 +1,9 @@
-use chrono::{Datelike, Local};
+use chrono::{Datelike, Local, NaiveDate};
 use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, Mutex};
 
-pub const MAX_CONTEXT_TOKENS: usize = 2000;
-pub const CHARS_PER_TOKEN: usize = 4;
-pub const MAX_CONTEXT_CHARS: usize = MAX_CONTEXT_TOKENS * CHARS_PER_TOKEN;
-
 /// Maximum size of the Extracted Notes section before trimming oldest entries.
 const MAX_EXTRACTED_NOTES_CHARS: usize = 8000;
 const EXTRACTED_SECTION_HEADER: &str = "## Extracted Notes";
@@ -22,6 +18,8 @@ pub struct MemoryStore {
     workspace: PathBuf,
     memory_dir: PathBuf,
     memory_file: PathBuf,
+    /// MEMORY.md size above which the oldest entries are archived.
+    max_file_chars: Option<usize>,
 }
 
 impl MemoryStore {
@@ -32,9 +30,17 @@ impl MemoryStore {
             workspace,
             memory_dir,
             memory_file,
+            max_file_chars: None,
         }
     }
 
+    /// Cap MEMORY.md at `max_chars` by moving the oldest dated entries to
+    /// `memory/archive-YYYY-MM.md`. Zero leaves it unbounded.
+    pub fn with_max_file_chars(mut self, max_chars: usize) -> Self {
+        self.max_file_chars = (max_chars > 0).then_some(max_chars);
+        self
+    }
+
     pub fn get_today_file(&self) -> PathBuf {
         self.memory_dir.join(format!("{}.md", today_date()))
     }
@@ -135,15 +141,73 @@ impl MemoryStore {
         } else {
             source.trim()
         };
-        let confidence = confidence.clamp(0.0, 1.0);
-        let today = today_date();
-        self.append_section_entries(
-            GROUNDED_FACTS_SECTION_HEADER,
-            &[format!(
-                "- [{today}] {fact} (source: {source}, confidence: {confidence:.2})"
-            )],
-            None,
-        );
+        let entry = GroundedFact {
+            date: today_date(),
+            fact: fact.to_string(),
+            source: source.to_string(),
+            confidence: confidence.clamp(0.0, 1.0),
+            stale_since: None,
+        };
+        self.append_section_entries(GROUNDED_FACTS_SECTION_HEADER, &[entry.render()], None);
+    }
+
+    /// Grounded facts currently in MEMORY.md, oldest first.
+    pub fn grounded_facts(&self) -> Vec<GroundedFact> {
+        let title = GROUNDED_FACTS_SECTION_HEADER.trim_start_matches("## ");
+        self.entries()
+            .into_iter()
+            .filter(|(section, _)| section == title)
+            .filter_map(|(_, entry)| GroundedFact::parse(&entry))
+            .collect()
+    }
+
+    /// Swap a grounded fact's entry for `updated`. Returns false if the
+    /// original entry is no longer in MEMORY.md.
+    pub fn replace_grounded_fact(&self, original: &GroundedFact, updated: &GroundedFact) -> bool {
+        let _guard = match MEMORY_FILE_LOCK.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let existing = fs::read_to_string(&self.memory_file).unwrap_or_default();
+        let old_line = original.render();
+        let mut replaced = false;
+        let lines: Vec<String> = existing
+            .lines()
+            .map(|line| {
+                if !replaced && line == old_line {
+                    replaced = true;
+                    updated.render()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        if !replaced {
+            return false;
+        }
+        let mut content = lines.join("\n");
+        if existing.ends_with('\n') {
+            content.push('\n');
+        }
+        self.replace_memory_file(&content)
+    }
+
+    /// Swap in new MEMORY.md contents through a temp file and a rename, so
+    /// readers never see a half-written file. Call with `MEMORY_FILE_LOCK`
+    /// held.
+    fn replace_memory_file(&self, content: &str) -> bool {
+        let tmp = self.memory_file.with_extension("md.tmp");
+        let written = fs::write(&tmp, content).and_then(|()| fs::rename(&tmp, &self.memory_file));
+        if let Err(err) = written {
+            eprintln!(
+                "Failed to rewrite memory file {}: {}",
+                self.memory_file.display(),
+                err
+            );
+            let _ = fs::remove_file(&tmp);
+            return false;
+        }
+        true
     }
 
     fn append_section_entries(
@@ -161,49 +225,83 @@ impl MemoryStore {
             Err(poisoned) => poisoned.into_inner(),
         };
 
-        let new_lines = entries.join("\n");
         let existing = fs::read_to_string(&self.memory_file).unwrap_or_default();
-        let updated = if let Some(section_start) = existing.find(section_header) {
-            let after_header = section_start + section_header.len();
-            let mut before = existing[..after_header].to_string();
-            let rest = &existing[after_header..];
-            let section_end = rest.find("\n## ").unwrap_or(rest.len());
-            let section_body = rest[..section_end].trim_start_matches('\n');
-            let after_section = &rest[section_end..];
-            let mut combined = if section_body.is_empty() {
-                new_lines
-            } else {
-                format!("{section_body}\n{new_lines}")
-            };
-
-            if let Some(limit) = max_section_chars {
-                while combined.len() > limit {
-                    if let Some(newline_pos) = combined.find('\n') {
-                        combined = combined[newline_pos + 1..].to_string();
-                    } else {
-                        break;
-                    }
+        let mut updated =
+            insert_section_entries(&existing, section_header, entries, max_section_chars);
+        if let Some(max_chars) = self.max_file_chars {
+            if let Some((kept, archived)) = split_overflow(&updated, max_chars) {
+                if self.append_to_archive(&archived) {
+                    updated = kept;
                 }
             }
-
-            before.push('\n');
-            before.push_str(&combined);
-            before.push_str(after_section);
-            before
-        } else {
-            let mut content = existing;
-            if !content.is_empty() && !content.ends_with('\n') {
-                content.push('\n');
-            }
-            content.push_str(&format!("\n{section_header}\n{new_lines}\n"));
-            content
-        };
+        }
 
         if let Ok(mut file) = fs::File::create(&self.memory_file) {
             let _ = file.write_all(updated.as_bytes());
         }
     }
 
+    pub fn archive_file(&self) -> PathBuf {
+        self.memory_dir
+            .join(format!("archive-{}.md", Local::now().format("%Y-%m")))
+    }
+
+    /// Append archived entries under their original section headers. Returns
+    /// false if the archive could not be written, so nothing is dropped.
+    fn append_to_archive(&self, archived: &[(String, Vec<String>)]) -> bool {
+        let path = self.archive_file();
+        let mut content = fs::read_to_string(&path).unwrap_or_default();
+        for (header, entries) in archived {
+            content = insert_section_entries(&content, header, entries, None);
+        }
+        match fs::write(&path, content) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("Failed to write memory archive {}: {}", path.display(), err);
+                false
+            }
+        }
+    }
+
+    /// Every bullet entry in MEMORY.md with its section title, in file order.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        let content = self.read_long_term();
+        let lines: Vec<&str> = content.lines().collect();
+        let (headers, spans) = entry_spans(&lines);
+        spans
+            .into_iter()
+            .map(|span| {
+                let title = headers[span.section].trim_start_matches("## ").to_string();
+                (title, lines[span.lines].join("\n"))
+            })
+            .collect()
+    }
+
+    /// Delete one entry (as returned by `entries`), leaving the rest of the
+    /// file as it was. Returns false if it is no longer in MEMORY.md.
+    pub fn remove_entry(&self, entry: &str) -> bool {
+        let _guard = match MEMORY_FILE_LOCK.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let existing = fs::read_to_string(&self.memory_file).unwrap_or_default();
+        let lines: Vec<&str> = existing.lines().collect();
+        let (_, spans) = entry_spans(&lines);
+        let Some(span) = spans
+            .into_iter()
+            .find(|span| lines[span.lines.clone()].join("\n") == entry)
+        else {
+            return false;
+        };
+        let mut content = [&lines[..span.lines.start], &lines[span.lines.end..]]
+            .concat()
+            .join("\n");
+        if existing.ends_with('\n') {
+            content.push('\n');
+        }
+        self.replace_memory_file(&content)
+    }
+
     #[allow(dead_code)]
     pub fn workspace(&self) -> &Path {
         &self.workspace
@@ -214,6 +312,214 @@ impl MemoryStore {
     }
 }
 
+/// Add `entries` to the end of `section_header`, creating the section if
+/// needed and trimming its oldest lines past `max_section_chars`.
+fn insert_section_entries(
+    existing: &str,
+    section_header: &str,
+    entries: &[String],
+    max_section_chars: Option<usize>,
+) -> String {
+    let new_lines = entries.join("\n");
+    if let Some(section_start) = existing.find(section_header) {
+        let after_header = section_start + section_header.len();
+        let mut before = existing[..after_header].to_string();
+        let rest = &existing[after_header..];
+        let section_end = rest.find("\n## ").unwrap_or(rest.len());
+        let section_body = rest[..section_end].trim_start_matches('\n');
+        let after_section = &rest[section_end..];
+        // Keep the blank lines that separate this section from the next.
+        let entries_end = section_body.trim_end().len();
+        let (section_body, gap) = section_body.split_at(entries_end);
+        let mut combined = if section_body.is_empty() {
+            new_lines
+        } else {
+            format!("{section_body}\n{new_lines}")
+        };
+
+        if let Some(limit) = max_section_chars {
+            while combined.len() > limit {
+                if let Some(newline_pos) = combined.find('\n') {
+                    combined = combined[newline_pos + 1..].to_string();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        before.push('\n');
+        before.push_str(&combined);
+        before.push_str(gap);
+        before.push_str(after_section);
+        before
+    } else {
+        let mut content = existing.to_string();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&format!("\n{section_header}\n{new_lines}\n"));
+        content
+    }
+}
+
+/// One `## Grounded Facts` bullet:
+/// `- [date] fact (source: src, confidence: 0.90[, stale: date])`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroundedFact {
+    pub date: String,
+    pub fact: String,
+    pub source: String,
+    pub confidence: f32,
+    /// Set when a re-check found the source no longer supports the fact.
+    pub stale_since: Option<String>,
+}
+
+impl GroundedFact {
+    pub fn parse(entry: &str) -> Option<Self> {
+        let rest = entry.trim().strip_prefix("- [")?;
+        let (date, rest) = rest.split_once("] ")?;
+        let (fact, meta) = rest.rsplit_once(" (source: ")?;
+        let mut meta = meta.strip_suffix(')')?;
+        let mut stale_since = None;
+        if let Some((head, stale)) = meta.rsplit_once(", stale: ") {
+            stale_since = Some(stale.to_string());
+            meta = head;
+        }
+        let (source, confidence) = meta.rsplit_once(", confidence: ")?;
+        Some(Self {
+            date: date.to_string(),
+            fact: fact.to_string(),
+            source: source.to_string(),
+            confidence: confidence.parse().ok()?,
+            stale_since,
+        })
+    }
+
+    pub fn render(&self) -> String {
+        let mut line = format!(
+            "- [{}] {} (source: {}, confidence: {:.2}",
+            self.date, self.fact, self.source, self.confidence
+        );
+        if let Some(stale) = &self.stale_since {
+            line.push_str(&format!(", stale: {stale}"));
+        }
+        line.push(')');
+        line
+    }
+}
+
+/// Entries moved out of `MEMORY.md`, grouped under their section header.
+type ArchivedSections = Vec<(String, Vec<String>)>;
+
+/// A bullet entry of MEMORY.md as the lines it spans in the file.
+struct EntrySpan {
+    section: usize,
+    /// Position among its section's entries, oldest first.
+    index: usize,
+    lines: std::ops::Range<usize>,
+    /// The `[YYYY-MM-DD]` the entry was written on, if it has one.
+    date: Option<NaiveDate>,
+}
+
+/// Section headers and the entries under them. An entry is a `- ` line plus
+/// the non-blank lines that continue it.
+fn entry_spans(lines: &[&str]) -> (Vec<String>, Vec<EntrySpan>) {
+    let mut headers: Vec<String> = Vec::new();
+    let mut spans: Vec<EntrySpan> = Vec::new();
+    let mut open = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("## ") {
+            headers.push(line.trim_end().to_string());
+            open = false;
+        } else if headers.is_empty() || line.trim().is_empty() {
+            open = false;
+        } else if line.starts_with("- ") {
+            let section = headers.len() - 1;
+            let index = spans.iter().filter(|s| s.section == section).count();
+            let date = line
+                .strip_prefix("- [")
+                .and_then(|rest| rest.split_once(']'))
+                .and_then(|(date, _)| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+            spans.push(EntrySpan {
+                section,
+                index,
+                lines: i..i + 1,
+                date,
+            });
+            open = true;
+        } else if open {
+            if let Some(span) = spans.last_mut() {
+                span.lines.end = i + 1;
+            }
+        }
+    }
+    (headers, spans)
+}
+
+/// When `content` is over `max_chars`, move out the oldest dated entries,
+/// whichever section they are in, until it fits. Each section keeps its
+/// newest entry, undated entries stay put, and every other line is left as
+/// it was. Returns the trimmed file and the removed entries grouped by
+/// header.
+fn split_overflow(content: &str, max_chars: usize) -> Option<(String, ArchivedSections)> {
+    if content.len() <= max_chars {
+        return None;
+    }
+    let lines: Vec<&str> = content.lines().collect();
+    let (headers, spans) = entry_spans(&lines);
+    let mut newest = vec![0; headers.len()];
+    for span in &spans {
+        newest[span.section] = span.index;
+    }
+    let mut oldest_first: Vec<&EntrySpan> = spans
+        .iter()
+        .filter(|span| span.date.is_some() && span.index < newest[span.section])
+        .collect();
+    oldest_first.sort_by_key(|span| (span.date, span.index, span.section));
+
+    let mut size = content.len();
+    let mut removed = vec![false; lines.len()];
+    let mut archived: Vec<Vec<&EntrySpan>> = (0..headers.len()).map(|_| Vec::new()).collect();
+    for span in oldest_first {
+        if size <= max_chars {
+            break;
+        }
+        for line in span.lines.clone() {
+            removed[line] = true;
+            size = size.saturating_sub(lines[line].len() + 1);
+        }
+        archived[span.section].push(span);
+    }
+
+    let moved: ArchivedSections = headers
+        .into_iter()
+        .zip(archived)
+        .filter(|(_, entries)| !entries.is_empty())
+        .map(|(header, mut entries)| {
+            entries.sort_by_key(|span| span.index);
+            let entries = entries
+                .into_iter()
+                .map(|span| lines[span.lines.clone()].join("\n"))
+                .collect();
+            (header, entries)
+        })
+        .collect();
+    if moved.is_empty() {
+        return None;
+    }
+    let mut kept = lines
+        .iter()
+        .zip(removed)
+        .filter(|(_, removed)| !removed)
+        .map(|(line, _)| *line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if content.ends_with('\n') {
+        kept.push('\n');
+    }
+    Some((kept, moved))
+}
+
 fn ensure_dir(path: &Path) -> PathBuf {
     if let Err(err) = fs::create_dir_all(path) {
         eprintln!("Failed to create dir {}: {}", path.display(), err);
@@ -221,7 +527,7 @@ fn ensure_dir(path: &Path) -> PathBuf {
     path.to_path_buf()
 }
 
-fn today_date() -> String {
+pub(crate) fn today_date() -> String {
     let now = Local::now().date_naive();
     format!("{:04}-{:02}-{:02}", now.year(), now.month(), now.day())
 }
@@ -312,4 +618,98 @@ mod tests {
 
         let _ = fs::remove_dir_all(workspace);
     }
+
+    #[test]
+    fn oversized_memory_file_moves_oldest_entries_to_archive() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-memtest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone()).with_max_file_chars(400);
+
+        store.append_user_observation("Lives in Lisbon");
+        for i in 0..12 {
+            store.append_remembered_fact(&format!("remembered fact number {i:02}"));
+        }
+
+        let content = store.read_long_term();
+        assert!(content.len() <= 400, "{} chars:\n{content}", content.len());
+        assert!(content.contains(REMEMBERED_FACTS_SECTION_HEADER));
+        assert!(content.contains(USER_OBSERVATIONS_SECTION_HEADER));
+        assert!(content.contains("Lives in Lisbon"));
+        assert!(content.contains("remembered fact number 11"));
+        assert!(!content.contains("remembered fact number 00"));
+
+        let archive = fs::read_to_string(store.archive_file()).unwrap();
+        assert!(archive.contains(REMEMBERED_FACTS_SECTION_HEADER));
+        assert!(archive.contains("remembered fact number 00"));
+        assert!(!archive.contains("remembered fact number 11"));
+        // Oldest first, so the archive reads in the original order.
+        let first = archive.find("number 00").unwrap();
+        let second = archive.find("number 01").unwrap();
+        assert!(first < second);
+
+        let _ = fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn archival_takes_the_oldest_entries_across_sections_and_keeps_layout() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-memtest-{}", Uuid::new_v4()));
+        let ancient = "- [2025-12-01] ancient observation";
+        let old = "- [2026-01-05] old fact one";
+        let original = format!(
+            "# Memory\n\nNotes I keep by hand.\n\n\
+             {REMEMBERED_FACTS_SECTION_HEADER}\n{old}\n- [2026-09-01] newer fact\n\n\
+             {USER_OBSERVATIONS_SECTION_HEADER}\nHow they like to be addressed:\n\
+             {ancient}\n  (seen twice)\n- [2026-08-01] recent observation\n"
+        );
+        let today = format!("- [{}] today fact", today_date());
+        let grown = original.replace(
+            "- [2026-09-01] newer fact\n",
+            &format!("- [2026-09-01] newer fact\n{today}\n"),
+        );
+        // Room for everything but the two oldest entries.
+        let cap = grown.len() - (ancient.len() + "\n  (seen twice)".len() + 1) - (old.len() + 1);
+
+        let store = MemoryStore::new(workspace.clone()).with_max_file_chars(cap);
+        fs::write(store.memory_dir().join("MEMORY.md"), &original).unwrap();
+        store.append_remembered_fact("today fact");
+
+        let expected = grown
+            .replace(&format!("{old}\n"), "")
+            .replace(&format!("{ancient}\n  (seen twice)\n"), "");
+        assert_eq!(store.read_long_term(), expected);
+
+        let archive = fs::read_to_string(store.archive_file()).unwrap();
+        assert!(archive.contains(&format!("{REMEMBERED_FACTS_SECTION_HEADER}\n{old}")));
+        assert!(archive.contains(&format!(
+            "{USER_OBSERVATIONS_SECTION_HEADER}\n{ancient}\n  (seen twice)"
+        )));
+        assert!(!archive.contains("newer fact"));
+
+        let _ = fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn appending_to_a_middle_section_keeps_the_blank_line_after_it() {
+        let content = format!(
+            "{REMEMBERED_FACTS_SECTION_HEADER}\n- a\n\n{USER_OBSERVATIONS_SECTION_HEADER}\n- b\n"
+        );
+        let updated = insert_section_entries(
+            &content,
+            REMEMBERED_FACTS_SECTION_HEADER,
+            &["- c".into()],
+            None,
+        );
+        assert_eq!(
+            updated,
+            format!(
+                "{REMEMBERED_FACTS_SECTION_HEADER}\n- a\n- c\n\n{USER_OBSERVATIONS_SECTION_HEADER}\n- b\n"
+            )
+        );
+        let updated = insert_section_entries(
+            &updated,
+            USER_OBSERVATIONS_SECTION_HEADER,
+            &["- d".into()],
+            None,
+        );
+        assert!(updated.ends_with("- b\n- d\n"));
+    }
 }
@@ -1 +1,3 @@
+pub mod extract;
 pub mod file_store;
+pub mod verify;
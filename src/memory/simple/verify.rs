@@ -0,0 +1,209 @@
+use crate::memory::simple::file_store::{today_date, GroundedFact, MemoryStore};
+use crate::tools::web::{WebFetchArgs, WebFetchTool};
+use anyhow::{anyhow, Result};
+use rig::tool::Tool;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How often the background pass re-checks URL-sourced grounded facts.
+const VERIFY_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Share of a fact's significant words that must still appear in its source.
+const MIN_TERM_COVERAGE: f32 = 0.8;
+/// Confidence multiplier for facts whose source no longer supports them.
+const STALE_CONFIDENCE_FACTOR: f32 = 0.5;
+
+/// Retrieves the current text behind a grounded fact's source URL.
+pub trait SourceFetcher {
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<String>> + Send;
+}
+
+impl SourceFetcher for WebFetchTool {
+    /// Goes through `web_fetch`, so URL validation and download limits apply.
+    fn fetch(&self, url: &str) -> impl Future<Output = Result<String>> + Send {
+        let args = serde_json::from_value::<WebFetchArgs>(json!({ "url": url }));
+        async move {
+            let raw = self.call(args?).await.map_err(|e| anyhow!("{e}"))?;
+            let Ok(value) = serde_json::from_str::<Value>(&raw) else {
+                return Err(anyhow!(raw));
+            };
+            if let Some(err) = value.get("error").and_then(Value::as_str) {
+                return Err(anyhow!(err.to_string()));
+            }
+            if let Some(status) = value.get("status").and_then(Value::as_u64) {
+                if status >= 400 {
+                    return Err(anyhow!("source returned HTTP {status}"));
+                }
+            }
+            Ok(value
+                .get("text")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or(raw))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub stale: usize,
+    pub failed: usize,
+}
+
+/// Re-fetch every URL-sourced grounded fact that isn't already stale. Facts
+/// whose source no longer contains them get their confidence halved and a
+/// `stale` date; unreachable sources are left alone.
+pub async fn verify_grounded_facts<F: SourceFetcher>(
+    store: &MemoryStore,
+    fetcher: &F,
+) -> VerifyReport {
+    let mut report = VerifyReport::default();
+    for fact in store.grounded_facts() {
+        if fact.stale_since.is_some() || !is_url(&fact.source) {
+            continue;
+        }
+        report.checked += 1;
+        let page = match fetcher.fetch(&fact.source).await {
+            Ok(page) => page,
+            Err(err) => {
+                warn!("grounded fact source {} not checked: {err}", fact.source);
+                report.failed += 1;
+                continue;
+            }
+        };
+        if source_supports(&page, &fact.fact) {
+            continue;
+        }
+        let updated = GroundedFact {
+            confidence: fact.confidence * STALE_CONFIDENCE_FACTOR,
+            stale_since: Some(today_date()),
+            ..fact.clone()
+        };
+        if store.replace_grounded_fact(&fact, &updated) {
+            info!(
+                source = %fact.source,
+                content = %fact.fact,
+                "grounded fact marked stale"
+            );
+            report.stale += 1;
+        }
+    }
+    report
+}
+
+/// Run `verify_grounded_facts` once per `VERIFY_INTERVAL` in the background.
+pub fn spawn_verifier<F>(store: MemoryStore, fetcher: F)
+where
+    F: SourceFetcher + Send + Sync + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker =
+            tokio::time::interval_at(Instant::now() + VERIFY_INTERVAL, VERIFY_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let report = verify_grounded_facts(&store, &fetcher).await;
+            info!(
+                "grounded fact verification: checked={} stale={} failed={}",
+                report.checked, report.stale, report.failed
+            );
+        }
+    });
+}
+
+fn is_url(source: &str) -> bool {
+    let source = source.trim().to_ascii_lowercase();
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3 || word.chars().all(|c| c.is_ascii_digit()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// True when most of the fact's significant words still occur in `page`.
+fn source_supports(page: &str, fact: &str) -> bool {
+    let wanted: HashSet<String> = terms(fact).collect();
+    if wanted.is_empty() {
+        return true;
+    }
+    let present: HashSet<String> = terms(page).collect();
+    let found = wanted.iter().filter(|term| present.contains(*term)).count();
+    found as f32 / wanted.len() as f32 >= MIN_TERM_COVERAGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    struct StubFetcher(HashMap<&'static str, &'static str>);
+
+    impl SourceFetcher for StubFetcher {
+        fn fetch(&self, url: &str) -> impl Future<Output = Result<String>> + Send {
+            let page = self.0.get(url).map(|page| page.to_string());
+            async move { page.ok_or_else(|| anyhow!("connection refused")) }
+        }
+    }
+
+    #[tokio::test]
+    async fn facts_missing_from_their_source_are_marked_stale() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-verify-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        store.append_grounded_fact(
+            "The API rate limit is 100 requests per minute",
+            "https://docs.example.com/limits",
+            0.9,
+        );
+        store.append_grounded_fact(
+            "Version 2.4 is the latest stable release",
+            "https://example.com/releases",
+            0.8,
+        );
+        store.append_grounded_fact("The office is in Berlin", "https://down.example.com", 0.7);
+        store.append_grounded_fact("Tests pass on main", "cargo test", 0.9);
+
+        let fetcher = StubFetcher(HashMap::from([
+            (
+                "https://docs.example.com/limits",
+                "Limits\nThe API rate limit is 100 requests per minute per token.",
+            ),
+            (
+                "https://example.com/releases",
+                "Version 3.0 is the latest stable release.",
+            ),
+        ]));
+
+        let report = verify_grounded_facts(&store, &fetcher).await;
+        assert_eq!(
+            report,
+            VerifyReport {
+                checked: 3,
+                stale: 1,
+                failed: 1
+            }
+        );
+
+        let facts = store.grounded_facts();
+        let find = |needle: &str| facts.iter().find(|f| f.fact.contains(needle)).unwrap();
+        let stale = find("Version 2.4");
+        assert_eq!(stale.stale_since.as_deref(), Some(today_date().as_str()));
+        assert!((stale.confidence - 0.4).abs() < 1e-6);
+        assert_eq!(find("rate limit").stale_since, None);
+        assert_eq!(find("Berlin").stale_since, None);
+        assert_eq!(find("Tests pass").stale_since, None);
+
+        // Already-stale facts are not re-checked.
+        let report = verify_grounded_facts(&store, &fetcher).await;
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.stale, 0);
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+}
@@ -10,6 +10,8 @@ pub struct LlmClient {
     http: reqwest::Client,
     base_url: String,
     headers: HeaderMap,
+    /// Query parameter appended to every request (Azure `api-version`).
+    query: Option<(String, String)>,
 }
 
 impl LlmClient {
@@ -52,11 +54,17 @@ impl LlmClient {
             http: reqwest::Client::new(),
             base_url,
             headers,
+            query: None,
         })
     }
 
     pub fn from_config(cfg: &AppConfig) -> Result<Self> {
-        match cfg.provider {
+        Self::for_provider(cfg, &cfg.provider)
+    }
+
+    /// Client for `provider` using its credentials from `cfg`.
+    pub fn for_provider(cfg: &AppConfig, provider: &ProviderKind) -> Result<Self> {
+        match provider {
             ProviderKind::OpenRouter => Self::new(
                 cfg.providers.openrouter.api_key.clone(),
                 cfg.providers.openrouter.base_url.clone(),
@@ -78,6 +86,22 @@ impl LlmClient {
                 None,
                 cfg.providers.ollama.extra_headers.clone(),
             ),
+            ProviderKind::Azure => {
+                let azure = &cfg.providers.azure;
+                if azure.api_key.trim().is_empty() {
+                    return Err(anyhow!("missing Azure OpenAI API key"));
+                }
+                // Azure authenticates via the `api-key` header, not a bearer token.
+                let mut client = Self::new_optional_key(
+                    String::new(),
+                    crate::providers::azure_base_url(azure),
+                    None,
+                    None,
+                    crate::providers::azure_headers(azure),
+                )?;
+                client.query = crate::providers::azure_query(azure);
+                Ok(client)
+            }
         }
     }
 
@@ -119,11 +143,16 @@ impl LlmClient {
             http: reqwest::Client::new(),
             base_url,
             headers,
+            query: None,
         })
     }
 
     fn url(&self, path: &str) -> String {
-        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+        let url = format!("{}{}", self.base_url.trim_end_matches('/'), path);
+        match &self.query {
+            Some((key, value)) => format!("{url}?{key}={value}"),
+            None => url,
+        }
     }
 
     pub async fn chat_completion(
@@ -41,6 +41,11 @@ impl ConversationSummarizer {
         Self { model, client }
     }
 
+    #[cfg(test)]
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     pub async fn summarize(&self, messages: &[ChatMessage]) -> Result<Option<ConversationSummary>> {
         if messages.is_empty() {
             return Ok(None);
@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use regex::Regex;
 use rig::vector_store::request::{SearchFilter, VectorSearchRequest};
 use rig::vector_store::{VectorStoreError, VectorStoreIndex};
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tracing::warn;
@@ -22,6 +23,9 @@ const MAX_CACHE_ENTRIES: usize = 512;
 /// Prevents unbounded full-table scans; the highest-priority/most-recent
 /// rows are returned first thanks to the composite index.
 const MAX_SEARCH_ROWS: usize = 500;
+/// How long a connection waits on another connection's lock (the service and
+/// a CLI command can share the file) before failing with "database is locked".
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
 
 static NAMESPACE_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^[a-zA-Z0-9_-]{1,64}$").unwrap());
@@ -40,6 +44,66 @@ pub struct MemoryItem {
     pub namespace: String,
 }
 
+impl MemoryItem {
+    /// Pinned memories (`"pinned": true` in metadata) are never pruned.
+    pub fn is_pinned(&self) -> bool {
+        self.metadata.get("pinned").and_then(Value::as_bool) == Some(true)
+    }
+}
+
+/// Row counts for one namespace, as shown by `/memory stats`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamespaceStats {
+    pub count: usize,
+    /// Prune cap in effect for the namespace.
+    pub cap: usize,
+    /// Rows per metadata `kind` (`"other"` when unset), largest first.
+    pub kinds: Vec<(String, usize)>,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+/// Outcome of [`VectorMemoryStore::rebuild`], as printed by `memory rebuild`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RebuildReport {
+    pub rows: usize,
+    /// Rows whose priority or access count changed.
+    pub reprioritized: usize,
+    /// Embedding width shared by most rows.
+    pub dimension: Option<usize>,
+    /// `(id, namespace, width)` of rows whose embedding doesn't match
+    /// `dimension`. They are kept, but similarity against them is always 0.
+    pub mismatched: Vec<(String, String, usize)>,
+}
+
+/// Portable representation of a stored memory used by `memory export`/`import`.
+///
+/// Embeddings are optional: when present they are tagged with the model that
+/// produced them so an import into a store with a different embedding model
+/// can re-embed instead of mixing incompatible vectors.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MemoryExportRecord {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub access_count: i64,
+    #[serde(default = "default_export_priority")]
+    pub priority: f32,
+    pub namespace: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+}
+
+fn default_export_priority() -> f32 {
+    0.5
+}
+
 /// Default priority weight used when blending similarity with priority score.
 const DEFAULT_PRIORITY_WEIGHT: f32 = 0.3;
 /// Default similarity threshold for vector search.
@@ -56,6 +120,8 @@ pub struct EmbeddingService {
     client: LlmClient,
     model: String,
     cache: Arc<AsyncMutex<EmbeddingCache>>,
+    /// On-disk copy of the cache, written through on insert.
+    disk: Option<Arc<Mutex<Connection>>>,
 }
 
 #[derive(Clone)]
@@ -76,7 +142,8 @@ impl EmbeddingCache {
         self.entries.get(key).map(|e| &e.embedding)
     }
 
-    fn insert(&mut self, key: String, embedding: Vec<f32>) {
+    /// Insert and return the entry's insertion order.
+    fn insert(&mut self, key: String, embedding: Vec<f32>) -> u64 {
         if self.entries.len() >= MAX_CACHE_ENTRIES {
             self.evict_oldest_quarter();
         }
@@ -88,6 +155,19 @@ impl EmbeddingCache {
                 insert_order: self.counter,
             },
         );
+        self.counter
+    }
+
+    /// Re-add an entry loaded from disk, keeping its original order.
+    fn restore(&mut self, key: String, embedding: Vec<f32>, insert_order: u64) {
+        self.counter = self.counter.max(insert_order);
+        self.entries.insert(
+            key,
+            CacheEntry {
+                embedding,
+                insert_order,
+            },
+        );
     }
 
     /// Evict the oldest 25% of entries by insertion order.
@@ -115,9 +195,51 @@ impl EmbeddingService {
             client,
             model,
             cache: Arc::new(AsyncMutex::new(EmbeddingCache::new())),
+            disk: None,
         }
     }
 
+    /// Persist the cache in a sqlite file keyed by text and model, loading
+    /// the most recent entries for this model now.
+    pub fn with_disk_cache(mut self, path: PathBuf) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS embedding_cache (\
+                model TEXT NOT NULL,\
+                text TEXT NOT NULL,\
+                embedding BLOB NOT NULL,\
+                insert_order INTEGER NOT NULL,\
+                PRIMARY KEY (model, text)\
+            )",
+            [],
+        )?;
+
+        let mut cache = EmbeddingCache::new();
+        {
+            let mut stmt = conn.prepare(
+                "SELECT text, embedding, insert_order FROM embedding_cache WHERE model = ?1 ORDER BY insert_order DESC LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![self.model, MAX_CACHE_ENTRIES as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Vec<u8>>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })?;
+            for row in rows {
+                let (text, blob, order) = row?;
+                cache.restore(text, bytes_to_f32s(&blob), order.max(0) as u64);
+            }
+        }
+
+        self.cache = Arc::new(AsyncMutex::new(cache));
+        self.disk = Some(Arc::new(Mutex::new(conn)));
+        Ok(self)
+    }
+
     pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
         if text.trim().is_empty() {
             return Err(anyhow!("cannot embed empty text"));
@@ -129,9 +251,46 @@ impl EmbeddingService {
         drop(cache);
         let embedding = self.client.embeddings(&self.model, text).await?;
         let mut cache = self.cache.lock().await;
-        cache.insert(text.to_string(), embedding.clone());
+        let order = cache.insert(text.to_string(), embedding.clone());
+        drop(cache);
+        self.persist(text, &embedding, order).await;
         Ok(embedding)
     }
+
+    /// Write one entry through to disk and trim rows beyond the cache cap.
+    /// Failures only cost a re-embed later, so they are logged, not returned.
+    async fn persist(&self, text: &str, embedding: &[f32], order: u64) {
+        let Some(disk) = self.disk.clone() else {
+            return;
+        };
+        let model = self.model.clone();
+        let text = text.to_string();
+        let blob = f32s_to_bytes(embedding);
+        let result = tokio::task::spawn_blocking(move || -> Result<()> {
+            let conn = disk.lock().map_err(|e| anyhow!("mutex poisoned: {e}"))?;
+            conn.execute(
+                "INSERT OR REPLACE INTO embedding_cache (model, text, embedding, insert_order) VALUES (?1, ?2, ?3, ?4)",
+                params![model, text, blob, order as i64],
+            )?;
+            conn.execute(
+                "DELETE FROM embedding_cache WHERE model = ?1 AND insert_order <= \
+                 (SELECT insert_order FROM embedding_cache WHERE model = ?1 \
+                  ORDER BY insert_order DESC LIMIT 1 OFFSET ?2)",
+                params![model, MAX_CACHE_ENTRIES as i64],
+            )?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| anyhow!("blocking task failed: {e}"))
+        .and_then(|r| r);
+        if let Err(err) = result {
+            warn!("failed to persist embedding cache entry: {err}");
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
 }
 
 #[derive(Clone)]
@@ -139,6 +298,9 @@ pub struct VectorMemoryStore {
     conn: Arc<Mutex<Connection>>,
     embedder: EmbeddingService,
     max_memories: usize,
+    /// Prune caps that replace `max_memories` for a namespace, keyed by the
+    /// exact namespace or by its channel prefix (`telegram` for `telegram_42`).
+    namespace_caps: Arc<HashMap<String, usize>>,
     namespace: String,
 }
 
@@ -153,15 +315,67 @@ impl VectorMemoryStore {
             std::fs::create_dir_all(parent)?;
         }
         let conn = Connection::open(db_path)?;
+        // WAL lets readers in other processes run alongside this writer.
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
         init_db(&conn)?;
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             embedder,
             max_memories,
+            namespace_caps: Arc::new(HashMap::new()),
             namespace: validate_namespace(&namespace)?,
         })
     }
 
+    /// Open an existing store without write access, for CLI inspection while
+    /// the service holds the database. Writes through it fail; search still
+    /// works but can't bump access counts.
+    pub fn open_read_only(
+        db_path: PathBuf,
+        embedder: EmbeddingService,
+        max_memories: usize,
+        namespace: String,
+    ) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            &db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|err| anyhow!("failed to open {}: {err}", db_path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+            embedder,
+            max_memories,
+            namespace_caps: Arc::new(HashMap::new()),
+            namespace: validate_namespace(&namespace)?,
+        })
+    }
+
+    pub fn with_namespace_caps(mut self, caps: HashMap<String, usize>) -> Self {
+        self.namespace_caps = Arc::new(caps);
+        self
+    }
+
+    /// Row cap for `namespace`: an exact override, then a channel-prefix
+    /// override, then the global `max_memories`.
+    fn max_memories_for(&self, namespace: &str) -> usize {
+        let channel = namespace.split('_').next().unwrap_or(namespace);
+        self.namespace_caps
+            .get(namespace)
+            .or_else(|| self.namespace_caps.get(channel))
+            .copied()
+            .unwrap_or(self.max_memories)
+    }
+
+    /// Pinned rows allowed in `namespace`: half its row cap, so pins can
+    /// never take over the whole namespace and stop pruning from working.
+    fn max_pinned_for(&self, namespace: &str) -> usize {
+        (self.max_memories_for(namespace) / 2).max(1)
+    }
+
     /// Run a blocking closure against the database connection on Tokio's
     /// blocking thread pool, avoiding stalls on the async runtime.
     async fn with_conn<F, T>(&self, f: F) -> Result<T>
@@ -184,6 +398,32 @@ impl VectorMemoryStore {
         metadata: HashMap<String, Value>,
         namespace: Option<&str>,
         precomputed_embedding: Option<Vec<f32>>,
+    ) -> Result<MemoryItem> {
+        let item = self
+            .new_item(content, metadata, namespace, precomputed_embedding)
+            .await?;
+        let row = item.clone();
+        let max_mem = self.max_memories_for(&item.namespace);
+        let max_pinned = self.max_pinned_for(&item.namespace);
+        self.with_conn(move |conn| {
+            if row.is_pinned() {
+                check_pin_room(conn, &row.namespace, 1, max_pinned)?;
+            }
+            insert_item(conn, &row)?;
+            prune_if_needed(conn, &row.namespace, max_mem)?;
+            Ok(())
+        })
+        .await?;
+        Ok(item)
+    }
+
+    /// Validate and embed `content` as a not-yet-stored memory.
+    async fn new_item(
+        &self,
+        content: &str,
+        metadata: HashMap<String, Value>,
+        namespace: Option<&str>,
+        precomputed_embedding: Option<Vec<f32>>,
     ) -> Result<MemoryItem> {
         let content = content.trim();
         if content.is_empty() {
@@ -198,32 +438,14 @@ impl VectorMemoryStore {
             _ => self.embedder.embed(content).await?,
         };
         let now = Utc::now();
-        let memory_id = Uuid::new_v4().to_string();
-        let embedding_blob = f32s_to_bytes(&embedding);
         let importance = metadata
             .get("importance")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.5);
         let priority = (importance * 0.4 + 0.3).clamp(0.0, 1.0) as f32;
 
-        let content_owned = content.to_string();
-        let ns = namespace.clone();
-        let mid = memory_id.clone();
-        let metadata_json = serde_json::to_string(&metadata)?;
-        let now_str = now.to_rfc3339();
-        let max_mem = self.max_memories;
-
-        self.with_conn(move |conn| {
-            conn.execute(
-                "INSERT INTO memories (id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                params![mid, content_owned, embedding_blob, metadata_json, now_str, now_str, 0i64, priority, ns],
-            )?;
-            prune_if_needed(conn, &ns, max_mem)?;
-            Ok(())
-        }).await?;
-
         Ok(MemoryItem {
-            id: memory_id,
+            id: Uuid::new_v4().to_string(),
             content: content.to_string(),
             embedding,
             metadata,
@@ -235,6 +457,52 @@ impl VectorMemoryStore {
         })
     }
 
+    /// Like `add`, but content over the size limit is split into chunks
+    /// stored as separate memories. Chunks share `metadata` plus a
+    /// `chunk_group` id, their `chunk_index` and the `chunk_count`. Every
+    /// chunk is embedded before any is written, and all of them go in one
+    /// transaction, so a failure never leaves part of a group behind.
+    pub async fn add_chunked(
+        &self,
+        content: &str,
+        metadata: HashMap<String, Value>,
+        namespace: Option<&str>,
+    ) -> Result<Vec<MemoryItem>> {
+        let content = content.trim();
+        if content.len() <= MAX_CONTENT_LENGTH {
+            return Ok(vec![self.add(content, metadata, namespace, None).await?]);
+        }
+        let chunks = split_content(content, MAX_CONTENT_LENGTH);
+        let group = Uuid::new_v4().to_string();
+        let mut items = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut meta = metadata.clone();
+            meta.insert("chunk_group".to_string(), Value::from(group.clone()));
+            meta.insert("chunk_index".to_string(), Value::from(index));
+            meta.insert("chunk_count".to_string(), Value::from(chunks.len()));
+            items.push(self.new_item(chunk, meta, namespace, None).await?);
+        }
+        let rows = items.clone();
+        let ns = items[0].namespace.clone();
+        let max_mem = self.max_memories_for(&ns);
+        let max_pinned = self.max_pinned_for(&ns);
+        self.with_conn(move |conn| {
+            let pinning = rows.iter().filter(|row| row.is_pinned()).count();
+            if pinning > 0 {
+                check_pin_room(conn, &ns, pinning, max_pinned)?;
+            }
+            let tx = conn.unchecked_transaction()?;
+            for row in &rows {
+                insert_item(&tx, row)?;
+            }
+            prune_if_needed(&tx, &ns, max_mem)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await?;
+        Ok(items)
+    }
+
     #[allow(dead_code)]
     pub async fn update(
         &self,
@@ -274,11 +542,12 @@ impl VectorMemoryStore {
             .get("importance")
             .and_then(|v| v.as_f64())
             .unwrap_or(0.5);
-        let age_days = (now - existing.created_at).num_seconds() as f64 / 86400.0;
-        let recency = (1.0 - (age_days / 30.0)).clamp(0.0, 1.0);
-        let access_score = ((existing.access_count as f64).sqrt() / 10.0).clamp(0.0, 1.0);
-        let priority =
-            (importance * 0.4 + recency * 0.3 + access_score * 0.3).clamp(0.0, 1.0) as f32;
+        let priority = priority_score(
+            importance,
+            Some(existing.created_at),
+            existing.access_count,
+            now,
+        );
 
         let content_owned = content.to_string();
         let ns = namespace.clone();
@@ -307,7 +576,6 @@ impl VectorMemoryStore {
         }))
     }
 
-    #[allow(dead_code)]
     pub async fn delete(&self, memory_id: &str, namespace: Option<&str>) -> Result<bool> {
         let namespace = validate_namespace(namespace.unwrap_or(&self.namespace))?;
         let mid = memory_id.to_string();
@@ -323,7 +591,44 @@ impl VectorMemoryStore {
         .await
     }
 
-    #[allow(dead_code)]
+    /// Set or clear the `pinned` flag on a memory. Returns false when the
+    /// id doesn't exist in the namespace, and fails when pinning it would go
+    /// past the namespace's pin cap.
+    pub async fn set_pinned(
+        &self,
+        memory_id: &str,
+        namespace: Option<&str>,
+        pinned: bool,
+    ) -> Result<bool> {
+        let namespace = validate_namespace(namespace.unwrap_or(&self.namespace))?;
+        let max_pinned = self.max_pinned_for(&namespace);
+        let mid = memory_id.to_string();
+        let sql = if pinned {
+            "UPDATE memories SET metadata = json_set(COALESCE(metadata, '{}'), '$.pinned', json('true')) WHERE id = ?1 AND namespace = ?2"
+        } else {
+            "UPDATE memories SET metadata = json_remove(COALESCE(metadata, '{}'), '$.pinned') WHERE id = ?1 AND namespace = ?2"
+        };
+
+        self.with_conn(move |conn| {
+            if pinned {
+                let already: bool = conn
+                    .query_row(
+                        "SELECT json_extract(metadata, '$.pinned') IS 1 FROM memories WHERE id = ?1 AND namespace = ?2",
+                        params![mid, namespace],
+                        |row| row.get(0),
+                    )
+                    .optional()?
+                    .unwrap_or(true);
+                if !already {
+                    check_pin_room(conn, &namespace, 1, max_pinned)?;
+                }
+            }
+            let rows = conn.execute(sql, params![mid, namespace])?;
+            Ok(rows > 0)
+        })
+        .await
+    }
+
     pub async fn get(
         &self,
         memory_id: &str,
@@ -368,10 +673,9 @@ impl VectorMemoryStore {
         namespace: Option<&str>,
         priority_weight: f32,
     ) -> Result<(Vec<(MemoryItem, f32)>, Vec<f32>)> {
-        let namespace = validate_namespace(namespace.unwrap_or(&self.namespace))?;
-        let query_embedding = self.embedder.embed(query).await?;
+        let query_embedding = self.embed_query(query).await?;
         let results = self
-            .search_inner(
+            .search_embedding(
                 query_embedding.clone(),
                 top_k,
                 threshold,
@@ -382,6 +686,307 @@ impl VectorMemoryStore {
         Ok((results, query_embedding))
     }
 
+    /// Embed a search query (through the embedding cache).
+    pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        self.embedder.embed(query).await
+    }
+
+    /// Search with an already computed query embedding.
+    pub async fn search_embedding(
+        &self,
+        query_embedding: Vec<f32>,
+        top_k: usize,
+        threshold: f32,
+        namespace: Option<&str>,
+        priority_weight: f32,
+    ) -> Result<Vec<(MemoryItem, f32)>> {
+        let namespace = validate_namespace(namespace.unwrap_or(&self.namespace))?;
+        self.search_inner(
+            query_embedding,
+            top_k,
+            threshold,
+            namespace,
+            priority_weight,
+        )
+        .await
+    }
+
+    /// Remove every memory in `namespace` whose metadata `kind` is `kind`,
+    /// limited to rows tagged with `session` when one is given (namespaces
+    /// can be shared by several chats). Returns how many were deleted.
+    pub async fn delete_kind(
+        &self,
+        namespace: &str,
+        kind: &str,
+        session: Option<&str>,
+    ) -> Result<usize> {
+        let ns = validate_namespace(namespace)?;
+        let kind = kind.to_string();
+        let session = session.map(str::to_string);
+        self.with_conn(move |conn| {
+            Ok(conn.execute(
+                "DELETE FROM memories WHERE namespace = ?1 AND json_extract(metadata, '$.kind') = ?2 \
+                 AND (?3 IS NULL OR json_extract(metadata, '$.session') = ?3)",
+                params![ns, kind, session],
+            )?)
+        })
+        .await
+    }
+
+    /// The `limit` most recently updated memories in `namespace`.
+    pub async fn list(&self, namespace: &str, limit: usize) -> Result<Vec<MemoryItem>> {
+        let ns = validate_namespace(namespace)?;
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace \
+                 FROM memories WHERE namespace = ?1 ORDER BY updated_at DESC LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![ns, limit as i64], parse_memory_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+        .await
+    }
+
+    /// Memories in `namespace` whose content contains `text` (ASCII
+    /// case-insensitive), newest first.
+    pub async fn find_containing(
+        &self,
+        namespace: &str,
+        text: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryItem>> {
+        let ns = validate_namespace(namespace)?;
+        let needle = text.to_string();
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace \
+                 FROM memories WHERE namespace = ?1 AND instr(lower(content), lower(?2)) > 0 \
+                 ORDER BY updated_at DESC LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![ns, needle, limit as i64], parse_memory_row)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+        .await
+    }
+
+    pub async fn stats(&self, namespace: &str) -> Result<NamespaceStats> {
+        let ns = validate_namespace(namespace)?;
+        let cap = self.max_memories_for(&ns);
+        self.with_conn(move |conn| {
+            let (count, oldest, newest): (i64, Option<String>, Option<String>) = conn.query_row(
+                "SELECT COUNT(*), MIN(created_at), MAX(updated_at) FROM memories WHERE namespace = ?1",
+                params![ns],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            let mut stmt = conn.prepare(
+                "SELECT COALESCE(json_extract(metadata, '$.kind'), 'other') AS kind, COUNT(*) AS n \
+                 FROM memories WHERE namespace = ?1 GROUP BY kind ORDER BY n DESC, kind ASC",
+            )?;
+            let kinds = stmt
+                .query_map(params![ns], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let parse = |raw: Option<String>| {
+                raw.and_then(|raw| DateTime::parse_from_rfc3339(&raw).ok())
+                    .map(|at| at.with_timezone(&Utc))
+            };
+            Ok(NamespaceStats {
+                count: count as usize,
+                cap,
+                kinds,
+                oldest: parse(oldest),
+                newest: parse(newest),
+            })
+        })
+        .await
+    }
+
+    /// Every namespace that currently holds at least one memory, sorted.
+    pub async fn namespaces(&self) -> Result<Vec<String>> {
+        self.with_conn(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT DISTINCT namespace FROM memories ORDER BY namespace ASC")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+        .await
+    }
+
+    /// Maintenance pass after bulk edits (import, manual SQL): recompute
+    /// every priority from importance, age and access count, reset negative
+    /// access counts, rebuild the indices and check that all embeddings have
+    /// the same width. No row is deleted or re-embedded.
+    pub async fn rebuild(&self) -> Result<RebuildReport> {
+        self.with_conn(|conn| {
+            let rows = {
+                let mut stmt = conn.prepare(
+                    "SELECT id, namespace, embedding, metadata, created_at, access_count, priority \
+                     FROM memories ORDER BY namespace ASC, created_at ASC",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, String>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                        row.get::<_, Option<f64>>(6)?,
+                    ))
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+
+            let mut widths: HashMap<usize, usize> = HashMap::new();
+            for (_, _, blob, ..) in &rows {
+                *widths.entry(blob.len() / 4).or_default() += 1;
+            }
+            let dimension = widths
+                .into_iter()
+                .filter(|(width, _)| *width > 0)
+                .max_by_key(|(width, count)| (*count, *width))
+                .map(|(width, _)| width);
+
+            let now = Utc::now();
+            let mut report = RebuildReport {
+                rows: rows.len(),
+                dimension,
+                ..Default::default()
+            };
+            let tx = conn.unchecked_transaction()?;
+            for (id, namespace, blob, metadata, created_at, access_count, priority) in &rows {
+                let width = blob.len() / 4;
+                if blob.len() % 4 != 0 || Some(width) != dimension {
+                    report
+                        .mismatched
+                        .push((id.clone(), namespace.clone(), width));
+                }
+                let importance = metadata
+                    .as_deref()
+                    .and_then(|raw| serde_json::from_str::<HashMap<String, Value>>(raw).ok())
+                    .and_then(|meta| meta.get("importance").and_then(Value::as_f64))
+                    .unwrap_or(0.5);
+                let created_at = DateTime::parse_from_rfc3339(created_at)
+                    .ok()
+                    .map(|at| at.with_timezone(&Utc));
+                let access = access_count.unwrap_or(0).max(0);
+                let score = priority_score(importance, created_at, access, now);
+                let stale = priority.is_none_or(|p| (p as f32 - score).abs() > 1e-6)
+                    || *access_count != Some(access);
+                if stale {
+                    tx.execute(
+                        "UPDATE memories SET priority = ?1, access_count = ?2 WHERE id = ?3 AND namespace = ?4",
+                        params![score, access, id, namespace],
+                    )?;
+                    report.reprioritized += 1;
+                }
+            }
+            tx.commit()?;
+            conn.execute_batch("REINDEX memories; ANALYZE memories;")?;
+            Ok(report)
+        })
+        .await
+    }
+
+    /// Dump every memory across all namespaces, oldest first.
+    pub async fn export_all(&self, include_embeddings: bool) -> Result<Vec<MemoryExportRecord>> {
+        let model = self.embedder.model().to_string();
+        self.with_conn(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace \
+                 FROM memories ORDER BY namespace ASC, created_at ASC",
+            )?;
+            let rows = stmt.query_map([], parse_memory_row)?;
+            let mut out = Vec::new();
+            for row in rows {
+                let item = row?;
+                let (embedding_model, embedding) = if include_embeddings && !item.embedding.is_empty() {
+                    (Some(model.clone()), Some(item.embedding))
+                } else {
+                    (None, None)
+                };
+                out.push(MemoryExportRecord {
+                    id: item.id,
+                    content: item.content,
+                    metadata: item.metadata,
+                    created_at: item.created_at,
+                    updated_at: item.updated_at,
+                    access_count: item.access_count,
+                    priority: item.priority,
+                    namespace: item.namespace,
+                    embedding_model,
+                    embedding,
+                });
+            }
+            Ok(out)
+        })
+        .await
+    }
+
+    /// Ingest exported records, preserving ids and timestamps. Records whose
+    /// embedding is missing or was produced by a different model are re-embedded.
+    /// Existing rows with the same id are replaced. Returns the number imported.
+    pub async fn import_records(&self, records: Vec<MemoryExportRecord>) -> Result<usize> {
+        let mut rows = Vec::with_capacity(records.len());
+        for record in records {
+            let content = record.content.trim().to_string();
+            if content.is_empty() || content.len() > MAX_CONTENT_LENGTH {
+                warn!(
+                    "memory import: skipping record id={} (invalid content)",
+                    record.id
+                );
+                continue;
+            }
+            let namespace = validate_namespace(&record.namespace)?;
+            let reusable = record.embedding_model.as_deref() == Some(self.embedder.model());
+            let embedding = match record.embedding {
+                Some(e) if reusable && !e.is_empty() => e,
+                _ => self.embedder.embed(&content).await?,
+            };
+            let id = if record.id.trim().is_empty() {
+                Uuid::new_v4().to_string()
+            } else {
+                record.id
+            };
+            rows.push((
+                id,
+                content,
+                f32s_to_bytes(&embedding),
+                serde_json::to_string(&record.metadata)?,
+                record.created_at.to_rfc3339(),
+                record.updated_at.to_rfc3339(),
+                record.access_count,
+                record.priority.clamp(0.0, 1.0),
+                namespace,
+            ));
+        }
+        let caps: HashMap<String, usize> = rows
+            .iter()
+            .map(|row| (row.8.clone(), self.max_memories_for(&row.8)))
+            .collect();
+
+        self.with_conn(move |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let mut namespaces = Vec::new();
+            for (id, content, blob, metadata, created, updated, access, priority, ns) in &rows {
+                tx.execute(
+                    "INSERT OR REPLACE INTO memories (id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![id, content, blob, metadata, created, updated, access, priority, ns],
+                )?;
+                if !namespaces.contains(ns) {
+                    namespaces.push(ns.clone());
+                }
+            }
+            for ns in &namespaces {
+                prune_if_needed(&tx, ns, caps[ns])?;
+            }
+            tx.commit()?;
+            Ok(rows.len())
+        })
+        .await
+    }
+
     /// Shared search implementation used by both `search()` and `VectorStoreIndex::top_n()`.
     /// Returns `(MemoryItem, similarity_score)` pairs sorted by combined score.
     /// Also bumps `access_count` for the returned memories.
@@ -437,6 +1042,55 @@ impl VectorMemoryStore {
     }
 }
 
+#[cfg(test)]
+impl VectorMemoryStore {
+    /// Store whose embedding endpoint is unreachable; tests pass precomputed
+    /// vectors and seed query embeddings with `seed_embedding`.
+    pub(crate) fn for_tests(db_path: PathBuf) -> Self {
+        Self::for_tests_at(db_path, "http://127.0.0.1:9")
+    }
+
+    /// Test store whose embedding requests go to `base_url`.
+    pub(crate) fn for_tests_at(db_path: PathBuf, base_url: &str) -> Self {
+        let client = LlmClient::new(
+            "test-key".to_string(),
+            base_url.to_string(),
+            None,
+            None,
+            Vec::new(),
+        )
+        .expect("test client");
+        let embedder = EmbeddingService::new(client, "test-embed".to_string());
+        Self::new(db_path, embedder, 100, "default".to_string()).expect("test store")
+    }
+
+    pub(crate) async fn seed_embedding(&self, text: &str, embedding: Vec<f32>) {
+        self.embedder
+            .cache
+            .lock()
+            .await
+            .insert(text.to_string(), embedding);
+    }
+}
+
+fn insert_item(conn: &Connection, item: &MemoryItem) -> Result<()> {
+    conn.execute(
+        "INSERT INTO memories (id, content, embedding, metadata, created_at, updated_at, access_count, priority, namespace) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            item.id,
+            item.content,
+            f32s_to_bytes(&item.embedding),
+            serde_json::to_string(&item.metadata)?,
+            item.created_at.to_rfc3339(),
+            item.updated_at.to_rfc3339(),
+            item.access_count,
+            item.priority,
+            item.namespace
+        ],
+    )?;
+    Ok(())
+}
+
 fn parse_memory_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<MemoryItem> {
     let embedding_blob: Vec<u8> = row.get(2)?;
     let embedding = bytes_to_f32s(&embedding_blob);
@@ -505,6 +1159,34 @@ fn init_db(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// Split `content` into pieces of at most `max_bytes`, preferring paragraph,
+/// line, sentence and then word boundaries in the back half of each piece.
+pub(crate) fn split_content(content: &str, max_bytes: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = content.trim();
+    while rest.len() > max_bytes {
+        let mut limit = max_bytes;
+        while !rest.is_char_boundary(limit) {
+            limit -= 1;
+        }
+        let window = &rest[..limit];
+        let cut = ["\n\n", "\n", ". ", " "]
+            .iter()
+            .filter_map(|sep| window.rfind(sep).map(|pos| pos + sep.len()))
+            .find(|pos| *pos > limit / 2)
+            .unwrap_or(limit);
+        let piece = rest[..cut].trim();
+        if !piece.is_empty() {
+            chunks.push(piece.to_string());
+        }
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+    chunks
+}
+
 fn validate_namespace(namespace: &str) -> Result<String> {
     if NAMESPACE_RE.is_match(namespace) {
         return Ok(namespace.to_string());
@@ -526,6 +1208,26 @@ fn validate_namespace(namespace: &str) -> Result<String> {
     Ok(trimmed)
 }
 
+/// Fail unless `adding` more pinned rows fit under `max_pinned`.
+fn check_pin_room(
+    conn: &Connection,
+    namespace: &str,
+    adding: usize,
+    max_pinned: usize,
+) -> Result<()> {
+    let pinned: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM memories WHERE namespace = ?1 AND json_extract(metadata, '$.pinned') IS 1",
+        params![namespace],
+        |row| row.get(0),
+    )?;
+    if pinned as usize + adding > max_pinned {
+        return Err(anyhow!(
+            "pin limit reached: {pinned} of at most {max_pinned} memories in this namespace are pinned; unpin one first"
+        ));
+    }
+    Ok(())
+}
+
 fn prune_if_needed(conn: &Connection, namespace: &str, max_memories: usize) -> Result<()> {
     let count: i64 = conn.query_row(
         "SELECT COUNT(*) FROM memories WHERE namespace = ?1",
@@ -534,8 +1236,10 @@ fn prune_if_needed(conn: &Connection, namespace: &str, max_memories: usize) -> R
     )?;
     if count as usize > max_memories {
         let excess = count as usize - max_memories;
+        // Pinned rows still count toward the cap but are never chosen.
         let mut stmt = conn.prepare(
-            "SELECT id FROM memories WHERE namespace = ?1 ORDER BY priority ASC, updated_at ASC LIMIT ?2",
+            "SELECT id FROM memories WHERE namespace = ?1 AND json_extract(metadata, '$.pinned') IS NOT 1 \
+             ORDER BY priority ASC, updated_at ASC LIMIT ?2",
         )?;
         let ids = stmt
             .query_map(params![namespace, excess as i64], |row| {
@@ -552,6 +1256,22 @@ fn prune_if_needed(conn: &Connection, namespace: &str, max_memories: usize) -> R
     Ok(())
 }
 
+/// Blend of importance, recency (fading over 30 days) and access count,
+/// in `0.0..=1.0`. A fresh, unread memory scores `importance * 0.4 + 0.3`.
+fn priority_score(
+    importance: f64,
+    created_at: Option<DateTime<Utc>>,
+    access_count: i64,
+    now: DateTime<Utc>,
+) -> f32 {
+    let recency = created_at.map_or(0.0, |created| {
+        let age_days = (now - created).num_seconds() as f64 / 86400.0;
+        (1.0 - (age_days / 30.0)).clamp(0.0, 1.0)
+    });
+    let access_score = ((access_count.max(0) as f64).sqrt() / 10.0).clamp(0.0, 1.0);
+    (importance * 0.4 + recency * 0.3 + access_score * 0.3).clamp(0.0, 1.0) as f32
+}
+
 fn f32s_to_bytes(vec: &[f32]) -> Vec<u8> {
     let mut out = Vec::with_capacity(vec.len() * 4);
     for v in vec {
@@ -590,7 +1310,7 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
 
 #[cfg(test)]
 mod tests {
-    use super::cosine_similarity;
+    use super::*;
 
     #[test]
     fn cosine_similarity_handles_dimension_mismatch() {
@@ -605,6 +1325,415 @@ mod tests {
         let sim = cosine_similarity(&v, &v);
         assert!((sim - 1.0).abs() < 1e-6);
     }
+
+    #[tokio::test]
+    async fn chunked_add_stores_nothing_when_a_chunk_fails_to_embed() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let store = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        let content = "word ".repeat(MAX_CONTENT_LENGTH / 2);
+        let chunks = split_content(content.trim(), MAX_CONTENT_LENGTH);
+        assert!(chunks.len() > 1);
+        // Only the first chunk can be embedded; the endpoint is unreachable.
+        store.seed_embedding(chunks[0].trim(), vec![1.0, 0.0]).await;
+
+        assert!(store
+            .add_chunked(&content, HashMap::new(), Some("tg_1"))
+            .await
+            .is_err());
+        assert!(store.list("tg_1", 10).await.unwrap().is_empty());
+
+        for chunk in &chunks {
+            store.seed_embedding(chunk.trim(), vec![1.0, 0.0]).await;
+        }
+        let items = store
+            .add_chunked(&content, HashMap::new(), Some("tg_1"))
+            .await
+            .unwrap();
+        assert_eq!(items.len(), chunks.len());
+        assert_eq!(store.list("tg_1", 10).await.unwrap().len(), chunks.len());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn export_then_import_round_trips_content_and_metadata() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let source = VectorMemoryStore::for_tests(root.join("source").join("vectors.db"));
+        let target = VectorMemoryStore::for_tests(root.join("target").join("vectors.db"));
+
+        rt.block_on(async {
+            let mut metadata = HashMap::new();
+            metadata.insert("importance".to_string(), Value::from(0.9));
+            metadata.insert("source".to_string(), Value::from("test"));
+            let first = source
+                .add(
+                    "User prefers tea",
+                    metadata,
+                    Some("tg_1"),
+                    Some(vec![1.0, 0.0]),
+                )
+                .await
+                .unwrap();
+            source
+                .add(
+                    "Project uses sqlite",
+                    HashMap::new(),
+                    Some("dc_2"),
+                    Some(vec![0.0, 1.0]),
+                )
+                .await
+                .unwrap();
+
+            let exported = source.export_all(true).await.unwrap();
+            assert_eq!(exported.len(), 2);
+            assert!(exported
+                .iter()
+                .all(|r| r.embedding_model.as_deref() == Some("test-embed")));
+            let parsed: Vec<MemoryExportRecord> = exported
+                .iter()
+                .map(|r| serde_json::from_str(&serde_json::to_string(r).unwrap()).unwrap())
+                .collect();
+
+            let imported = target.import_records(parsed).await.unwrap();
+            assert_eq!(imported, 2);
+
+            let restored = target.get(&first.id, Some("tg_1")).await.unwrap().unwrap();
+            assert_eq!(restored.content, "User prefers tea");
+            assert_eq!(restored.metadata.get("source"), Some(&Value::from("test")));
+            assert_eq!(restored.created_at, first.created_at);
+            assert_eq!(restored.embedding, vec![1.0, 0.0]);
+            assert!(target.get(&first.id, Some("dc_2")).await.unwrap().is_none());
+        });
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn rebuild_normalizes_inconsistent_rows_without_losing_any() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let store = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        let mut ids = Vec::new();
+        for (i, ns) in ["telegram_1", "telegram_1", "discord_2"].iter().enumerate() {
+            let item = store
+                .add(
+                    &format!("fact {i}"),
+                    HashMap::from([("importance".to_string(), Value::from(1.0))]),
+                    Some(ns),
+                    Some(vec![1.0, 0.0, i as f32]),
+                )
+                .await
+                .unwrap();
+            ids.push(item.id);
+        }
+        // Simulate a hand-edited database: a wild priority, a negative
+        // access count and an embedding from another model.
+        let (wild, odd) = (ids[0].clone(), ids[2].clone());
+        store
+            .with_conn(move |conn| {
+                conn.execute(
+                    "UPDATE memories SET priority = 7.5, access_count = -4 WHERE id = ?1",
+                    params![wild],
+                )?;
+                conn.execute(
+                    "UPDATE memories SET embedding = ?1 WHERE id = ?2",
+                    params![f32s_to_bytes(&[0.5; 5]), odd],
+                )?;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        let report = store.rebuild().await.unwrap();
+        assert_eq!(report.rows, 3);
+        assert_eq!(report.reprioritized, 1);
+        assert_eq!(report.dimension, Some(3));
+        assert_eq!(
+            report.mismatched,
+            vec![(ids[2].clone(), "discord_2".to_string(), 5)]
+        );
+
+        let rows = store.export_all(true).await.unwrap();
+        assert_eq!(rows.len(), 3);
+        let fixed = rows.iter().find(|r| r.id == ids[0]).unwrap();
+        assert!((fixed.priority - 0.7).abs() < 0.01, "{}", fixed.priority);
+        assert_eq!(fixed.access_count, 0);
+        assert_eq!(fixed.content, "fact 0");
+        let odd = rows.iter().find(|r| r.id == ids[2]).unwrap();
+        assert_eq!(odd.embedding.as_deref(), Some(&[0.5; 5][..]));
+
+        assert_eq!(store.rebuild().await.unwrap().reprioritized, 0);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn read_only_reader_runs_alongside_the_writer() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let path = root.join("vectors.db");
+        let writer = VectorMemoryStore::for_tests(path.clone());
+        writer
+            .add(
+                "seed",
+                HashMap::new(),
+                Some("telegram_1"),
+                Some(vec![1.0, 0.0]),
+            )
+            .await
+            .unwrap();
+        let client = LlmClient::new(
+            "test-key".to_string(),
+            "http://127.0.0.1:9".to_string(),
+            None,
+            None,
+            Vec::new(),
+        )
+        .unwrap();
+        let reader = VectorMemoryStore::open_read_only(
+            path,
+            EmbeddingService::new(client, "test-embed".to_string()),
+            100,
+            "default".to_string(),
+        )
+        .unwrap();
+
+        let writes = tokio::spawn(async move {
+            for i in 0..40 {
+                writer
+                    .add(
+                        &format!("fact {i}"),
+                        HashMap::new(),
+                        Some("telegram_1"),
+                        Some(vec![1.0, i as f32]),
+                    )
+                    .await
+                    .unwrap();
+            }
+        });
+        let mut seen = 0;
+        for _ in 0..40 {
+            let rows = reader.export_all(false).await.expect("read while writing");
+            assert!(rows.len() >= seen, "reads never go backwards");
+            seen = rows.len();
+        }
+        writes.await.unwrap();
+        assert_eq!(reader.export_all(false).await.unwrap().len(), 41);
+        assert!(reader
+            .add(
+                "nope",
+                HashMap::new(),
+                Some("telegram_1"),
+                Some(vec![1.0, 0.0])
+            )
+            .await
+            .is_err());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn namespace_cap_override_retains_more_rows() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let mut store = VectorMemoryStore::for_tests(root.join("vectors.db"))
+            .with_namespace_caps(HashMap::from([("telegram".to_string(), 5)]));
+        store.max_memories = 2;
+
+        for i in 0..6 {
+            for ns in ["telegram_1", "discord_1"] {
+                store
+                    .add(
+                        &format!("fact {i}"),
+                        HashMap::new(),
+                        Some(ns),
+                        Some(vec![1.0, i as f32]),
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let rows = store.export_all(false).await.unwrap();
+        let count = |ns: &str| rows.iter().filter(|r| r.namespace == ns).count();
+        assert_eq!(count("telegram_1"), 5);
+        assert_eq!(count("discord_1"), 2);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn pinned_memory_survives_pruning() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let mut store = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        store.max_memories = 3;
+
+        // Low importance would make this the first row evicted.
+        let contact = store
+            .add(
+                "emergency contact: Sam, 555-0100",
+                HashMap::from([("importance".to_string(), Value::from(0.0))]),
+                Some("telegram_1"),
+                Some(vec![1.0, 0.0]),
+            )
+            .await
+            .unwrap();
+        assert!(store
+            .set_pinned(&contact.id, Some("telegram_1"), true)
+            .await
+            .unwrap());
+
+        for i in 0..6 {
+            store
+                .add(
+                    &format!("chatter {i}"),
+                    HashMap::new(),
+                    Some("telegram_1"),
+                    Some(vec![0.0, 1.0]),
+                )
+                .await
+                .unwrap();
+        }
+
+        let rows = store.list("telegram_1", 10).await.unwrap();
+        assert_eq!(rows.len(), 3);
+        let pinned = rows
+            .iter()
+            .find(|r| r.id == contact.id)
+            .expect("pinned row kept");
+        assert!(pinned.is_pinned());
+        assert_eq!(
+            rows.iter()
+                .filter(|r| r.content.starts_with("chatter"))
+                .count(),
+            2
+        );
+
+        assert!(store
+            .set_pinned(&contact.id, Some("telegram_1"), false)
+            .await
+            .unwrap());
+        store
+            .add(
+                "chatter 6",
+                HashMap::new(),
+                Some("telegram_1"),
+                Some(vec![0.0, 1.0]),
+            )
+            .await
+            .unwrap();
+        assert!(store
+            .get(&contact.id, Some("telegram_1"))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!store
+            .set_pinned("missing", Some("telegram_1"), true)
+            .await
+            .unwrap());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn pins_are_capped_at_half_the_namespace() {
+        let root = std::env::temp_dir().join(format!("lightclaw-vectest-{}", Uuid::new_v4()));
+        let mut store = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        store.max_memories = 4;
+        let pinned = || HashMap::from([("pinned".to_string(), Value::Bool(true))]);
+
+        for i in 0..2 {
+            store
+                .add(
+                    &format!("pin {i}"),
+                    pinned(),
+                    Some("telegram_1"),
+                    Some(vec![1.0, 0.0]),
+                )
+                .await
+                .unwrap();
+        }
+        let err = store
+            .add("pin 2", pinned(), Some("telegram_1"), Some(vec![1.0, 0.0]))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("pin limit"), "{err}");
+
+        let plain = store
+            .add(
+                "plain",
+                HashMap::new(),
+                Some("telegram_1"),
+                Some(vec![0.0, 1.0]),
+            )
+            .await
+            .unwrap();
+        assert!(store
+            .set_pinned(&plain.id, Some("telegram_1"), true)
+            .await
+            .is_err());
+        // Other namespaces have their own budget.
+        store
+            .add("pin", pinned(), Some("telegram_2"), Some(vec![1.0, 0.0]))
+            .await
+            .unwrap();
+
+        // Unpinning one makes room again.
+        let first = store
+            .list("telegram_1", 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|r| r.content == "pin 0")
+            .unwrap();
+        store
+            .set_pinned(&first.id, Some("telegram_1"), false)
+            .await
+            .unwrap();
+        assert!(store
+            .set_pinned(&plain.id, Some("telegram_1"), true)
+            .await
+            .unwrap());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn disk_cache_survives_restart() {
+        let body = r#"{"data":[{"embedding":[0.25,0.5,0.75]}]}"#;
+        let (addr, _) = crate::test_http::serve(vec![crate::test_http::json(body)]).await;
+        let client = |base: String| {
+            LlmClient::new("test-key".to_string(), base, None, None, Vec::new()).unwrap()
+        };
+        let root = std::env::temp_dir().join(format!("lightclaw-embcache-{}", Uuid::new_v4()));
+        let path = root.join("embedding_cache.db");
+
+        let first = EmbeddingService::new(client(format!("http://{addr}/v1")), "m".to_string())
+            .with_disk_cache(path.clone())
+            .unwrap();
+        assert_eq!(
+            first.embed("daily standup").await.unwrap(),
+            vec![0.25, 0.5, 0.75]
+        );
+        drop(first);
+
+        // After a "restart" the endpoint is unreachable, so only the disk
+        // cache can answer.
+        let offline = || client("http://127.0.0.1:9".to_string());
+        let restarted = EmbeddingService::new(offline(), "m".to_string())
+            .with_disk_cache(path.clone())
+            .unwrap();
+        assert_eq!(
+            restarted.embed("daily standup").await.unwrap(),
+            vec![0.25, 0.5, 0.75]
+        );
+
+        // Entries are keyed by model as well as text.
+        let other_model = EmbeddingService::new(offline(), "other".to_string())
+            .with_disk_cache(path)
+            .unwrap();
+        assert!(other_model.embed("daily standup").await.is_err());
+
+        let _ = std::fs::remove_dir_all(root);
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,254 @@
+use crate::config::AppConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Turns kept for percentile calculations.
+const WINDOW: usize = 500;
+/// Turns finishing within this long of each other share one summary write.
+const FLUSH_DELAY: Duration = Duration::from_secs(1);
+
+/// Rolling window of per-turn latency and tool-call counts.
+#[derive(Debug, Default)]
+pub struct TurnHistogram {
+    samples: VecDeque<TurnSample>,
+    total_turns: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TurnSample {
+    latency_ms: u64,
+    tool_calls: usize,
+}
+
+/// Aggregates written to disk for `lightclaw stats`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSummary {
+    pub total_turns: u64,
+    pub window_turns: usize,
+    pub p50_latency_ms: u64,
+    pub p95_latency_ms: u64,
+    pub max_latency_ms: u64,
+    pub avg_tool_calls: f64,
+    pub max_tool_calls: usize,
+    pub updated_at: String,
+}
+
+impl TurnHistogram {
+    pub fn record(&mut self, latency_ms: u64, tool_calls: usize) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(TurnSample {
+            latency_ms,
+            tool_calls,
+        });
+        self.total_turns += 1;
+    }
+
+    pub fn summary(&self) -> MetricsSummary {
+        let mut latencies: Vec<u64> = self.samples.iter().map(|s| s.latency_ms).collect();
+        latencies.sort_unstable();
+        let tool_calls: usize = self.samples.iter().map(|s| s.tool_calls).sum();
+        MetricsSummary {
+            total_turns: self.total_turns,
+            window_turns: self.samples.len(),
+            p50_latency_ms: percentile(&latencies, 50),
+            p95_latency_ms: percentile(&latencies, 95),
+            max_latency_ms: latencies.last().copied().unwrap_or(0),
+            avg_tool_calls: if self.samples.is_empty() {
+                0.0
+            } else {
+                tool_calls as f64 / self.samples.len() as f64
+            },
+            max_tool_calls: self.samples.iter().map(|s| s.tool_calls).max().unwrap_or(0),
+            updated_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Nearest-rank percentile over an ascending slice.
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+/// Shared recorder used by the agent loop; persists a summary shortly after
+/// each burst of turns so the `stats` command can read it from another
+/// process.
+#[derive(Clone)]
+pub struct TurnMetrics {
+    histogram: Arc<Mutex<TurnHistogram>>,
+    path: PathBuf,
+    /// Newest summary not yet written to disk.
+    pending: Arc<Mutex<Option<MetricsSummary>>>,
+    flush_scheduled: Arc<AtomicBool>,
+    /// Serializes writers so two flushes never share the temp file.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl TurnMetrics {
+    pub fn new(cfg: &AppConfig) -> Self {
+        Self {
+            histogram: Arc::new(Mutex::new(TurnHistogram::default())),
+            path: metrics_path(cfg),
+            pending: Arc::new(Mutex::new(None)),
+            flush_scheduled: Arc::new(AtomicBool::new(false)),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn record(&self, session_key: &str, latency_ms: u64, tool_calls: usize) {
+        info!(
+            "turn metrics: session={session_key} latency_ms={latency_ms} tool_calls={tool_calls}"
+        );
+        let summary = match self.histogram.lock() {
+            Ok(mut histogram) => {
+                histogram.record(latency_ms, tool_calls);
+                histogram.summary()
+            }
+            Err(_) => return,
+        };
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(summary);
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            self.flush();
+            return;
+        };
+        if self.flush_scheduled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let this = self.clone();
+        runtime.spawn(async move {
+            tokio::time::sleep(FLUSH_DELAY).await;
+            this.flush_scheduled.store(false, Ordering::Release);
+            let _ = tokio::task::spawn_blocking(move || this.flush()).await;
+        });
+    }
+
+    /// Write the pending summary, if any. Blocking; runs off the async
+    /// workers.
+    fn flush(&self) {
+        let Some(summary) = self.pending.lock().ok().and_then(|mut p| p.take()) else {
+            return;
+        };
+        let _guard = self.write_lock.lock();
+        if let Err(err) = write_summary(&self.path, &summary) {
+            warn!("failed to persist turn metrics: {err}");
+        }
+    }
+}
+
+fn metrics_path(cfg: &AppConfig) -> PathBuf {
+    cfg.data_dir.join("metrics.json")
+}
+
+fn write_summary(path: &Path, summary: &MetricsSummary) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(summary)?)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
+
+/// Print the latest summary written by a running agent.
+pub fn print_stats() -> Result<()> {
+    let cfg = AppConfig::load_relaxed();
+    let path = metrics_path(&cfg);
+    let raw = std::fs::read_to_string(&path).map_err(|_| {
+        anyhow!(
+            "no metrics recorded yet at {} (start lightclaw and send a message first)",
+            path.display()
+        )
+    })?;
+    let summary: MetricsSummary = serde_json::from_str(&raw)?;
+    println!("Turns (total):        {}", summary.total_turns);
+    println!("Turns (window):       {}", summary.window_turns);
+    println!("Latency p50:          {} ms", summary.p50_latency_ms);
+    println!("Latency p95:          {} ms", summary.p95_latency_ms);
+    println!("Latency max:          {} ms", summary.max_latency_ms);
+    println!(
+        "Tool calls per turn:  {:.2} avg, {} max",
+        summary.avg_tool_calls, summary.max_tool_calls
+    );
+    println!("Updated:              {}", summary.updated_at);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_reports_nearest_rank_percentiles() {
+        let mut histogram = TurnHistogram::default();
+        for ms in 1..=100 {
+            histogram.record(ms * 10, (ms % 4) as usize);
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.total_turns, 100);
+        assert_eq!(summary.p50_latency_ms, 500);
+        assert_eq!(summary.p95_latency_ms, 950);
+        assert_eq!(summary.max_latency_ms, 1000);
+        assert_eq!(summary.max_tool_calls, 3);
+        assert!((summary.avg_tool_calls - 1.5).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn bursts_of_turns_share_one_deferred_write() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-metrics-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir = dir.clone();
+        let metrics = TurnMetrics::new(&cfg);
+        let path = metrics_path(&cfg);
+
+        metrics.record("s", 100, 1);
+        metrics.record("s", 300, 2);
+        assert!(!path.exists());
+
+        tokio::time::sleep(FLUSH_DELAY * 2).await;
+        // The write itself runs on the blocking pool, outside paused time.
+        for _ in 0..100 {
+            if path.exists() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            tokio::task::yield_now().await;
+        }
+        let summary: MetricsSummary =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(summary.total_turns, 2);
+        assert_eq!(summary.max_latency_ms, 300);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn histogram_keeps_a_rolling_window() {
+        let mut histogram = TurnHistogram::default();
+        for _ in 0..WINDOW {
+            histogram.record(10_000, 0);
+        }
+        for _ in 0..WINDOW {
+            histogram.record(20, 1);
+        }
+        let summary = histogram.summary();
+        assert_eq!(summary.total_turns, (WINDOW * 2) as u64);
+        assert_eq!(summary.window_turns, WINDOW);
+        assert_eq!(summary.p95_latency_ms, 20);
+
+        assert_eq!(TurnHistogram::default().summary().p50_latency_ms, 0);
+        assert_eq!(percentile(&[7], 95), 7);
+    }
+}
@@ -0,0 +1,270 @@
+use crate::config::PluginsConfig;
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::warn;
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Instructions (roughly) a module may run per message before it is cut off.
+const FUEL_PER_CALL: u64 = 50_000_000;
+/// Linear memory a module may hold while handling one message.
+const MAX_MEMORY_BYTES: usize = 16 * 1024 * 1024;
+
+/// Which way a message is travelling, passed to the module as `direction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Direction {
+    Inbound = 0,
+    Outbound = 1,
+}
+
+/// A WebAssembly module that rewrites message text, from `plugins.wasm`.
+///
+/// The module may import nothing, so it has no host access beyond the text
+/// it is handed, and must export:
+///
+/// - `memory`;
+/// - `alloc(len: i32) -> i32`, returning where to write `len` bytes of input;
+/// - `transform(ptr: i32, len: i32, direction: i32) -> i64`, given the UTF-8
+///   text at `ptr` and returning the result as `(ptr << 32) | len`.
+///
+/// Each message gets a fresh instance with `FUEL_PER_CALL` of fuel and at
+/// most `MAX_MEMORY_BYTES` of memory. Any failure leaves the text as it was.
+pub(crate) struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmPlugin {
+    /// The configured plugin, or `None` when there is none or it can't be
+    /// loaded; messages then pass through untouched.
+    pub(crate) fn from_config(cfg: &PluginsConfig) -> Option<Self> {
+        let path = cfg.wasm.as_deref()?;
+        match Self::load(path) {
+            Ok(plugin) => Some(plugin),
+            Err(err) => {
+                warn!("plugins.wasm: can't load {}: {err:#}", path.display());
+                None
+            }
+        }
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::from_file(&engine, path)?;
+        if let Some(import) = module.imports().next() {
+            return Err(anyhow!(
+                "imports {}::{}, but plugins get no host functions",
+                import.module(),
+                import.name()
+            ));
+        }
+        for export in ["memory", "alloc", "transform"] {
+            if module.get_export(export).is_none() {
+                return Err(anyhow!("missing export `{export}`"));
+            }
+        }
+        Ok(Self { engine, module })
+    }
+
+    /// `apply` on the blocking pool, so a module using its whole fuel budget
+    /// doesn't stall the async runtime.
+    pub(crate) async fn transform(self: &Arc<Self>, direction: Direction, text: String) -> String {
+        let plugin = self.clone();
+        let input = text.clone();
+        match tokio::task::spawn_blocking(move || plugin.apply(direction, input)).await {
+            Ok(out) => out,
+            Err(err) => {
+                warn!(
+                    "wasm plugin task failed on {direction:?} message, passing it through: {err}"
+                );
+                text
+            }
+        }
+    }
+
+    /// `text` as the module rewrites it, or unchanged if the module fails.
+    pub(crate) fn apply(&self, direction: Direction, text: String) -> String {
+        match self.run(direction, &text) {
+            Ok(out) => out,
+            Err(err) => {
+                warn!("wasm plugin failed on {direction:?} message, passing it through: {err:#}");
+                text
+            }
+        }
+    }
+
+    fn run(&self, direction: Direction, text: &str) -> Result<String> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL_PER_CALL)?;
+
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("`memory` is not a memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let transform = instance.get_typed_func::<(i32, i32, i32), i64>(&mut store, "transform")?;
+
+        let len = i32::try_from(text.len())?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, text.as_bytes())?;
+        let packed = transform.call(&mut store, (ptr, len, direction as i32))? as u64;
+
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut out = vec![0; out_len];
+        memory.read(&store, out_ptr, &mut out)?;
+        Ok(String::from_utf8(out)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{
+        Attachment, InboundMessage, MessageBus, OutboundAction, OutboundMessage, OutboundSource,
+    };
+
+    /// Uppercases ASCII letters in place.
+    const UPPERCASE: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $top (mut i32) (i32.const 1024))
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $top))
+            (global.set $top (i32.add (global.get $top) (local.get $len)))
+            (local.get $ptr))
+          (func (export "transform") (param $ptr i32) (param $len i32) (param $dir i32) (result i64)
+            (local $i i32)
+            (local $c i32)
+            (block $done
+              (loop $scan
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $c (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+                (if (i32.and (i32.ge_u (local.get $c) (i32.const 97))
+                             (i32.le_u (local.get $c) (i32.const 122)))
+                  (then
+                    (i32.store8 (i32.add (local.get $ptr) (local.get $i))
+                                (i32.sub (local.get $c) (i32.const 32)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $scan)))
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                    (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    fn plugin_file(dir: &Path, name: &str, wat: &str) -> std::path::PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, wat).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn uppercase_plugin_transforms_both_directions_on_the_bus() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = PluginsConfig {
+            wasm: Some(plugin_file(dir.path(), "upper.wat", UPPERCASE)),
+        };
+        let plugin = WasmPlugin::from_config(&cfg).expect("plugin loads");
+        assert_eq!(
+            plugin.apply(Direction::Inbound, "hello, world".to_string()),
+            "HELLO, WORLD"
+        );
+
+        let bus = MessageBus::new().with_plugin(Some(plugin));
+        let mut outbound = bus.subscribe_outbound();
+        bus.publish_inbound(InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            sender_id: "2".to_string(),
+            content: "remind me at noon".to_string(),
+            message_id: None,
+            mention: None,
+            attachments: Vec::new(),
+        })
+        .await;
+        let (msg, _ack) = bus.consume_inbound().await.unwrap();
+        assert_eq!(msg.content, "REMIND ME AT NOON");
+
+        // Voice notes arrive as transcripts, which are rewritten too.
+        bus.publish_inbound(InboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            sender_id: "2".to_string(),
+            content: String::new(),
+            message_id: None,
+            mention: None,
+            attachments: vec![Attachment::Audio {
+                transcript: "call mom".to_string(),
+            }],
+        })
+        .await;
+        let (msg, _ack) = bus.consume_inbound().await.unwrap();
+        assert_eq!(msg.content, "");
+        assert_eq!(
+            msg.attachments,
+            vec![Attachment::Audio {
+                transcript: "CALL MOM".to_string()
+            }]
+        );
+        assert_eq!(bus.outbound_text("once".to_string()).await, "ONCE");
+
+        bus.publish_outbound(OutboundMessage {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            content: "done".to_string(),
+            reply_to: None,
+            handle: None,
+            action: OutboundAction::Send,
+            source: OutboundSource::Reply,
+            attachments: Vec::new(),
+            mention: None,
+        })
+        .await;
+        assert_eq!(outbound.recv().await.unwrap().content, "DONE");
+    }
+
+    #[test]
+    fn plugins_fail_open_and_get_no_host_access() {
+        let dir = tempfile::tempdir().unwrap();
+        let spin = plugin_file(
+            dir.path(),
+            "spin.wat",
+            r#"(module
+                 (memory (export "memory") 1)
+                 (func (export "alloc") (param i32) (result i32) (i32.const 0))
+                 (func (export "transform") (param i32 i32 i32) (result i64)
+                   (loop $forever (br $forever))
+                   (i64.const 0)))"#,
+        );
+        let plugin = WasmPlugin::load(&spin).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(plugin.apply(Direction::Outbound, "hi".to_string()), "hi");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // 64 MiB of initial memory is over the budget.
+        let greedy = UPPERCASE.replace(
+            r#"(memory (export "memory") 1)"#,
+            r#"(memory (export "memory") 1024)"#,
+        );
+        let greedy = WasmPlugin::load(&plugin_file(dir.path(), "greedy.wat", &greedy)).unwrap();
+        assert_eq!(greedy.apply(Direction::Inbound, "hi".to_string()), "hi");
+
+        let importing = plugin_file(
+            dir.path(),
+            "importing.wat",
+            r#"(module (import "env" "read_file" (func)))"#,
+        );
+        let err = WasmPlugin::load(&importing).err().unwrap();
+        assert!(err.to_string().contains("env::read_file"), "{err}");
+        let missing = PluginsConfig {
+            wasm: Some(dir.path().join("missing.wasm")),
+        };
+        assert!(WasmPlugin::from_config(&missing).is_none());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::config::OutboundConfig;
+use regex::Regex;
+use std::sync::LazyLock;
+use tracing::warn;
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b").unwrap());
+
+/// Phone numbers: `+` followed by at least nine digits (optionally grouped),
+/// or three digit groups split by spaces, dots or dashes, like
+/// `(415) 555-0134`. A bare run of digits such as an order number is left
+/// alone.
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\+\d[\d\s().-]{7,}\d|\(?\b\d{2,4}\)?[\s.-]\d{3,4}[\s.-]\d{3,5}\b").unwrap()
+});
+
+/// One step applied to reply text before it reaches a channel.
+pub(crate) trait PostProcessor: Send + Sync {
+    fn process(&self, text: String) -> String;
+}
+
+/// Appends a fixed line to every reply.
+pub(crate) struct Footer(pub String);
+
+impl PostProcessor for Footer {
+    fn process(&self, text: String) -> String {
+        if self.0.trim().is_empty() {
+            return text;
+        }
+        format!("{}\n\n{}", text.trim_end(), self.0)
+    }
+}
+
+/// Masks email addresses and phone numbers.
+pub(crate) struct RedactPii;
+
+impl PostProcessor for RedactPii {
+    fn process(&self, text: String) -> String {
+        let text = EMAIL.replace_all(&text, "[email]");
+        PHONE.replace_all(&text, "[phone]").into_owned()
+    }
+}
+
+/// Cuts replies longer than the limit (in characters) and marks the cut.
+pub(crate) struct TrimLength(pub usize);
+
+impl PostProcessor for TrimLength {
+    fn process(&self, text: String) -> String {
+        if self.0 == 0 || text.chars().count() <= self.0 {
+            return text;
+        }
+        let kept: String = text.chars().take(self.0.saturating_sub(1)).collect();
+        format!("{}…", kept.trim_end())
+    }
+}
+
+/// Stages from `outbound.post_process`, run in the configured order.
+#[derive(Default)]
+pub(crate) struct Pipeline {
+    stages: Vec<Box<dyn PostProcessor>>,
+}
+
+impl Pipeline {
+    pub(crate) fn from_config(cfg: &OutboundConfig) -> Self {
+        let stages = cfg
+            .post_process
+            .iter()
+            .filter_map(|name| -> Option<Box<dyn PostProcessor>> {
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "footer" => Some(Box::new(Footer(cfg.footer.clone()))),
+                    "redact_pii" | "redact" => Some(Box::new(RedactPii)),
+                    "trim" | "max_length" => Some(Box::new(TrimLength(cfg.max_chars))),
+                    _ => {
+                        warn!("outbound.post_process: unknown stage '{name}', skipping");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { stages }
+    }
+
+    pub(crate) fn apply(&self, text: String) -> String {
+        self.stages
+            .iter()
+            .fold(text, |text, stage| stage.process(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction_masks_emails_and_phone_numbers() {
+        let out = RedactPii.process("Mail ana@example.com or call +1 415-555-0134.".to_string());
+        assert_eq!(out, "Mail [email] or call [phone].");
+        assert_eq!(
+            RedactPii.process("(415) 555-0134 or +442071838750".to_string()),
+            "[phone] or [phone]"
+        );
+        for untouched in [
+            "Order 42 ships",
+            "Order 123456789012 ships",
+            "Due 2024-01-15",
+            "Host 192.168.1.100",
+        ] {
+            assert_eq!(RedactPii.process(untouched.to_string()), untouched);
+        }
+    }
+
+    #[test]
+    fn stages_run_in_configured_order() {
+        let cfg = OutboundConfig {
+            post_process: vec!["redact_pii".into(), "footer".into(), "unknown".into()],
+            footer: "— sent by lightclaw".to_string(),
+            max_chars: 0,
+        };
+        let out = Pipeline::from_config(&cfg).apply("Reach me at ana@example.com\n".to_string());
+        assert_eq!(out, "Reach me at [email]\n\n— sent by lightclaw");
+
+        let trim = TrimLength(6).process("abcdefghij".to_string());
+        assert_eq!(trim, "abcde…");
+    }
+}
@@ -1,5 +1,10 @@
 use rig::providers::openai;
 
+use crate::config::AzureEntry;
+
+/// API version that selects Azure's version-less, OpenAI-compatible v1 surface.
+const AZURE_V1: &str = "v1";
+
 /// Build an OpenAI-compatible client (works for OpenAI and Ollama).
 pub fn build_openai_client(
     api_key: &str,
@@ -28,3 +33,97 @@ pub fn build_openai_client(
         .build()
         .expect("failed to build OpenAI-compatible client")
 }
+
+/// Base URL for Azure OpenAI requests.
+///
+/// Dated API versions use the deployment-scoped path
+/// (`{endpoint}/openai/deployments/{deployment}`) together with
+/// [`azure_query`]; `v1` uses `{endpoint}/openai/v1` where the deployment is
+/// passed as the model name.
+pub fn azure_base_url(entry: &AzureEntry) -> String {
+    let endpoint = entry.endpoint.trim().trim_end_matches('/');
+    if is_azure_v1(entry) {
+        format!("{endpoint}/openai/v1")
+    } else {
+        format!("{endpoint}/openai/deployments/{}", entry.deployment.trim())
+    }
+}
+
+/// `api-version` query parameter required by deployment-scoped Azure URLs.
+pub fn azure_query(entry: &AzureEntry) -> Option<(String, String)> {
+    if is_azure_v1(entry) {
+        None
+    } else {
+        Some((
+            "api-version".to_string(),
+            entry.api_version.trim().to_string(),
+        ))
+    }
+}
+
+/// Azure authenticates with an `api-key` header rather than a bearer token.
+pub fn azure_headers(entry: &AzureEntry) -> Vec<(String, String)> {
+    let mut headers = vec![("api-key".to_string(), entry.api_key.clone())];
+    headers.extend(entry.extra_headers.iter().cloned());
+    headers
+}
+
+/// Build the chat client for Azure routes.
+///
+/// Rig appends endpoint paths to the base URL and cannot carry a query
+/// string, so agent routes always target the v1 surface, which accepts the
+/// deployment name as the model and needs no `api-version`.
+pub fn build_azure_client(entry: &AzureEntry) -> openai::Client {
+    let v1 = AzureEntry {
+        api_version: AZURE_V1.to_string(),
+        ..entry.clone()
+    };
+    build_openai_client(&entry.api_key, &azure_base_url(&v1), &azure_headers(entry))
+}
+
+fn is_azure_v1(entry: &AzureEntry) -> bool {
+    let version = entry.api_version.trim();
+    version.is_empty() || version.eq_ignore_ascii_case(AZURE_V1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(api_version: &str) -> AzureEntry {
+        AzureEntry {
+            api_key: "secret".to_string(),
+            endpoint: "https://contoso.openai.azure.com/".to_string(),
+            deployment: "gpt-4o-prod".to_string(),
+            api_version: api_version.to_string(),
+            extra_headers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn azure_dated_version_uses_deployment_path_and_query() {
+        let entry = entry("2024-10-21");
+        assert_eq!(
+            azure_base_url(&entry),
+            "https://contoso.openai.azure.com/openai/deployments/gpt-4o-prod"
+        );
+        assert_eq!(
+            azure_query(&entry),
+            Some(("api-version".to_string(), "2024-10-21".to_string()))
+        );
+        assert_eq!(
+            azure_headers(&entry),
+            vec![("api-key".to_string(), "secret".to_string())]
+        );
+    }
+
+    #[test]
+    fn azure_v1_uses_versionless_surface() {
+        let entry = entry("v1");
+        assert_eq!(
+            azure_base_url(&entry),
+            "https://contoso.openai.azure.com/openai/v1"
+        );
+        assert_eq!(azure_query(&entry), None);
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{anyhow, Error, Result};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// Bounded exponential backoff for outbound HTTP calls.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first one.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Upper bound for both computed backoff and server-provided `Retry-After`.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    fn delay_for(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        let computed = self.base_delay.saturating_mul(1u32 << retry.min(16));
+        retry_after.unwrap_or(computed).min(self.max_delay)
+    }
+}
+
+/// Outcome of a single attempt.
+pub enum Attempt<T> {
+    Done(T),
+    /// Transient failure; `after` carries a server-requested delay if any.
+    Retry {
+        error: Error,
+        after: Option<Duration>,
+    },
+    Fail(Error),
+}
+
+/// Run `op` until it succeeds, fails permanently, or the policy is exhausted.
+pub async fn run<T, F, Fut>(policy: &RetryPolicy, label: &str, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Attempt<T>>,
+{
+    let attempts = policy.max_retries + 1;
+    let mut retry = 0u32;
+    loop {
+        match op().await {
+            Attempt::Done(value) => return Ok(value),
+            Attempt::Fail(error) => return Err(error),
+            Attempt::Retry { error, after } => {
+                if retry >= policy.max_retries {
+                    return Err(anyhow!(
+                        "{label} failed after {attempts} attempt(s): {error}"
+                    ));
+                }
+                let delay = policy.delay_for(retry, after);
+                warn!(
+                    "{label} attempt {} failed, retrying in {}ms: {error}",
+                    retry + 1,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+        }
+    }
+}
+
+/// Statuses worth retrying: request timeout, rate limiting, and server errors.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// Transport errors worth retrying (timeouts and connection failures).
+pub fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request()
+}
+
+/// Parse `Retry-After` as delta-seconds or an HTTP date.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let raw = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = raw.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(raw).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn retry_after_accepts_seconds_and_caps_delay() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        let after = retry_after(&headers);
+        assert_eq!(after, Some(Duration::from_secs(120)));
+
+        let policy = RetryPolicy::new(3);
+        assert_eq!(policy.delay_for(0, after), Duration::from_secs(30));
+        assert_eq!(policy.delay_for(2, None), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn run_stops_after_max_retries() {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        };
+        let mut calls = 0;
+        let result: Result<()> = run(&policy, "probe", || {
+            calls += 1;
+            async {
+                Attempt::Retry {
+                    error: anyhow!("boom"),
+                    after: None,
+                }
+            }
+        })
+        .await;
+        assert_eq!(calls, 3);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("after 3 attempt(s)"));
+    }
+}
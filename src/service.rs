@@ -8,7 +8,7 @@ use std::env;
 use std::ffi::OsString;
 use std::fs;
 use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 const SERVICE_LABEL: &str = "io.lightclaw.agent";
@@ -57,7 +57,7 @@ pub fn install(scope: Scope) -> Result<()> {
             contents: None,
             username: None,
             working_directory,
-            environment: service_environment(),
+            environment: service_environment()?,
             autostart: true,
             restart_policy: RestartPolicy::OnFailure {
                 delay_secs: Some(5),
@@ -263,21 +263,148 @@ fn service_manager_for(scope: Scope) -> Result<Box<dyn ServiceManager>> {
     Ok(manager)
 }
 
-fn service_environment() -> Option<Vec<(String, String)>> {
-    let mut vars = Vec::new();
-    for key in ["RUST_LOG", "LIGHTCLAW_DATA_DIR", "LIGHTCLAW_WORKSPACE_DIR"] {
-        if let Ok(value) = env::var(key) {
-            vars.push((key.to_string(), value));
+/// Prefixes of lightclaw's own overrides; every variable under them is
+/// forwarded so the service sees the same effective config as the CLI.
+const SERVICE_ENV_PREFIXES: &[&str] = &["LIGHTCLAW_", "RUSTBOT_"];
+
+/// Set in the service definition to the file holding the forwarded
+/// variables, so secrets stay out of the world-readable unit or plist.
+const ENV_FILE_VAR: &str = "LIGHTCLAW_ENV_FILE";
+
+/// Third-party variables the config reads: provider credentials and
+/// endpoints, channel tokens, search keys, and the log filter.
+const SERVICE_ENV_VARS: &[&str] = &[
+    "RUST_LOG",
+    "LLM_PROVIDER",
+    "OPENROUTER_API_KEY",
+    "OPENROUTER_BASE_URL",
+    "OPENROUTER_HTTP_REFERER",
+    "OPENROUTER_APP_TITLE",
+    "OPENAI_API_KEY",
+    "OPENAI_BASE_URL",
+    "OLLAMA_API_KEY",
+    "OLLAMA_BASE_URL",
+    "AZURE_OPENAI_API_KEY",
+    "AZURE_OPENAI_ENDPOINT",
+    "AZURE_OPENAI_DEPLOYMENT",
+    "AZURE_OPENAI_API_VERSION",
+    "MISTRAL_API_KEY",
+    "MISTRAL_BASE_URL",
+    "TELEGRAM_BOT_TOKEN",
+    "TELOXIDE_TOKEN",
+    "DISCORD_BOT_TOKEN",
+    "BRAVE_API_KEY",
+    "FIRECRAWL_API_KEY",
+];
+
+/// Write the forwarded variables to an owner-only file next to the config
+/// and point the service at it; `None` when there is nothing to forward.
+fn service_environment() -> Result<Option<Vec<(String, String)>>> {
+    let vars = service_environment_from(env::vars());
+    if vars.is_empty() {
+        return Ok(None);
+    }
+    let path = env_file_path();
+    write_env_file(&path, &vars)?;
+    let names = vars.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>();
+    println!(
+        "Forwarding environment to the service via {}: {}",
+        path.display(),
+        names.join(", ")
+    );
+    Ok(Some(vec![(
+        ENV_FILE_VAR.to_string(),
+        path.display().to_string(),
+    )]))
+}
+
+fn env_file_path() -> PathBuf {
+    let config = config::config_path();
+    config
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("service.env")
+}
+
+/// `vars` as `KEY=VALUE` lines, readable only by the owner.
+fn write_env_file(path: &Path, vars: &[(String, String)]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    // `mode` only applies to new files; tighten one left by an older install.
+    #[cfg(unix)]
+    fs::set_permissions(path, std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(render_env_file(vars).as_bytes())?;
+    Ok(())
+}
+
+fn render_env_file(vars: &[(String, String)]) -> String {
+    let mut out = String::new();
+    for (key, value) in vars {
+        if value.contains('\n') {
+            eprintln!("warning: not forwarding {key}: multi-line values are unsupported");
+            continue;
         }
+        out.push_str(&format!("{key}={value}\n"));
     }
+    out
+}
 
-    if vars.is_empty() {
-        None
-    } else {
-        Some(vars)
+fn parse_env_file(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .collect()
+}
+
+/// Load the variables `service install` saved when running as the service
+/// (`LIGHTCLAW_ENV_FILE` set). Variables already in the environment win.
+/// Call before anything reads the environment.
+pub fn load_env_file() {
+    let Some(path) = env::var_os(ENV_FILE_VAR).map(PathBuf::from) else {
+        return;
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            for (key, value) in parse_env_file(&text) {
+                if env::var_os(&key).is_none() {
+                    env::set_var(key, value);
+                }
+            }
+        }
+        Err(err) => eprintln!(
+            "warning: failed to read service environment {}: {err}",
+            path.display()
+        ),
     }
 }
 
+/// The subset of `vars` the service is installed with, sorted by name.
+fn service_environment_from(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(key, _)| {
+            key != ENV_FILE_VAR
+                && (SERVICE_ENV_VARS.contains(&key.as_str())
+                    || SERVICE_ENV_PREFIXES
+                        .iter()
+                        .any(|prefix| key.starts_with(prefix)))
+        })
+        .collect();
+    out.sort();
+    out
+}
+
 fn print_last_lines(path: &Path, lines: usize) -> Result<()> {
     if lines == 0 {
         return Ok(());
@@ -327,3 +454,65 @@ fn warn_if_dev_binary(path: &Path) {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_environment_forwards_only_the_allowlist() {
+        let env = [
+            ("LIGHTCLAW_RESTRICT_TO_WORKSPACE", "false"),
+            ("LIGHTCLAW_SAFE_MODE", "true"),
+            ("RUSTBOT_DATA_DIR", "/srv/legacy"),
+            ("OPENROUTER_API_KEY", "sk-or-1"),
+            ("TELEGRAM_BOT_TOKEN", "123:abc"),
+            ("RUST_LOG", "debug"),
+            ("HOME", "/home/me"),
+            ("PATH", "/usr/bin"),
+            ("AWS_SECRET_ACCESS_KEY", "nope"),
+            ("LIGHTCLAWX", "not a prefix match"),
+            ("LIGHTCLAW_ENV_FILE", "/home/me/.lightclaw/service.env"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let forwarded = service_environment_from(env);
+        let names: Vec<&str> = forwarded.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "LIGHTCLAW_RESTRICT_TO_WORKSPACE",
+                "LIGHTCLAW_SAFE_MODE",
+                "OPENROUTER_API_KEY",
+                "RUSTBOT_DATA_DIR",
+                "RUST_LOG",
+                "TELEGRAM_BOT_TOKEN",
+            ]
+        );
+        assert!(forwarded.contains(&(
+            "LIGHTCLAW_RESTRICT_TO_WORKSPACE".to_string(),
+            "false".to_string()
+        )));
+    }
+    #[test]
+    fn forwarded_secrets_go_to_an_owner_only_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("service.env");
+        let vars = [
+            ("OPENROUTER_API_KEY", "sk-or-1=x"),
+            ("LIGHTCLAW_SAFE_MODE", "true"),
+            ("LIGHTCLAW_SYSTEM_PROMPT", "line one\nline two"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        write_env_file(&path, &vars).unwrap();
+
+        let saved = parse_env_file(&fs::read_to_string(&path).unwrap());
+        assert_eq!(saved, vars[..2].to_vec());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -1,6 +1,11 @@
+use dashmap::DashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use tracing::debug;
 
 use crate::memory::smart::client::ChatMessage;
+use crate::tokenizer::estimate_tokens;
+
+const RECALL_HEADER: &str = "[Recalling from earlier in our conversation]";
 
 const FACT_KEYWORDS: &[&str] = &[
     "my name is",
@@ -21,9 +26,18 @@ const FACT_KEYWORDS: &[&str] = &[
     "configured to",
 ];
 
+/// Default token size at which history is compacted.
+pub const DEFAULT_COMPACTION_MAX_TOKENS: usize = 24_000;
+
+/// Message token counts kept between turns; the cache starts over when full.
+const MAX_CACHED_TOKEN_COUNTS: usize = 4096;
+
 #[derive(Clone, Debug)]
 pub struct CompactionConfig {
     pub threshold: usize,
+    /// History is also compacted once it holds this many tokens, however
+    /// few messages that is (`agents.defaults.compaction_max_tokens`).
+    pub max_tokens: usize,
     pub recent_turns_keep: usize,
     pub summary_max_turns: usize,
     pub max_facts: usize,
@@ -33,6 +47,7 @@ impl Default for CompactionConfig {
     fn default() -> Self {
         Self {
             threshold: 50,
+            max_tokens: DEFAULT_COMPACTION_MAX_TOKENS,
             recent_turns_keep: 8,
             summary_max_turns: 15,
             max_facts: 10,
@@ -42,21 +57,63 @@ impl Default for CompactionConfig {
 
 pub struct SessionCompactor {
     pub config: CompactionConfig,
+    /// Token counts by hash of model and message, so the history a session
+    /// carries from turn to turn is encoded once rather than every turn.
+    token_counts: DashMap<u64, usize>,
 }
 
 impl SessionCompactor {
     pub fn new(config: Option<CompactionConfig>) -> Self {
         Self {
             config: config.unwrap_or_default(),
+            token_counts: DashMap::new(),
+        }
+    }
+
+    /// Whether `messages` have outgrown `threshold` or, counted for `model`,
+    /// `max_tokens`.
+    pub fn needs_compaction(&self, messages: &[ChatMessage], model: &str) -> bool {
+        if messages.len() >= self.config.threshold {
+            return true;
+        }
+        let mut total = 0;
+        for message in messages {
+            total += self.message_tokens(&message.content, model);
+            if total > self.config.max_tokens {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn message_tokens(&self, content: &str, model: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        model.hash(&mut hasher);
+        content.hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(tokens) = self.token_counts.get(&key) {
+            return *tokens;
+        }
+        let tokens = estimate_tokens(content, model);
+        if self.token_counts.len() >= MAX_CACHED_TOKEN_COUNTS {
+            self.token_counts.clear();
         }
+        self.token_counts.insert(key, tokens);
+        tokens
     }
 
-    pub fn compact(&self, messages: &[ChatMessage]) -> Vec<ChatMessage> {
-        if messages.len() < self.config.threshold {
+    #[cfg(test)]
+    pub(crate) fn cached_token_counts(&self) -> usize {
+        self.token_counts.len()
+    }
+
+    pub fn compact(&self, messages: &[ChatMessage], model: &str) -> Vec<ChatMessage> {
+        if !self.needs_compaction(messages, model) {
             debug!(
-                "Skipping compaction: {} < {}",
+                "Skipping compaction: {} messages within threshold {} and {} tokens",
                 messages.len(),
-                self.config.threshold
+                self.config.threshold,
+                self.config.max_tokens
             );
             return messages.to_vec();
         }
@@ -90,10 +147,7 @@ impl SessionCompactor {
         }
 
         if !recall_parts.is_empty() {
-            let recall = format!(
-                "[Recalling from earlier in our conversation]\n\n{}",
-                recall_parts.join("\n\n")
-            );
+            let recall = format!("{RECALL_HEADER}\n\n{}", recall_parts.join("\n\n"));
             compacted.push(ChatMessage {
                 role: "assistant".to_string(),
                 content: recall,
@@ -104,6 +158,44 @@ impl SessionCompactor {
         compacted
     }
 
+    /// Collapse every turn except the last `recent_turns_keep` into a single
+    /// recall message, regardless of `threshold`. An earlier recall message at
+    /// the start is folded into the new one. Returns `None` when there is
+    /// nothing old enough to collapse.
+    pub fn collapse_old(&self, messages: &[ChatMessage]) -> Option<Vec<ChatMessage>> {
+        let recent_start = messages
+            .len()
+            .saturating_sub(self.config.recent_turns_keep * 2);
+        let (old, recent) = messages.split_at(recent_start);
+        let (previous, old) = match old.split_first() {
+            Some((first, rest)) if first.content.starts_with(RECALL_HEADER) => {
+                (Some(first.content[RECALL_HEADER.len()..].trim()), rest)
+            }
+            _ => (None, old),
+        };
+        if old.is_empty() {
+            return None;
+        }
+
+        let mut recall_parts: Vec<String> = previous
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+            .into_iter()
+            .collect();
+        let facts = self.extract_facts(old);
+        if !facts.is_empty() {
+            recall_parts.push(format!("Key facts:\n{}", facts));
+        }
+        recall_parts.push(format!("Discussion summary:\n{}", self.summarize(old)));
+
+        let mut compacted = vec![ChatMessage {
+            role: "assistant".to_string(),
+            content: format!("{RECALL_HEADER}\n\n{}", recall_parts.join("\n\n")),
+        }];
+        compacted.extend_from_slice(recent);
+        Some(compacted)
+    }
+
     fn extract_facts(&self, messages: &[ChatMessage]) -> String {
         let facts = extract_facts_from_messages(messages, self.config.max_facts);
         facts
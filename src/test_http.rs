@@ -0,0 +1,115 @@
+//! Local HTTP/1.1 endpoints for tests: canned replies, captured requests
+//! and endpoints that never answer.
+
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// A bound listener on a free local port.
+pub(crate) async fn listen() -> (TcpListener, SocketAddr) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    (listener, addr)
+}
+
+/// Read one request off `sock` and return its body: the headers, then
+/// `Content-Length` bytes, or everything up to the last chunk of a chunked
+/// body (left encoded). `None` if the client hung up before the headers.
+pub(crate) async fn read_request(sock: &mut TcpStream) -> Option<Vec<u8>> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    let body_start = loop {
+        let n = sock.read(&mut buf).await.unwrap_or(0);
+        if n == 0 {
+            return None;
+        }
+        raw.extend_from_slice(&buf[..n]);
+        if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let headers = String::from_utf8_lossy(&raw[..body_start]).to_ascii_lowercase();
+    let length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|len| len.trim().parse::<usize>().ok());
+    loop {
+        let complete = match length {
+            Some(len) => raw.len() >= body_start + len,
+            None => !headers.contains("chunked") || raw.ends_with(b"0\r\n\r\n"),
+        };
+        if complete {
+            break;
+        }
+        let n = sock.read(&mut buf).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        raw.extend_from_slice(&buf[..n]);
+    }
+    Some(raw.split_off(body_start))
+}
+
+/// A full response with `status` (e.g. `"200 OK"`), `headers` and `body`.
+/// The connection is closed after it.
+pub(crate) fn response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut head = format!("HTTP/1.1 {status}\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+    let mut out = head.into_bytes();
+    out.extend_from_slice(body);
+    out
+}
+
+/// A `200 OK` JSON response.
+pub(crate) fn json(body: &str) -> Vec<u8> {
+    response(
+        "200 OK",
+        &[("Content-Type", "application/json")],
+        body.as_bytes(),
+    )
+}
+
+/// Answer one request per connection with `replies` in order, repeating the
+/// last one once they run out. Each request body is sent on the returned
+/// channel.
+pub(crate) async fn serve(replies: Vec<Vec<u8>>) -> (SocketAddr, mpsc::UnboundedReceiver<Vec<u8>>) {
+    let (listener, addr) = listen().await;
+    let (body_tx, body_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut replies = replies.into_iter();
+        let mut last = Vec::new();
+        while let Ok((mut sock, _)) = listener.accept().await {
+            let Some(body) = read_request(&mut sock).await else {
+                continue;
+            };
+            let _ = body_tx.send(body);
+            if let Some(next) = replies.next() {
+                last = next;
+            }
+            let _ = sock.write_all(&last).await;
+            let _ = sock.shutdown().await;
+        }
+    });
+    (addr, body_rx)
+}
+
+/// An endpoint that accepts connections and never answers. Abort the handle
+/// to close them.
+pub(crate) async fn hang() -> (SocketAddr, JoinHandle<()>) {
+    let (listener, addr) = listen().await;
+    let handle = tokio::spawn(async move {
+        let mut held = Vec::new();
+        while let Ok((sock, _)) = listener.accept().await {
+            held.push(sock);
+        }
+    });
+    (addr, handle)
+}
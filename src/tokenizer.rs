@@ -0,0 +1,205 @@
+use dashmap::DashMap;
+use std::sync::{Arc, LazyLock};
+use tiktoken_rs::CoreBPE;
+
+/// BPE encoders by model name; `None` for models tiktoken doesn't know.
+static ENCODERS: LazyLock<DashMap<String, Option<Arc<CoreBPE>>>> = LazyLock::new(DashMap::new);
+
+/// Tokens `text` takes up for `model`: exact for OpenAI-family models,
+/// which tiktoken knows, and `heuristic_tokens` for the rest.
+pub(crate) fn estimate_tokens(text: &str, model: &str) -> usize {
+    match encoder(model) {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => heuristic_tokens(text),
+    }
+}
+
+/// The start of `text` that fits in `max_tokens` for `model`, or `None`
+/// when all of it already does.
+pub(crate) fn truncate_to_tokens(text: &str, max_tokens: usize, model: &str) -> Option<String> {
+    let Some(bpe) = encoder(model) else {
+        return truncate_heuristic(text, max_tokens);
+    };
+    let tokens = bpe.encode_ordinary(text);
+    if tokens.len() <= max_tokens {
+        return None;
+    }
+    // A cut can land inside a multi-byte character; back off until the
+    // kept tokens decode.
+    let mut keep = max_tokens;
+    loop {
+        if let Ok(cut) = bpe.decode(tokens[..keep].to_vec()) {
+            return Some(cut);
+        }
+        if keep == 0 {
+            return Some(String::new());
+        }
+        keep -= 1;
+    }
+}
+
+/// The encoder for `model`, ignoring a `provider/` prefix, built once per
+/// model.
+fn encoder(model: &str) -> Option<Arc<CoreBPE>> {
+    let name = model.trim().rsplit('/').next().unwrap_or_default();
+    if let Some(cached) = ENCODERS.get(name) {
+        return cached.clone();
+    }
+    ENCODERS
+        .entry(name.to_string())
+        .or_insert_with(|| tiktoken_rs::get_bpe_from_model(name).ok().map(Arc::new))
+        .clone()
+}
+
+/// Token estimates for models without a known tokenizer.
+///
+/// A flat characters-per-token ratio is close for English prose but badly
+/// undercounts code, where most symbols are tokens of their own, and
+/// non-Latin scripts, where a single character is often one or more tokens.
+/// Text is therefore counted in runs:
+///
+/// - letters and digits: one token per four characters of each word;
+/// - ASCII punctuation and symbols: one token each;
+/// - CJK, kana and Hangul: one token per character;
+/// - other non-ASCII letters (accented Latin, Cyrillic, Greek, ...): one token
+///   per two characters of each word.
+///
+/// Whitespace is folded into the token that follows it.
+fn heuristic_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut run = Run::None;
+    let mut run_len = 0;
+    for ch in text.chars() {
+        let class = Run::of(ch);
+        if class != run {
+            tokens += run.tokens(run_len);
+            run = class;
+            run_len = 0;
+        }
+        run_len += 1;
+    }
+    tokens + run.tokens(run_len)
+}
+
+/// `truncate_to_tokens` by `heuristic_tokens`.
+fn truncate_heuristic(text: &str, max_tokens: usize) -> Option<String> {
+    let total = heuristic_tokens(text);
+    if total <= max_tokens {
+        return None;
+    }
+    let chars = text.chars().count();
+    let mut keep = chars * max_tokens / total;
+    loop {
+        let cut: String = text.chars().take(keep).collect();
+        if keep == 0 || heuristic_tokens(&cut) <= max_tokens {
+            return Some(cut);
+        }
+        keep = keep * 9 / 10;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Run {
+    None,
+    Space,
+    Word,
+    Symbol,
+    Ideograph,
+    OtherWord,
+}
+
+impl Run {
+    fn of(ch: char) -> Self {
+        if ch.is_whitespace() {
+            Self::Space
+        } else if ch.is_ascii_alphanumeric() || ch == '_' {
+            Self::Word
+        } else if ch.is_ascii() {
+            Self::Symbol
+        } else if is_ideographic(ch) {
+            Self::Ideograph
+        } else if ch.is_alphanumeric() {
+            Self::OtherWord
+        } else {
+            Self::Symbol
+        }
+    }
+
+    fn tokens(self, len: usize) -> usize {
+        match self {
+            Self::None | Self::Space => 0,
+            Self::Word => len.div_ceil(4),
+            Self::Symbol | Self::Ideograph => len,
+            Self::OtherWord => len.div_ceil(2),
+        }
+    }
+}
+
+/// CJK ideographs, kana and Hangul: scripts without spaces between words.
+fn is_ideographic(ch: char) -> bool {
+    matches!(
+        ch as u32,
+        0x3040..=0x30FF // Hiragana, Katakana
+            | 0x3400..=0x4DBF // CJK Extension A
+            | 0x4E00..=0x9FFF // CJK Unified Ideographs
+            | 0xAC00..=0xD7AF // Hangul syllables
+            | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+            | 0x20000..=0x2FA1F // CJK Extensions B-F and supplement
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The flat estimate this module replaces.
+    fn by_chars(text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+
+    #[test]
+    fn code_and_cjk_count_higher_than_the_flat_ratio() {
+        let prose = "The quick brown fox jumps over the lazy dog near the riverbank.";
+        let estimate = heuristic_tokens(prose);
+        assert!(estimate.abs_diff(by_chars(prose)) <= 4, "{estimate}");
+
+        let code = "fn main() { let v: Vec<u8> = (0..10).map(|i| i * 2).collect(); }";
+        assert!(heuristic_tokens(code) > by_chars(code) * 3 / 2);
+
+        let cjk = "今日はいい天気ですね。散歩に行きましょう。";
+        assert!(heuristic_tokens(cjk) >= cjk.chars().count() - 2);
+        assert!(heuristic_tokens(cjk) > by_chars(cjk) * 3);
+
+        assert_eq!(heuristic_tokens(""), 0);
+        assert_eq!(heuristic_tokens("   \n"), 0);
+    }
+
+    #[test]
+    fn openai_models_are_counted_by_tiktoken_and_others_by_the_heuristic() {
+        let text = "Hello, world!";
+        // o200k/cl100k: "Hello" "," " world" "!".
+        assert_eq!(estimate_tokens(text, "gpt-4o"), 4);
+        assert_eq!(estimate_tokens(text, "openai/gpt-4o-mini"), 4);
+        assert_eq!(estimate_tokens(text, "gpt-3.5-turbo"), 4);
+        assert_eq!(heuristic_tokens(text), 6);
+        assert_eq!(estimate_tokens(text, "llama3.1:8b"), 6);
+        assert_eq!(estimate_tokens(text, "anthropic/claude-sonnet-4"), 6);
+        // The second lookup comes from the cache.
+        assert!(encoder("gpt-4o").is_some());
+        assert!(ENCODERS.get("gpt-4o").is_some_and(|bpe| bpe.is_some()));
+        assert!(ENCODERS.get("llama3.1:8b").is_some_and(|bpe| bpe.is_none()));
+    }
+
+    #[test]
+    fn truncation_fits_the_budget() {
+        let code = "a[i] = b[j] + c[k];\n".repeat(50);
+        for model in ["llama3.1:8b", "gpt-4o"] {
+            assert_eq!(truncate_to_tokens(&code, 10_000, model), None);
+            let cut = truncate_to_tokens(&code, 100, model).unwrap();
+            let tokens = estimate_tokens(&cut, model);
+            assert!(tokens <= 100, "{model}: {tokens}");
+            assert!(tokens > 80, "{model}: {tokens}");
+            assert!(code.starts_with(&cut), "{model}");
+        }
+    }
+}
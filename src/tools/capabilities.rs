@@ -0,0 +1,167 @@
+use crate::config::{AppConfig, MemoryMode, ModelRoute};
+use crate::tools::context;
+use crate::tools::ToolError;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Reports what this agent can actually do right now, built from the live
+/// tool registry and config rather than the preamble.
+#[derive(Clone)]
+pub struct CapabilitiesTool {
+    tools: Vec<String>,
+    memory_mode: MemoryMode,
+    shared_namespace: Option<String>,
+    routes: Vec<String>,
+    route: Option<String>,
+    max_tool_turns: usize,
+    exec_timeout_secs: u64,
+    safe_mode: bool,
+}
+
+impl CapabilitiesTool {
+    /// `tools` are the names registered on the agent, sorted for stable output.
+    pub fn new(cfg: &AppConfig, mut tools: Vec<String>) -> Self {
+        tools.sort();
+        Self {
+            tools,
+            memory_mode: cfg.memory.mode.clone(),
+            shared_namespace: cfg.memory.shared_namespace.clone(),
+            routes: cfg.model_routes().iter().map(route_name).collect(),
+            route: None,
+            max_tool_turns: cfg.model.max_tool_turns,
+            exec_timeout_secs: cfg.tools.exec_timeout_secs,
+            safe_mode: cfg.tools.safe_mode,
+        }
+    }
+
+    /// Copy for the agent serving `route`, which has its own tool-turn cap.
+    pub fn for_route(&self, route: &ModelRoute, max_tool_turns: usize) -> Self {
+        Self {
+            route: Some(route_name(route)),
+            max_tool_turns,
+            ..self.clone()
+        }
+    }
+
+    fn report(&self) -> serde_json::Value {
+        let namespace = context::current().map(|ctx| ctx.namespace);
+        let memory = match self.memory_mode {
+            MemoryMode::None => json!({ "mode": self.memory_mode }),
+            MemoryMode::Simple => json!({
+                "mode": self.memory_mode,
+                "files": ["MEMORY.md", "memory/YYYY-MM-DD.md"],
+            }),
+            MemoryMode::Smart => json!({
+                "mode": self.memory_mode,
+                "files": ["MEMORY.md", "memory/YYYY-MM-DD.md"],
+                "namespace": namespace,
+                "namespace_convention": "one vector namespace per session (channel:chat_id), applied automatically; omit namespace",
+                "shared_namespace": self.shared_namespace,
+            }),
+        };
+        json!({
+            "tools": self.tools,
+            "memory": memory,
+            "model": {
+                "route": self.route,
+                "fallback_order": self.routes,
+            },
+            "limits": {
+                "max_tool_turns": self.max_tool_turns,
+                "exec_timeout_secs": self.exec_timeout_secs,
+                "safe_mode": self.safe_mode,
+            },
+        })
+    }
+}
+
+fn route_name(route: &ModelRoute) -> String {
+    format!("{}/{}", route.provider.as_str(), route.model)
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct CapabilitiesArgs {}
+
+impl Tool for CapabilitiesTool {
+    const NAME: &'static str = "capabilities";
+    type Args = CapabilitiesArgs;
+    type Output = String;
+    type Error = ToolError;
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        async {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "List the tools enabled right now, the active memory mode and namespace, the current model route, and limits such as max_tool_turns. Check this instead of assuming a tool exists.".to_string(),
+                parameters: serde_json::to_value(schemars::schema_for!(CapabilitiesArgs)).unwrap(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        _args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let report = self.report();
+        async move { serde_json::to_string_pretty(&report).map_err(|e| ToolError::msg(e.to_string())) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+    use crate::cron::CronService;
+    use crate::memory::simple::file_store::MemoryStore;
+    use crate::tools::ToolRegistry;
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn capabilities_reflect_disabled_tools() {
+        let workspace =
+            std::env::temp_dir().join(format!("lightclaw-caps-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.workspace_dir = workspace.clone();
+        cfg.data_dir = workspace.join("data");
+        cfg.tools.disabled = vec!["exec".to_string()];
+        let bus = MessageBus::new();
+        let registry = ToolRegistry::new(
+            cfg.clone(),
+            CronService::new(&cfg, bus.clone()),
+            bus,
+            MemoryStore::new(workspace.clone()),
+            None,
+        );
+
+        let route = ModelRoute {
+            provider: cfg.provider.clone(),
+            model: cfg.model.model.clone(),
+        };
+        let tool = registry.capabilities.for_route(&route, 7);
+        let raw = tool.call(CapabilitiesArgs {}).await.unwrap();
+        let report: Value = serde_json::from_str(&raw).unwrap();
+
+        let tools: Vec<&str> = report["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(Value::as_str)
+            .collect();
+        assert!(!tools.contains(&"exec"));
+        assert!(tools.contains(&"read_file"));
+        assert!(tools.contains(&"capabilities"));
+        assert_eq!(report["memory"]["mode"], "simple");
+        assert_eq!(
+            report["model"]["route"],
+            format!("openrouter/{}", cfg.model.model)
+        );
+        assert_eq!(report["limits"]["max_tool_turns"], 7);
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::bus::MessageBus;
+use crate::config::{AppConfig, MemoryMode, ModelRoute};
+use crate::cron::CronService;
+use crate::memory::simple::file_store::MemoryStore;
+use crate::tools::ToolRegistry;
+use anyhow::Result;
+use clap::Subcommand;
+use serde_json::Value;
+use tracing::warn;
+
+#[derive(Subcommand, Debug)]
+pub enum ToolsCommands {
+    /// Print the JSON definition (name, description, parameters) of every
+    /// tool the agent is given under the current config
+    Schema,
+}
+
+pub async fn handle_tools(command: ToolsCommands) -> Result<()> {
+    // Listing tools needs no provider credentials.
+    let cfg = AppConfig::load_relaxed();
+
+    match command {
+        ToolsCommands::Schema => {
+            let vector_store = if cfg.memory.mode == MemoryMode::Smart {
+                crate::memory::cli::open_vector_store(&cfg)
+                    .map_err(|err| warn!("vector memory unavailable: {err}"))
+                    .ok()
+            } else {
+                None
+            };
+            let bus = MessageBus::new();
+            let registry = ToolRegistry::new(
+                cfg.clone(),
+                CronService::new(&cfg, bus.clone()),
+                bus,
+                MemoryStore::new(cfg.workspace_dir.clone()),
+                vector_store,
+            );
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&schema(&cfg, &registry).await)?
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The tool definitions the primary model's agent gets, as a JSON array.
+async fn schema(cfg: &AppConfig, registry: &ToolRegistry) -> Value {
+    let route = ModelRoute {
+        provider: cfg.provider.clone(),
+        model: cfg.model.model.clone(),
+    };
+    let definitions = registry
+        .definitions(&route, cfg.max_tool_turns_for(&route))
+        .await;
+    serde_json::to_value(definitions).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn schema_lists_enabled_tools_with_their_parameters() {
+        let root = std::env::temp_dir().join(format!("lightclaw-schema-{}", Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.memory.mode = MemoryMode::None;
+        cfg.workspace_dir = root.join("workspace");
+        cfg.data_dir = root.join("data");
+        cfg.tools.disabled = vec!["exec".to_string(), "write_file".to_string()];
+        let bus = MessageBus::new();
+        let registry = ToolRegistry::new(
+            cfg.clone(),
+            CronService::new(&cfg, bus.clone()),
+            bus,
+            MemoryStore::new(cfg.workspace_dir.clone()),
+            None,
+        );
+
+        let schema = schema(&cfg, &registry).await;
+        let tools = schema.as_array().unwrap();
+        let names: Vec<&str> = tools.iter().filter_map(|t| t["name"].as_str()).collect();
+        assert!(!names.contains(&"exec"));
+        assert!(!names.contains(&"write_file"));
+        // No memory: `remember` is never registered.
+        assert!(!names.contains(&"remember"));
+        assert!(names.contains(&"read_file"));
+
+        let web_search = tools.iter().find(|t| t["name"] == "web_search").unwrap();
+        assert!(!web_search["description"].as_str().unwrap().is_empty());
+        let props = &web_search["parameters"]["properties"];
+        assert!(props.get("query").is_some(), "{web_search}");
+        assert!(props.get("count").is_some(), "{web_search}");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::bus::{ProgressEvent, ProgressKind};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+tokio::task_local! {
+    static TOOL_CONTEXT: ToolContext;
+}
+
+/// Authoritative session details for the turn that is currently executing.
+///
+/// The agent loop sets this around each completion so tools can scope their
+/// effects to the originating session instead of trusting model-supplied ids.
+#[derive(Clone, Debug, Default)]
+pub struct ToolContext {
+    pub channel: String,
+    pub chat_id: String,
+    pub sender_id: String,
+    /// User that notifications sent this turn should @-mention.
+    pub mention: Option<String>,
+    /// Vector memory namespace derived from the session key.
+    pub namespace: String,
+    /// Extra namespace every session may read and write, when enabled.
+    pub shared_namespace: Option<String>,
+    /// Where tool invocations are announced for channel progress indicators.
+    pub progress: Option<broadcast::Sender<ProgressEvent>>,
+    /// Tool invocations made during this turn, read back for turn metrics.
+    pub tool_calls: Arc<AtomicUsize>,
+}
+
+/// Run `fut` with `ctx` visible to every tool call it makes.
+pub async fn scope<F: Future>(ctx: ToolContext, fut: F) -> F::Output {
+    TOOL_CONTEXT.scope(ctx, fut).await
+}
+
+/// Context of the current turn, or `None` outside an agent turn (CLI, tests).
+pub fn current() -> Option<ToolContext> {
+    TOOL_CONTEXT.try_with(Clone::clone).ok()
+}
+
+/// Count a tool invocation for the current turn and announce it to listeners.
+pub fn report_tool_call(name: &str) {
+    let Some(ctx) = current() else {
+        return;
+    };
+    ctx.tool_calls.fetch_add(1, Ordering::Relaxed);
+    if let Some(progress) = &ctx.progress {
+        let _ = progress.send(ProgressEvent {
+            channel: ctx.channel.clone(),
+            chat_id: ctx.chat_id.clone(),
+            sender_id: ctx.sender_id.clone(),
+            kind: ProgressKind::ToolCall {
+                name: name.to_string(),
+            },
+        });
+    }
+}
@@ -1,4 +1,7 @@
+use crate::config::ChannelsConfig;
+use crate::cron::types::CronJob;
 use crate::cron::CronService;
+use crate::tools::context;
 use crate::tools::ToolError;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
@@ -7,17 +10,19 @@ use serde::Deserialize;
 #[derive(Clone)]
 pub struct CronTool {
     service: CronService,
+    /// For showing next runs on the chat's wall clock.
+    channels: ChannelsConfig,
 }
 
 impl CronTool {
-    pub fn new(service: CronService) -> Self {
-        Self { service }
+    pub fn new(service: CronService, channels: ChannelsConfig) -> Self {
+        Self { service, channels }
     }
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
 pub struct CronArgs {
-    /// One of: add, list, remove, status
+    /// One of: add, list, remove (or cancel), status
     pub action: String,
     /// Job name (required for add)
     pub name: Option<String>,
@@ -29,8 +34,23 @@ pub struct CronArgs {
     pub channel: Option<String>,
     /// Delivery target for add (e.g. Telegram chat id)
     pub to: Option<String>,
-    /// Job id (required for remove)
+    /// Job id (required for remove/cancel)
     pub id: Option<String>,
+    /// For add: @-mention the requesting user when the job sends a notification
+    #[serde(default)]
+    pub mention_user: bool,
+    /// For add: make this a check-in that only fires when the chat has been quiet for this many minutes
+    pub idle_minutes: Option<u64>,
+}
+
+impl CronArgs {
+    /// `list` and `status` only read, so they still run in safe mode.
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self.action.trim().to_lowercase().as_str(),
+            "list" | "status"
+        )
+    }
 }
 
 impl Tool for CronTool {
@@ -46,7 +66,7 @@ impl Tool for CronTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Manage scheduled tasks. Use action=add for new schedules, list to inspect jobs, remove to delete by id, status for scheduler summary. For add: use schedule as cron expression (e.g. '0 9 * * *'), seconds interval (e.g. '14400' for every 4h), or @-style cron. The message field is the inbound text injected when the job fires. Set channel/to to route the cron turn to a destination context (typically current channel/chat), then use send_message if that turn should notify the user.".to_string(),
+                description: "Manage scheduled tasks. Use action=add for new schedules, list to show this chat's jobs and reminders with their next run, remove (or cancel) to delete one of them by id, status for scheduler summary. For add: use schedule as cron expression (e.g. '0 9 * * *'), seconds interval (e.g. '14400' for every 4h), or @-style cron. The message field is the inbound text injected when the job fires. channel/to route the cron turn to a destination context and default to the current chat; use send_message if that turn should notify the user. Set mention_user=true to ping the requesting user in that notification. Set idle_minutes for a check-in that is skipped whenever the user wrote within that many minutes (it targets the current chat unless channel/to are given).".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(CronArgs)).unwrap(),
             }
         }
@@ -57,6 +77,7 @@ impl Tool for CronTool {
         args: Self::Args,
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         let service = self.service.clone();
+        let channels = self.channels.clone();
         async move {
             let action = args.action.trim().to_lowercase();
 
@@ -71,17 +92,58 @@ impl Tool for CronTool {
                     let schedule = args
                         .schedule
                         .ok_or_else(|| ToolError::msg("Missing required field: schedule"))?;
+                    // Jobs deliver to the current chat unless told otherwise.
+                    let scope = session_scope();
+                    let channel = args
+                        .channel
+                        .or_else(|| scope.as_ref().map(|(channel, _)| channel.clone()));
+                    let to = args
+                        .to
+                        .or_else(|| scope.as_ref().map(|(_, chat_id)| chat_id.clone()));
+                    if let Some(minutes) = args.idle_minutes.filter(|m| *m > 0) {
+                        let (Some(channel), Some(to)) = (channel, to) else {
+                            return Ok(
+                                "Error: check-ins need channel and to outside a chat".to_string()
+                            );
+                        };
+                        service
+                            .add_check_in(
+                                name,
+                                schedule,
+                                message,
+                                channel,
+                                to,
+                                std::time::Duration::from_secs(minutes * 60),
+                            )
+                            .await
+                            .map_err(|e| ToolError::msg(e.to_string()))?;
+                        return Ok(format!(
+                            "Check-in added; it is skipped while the chat was active in the last {minutes} minutes."
+                        ));
+                    }
+                    let mention_user = if args.mention_user {
+                        context::current()
+                            .map(|ctx| ctx.sender_id)
+                            .filter(|id| id != "cron" && !id.is_empty())
+                    } else {
+                        None
+                    };
                     service
-                        .add_job(name, schedule, message, args.channel, args.to)
+                        .add_job(name, schedule, message, channel, to, mention_user)
                         .await
                         .map_err(|e| ToolError::msg(e.to_string()))?;
                     Ok("Cron job added.".to_string())
                 }
                 "list" => {
-                    let jobs = service
+                    let scope = session_scope();
+                    let (channel, chat_id) = scope.clone().unwrap_or_default();
+                    let jobs: Vec<CronJob> = service
                         .list_jobs()
                         .await
-                        .map_err(|e| ToolError::msg(e.to_string()))?;
+                        .map_err(|e| ToolError::msg(e.to_string()))?
+                        .into_iter()
+                        .filter(|job| in_scope(job, scope.as_ref()))
+                        .collect();
                     if jobs.is_empty() {
                         return Ok("No cron jobs found.".to_string());
                     }
@@ -90,19 +152,19 @@ impl Tool for CronTool {
                         let schedule = if job.schedule.kind == "every" {
                             format!("every {}ms", job.schedule.every_ms.unwrap_or(0))
                         } else if job.schedule.kind == "at" {
-                            "at".to_string()
+                            "once".to_string()
                         } else {
                             job.schedule.expr.unwrap_or_else(|| "?".to_string())
                         };
                         let next = job
                             .state
                             .next_run_at_ms
-                            .map(|ms| {
-                                chrono::DateTime::<chrono::Utc>::from(
-                                    std::time::UNIX_EPOCH
-                                        + std::time::Duration::from_millis(ms as u64),
-                                )
-                                .to_rfc3339()
+                            .and_then(chrono::DateTime::from_timestamp_millis)
+                            .map(|at| {
+                                channels
+                                    .chat_time(&channel, &chat_id, at)
+                                    .format("%Y-%m-%d %H:%M %:z")
+                                    .to_string()
                             })
                             .unwrap_or_else(|| "N/A".to_string());
                         out.push_str(&format!(
@@ -116,41 +178,244 @@ impl Tool for CronTool {
                     }
                     Ok(out)
                 }
-                "remove" => {
+                "remove" | "cancel" => {
                     let id = args
                         .id
                         .ok_or_else(|| ToolError::msg("Missing required field: id"))?;
+                    // Jobs of other chats are reported as missing, not refused,
+                    // so their ids can't be probed.
+                    let scope = session_scope();
+                    let jobs = service
+                        .list_jobs()
+                        .await
+                        .map_err(|e| ToolError::msg(e.to_string()))?;
+                    let Some(job) = jobs.iter().find(|job| job.id == id.trim()) else {
+                        return Ok("Cron job not found.".to_string());
+                    };
+                    if !in_scope(job, scope.as_ref()) {
+                        return Ok("Cron job not found.".to_string());
+                    }
                     let removed = service
-                        .remove_job(&id)
+                        .remove_job(&job.id)
                         .await
                         .map_err(|e| ToolError::msg(e.to_string()))?;
                     if removed {
-                        Ok("Cron job removed.".to_string())
+                        Ok(format!("Cron job removed: {}.", job.name))
                     } else {
                         Ok("Cron job not found.".to_string())
                     }
                 }
                 "status" => {
-                    let status = service
-                        .status()
+                    // Counted over this chat's jobs only, like `list`.
+                    let scope = session_scope();
+                    let (channel, chat_id) = scope.clone().unwrap_or_default();
+                    let jobs: Vec<CronJob> = service
+                        .list_jobs()
                         .await
-                        .map_err(|e| ToolError::msg(e.to_string()))?;
-                    let next = status
-                        .next_wake_at_ms
-                        .map(|ms| {
-                            chrono::DateTime::<chrono::Utc>::from(
-                                std::time::UNIX_EPOCH + std::time::Duration::from_millis(ms as u64),
-                            )
-                            .to_rfc3339()
-                        })
+                        .map_err(|e| ToolError::msg(e.to_string()))?
+                        .into_iter()
+                        .filter(|job| in_scope(job, scope.as_ref()))
+                        .collect();
+                    let enabled = jobs.iter().filter(|job| job.enabled).count();
+                    let next = jobs
+                        .iter()
+                        .filter(|job| job.enabled)
+                        .filter_map(|job| job.state.next_run_at_ms)
+                        .min()
+                        .and_then(chrono::DateTime::from_timestamp_millis)
+                        .map(|at| channels.chat_time(&channel, &chat_id, at).to_rfc3339())
                         .unwrap_or_else(|| "N/A".to_string());
                     Ok(format!(
                         "jobs: {}, enabled: {}, next_wake: {}",
-                        status.jobs, status.enabled_jobs, next
+                        jobs.len(),
+                        enabled,
+                        next
                     ))
                 }
-                _ => Ok("Invalid action. Use: add, list, remove, status.".to_string()),
+                _ => Ok("Invalid action. Use: add, list, remove, cancel, status.".to_string()),
             }
         }
     }
 }
+
+/// `(channel, chat_id)` of the chat the tool runs in, if any.
+fn session_scope() -> Option<(String, String)> {
+    context::current()
+        .filter(|ctx| !ctx.channel.is_empty() && !ctx.chat_id.is_empty())
+        .map(|ctx| (ctx.channel, ctx.chat_id))
+}
+
+/// Inside a chat, only jobs delivering to that chat are visible; without
+/// a chat (CLI, tests) every job is.
+fn in_scope(job: &CronJob, scope: Option<&(String, String)>) -> bool {
+    scope.is_none_or(|(channel, chat_id)| {
+        job.payload.channel.as_deref() == Some(channel.as_str())
+            && job.payload.to.as_deref() == Some(chat_id.as_str())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+    use crate::config::AppConfig;
+
+    fn args(action: &str, id: Option<&str>) -> CronArgs {
+        CronArgs {
+            action: action.to_string(),
+            name: None,
+            message: None,
+            schedule: None,
+            channel: None,
+            to: None,
+            id: id.map(str::to_string),
+            mention_user: false,
+            idle_minutes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn list_and_cancel_only_see_the_current_chats_jobs() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-cron-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir = dir.clone();
+        let service = CronService::new(&cfg, MessageBus::new());
+        for (name, chat) in [
+            ("water plants", "100"),
+            ("standup", "100"),
+            ("secret", "200"),
+        ] {
+            service
+                .add_job(
+                    name.to_string(),
+                    "0 9 * * *".to_string(),
+                    format!("{name} time"),
+                    Some("telegram".to_string()),
+                    Some(chat.to_string()),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+        let jobs = service.list_jobs().await.unwrap();
+        let id_of = |name: &str| jobs.iter().find(|j| j.name == name).unwrap().id.clone();
+        let tool = CronTool::new(service.clone(), cfg.channels.clone());
+        let ctx = context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "100".to_string(),
+            sender_id: "7".to_string(),
+            ..Default::default()
+        };
+
+        let listed = context::scope(ctx.clone(), tool.call(args("list", None)))
+            .await
+            .unwrap();
+        assert!(listed.contains("water plants") && listed.contains("standup"));
+        assert!(!listed.contains("secret"), "{listed}");
+        assert_eq!(listed.lines().count(), 2);
+
+        let secret = id_of("secret");
+        let reply = context::scope(ctx.clone(), tool.call(args("cancel", Some(&secret))))
+            .await
+            .unwrap();
+        assert_eq!(reply, "Cron job not found.");
+
+        let standup = id_of("standup");
+        let reply = context::scope(ctx, tool.call(args("cancel", Some(&standup))))
+            .await
+            .unwrap();
+        assert_eq!(reply, "Cron job removed: standup.");
+        let left: Vec<String> = service
+            .list_jobs()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|job| job.name)
+            .collect();
+        assert_eq!(left, vec!["water plants", "secret"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn add_status_and_next_runs_follow_the_current_chat() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-cron-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir = dir.clone();
+        cfg.channels
+            .timezones
+            .insert("telegram:100".to_string(), "Asia/Tokyo".to_string());
+        let service = CronService::new(&cfg, MessageBus::new());
+        service
+            .add_job(
+                "other chat".to_string(),
+                "0 9 * * *".to_string(),
+                "hello".to_string(),
+                Some("telegram".to_string()),
+                Some("200".to_string()),
+                None,
+            )
+            .await
+            .unwrap();
+        let tool = CronTool::new(service.clone(), cfg.channels.clone());
+        let ctx = context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "100".to_string(),
+            sender_id: "7".to_string(),
+            ..Default::default()
+        };
+
+        let mut add = args("add", None);
+        add.name = Some("standup".to_string());
+        add.message = Some("standup time".to_string());
+        add.schedule = Some("0 9 * * *".to_string());
+        context::scope(ctx.clone(), tool.call(add)).await.unwrap();
+        let jobs = service.list_jobs().await.unwrap();
+        let standup = jobs.iter().find(|job| job.name == "standup").unwrap();
+        assert_eq!(standup.payload.channel.as_deref(), Some("telegram"));
+        assert_eq!(standup.payload.to.as_deref(), Some("100"));
+
+        let status = context::scope(ctx.clone(), tool.call(args("status", None)))
+            .await
+            .unwrap();
+        assert!(status.starts_with("jobs: 1, enabled: 1,"), "{status}");
+        assert!(status.ends_with("+09:00"), "{status}");
+
+        let listed = context::scope(ctx, tool.call(args("list", None)))
+            .await
+            .unwrap();
+        assert!(listed.trim_end().ends_with("+09:00"), "{listed}");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn safe_mode_still_lists_jobs_but_refuses_changes() {
+        use crate::tools::safe_mode::{SafeMode, SAFE_MODE_REFUSAL};
+
+        let dir = std::env::temp_dir().join(format!("lightclaw-cron-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir = dir.clone();
+        let service = CronService::new(&cfg, MessageBus::new());
+        let tool = SafeMode::new(CronTool::new(service.clone(), cfg.channels.clone()), true)
+            .allowing(CronArgs::is_read_only);
+
+        let listed = tool.call(args("list", None)).await.unwrap();
+        assert_ne!(listed, SAFE_MODE_REFUSAL);
+        let status = tool.call(args(" Status ", None)).await.unwrap();
+        assert_ne!(status, SAFE_MODE_REFUSAL);
+
+        let mut add = args("add", None);
+        add.name = Some("standup".to_string());
+        add.message = Some("standup time".to_string());
+        add.schedule = Some("0 9 * * *".to_string());
+        assert_eq!(tool.call(add).await.unwrap(), SAFE_MODE_REFUSAL);
+        assert_eq!(
+            tool.call(args("remove", Some("x"))).await.unwrap(),
+            SAFE_MODE_REFUSAL
+        );
+        assert!(service.list_jobs().await.unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
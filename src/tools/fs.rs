@@ -1,3 +1,4 @@
+use crate::tools::output::OutputSink;
 use crate::tools::ToolError;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
@@ -16,6 +17,7 @@ fn expand_path(raw: &str) -> PathBuf {
 
 /// Resolve a path and optionally enforce that it is under `allowed_dir`.
 /// Used by file tools and by exec (for working_dir) when restrict_to_workspace is true.
+/// Relative paths are taken from `allowed_dir` when set, else the process cwd.
 pub(crate) fn resolve_path(
     path: &str,
     allowed_dir: Option<&Path>,
@@ -25,8 +27,10 @@ pub(crate) fn resolve_path(
     let abs = if expanded.is_absolute() {
         expanded
     } else {
-        std::env::current_dir()
-            .unwrap_or_else(|_| PathBuf::from("."))
+        allowed_dir
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."))
             .join(expanded)
     };
 
@@ -46,8 +50,10 @@ pub(crate) fn resolve_path(
             .map_err(|e| format!("failed to resolve allowed dir: {e}"))?;
         if !resolved.starts_with(&allowed) {
             return Err(format!(
-                "path {} is outside allowed directory {}",
+                "path '{path}' resolves to {}, which is outside the workspace {}. \
+                 Use a path under {} (tools.restrict_to_workspace is enabled).",
                 resolved.display(),
+                allowed.display(),
                 allowed.display()
             ));
         }
@@ -59,11 +65,15 @@ pub(crate) fn resolve_path(
 #[derive(Clone)]
 pub struct ReadFileTool {
     allowed_dir: Option<PathBuf>,
+    outputs: OutputSink,
 }
 
 impl ReadFileTool {
-    pub fn new(allowed_dir: Option<PathBuf>) -> Self {
-        Self { allowed_dir }
+    pub fn new(allowed_dir: Option<PathBuf>, outputs: OutputSink) -> Self {
+        Self {
+            allowed_dir,
+            outputs,
+        }
     }
 }
 
@@ -71,6 +81,9 @@ impl ReadFileTool {
 pub struct ReadFileArgs {
     /// The file path to read
     pub path: String,
+    /// Copy the content to workspace outputs/ and return only a preview plus the path
+    #[serde(default)]
+    pub output_to_file: bool,
 }
 
 impl Tool for ReadFileTool {
@@ -106,6 +119,9 @@ impl Tool for ReadFileTool {
                 return Ok(format!("Error: Not a file: {}", args.path));
             }
             match tokio::fs::read_to_string(&path).await {
+                Ok(content) if args.output_to_file => {
+                    Ok(self.outputs.spill(Self::NAME, &content).await)
+                }
                 Ok(content) => Ok(content),
                 Err(e) => Ok(format!("Error reading file: {e}")),
             }
@@ -321,3 +337,35 @@ impl Tool for ListDirTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn paths_outside_the_workspace_are_rejected_with_the_allowed_root() {
+        let root = std::env::temp_dir().join(format!("lightclaw-fs-{}", Uuid::new_v4()));
+        let workspace = root.join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(root.join("secret.txt"), b"x").unwrap();
+        let allowed = workspace.canonicalize().unwrap();
+
+        let outside = root.join("secret.txt");
+        let err = resolve_path(outside.to_str().unwrap(), Some(&workspace), false).unwrap_err();
+        assert!(err.contains(outside.to_str().unwrap()), "{err}");
+        assert!(
+            err.contains(&format!("outside the workspace {}", allowed.display())),
+            "{err}"
+        );
+
+        let err = resolve_path("../secret.txt", Some(&workspace), true).unwrap_err();
+        assert!(err.contains("'../secret.txt'"), "{err}");
+
+        // Relative paths resolve inside the workspace.
+        let inside = resolve_path("notes/today.md", Some(&workspace), true).unwrap();
+        assert_eq!(inside, workspace.join("notes/today.md"));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::config::ToolsConfig;
+use crate::tools::ALL_TOOLS;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// How much of each tool's result the model sees, from
+/// `tools.max_output_chars` and `tools.max_output_chars_by_tool`.
+#[derive(Clone, Debug)]
+pub struct OutputCaps {
+    default_chars: usize,
+    by_tool: HashMap<String, usize>,
+}
+
+impl OutputCaps {
+    pub fn new(tools: &ToolsConfig) -> Self {
+        for name in tools.max_output_chars_by_tool.keys() {
+            if !ALL_TOOLS.contains(&name.as_str()) {
+                warn!(
+                    "unknown tool '{}' in tools.max_output_chars_by_tool (known: {})",
+                    name,
+                    ALL_TOOLS.join(", ")
+                );
+            }
+        }
+        Self {
+            default_chars: tools.max_output_chars,
+            by_tool: tools.max_output_chars_by_tool.clone(),
+        }
+    }
+
+    /// Cap for the tool named `name`; `None` passes results through whole.
+    pub fn for_tool(&self, name: &str) -> Option<usize> {
+        let chars = self
+            .by_tool
+            .get(name)
+            .copied()
+            .unwrap_or(self.default_chars);
+        (chars > 0).then_some(chars)
+    }
+}
+
+/// Wraps a tool so a result longer than its cap is cut to the first `limit`
+/// characters, followed by a marker giving the original length.
+#[derive(Clone)]
+pub struct Capped<T> {
+    inner: T,
+    limit: Option<usize>,
+}
+
+impl<T> Capped<T> {
+    pub fn new(inner: T, limit: Option<usize>) -> Self {
+        Self { inner, limit }
+    }
+}
+
+impl<T: Tool<Output = String>> Tool for Capped<T> {
+    const NAME: &'static str = T::NAME;
+    type Args = T::Args;
+    type Output = String;
+    type Error = T::Error;
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        self.inner.definition(prompt)
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let call = self.inner.call(args);
+        let limit = self.limit;
+        async move {
+            let output = call.await?;
+            Ok(match limit {
+                Some(limit) => cap_output(output, limit),
+                None => output,
+            })
+        }
+    }
+}
+
+fn cap_output(output: String, limit: usize) -> String {
+    let total = output.chars().count();
+    if total <= limit {
+        return output;
+    }
+    let kept: String = output.chars().take(limit).collect();
+    format!("{kept}\n\n[truncated: showing {limit} of {total} chars]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+    use crate::config::{AppConfig, MemoryMode};
+    use crate::cron::CronService;
+    use crate::memory::simple::file_store::MemoryStore;
+    use crate::tools::fs::ReadFileArgs;
+    use crate::tools::ToolRegistry;
+
+    #[tokio::test]
+    async fn long_results_are_cut_at_the_cap_and_marked() {
+        let root = std::env::temp_dir().join(format!("lightclaw-limit-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.memory.mode = MemoryMode::None;
+        cfg.workspace_dir = root.join("workspace");
+        cfg.data_dir = root.join("data");
+        cfg.tools.max_output_chars_by_tool = HashMap::from([("read_file".to_string(), 120)]);
+        std::fs::create_dir_all(&cfg.workspace_dir).unwrap();
+        let long = cfg.workspace_dir.join("long.txt");
+        let short = cfg.workspace_dir.join("short.txt");
+        std::fs::write(&long, "é".repeat(500)).unwrap();
+        std::fs::write(&short, "é".repeat(80)).unwrap();
+        let bus = MessageBus::new();
+        let registry = ToolRegistry::new(
+            cfg.clone(),
+            CronService::new(&cfg, bus.clone()),
+            bus,
+            MemoryStore::new(cfg.workspace_dir.clone()),
+            None,
+        );
+        let read_file = registry.bounded(registry.read_file.clone());
+        let read = |path: &std::path::Path| ReadFileArgs {
+            path: path.display().to_string(),
+            output_to_file: false,
+        };
+
+        let out = read_file.call(read(&long)).await.unwrap();
+        let (kept, marker) = out.split_once("\n\n").unwrap();
+        assert_eq!(kept, "é".repeat(120));
+        assert_eq!(marker, "[truncated: showing 120 of 500 chars]");
+
+        let whole = read_file.call(read(&short)).await.unwrap();
+        assert_eq!(whole, "é".repeat(80));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn each_tool_gets_its_override_or_the_default() {
+        let mut cfg = AppConfig::defaults();
+        cfg.tools.max_output_chars = 4_000;
+        cfg.tools.max_output_chars_by_tool =
+            HashMap::from([("exec".to_string(), 1_000), ("web_fetch".to_string(), 0)]);
+        let caps = OutputCaps::new(&cfg.tools);
+        for name in ALL_TOOLS {
+            let expected = match name {
+                "exec" => Some(1_000),
+                "web_fetch" => None,
+                _ => Some(4_000),
+            };
+            assert_eq!(caps.for_tool(name), expected, "{name}");
+            if let Some(limit) = expected {
+                let capped = cap_output("x".repeat(limit * 2), limit);
+                assert!(capped.starts_with(&"x".repeat(limit)));
+                assert!(!capped.starts_with(&"x".repeat(limit + 1)), "{name}");
+            }
+        }
+
+        cfg.tools.max_output_chars = 0;
+        assert_eq!(OutputCaps::new(&cfg.tools).for_tool("read_file"), None);
+    }
+}
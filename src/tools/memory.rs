@@ -1,17 +1,20 @@
+use crate::config::RecallConfig;
 use crate::memory::simple::file_store::MemoryStore;
 use crate::memory::smart::vector_store::VectorMemoryStore;
+use crate::tools::context;
 use crate::tools::ToolError;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use tracing::warn;
 
 fn allowed_memory_path(name: &str) -> bool {
     if name == "MEMORY.md" {
         return true;
     }
-    is_daily_memory_file(name)
+    is_daily_memory_file(name) || is_archive_memory_file(name)
 }
 
 fn normalize_memory_path(path: &str) -> Option<String> {
@@ -40,6 +43,92 @@ fn is_daily_memory_file(name: &str) -> bool {
     })
 }
 
+/// `archive-YYYY-MM.md`, written when MEMORY.md outgrows its size cap.
+fn is_archive_memory_file(name: &str) -> bool {
+    let Some(month) = name
+        .strip_prefix("archive-")
+        .and_then(|rest| rest.strip_suffix(".md"))
+    else {
+        return false;
+    };
+    month.len() == 7
+        && month.bytes().enumerate().all(|(i, c)| match i {
+            4 => c == b'-',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Resolve the vector namespace for a tool call.
+///
+/// Inside an agent turn the session namespace is authoritative: an omitted
+/// namespace defaults to it, and a model-supplied one must match it (or the
+/// configured shared namespace). Outside a turn the caller must supply one,
+/// signalled by `Ok(None)` when it is missing.
+fn resolve_namespace(requested: Option<&str>) -> Result<Option<String>, String> {
+    let requested = requested.map(str::trim).filter(|ns| !ns.is_empty());
+    let Some(ctx) = context::current() else {
+        return Ok(requested.map(str::to_string));
+    };
+    match requested {
+        None => Ok(Some(ctx.namespace)),
+        Some(ns) if ns == ctx.namespace => Ok(Some(ctx.namespace)),
+        Some(ns) if ctx.shared_namespace.as_deref() == Some(ns) => Ok(Some(ns.to_string())),
+        // A misspelling of the session's own namespace (`telegram:1`,
+        // `Telegram-1`) is corrected rather than searched verbatim.
+        Some(ns) if namespace_key(ns) == namespace_key(&ctx.namespace) => Ok(Some(ctx.namespace)),
+        Some(ns) => Err(format!(
+            "Error: namespace '{ns}' does not belong to the current session; omit namespace or use '{}'",
+            ctx.namespace
+        )),
+    }
+}
+
+/// Namespace reduced to lowercase alphanumerics, so `telegram:1`,
+/// `Telegram-1` and `telegram_1` compare equal.
+fn namespace_key(namespace: &str) -> String {
+    namespace
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Hint for a search that found nothing in `requested`: stored namespaces
+/// that look like it, or all of them when none do. Inside a turn only the
+/// session's own namespaces are named, never other chats'.
+async fn namespace_hint(store: &VectorMemoryStore, requested: &str) -> Option<String> {
+    let mut known = store.namespaces().await.ok()?;
+    if let Some(ctx) = context::current() {
+        known.retain(|ns| {
+            *ns == ctx.namespace || ctx.shared_namespace.as_deref() == Some(ns.as_str())
+        });
+    }
+    if known.is_empty() || known.iter().any(|ns| ns == requested) {
+        return None;
+    }
+    let key = namespace_key(requested);
+    let close: Vec<&String> = known
+        .iter()
+        .filter(|ns| {
+            let other = namespace_key(ns);
+            !key.is_empty() && (other.contains(&key) || key.contains(&other))
+        })
+        .collect();
+    let shown: Vec<&String> = if close.is_empty() {
+        known.iter().take(MAX_NAMESPACE_HINTS).collect()
+    } else {
+        close
+    };
+    let list = shown
+        .iter()
+        .map(|ns| format!("'{ns}'"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(format!(
+        "namespace '{requested}' has no memories; valid namespaces: {list} (format: <channel>_<chat_id>)"
+    ))
+}
+
 fn collect_memory_file_sources(memory_store: &MemoryStore) -> Vec<(String, String)> {
     let mut sources = Vec::new();
 
@@ -65,8 +154,12 @@ fn collect_memory_file_sources(memory_store: &MemoryStore) -> Vec<(String, Strin
         }
     }
 
-    // Newest date files first because names are YYYY-MM-DD.md.
-    dated_files.sort_by(|a, b| b.0.cmp(&a.0));
+    // Newest date files first because names are YYYY-MM-DD.md; archives last.
+    dated_files.sort_by(|a, b| {
+        is_archive_memory_file(&a.0)
+            .cmp(&is_archive_memory_file(&b.0))
+            .then_with(|| b.0.cmp(&a.0))
+    });
     for (name, path) in dated_files {
         if let Ok(content) = std::fs::read_to_string(path) {
             if !content.trim().is_empty() {
@@ -86,13 +179,19 @@ fn collect_memory_file_sources(memory_store: &MemoryStore) -> Vec<(String, Strin
 pub struct MemorySearchTool {
     memory_store: MemoryStore,
     vector_store: Option<VectorMemoryStore>,
+    recall: RecallConfig,
 }
 
 impl MemorySearchTool {
-    pub fn new(memory_store: MemoryStore, vector_store: Option<VectorMemoryStore>) -> Self {
+    pub fn new(
+        memory_store: MemoryStore,
+        vector_store: Option<VectorMemoryStore>,
+        recall: RecallConfig,
+    ) -> Self {
         Self {
             memory_store,
             vector_store,
+            recall,
         }
     }
 }
@@ -101,17 +200,18 @@ impl MemorySearchTool {
 pub struct MemorySearchArgs {
     /// Search query (semantic for Smart mode, keyword for Simple)
     pub query: String,
-    /// Max results to return
-    #[serde(default = "default_max_results")]
-    pub max_results: usize,
-    /// Namespace for vector memory in Smart mode (example: telegram_123456)
+    /// Max results to return (defaults to the configured recall size)
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    /// Namespace for vector memory in Smart mode; defaults to the current session
     #[serde(default)]
     pub namespace: Option<String>,
 }
 
-fn default_max_results() -> usize {
-    6
-}
+/// Result cap for file search when the model omits `max_results`.
+const DEFAULT_FILE_MAX_RESULTS: usize = 6;
+/// Namespaces listed in a hint when none resemble the requested one.
+const MAX_NAMESPACE_HINTS: usize = 10;
 
 #[derive(Serialize)]
 struct MemorySearchResult {
@@ -136,7 +236,7 @@ impl Tool for MemorySearchTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Semantically search memory for prior work, decisions, dates, people, preferences, or todos. In smart mode pass namespace (channel_chat_id style, e.g. telegram_123456) to avoid cross-session recall. Returns snippets with path and score.".to_string(),
+                description: "Semantically search memory for prior work, decisions, dates, people, preferences, or todos. In smart mode results are scoped to the current session's namespace; namespace may be omitted. Returns snippets with path and score.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(MemorySearchArgs)).unwrap(),
             }
         }
@@ -148,23 +248,36 @@ impl Tool for MemorySearchTool {
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         let memory_store = self.memory_store.clone();
         let vector_store = self.vector_store.clone();
+        let recall = self.recall;
         let query = args.query;
-        let max_results = args.max_results.min(20);
+        let requested = args.max_results;
         let namespace = args.namespace;
 
         async move {
             if let Some(vs) = &vector_store {
+                let requested_ns = namespace
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|ns| !ns.is_empty());
                 let namespace =
-                    match namespace.as_deref() {
-                        Some(ns) if !ns.trim().is_empty() => ns,
-                        _ => return Ok(
+                    match resolve_namespace(requested_ns) {
+                        Ok(Some(ns)) => ns,
+                        Ok(None) => return Ok(
                             "Error: namespace is required in smart mode (example: telegram_123456)"
                                 .to_string(),
                         ),
+                        Err(err) => return Ok(err),
                     };
                 // Smart mode: vector search in the provided namespace.
+                let max_results = requested.unwrap_or(recall.top_k).min(20);
                 match vs
-                    .search(&query, max_results, 0.0, Some(namespace), 0.3)
+                    .search(
+                        &query,
+                        max_results,
+                        recall.threshold,
+                        Some(&namespace),
+                        recall.priority_weight,
+                    )
                     .await
                 {
                     Ok(pairs) => {
@@ -177,16 +290,33 @@ impl Tool for MemorySearchTool {
                                 score: Some(score),
                             })
                             .collect();
-                        Ok(serde_json::to_string_pretty(&serde_json::json!({
+                        let mut body = serde_json::json!({
                             "results": results,
                             "source": "vector"
-                        }))
-                        .unwrap_or_else(|_| "[]".to_string()))
+                        });
+                        match requested_ns {
+                            Some(ns) if ns != namespace => {
+                                body["note"] = serde_json::json!(format!(
+                                    "namespace '{ns}' corrected to the session namespace '{namespace}'"
+                                ));
+                            }
+                            Some(ns) if results.is_empty() => {
+                                if let Some(hint) = namespace_hint(vs, ns).await {
+                                    body["hint"] = serde_json::json!(hint);
+                                }
+                            }
+                            _ => {}
+                        }
+                        Ok(
+                            serde_json::to_string_pretty(&body)
+                                .unwrap_or_else(|_| "[]".to_string()),
+                        )
                     }
                     Err(e) => Ok(format!("Error: vector search failed: {e}")),
                 }
             } else {
                 // Simple mode: text search over memory files
+                let max_results = requested.unwrap_or(DEFAULT_FILE_MAX_RESULTS).min(20);
                 let q_lower = query.to_lowercase();
                 let mut results = Vec::new();
                 let sources = collect_memory_file_sources(&memory_store);
@@ -216,12 +346,97 @@ impl Tool for MemorySearchTool {
     }
 }
 
+// ---------------------------------------------------------------------------
+// memory_pin (Smart mode)
+// ---------------------------------------------------------------------------
+
+#[derive(Clone)]
+pub struct MemoryPinTool {
+    vector_store: VectorMemoryStore,
+}
+
+impl MemoryPinTool {
+    pub fn new(vector_store: VectorMemoryStore) -> Self {
+        Self { vector_store }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct MemoryPinArgs {
+    /// Vector memory id (the <memory-id> in vector/<memory-id>)
+    pub id: String,
+    /// true to pin, false to unpin
+    #[serde(default = "default_pin")]
+    pub pinned: bool,
+}
+
+fn default_pin() -> bool {
+    true
+}
+
+impl Tool for MemoryPinTool {
+    const NAME: &'static str = "memory_pin";
+    type Args = MemoryPinArgs;
+    type Output = String;
+    type Error = ToolError;
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        async {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Pin a vector memory in the current session so it is never pruned when memory fills up, or unpin it with pinned=false. Takes the id from a vector/<memory-id> path returned by memory_search.".to_string(),
+                parameters: serde_json::to_value(schemars::schema_for!(MemoryPinArgs)).unwrap(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let store = self.vector_store.clone();
+        async move {
+            let id = args.id.trim();
+            let id = id.strip_prefix("vector/").unwrap_or(id);
+            if id.is_empty() {
+                return Ok("Error: id cannot be empty".to_string());
+            }
+            let namespace = match resolve_namespace(None) {
+                Ok(Some(ns)) => ns,
+                Ok(None) => return Ok("Error: memory_pin only works inside a chat".to_string()),
+                Err(err) => return Ok(err),
+            };
+            match store.set_pinned(id, Some(&namespace), args.pinned).await {
+                Ok(true) if args.pinned => {
+                    Ok(format!("Pinned vector/{id}; it will not be pruned."))
+                }
+                Ok(true) => Ok(format!("Unpinned vector/{id}.")),
+                Ok(false) => Ok(format!("Error: vector memory not found: {id}")),
+                Err(e) => Ok(format!("Error: pin failed: {e}")),
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::smart::vector_store::split_content;
     use tokio::runtime::Runtime;
     use uuid::Uuid;
 
+    fn test_recall() -> RecallConfig {
+        RecallConfig {
+            top_k: 3,
+            threshold: 0.08,
+            priority_weight: 0.3,
+            embed_timeout_ms: 3000,
+        }
+    }
+
     #[test]
     fn memory_search_simple_scans_historical_daily_files() {
         let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
@@ -235,13 +450,13 @@ mod tests {
         )
         .expect("write historical");
 
-        let tool = MemorySearchTool::new(store, None);
+        let tool = MemorySearchTool::new(store, None, test_recall());
         let rt = Runtime::new().expect("runtime");
         let out = rt
             .block_on(async {
                 tool.call(MemorySearchArgs {
                     query: "rust-analyzer".to_string(),
-                    max_results: 5,
+                    max_results: Some(5),
                     namespace: None,
                 })
                 .await
@@ -257,6 +472,328 @@ mod tests {
         let _ = std::fs::remove_dir_all(workspace);
     }
 
+    #[test]
+    fn memory_search_applies_configured_recall_defaults() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        let vectors = VectorMemoryStore::for_tests(workspace.join("vectors.db"));
+        let recall = RecallConfig {
+            top_k: 1,
+            threshold: 0.5,
+            priority_weight: 0.0,
+            embed_timeout_ms: 3000,
+        };
+        let tool = MemorySearchTool::new(store, Some(vectors.clone()), recall);
+        let rt = Runtime::new().expect("runtime");
+
+        let (capped, uncapped) = rt.block_on(async {
+            for (content, embedding) in [
+                ("User drinks green tea", vec![1.0, 0.0]),
+                ("User sometimes drinks coffee", vec![0.6, 0.8]),
+                ("Server runs Debian", vec![0.0, 1.0]),
+            ] {
+                vectors
+                    .add(content, HashMap::new(), Some("tg_1"), Some(embedding))
+                    .await
+                    .expect("add");
+            }
+            vectors.seed_embedding("drinks", vec![1.0, 0.0]).await;
+            let capped = tool
+                .call(MemorySearchArgs {
+                    query: "drinks".to_string(),
+                    max_results: None,
+                    namespace: Some("tg_1".to_string()),
+                })
+                .await
+                .expect("tool call");
+            let uncapped = tool
+                .call(MemorySearchArgs {
+                    query: "drinks".to_string(),
+                    max_results: Some(10),
+                    namespace: Some("tg_1".to_string()),
+                })
+                .await
+                .expect("tool call");
+            (capped, uncapped)
+        });
+
+        let capped: Value = serde_json::from_str(&capped).expect("json output");
+        let capped = capped["results"].as_array().expect("results array");
+        assert_eq!(capped.len(), 1);
+        assert_eq!(capped[0]["snippet"], "User drinks green tea");
+
+        // The configured threshold still filters the unrelated memory.
+        let uncapped: Value = serde_json::from_str(&uncapped).expect("json output");
+        assert_eq!(uncapped["results"].as_array().expect("results").len(), 2);
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn memory_search_never_recalls_another_sessions_namespace() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        let vectors = VectorMemoryStore::for_tests(workspace.join("vectors.db"));
+        let tool = MemorySearchTool::new(store, Some(vectors.clone()), test_recall());
+        let ctx = context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            sender_id: "1".to_string(),
+            namespace: "telegram_1".to_string(),
+            shared_namespace: None,
+            ..Default::default()
+        };
+        let rt = Runtime::new().expect("runtime");
+
+        rt.block_on(async {
+            vectors
+                .add(
+                    "Alice's bank PIN hint is blue",
+                    HashMap::new(),
+                    Some("telegram_1"),
+                    Some(vec![1.0, 0.0]),
+                )
+                .await
+                .expect("add");
+            vectors
+                .add(
+                    "Bob likes hiking",
+                    HashMap::new(),
+                    Some("discord_2"),
+                    Some(vec![0.9, 0.1]),
+                )
+                .await
+                .expect("add");
+            vectors.seed_embedding("secrets", vec![1.0, 0.0]).await;
+
+            // Direct store recall is partitioned by namespace.
+            let foreign = vectors
+                .search("secrets", 10, 0.0, Some("discord_2"), 0.0)
+                .await
+                .expect("search");
+            assert!(foreign
+                .iter()
+                .all(|(item, _)| item.namespace == "discord_2"));
+            assert!(!foreign
+                .iter()
+                .any(|(item, _)| item.content.contains("Alice")));
+
+            let own = context::scope(
+                ctx.clone(),
+                tool.call(MemorySearchArgs {
+                    query: "secrets".to_string(),
+                    max_results: None,
+                    namespace: None,
+                }),
+            )
+            .await
+            .expect("tool call");
+            assert!(own.contains("Alice"));
+            assert!(!own.contains("Bob"));
+
+            // A prompt-injected namespace for another session is rejected.
+            let injected = context::scope(
+                ctx,
+                tool.call(MemorySearchArgs {
+                    query: "secrets".to_string(),
+                    max_results: None,
+                    namespace: Some("discord_2".to_string()),
+                }),
+            )
+            .await
+            .expect("tool call");
+            assert!(injected.starts_with("Error: namespace 'discord_2'"));
+            assert!(!injected.contains("Bob"));
+        });
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn memory_search_corrects_or_hints_mistyped_namespaces() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        let vectors = VectorMemoryStore::for_tests(workspace.join("vectors.db"));
+        let tool = MemorySearchTool::new(store, Some(vectors.clone()), test_recall());
+        let ctx = context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "1".to_string(),
+            sender_id: "1".to_string(),
+            namespace: "telegram_1".to_string(),
+            ..Default::default()
+        };
+        let search = |namespace: &str| MemorySearchArgs {
+            query: "coffee".to_string(),
+            max_results: None,
+            namespace: Some(namespace.to_string()),
+        };
+        let rt = Runtime::new().expect("runtime");
+
+        rt.block_on(async {
+            vectors
+                .add(
+                    "Prefers oat milk in coffee",
+                    HashMap::new(),
+                    Some("telegram_1"),
+                    Some(vec![1.0, 0.0]),
+                )
+                .await
+                .expect("add");
+            vectors.seed_embedding("coffee", vec![1.0, 0.0]).await;
+
+            // Inside a turn, a near miss of the session namespace is corrected.
+            let corrected = context::scope(ctx, tool.call(search("Telegram-1")))
+                .await
+                .expect("tool call");
+            assert!(corrected.contains("oat milk"));
+            assert!(corrected.contains("corrected to the session namespace 'telegram_1'"));
+
+            // Outside a turn, an empty search points at the stored namespace.
+            let hinted = tool.call(search("Telegram-1")).await.expect("tool call");
+            assert!(!hinted.contains("oat milk"));
+            assert!(hinted.contains("valid namespaces: 'telegram_1'"));
+
+            let exact = tool.call(search("telegram_1")).await.expect("tool call");
+            assert!(exact.contains("oat milk"));
+            assert!(!exact.contains("hint"));
+
+            // Inside a turn the hint never names another chat's namespace.
+            vectors
+                .add(
+                    "Takes tea, no coffee",
+                    HashMap::new(),
+                    Some("discord_9"),
+                    Some(vec![0.0, 1.0]),
+                )
+                .await
+                .expect("add");
+            let shared_ctx = context::ToolContext {
+                namespace: "telegram_1".to_string(),
+                shared_namespace: Some("shared".to_string()),
+                ..Default::default()
+            };
+            let hinted = context::scope(shared_ctx, tool.call(search("shared")))
+                .await
+                .expect("tool call");
+            assert!(
+                hinted.contains("valid namespaces: 'telegram_1'"),
+                "{hinted}"
+            );
+            assert!(!hinted.contains("discord_9"), "{hinted}");
+        });
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn remember_splits_oversized_content_into_linked_chunks() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        let vectors = VectorMemoryStore::for_tests(workspace.join("vectors.db"));
+        let tool = RememberTool::new_hybrid(vectors.clone(), store);
+        let ctx = context::ToolContext {
+            namespace: "telegram_1".to_string(),
+            ..Default::default()
+        };
+        let fact = (0..400)
+            .map(|i| format!("Deployment step {i} runs after the previous one succeeds."))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert!(fact.len() > 20_000);
+        let rt = Runtime::new().expect("runtime");
+
+        rt.block_on(async {
+            let chunks = split_content(&fact, 8192);
+            for chunk in &chunks {
+                vectors.seed_embedding(chunk, vec![1.0, 0.0]).await;
+            }
+
+            let out = context::scope(
+                ctx,
+                tool.call(RememberArgs {
+                    content: fact.clone(),
+                    kind: None,
+                    namespace: None,
+                    source: None,
+                    confidence: None,
+                    pin: false,
+                }),
+            )
+            .await
+            .expect("tool call");
+            assert!(
+                out.contains(&format!("stored as {} linked chunks", chunks.len())),
+                "{out}"
+            );
+
+            let records = vectors.export_all(false).await.expect("export");
+            assert_eq!(records.len(), chunks.len());
+            assert!(records.len() >= 3);
+            let group = records[0].metadata["chunk_group"].clone();
+            let mut indexes: Vec<u64> = records
+                .iter()
+                .map(|r| {
+                    assert_eq!(r.metadata["chunk_group"], group);
+                    assert_eq!(r.metadata["chunk_count"], chunks.len());
+                    assert_eq!(r.metadata["kind"], "remembered_fact");
+                    assert!(r.content.len() <= 8192);
+                    r.metadata["chunk_index"].as_u64().unwrap()
+                })
+                .collect();
+            indexes.sort();
+            assert_eq!(indexes, (0..chunks.len() as u64).collect::<Vec<_>>());
+            let total: usize = records.iter().map(|r| r.content.len()).sum();
+            assert!(total + chunks.len() >= fact.len());
+        });
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[test]
+    fn async_remember_returns_before_the_vector_add_finishes() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
+        let store = MemoryStore::new(workspace.clone());
+        let ctx = context::ToolContext {
+            namespace: "telegram_1".to_string(),
+            ..Default::default()
+        };
+        let rt = Runtime::new().expect("runtime");
+
+        rt.block_on(async {
+            // An embedding endpoint that accepts requests and never answers.
+            let (addr, hang) = crate::test_http::hang().await;
+            let vectors = VectorMemoryStore::for_tests_at(
+                workspace.join("vectors.db"),
+                &format!("http://{addr}"),
+            );
+            let tool =
+                RememberTool::new_hybrid(vectors.clone(), store.clone()).with_async_vectors(true);
+
+            let started = std::time::Instant::now();
+            let out = context::scope(
+                ctx,
+                tool.call(RememberArgs {
+                    content: "Allergic to penicillin".to_string(),
+                    kind: None,
+                    namespace: None,
+                    source: None,
+                    confidence: None,
+                    pin: false,
+                }),
+            )
+            .await
+            .expect("tool call");
+            assert!(started.elapsed() < std::time::Duration::from_secs(2));
+            assert_eq!(out, "Remembered (remembered_fact)");
+            assert!(store.read_long_term().contains("Allergic to penicillin"));
+            assert!(vectors.export_all(false).await.expect("export").is_empty());
+
+            hang.abort();
+        });
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
     #[test]
     fn remember_tool_file_backend_persists_fact() {
         let workspace = std::env::temp_dir().join(format!("lightclaw-tooltest-{}", Uuid::new_v4()));
@@ -272,6 +809,7 @@ mod tests {
                     namespace: None,
                     source: None,
                     confidence: None,
+                    pin: false,
                 })
                 .await
             })
@@ -379,7 +917,7 @@ impl Tool for MemoryGetTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Read memory by path. Supports MEMORY.md, memory/MEMORY.md, YYYY-MM-DD.md, memory/YYYY-MM-DD.md, and vector/<memory-id>. For vector/<memory-id> in smart mode, provide namespace.".to_string(),
+                description: "Read memory by path. Supports MEMORY.md, memory/MEMORY.md, YYYY-MM-DD.md, memory/YYYY-MM-DD.md, and vector/<memory-id>. Vector paths resolve in the current session's namespace.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(MemoryGetArgs)).unwrap(),
             }
         }
@@ -405,13 +943,14 @@ impl Tool for MemoryGetTool {
                 let Some(store) = vector_store else {
                     return Ok("Error: vector memory is not enabled".to_string());
                 };
-                let namespace = match namespace.as_deref() {
-                    Some(ns) if !ns.trim().is_empty() => ns,
-                    _ => {
+                let namespace = match resolve_namespace(namespace.as_deref()) {
+                    Ok(Some(ns)) => ns,
+                    Ok(None) => {
                         return Ok("Error: namespace is required for vector paths in smart mode (example: telegram_123456)".to_string())
                     }
+                    Err(err) => return Ok(err),
                 };
-                let item = match store.get(memory_id, Some(namespace)).await {
+                let item = match store.get(memory_id, Some(&namespace)).await {
                     Ok(Some(item)) => item,
                     Ok(None) => return Ok(format!("Error: vector memory not found: {memory_id}")),
                     Err(e) => return Ok(format!("Error: vector memory lookup failed: {e}")),
@@ -468,7 +1007,7 @@ impl Tool for MemoryGetTool {
 enum RememberBackend {
     File(MemoryStore),
     Hybrid {
-        vector_store: VectorMemoryStore,
+        vector_store: Box<VectorMemoryStore>,
         memory_store: MemoryStore,
     },
 }
@@ -476,23 +1015,39 @@ enum RememberBackend {
 #[derive(Clone)]
 pub struct RememberTool {
     backend: RememberBackend,
+    /// Embed on a background task instead of awaiting the vector insert.
+    async_vectors: bool,
 }
 
 impl RememberTool {
     pub fn new_file(memory_store: MemoryStore) -> Self {
         Self {
             backend: RememberBackend::File(memory_store),
+            async_vectors: false,
         }
     }
 
     pub fn new_hybrid(vector_store: VectorMemoryStore, memory_store: MemoryStore) -> Self {
         Self {
             backend: RememberBackend::Hybrid {
-                vector_store,
+                vector_store: Box::new(vector_store),
                 memory_store,
             },
+            async_vectors: false,
         }
     }
+
+    /// Return once file memory is written and add the vector memory in the
+    /// background (`memory.async_remember`).
+    pub fn with_async_vectors(mut self, enabled: bool) -> Self {
+        self.async_vectors = enabled;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_file_backed(&self) -> bool {
+        matches!(self.backend, RememberBackend::File(_))
+    }
 }
 
 #[derive(Clone, Copy, Deserialize, schemars::JsonSchema)]
@@ -528,7 +1083,7 @@ pub struct RememberArgs {
     /// Memory type: remembered_fact, conversation_observation, user_observation, grounded_fact
     #[serde(default)]
     pub kind: Option<RememberKind>,
-    /// Namespace for vector memory in Smart mode (example: telegram_123456)
+    /// Namespace for vector memory in Smart mode; defaults to the current session
     #[serde(default)]
     pub namespace: Option<String>,
     /// Optional source for grounded facts (tool, URL, file path, API endpoint)
@@ -537,6 +1092,9 @@ pub struct RememberArgs {
     /// Confidence score for grounded facts [0.0..1.0]
     #[serde(default)]
     pub confidence: Option<f32>,
+    /// Pin the vector memory so it is never pruned (critical facts only)
+    #[serde(default)]
+    pub pin: bool,
 }
 
 impl Tool for RememberTool {
@@ -552,7 +1110,7 @@ impl Tool for RememberTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Save information to long-term memory. Use kind to classify as remembered_fact, conversation_observation, user_observation, or grounded_fact. In smart mode pass namespace for vector memory isolation; grounded_facts can include source/confidence.".to_string(),
+                description: "Save information to long-term memory. Use kind to classify as remembered_fact, conversation_observation, user_observation, or grounded_fact. In smart mode vector memory is stored in the current session's namespace; grounded_facts can include source/confidence. Set pin=true for critical facts (emergency contacts, allergies) that must never be pruned.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(RememberArgs)).unwrap(),
             }
         }
@@ -563,11 +1121,13 @@ impl Tool for RememberTool {
         args: Self::Args,
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         let backend = self.backend.clone();
+        let async_vectors = self.async_vectors;
         let content = args.content.trim().to_string();
         let kind = args.kind.unwrap_or_default();
         let namespace = args.namespace;
         let source = args.source;
         let confidence = args.confidence.unwrap_or(0.7).clamp(0.0, 1.0);
+        let pin = args.pin;
 
         async move {
             if content.is_empty() {
@@ -609,11 +1169,14 @@ impl Tool for RememberTool {
                             confidence,
                         ),
                     }
-                    let namespace = match namespace.as_deref() {
-                        Some(ns) if !ns.trim().is_empty() => ns,
-                        _ => {
+                    let namespace = match resolve_namespace(namespace.as_deref()) {
+                        Ok(Some(ns)) => ns,
+                        Ok(None) => {
                             return Ok("Remembered in file memory only: namespace is required for vector memory in smart mode (example: telegram_123456)".to_string())
                         }
+                        Err(err) => {
+                            return Ok(format!("Remembered in file memory only. {err}"))
+                        }
                     };
                     let mut meta = HashMap::new();
                     meta.insert("importance".to_string(), Value::from(confidence as f64));
@@ -624,10 +1187,30 @@ impl Tool for RememberTool {
                             meta.insert("source".to_string(), Value::from(src));
                         }
                     }
+                    if pin {
+                        meta.insert("pinned".to_string(), Value::Bool(true));
+                    }
+                    if async_vectors {
+                        tokio::spawn(async move {
+                            if let Err(err) = vector_store
+                                .add_chunked(&content, meta, Some(&namespace))
+                                .await
+                            {
+                                warn!("background vector add for remember failed: {err}");
+                            }
+                        });
+                        return Ok(format!("Remembered ({})", kind.as_str()));
+                    }
                     match vector_store
-                        .add(&content, meta, Some(namespace), None)
+                        .add_chunked(&content, meta, Some(&namespace))
                         .await
                     {
+                        Ok(items) if items.len() > 1 => Ok(format!(
+                            "Remembered ({}); content was too long for one memory, so it was stored as {} linked chunks (chunk_group {})",
+                            kind.as_str(),
+                            items.len(),
+                            items[0].metadata.get("chunk_group").and_then(Value::as_str).unwrap_or_default()
+                        )),
                         Ok(_) => Ok(format!("Remembered ({})", kind.as_str())),
                         Err(e) => Ok(format!(
                             "Remembered in file memory ({}) (vector add failed: {})",
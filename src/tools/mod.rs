@@ -1,16 +1,31 @@
 use crate::bus::MessageBus;
-use crate::config::{AppConfig, MemoryMode};
+use crate::config::{AppConfig, MemoryMode, ModelRoute};
 use crate::cron::CronService;
 use crate::memory::simple::file_store::MemoryStore;
 use crate::memory::smart::vector_store::VectorMemoryStore;
 use crate::skills::SkillManager;
+use crate::tools::safe_mode::SafeMode;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use std::collections::HashSet;
+use tracing::warn;
 
 pub mod activate_skill;
+pub mod capabilities;
+pub mod cli;
+pub mod context;
 pub mod cron;
 pub mod fs;
+pub mod limit;
 pub mod memory;
+pub mod output;
+pub mod remind;
+pub mod safe_mode;
+pub mod scratchpad;
 pub mod send;
 pub mod shell;
+pub mod timeout;
+pub mod tracked;
 pub mod web;
 
 #[derive(Debug)]
@@ -30,21 +45,119 @@ impl std::fmt::Display for ToolError {
 
 impl std::error::Error for ToolError {}
 
+/// Names of every tool the agent can be given, in registration order.
+pub const ALL_TOOLS: [&str; 18] = [
+    fs::ReadFileTool::NAME,
+    fs::WriteFileTool::NAME,
+    fs::EditFileTool::NAME,
+    fs::ListDirTool::NAME,
+    shell::ExecTool::NAME,
+    web::WebSearchTool::NAME,
+    web::WebFetchTool::NAME,
+    activate_skill::ActivateSkillTool::NAME,
+    cron::CronTool::NAME,
+    remind::RemindTool::NAME,
+    send::SendMessageTool::NAME,
+    memory::MemorySearchTool::NAME,
+    memory::MemoryGetTool::NAME,
+    memory::RememberTool::NAME,
+    memory::MemoryPinTool::NAME,
+    scratchpad::ScratchWriteTool::NAME,
+    scratchpad::ScratchReadTool::NAME,
+    capabilities::CapabilitiesTool::NAME,
+];
+
+/// Apply `tools.enabled` then `tools.disabled`, warning about unknown names.
+pub fn resolve_enabled_tools(
+    enabled: Option<&[String]>,
+    disabled: &[String],
+) -> HashSet<&'static str> {
+    let known = |name: &str| ALL_TOOLS.iter().find(|tool| **tool == name).copied();
+    for name in enabled.unwrap_or_default().iter().chain(disabled) {
+        if known(name.trim()).is_none() {
+            warn!(
+                "unknown tool '{}' in tools config (known: {})",
+                name,
+                ALL_TOOLS.join(", ")
+            );
+        }
+    }
+
+    let mut active: HashSet<&'static str> = match enabled {
+        Some(list) => list.iter().filter_map(|name| known(name.trim())).collect(),
+        None => ALL_TOOLS.into_iter().collect(),
+    };
+    for name in disabled {
+        active.remove(name.trim());
+    }
+    active
+}
+
+/// Runs `$body` with `$tool` bound to each tool agents are given, in
+/// registration order, skipping disabled ones. `$capabilities` stands in
+/// for the registry's own capabilities tool, specialized to the route.
+macro_rules! for_each_agent_tool {
+    ($tools:expr, $capabilities:expr, |$tool:ident| $body:block) => {{
+        let tools = $tools;
+        macro_rules! visit {
+            ($each:expr) => {
+                if tools.enables(&$each) {
+                    let $tool = &$each;
+                    $body
+                }
+            };
+        }
+        visit!(tools.read_file);
+        visit!(tools.write_file);
+        visit!(tools.edit_file);
+        visit!(tools.list_dir);
+        visit!(tools.exec);
+        visit!(tools.web_search);
+        visit!(tools.web_fetch);
+        visit!(tools.activate_skill);
+        visit!(tools.cron);
+        visit!(tools.remind);
+        visit!(tools.send_message);
+        visit!(tools.memory_search);
+        visit!(tools.memory_get);
+        if let Some($tool) = &tools.remember {
+            $body
+        }
+        if let Some($tool) = &tools.memory_pin {
+            $body
+        }
+        visit!(tools.scratch_write);
+        visit!(tools.scratch_read);
+        visit!($capabilities);
+    }};
+}
+pub(crate) use for_each_agent_tool;
+
 #[derive(Clone)]
 pub struct ToolRegistry {
     pub read_file: fs::ReadFileTool,
-    pub write_file: fs::WriteFileTool,
-    pub edit_file: fs::EditFileTool,
+    pub write_file: SafeMode<fs::WriteFileTool>,
+    pub edit_file: SafeMode<fs::EditFileTool>,
     pub list_dir: fs::ListDirTool,
-    pub exec: shell::ExecTool,
+    pub exec: SafeMode<shell::ExecTool>,
     pub web_search: web::WebSearchTool,
     pub web_fetch: web::WebFetchTool,
     pub activate_skill: activate_skill::ActivateSkillTool,
-    pub cron: cron::CronTool,
+    pub cron: SafeMode<cron::CronTool>,
+    pub remind: SafeMode<remind::RemindTool>,
     pub send_message: send::SendMessageTool,
     pub memory_search: memory::MemorySearchTool,
     pub memory_get: memory::MemoryGetTool,
-    pub remember: Option<memory::RememberTool>,
+    pub remember: Option<SafeMode<memory::RememberTool>>,
+    /// Only with a vector store: MEMORY.md entries are never pruned.
+    pub memory_pin: Option<SafeMode<memory::MemoryPinTool>>,
+    pub scratchpad: scratchpad::Scratchpad,
+    pub scratch_write: scratchpad::ScratchWriteTool,
+    pub scratch_read: scratchpad::ScratchReadTool,
+    pub capabilities: capabilities::CapabilitiesTool,
+    enabled: HashSet<&'static str>,
+    timeouts: timeout::ToolTimeouts,
+    output_caps: limit::OutputCaps,
 }
 
 impl ToolRegistry {
@@ -60,42 +173,199 @@ impl ToolRegistry {
         } else {
             None
         };
-        let memory_search =
-            memory::MemorySearchTool::new(memory_store.clone(), vector_store.clone());
+        let memory_search = memory::MemorySearchTool::new(
+            memory_store.clone(),
+            vector_store.clone(),
+            cfg.memory.recall,
+        );
         let memory_get = memory::MemoryGetTool::new(memory_store.clone(), vector_store.clone());
+        let safe = cfg.tools.safe_mode;
+        if safe {
+            warn!(
+                "safe mode is on: file writes, exec, cron changes, and memory writes are refused"
+            );
+        }
+        let enabled = resolve_enabled_tools(cfg.tools.enabled.as_deref(), &cfg.tools.disabled);
+        let memory_pin = vector_store
+            .clone()
+            .filter(|_| cfg.memory.mode == MemoryMode::Smart)
+            .filter(|_| enabled.contains(memory::MemoryPinTool::NAME))
+            .map(|store| SafeMode::new(memory::MemoryPinTool::new(store), safe));
         let remember = match cfg.memory.mode {
             MemoryMode::None => None,
             MemoryMode::Simple => Some(memory::RememberTool::new_file(memory_store.clone())),
             MemoryMode::Smart => vector_store
-                .map(|store| memory::RememberTool::new_hybrid(store, memory_store.clone()))
+                .map(|store| {
+                    memory::RememberTool::new_hybrid(store, memory_store.clone())
+                        .with_async_vectors(cfg.memory.async_remember)
+                })
                 .or_else(|| Some(memory::RememberTool::new_file(memory_store.clone()))),
-        };
+        }
+        .filter(|_| enabled.contains(memory::RememberTool::NAME))
+        .map(|tool| SafeMode::new(tool, safe));
         let skill_manager = SkillManager::from_workspace_dir(cfg.workspace_dir.as_path());
+        let outputs = output::OutputSink::new(&cfg.workspace_dir, safe);
+        // Names as registered on the agent: `remember` only exists with memory.
+        let registered = ALL_TOOLS
+            .into_iter()
+            .filter(|name| enabled.contains(name))
+            .filter(|name| *name != memory::RememberTool::NAME || remember.is_some())
+            .filter(|name| *name != memory::MemoryPinTool::NAME || memory_pin.is_some())
+            .map(str::to_string)
+            .collect();
+        let capabilities = capabilities::CapabilitiesTool::new(&cfg, registered);
+        let scratchpad = scratchpad::Scratchpad::default();
         Self {
-            read_file: fs::ReadFileTool::new(allowed_dir.clone()),
-            write_file: fs::WriteFileTool::new(allowed_dir.clone()),
-            edit_file: fs::EditFileTool::new(allowed_dir.clone()),
+            read_file: fs::ReadFileTool::new(allowed_dir.clone(), outputs.clone()),
+            write_file: SafeMode::new(fs::WriteFileTool::new(allowed_dir.clone()), safe),
+            edit_file: SafeMode::new(fs::EditFileTool::new(allowed_dir.clone()), safe),
             list_dir: fs::ListDirTool::new(allowed_dir.clone()),
-            exec: shell::ExecTool::new(
-                cfg.tools.exec_timeout_secs,
-                cfg.workspace_dir.clone(),
-                allowed_dir,
+            exec: SafeMode::new(
+                shell::ExecTool::new(
+                    cfg.tools.exec_timeout_secs,
+                    cfg.workspace_dir.clone(),
+                    allowed_dir.clone(),
+                    shell::ConfirmGate::new(&cfg.tools.exec_confirm_patterns),
+                    outputs.clone(),
+                    bus.clone(),
+                ),
+                safe,
             ),
             web_search: web::WebSearchTool::new(
-                cfg.tools.web_search_provider.clone(),
+                cfg.web_search_chain(),
                 cfg.tools.brave_api_key.clone(),
                 cfg.tools.firecrawl_api_key.clone(),
+                cfg.tools.searxng_base_url.clone(),
             ),
             web_fetch: web::WebFetchTool::new(
                 cfg.tools.web_fetch_provider.clone(),
                 cfg.tools.firecrawl_api_key.clone(),
+                web::FetchLimits {
+                    max_download_bytes: cfg.tools.web_fetch_max_download_bytes,
+                    max_redirects: cfg.tools.web_fetch_max_redirects,
+                    allow_internal_hosts: cfg.tools.web_fetch_allow_internal_hosts,
+                },
+                outputs,
             ),
             activate_skill: activate_skill::ActivateSkillTool::new(skill_manager),
-            cron: cron::CronTool::new(cron_service),
-            send_message: send::SendMessageTool::new(bus),
+            cron: SafeMode::new(
+                cron::CronTool::new(cron_service.clone(), cfg.channels.clone()),
+                safe,
+            )
+            .allowing(cron::CronArgs::is_read_only),
+            remind: SafeMode::new(
+                remind::RemindTool::new(cron_service, cfg.channels.clone()),
+                safe,
+            ),
+            send_message: send::SendMessageTool::new(
+                bus,
+                safe,
+                cfg.tools.send_allow_cross_session,
+                allowed_dir,
+            ),
             memory_search,
             memory_get,
             remember,
+            memory_pin,
+            scratch_write: scratchpad::ScratchWriteTool::new(scratchpad.clone()),
+            scratch_read: scratchpad::ScratchReadTool::new(scratchpad.clone()),
+            scratchpad,
+            capabilities,
+            enabled,
+            timeouts: timeout::ToolTimeouts::new(&cfg.tools),
+            output_caps: limit::OutputCaps::new(&cfg.tools),
         }
     }
+
+    /// Whether the operator left `tool` enabled in `tools.enabled`/`tools.disabled`.
+    pub fn enables<T: Tool>(&self, _tool: &T) -> bool {
+        self.enabled.contains(T::NAME)
+    }
+
+    /// `tool` bounded by its configured timeout.
+    pub fn timed<T: Tool>(&self, tool: T) -> timeout::Timed<T> {
+        timeout::Timed::new(tool, self.timeouts.for_tool(T::NAME))
+    }
+
+    /// `tool` bounded by its configured timeout and output cap, as
+    /// registered on agents.
+    pub fn bounded<T: Tool<Output = String>>(&self, tool: T) -> limit::Capped<timeout::Timed<T>> {
+        limit::Capped::new(self.timed(tool), self.output_caps.for_tool(T::NAME))
+    }
+
+    /// Definitions of the tools an agent serving `route` is given, in
+    /// registration order.
+    pub async fn definitions(
+        &self,
+        route: &ModelRoute,
+        max_tool_turns: usize,
+    ) -> Vec<ToolDefinition> {
+        let mut out = Vec::new();
+        let capabilities = self.capabilities.for_route(route, max_tool_turns);
+        for_each_agent_tool!(self, capabilities, |tool| {
+            out.push(tool.definition(String::new()).await);
+        });
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_tools_are_removed_from_the_active_set() {
+        let all = resolve_enabled_tools(None, &[]);
+        assert_eq!(all.len(), ALL_TOOLS.len());
+
+        let read_only =
+            resolve_enabled_tools(None, &["exec".to_string(), "write_file".to_string()]);
+        assert!(!read_only.contains("exec"));
+        assert!(!read_only.contains("write_file"));
+        assert!(read_only.contains("read_file"));
+
+        let allowlist = vec![
+            "read_file".to_string(),
+            "web_search".to_string(),
+            "no_such_tool".to_string(),
+        ];
+        let only = resolve_enabled_tools(Some(&allowlist), &["web_search".to_string()]);
+        assert_eq!(only.into_iter().collect::<Vec<_>>(), vec!["read_file"]);
+    }
+
+    #[tokio::test]
+    async fn disabled_tools_are_not_registered_on_agents() {
+        let root = std::env::temp_dir().join(format!("lightclaw-tools-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.memory.mode = MemoryMode::None;
+        cfg.workspace_dir = root.join("workspace");
+        cfg.data_dir = root.join("data");
+        cfg.tools.disabled = vec!["exec".to_string(), "web_fetch".to_string()];
+        let bus = MessageBus::new();
+        let registry = ToolRegistry::new(
+            cfg.clone(),
+            CronService::new(&cfg, bus.clone()),
+            bus,
+            MemoryStore::new(cfg.workspace_dir.clone()),
+            None,
+        );
+
+        let route = ModelRoute {
+            provider: cfg.provider.clone(),
+            model: "test-model".to_string(),
+        };
+        let names: Vec<String> = registry
+            .definitions(&route, cfg.max_tool_turns_for(&route))
+            .await
+            .into_iter()
+            .map(|def| def.name)
+            .collect();
+        assert!(!names.iter().any(|name| name == "exec"));
+        assert!(!names.iter().any(|name| name == "web_fetch"));
+        assert!(names.iter().any(|name| name == "read_file"));
+        assert!(names.iter().any(|name| name == "list_dir"));
+        assert_eq!(names.last().map(String::as_str), Some("capabilities"));
+
+        let _ = std::fs::remove_dir_all(root);
+    }
 }
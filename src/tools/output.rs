@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+/// Characters of a spilled result shown inline to the model.
+const DEFAULT_PREVIEW_CHARS: usize = 1_500;
+
+/// Where tools write their full result when called with `output_to_file`, so
+/// large outputs don't flood the conversation.
+#[derive(Clone, Debug)]
+pub struct OutputSink {
+    dir: PathBuf,
+    preview_chars: usize,
+    /// `tools.safe_mode`: nothing is written, results stay inline.
+    safe_mode: bool,
+}
+
+impl OutputSink {
+    pub fn new(workspace_dir: &Path, safe_mode: bool) -> Self {
+        Self {
+            dir: workspace_dir.join("outputs"),
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            safe_mode,
+        }
+    }
+
+    pub fn safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    /// Write raw `bytes` under `outputs/` with the given extension and return
+    /// the path, for results that can't be previewed as text. Refused in
+    /// safe mode.
+    pub async fn save_bytes(
+        &self,
+        tool: &str,
+        extension: &str,
+        bytes: &[u8],
+    ) -> std::io::Result<PathBuf> {
+        if self.safe_mode {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "safe mode is on",
+            ));
+        }
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let name = format!(
+            "{tool}-{}-{}.{extension}",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            &id[..8]
+        );
+        let path = self.dir.join(name);
+        tokio::fs::write(&path, bytes).await?;
+        Ok(path)
+    }
+
+    /// Write `content` under `outputs/` and return a preview plus the path.
+    /// In safe mode only the preview comes back.
+    pub async fn spill(&self, tool: &str, content: &str) -> String {
+        let (preview, hidden) = self.preview(content);
+        if self.safe_mode {
+            let more = match hidden {
+                0 => String::new(),
+                n => format!("\n... ({n} more chars not shown)"),
+            };
+            return format!(
+                "Safe mode is on, so the full output was not saved to a file.\nPreview:\n{preview}{more}"
+            );
+        }
+        if let Err(err) = tokio::fs::create_dir_all(&self.dir).await {
+            return format!("Error creating {}: {err}", self.dir.display());
+        }
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let name = format!(
+            "{tool}-{}-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            &id[..8]
+        );
+        let path = self.dir.join(name);
+        if let Err(err) = tokio::fs::write(&path, content.as_bytes()).await {
+            return format!("Error writing {}: {err}", path.display());
+        }
+
+        let more = match hidden {
+            0 => String::new(),
+            n => format!("\n... ({n} more chars in file)"),
+        };
+        format!(
+            "Full output ({} chars) saved to {}\nPreview:\n{preview}{more}",
+            content.chars().count(),
+            path.display()
+        )
+    }
+
+    /// The first `preview_chars` of `content` and how many chars it leaves out.
+    fn preview(&self, content: &str) -> (String, usize) {
+        let total = content.chars().count();
+        let preview = content.chars().take(self.preview_chars).collect();
+        (preview, total.saturating_sub(self.preview_chars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn spill_writes_full_content_and_truncates_preview() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-output-{}", Uuid::new_v4()));
+        let sink = OutputSink {
+            dir: workspace.join("outputs"),
+            preview_chars: 10,
+            safe_mode: false,
+        };
+        let content = "0123456789abcdefghij".repeat(50);
+
+        let reply = sink.spill("exec", &content).await;
+        let path_line = reply.lines().next().unwrap();
+        let path = path_line.split(" saved to ").nth(1).expect("path in reply");
+        assert!(path.contains("outputs"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+        assert!(reply.contains("Preview:\n0123456789\n... (990 more chars in file)"));
+
+        let short = sink.spill("read_file", "tiny").await;
+        assert!(short.ends_with("Preview:\ntiny"));
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[tokio::test]
+    async fn safe_mode_keeps_the_preview_without_writing() {
+        let workspace = std::env::temp_dir().join(format!("lightclaw-output-{}", Uuid::new_v4()));
+        let sink = OutputSink {
+            dir: workspace.join("outputs"),
+            preview_chars: 10,
+            safe_mode: true,
+        };
+
+        let reply = sink.spill("exec", &"x".repeat(25)).await;
+        assert!(reply.starts_with("Safe mode is on"), "{reply}");
+        assert!(reply.ends_with("Preview:\nxxxxxxxxxx\n... (15 more chars not shown)"));
+        assert!(!workspace.exists());
+
+        let err = sink
+            .save_bytes("web_fetch", "png", b"\x89PNG")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(!workspace.exists());
+    }
+}
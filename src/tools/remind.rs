@@ -0,0 +1,378 @@
+use crate::config::ChannelsConfig;
+use crate::cron::CronService;
+use crate::tools::context;
+use crate::tools::ToolError;
+use chrono::{
+    DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+};
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+
+/// Absolute forms accepted besides RFC 3339, read in the chat's timezone.
+const ABSOLUTE_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+];
+
+/// Furthest ahead a reminder can be set.
+const MAX_AHEAD_DAYS: i64 = 5 * 366;
+
+/// One-shot reminders: a thin layer over `CronService` that resolves a
+/// natural-language `when` and delivers back to the current chat.
+#[derive(Clone)]
+pub struct RemindTool {
+    service: CronService,
+    /// Per-chat timezones `when` is read in.
+    channels: ChannelsConfig,
+}
+
+impl RemindTool {
+    pub fn new(service: CronService, channels: ChannelsConfig) -> Self {
+        Self { service, channels }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct RemindArgs {
+    /// When to fire: "in 2 hours", "in 1h30m", "tomorrow at 9am", "at 17:30",
+    /// or an absolute time like "2026-05-01 18:00"
+    pub when: String,
+    /// What to remind the user about
+    pub message: String,
+}
+
+/// Resolve `raw` against `now`. Clock times without a day roll over to
+/// tomorrow once they have passed; explicit days are taken as given. Times
+/// more than `MAX_AHEAD_DAYS` out are refused.
+pub(crate) fn parse_when<Tz: TimeZone>(
+    raw: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<Utc>, String> {
+    let at = resolve_when(raw, now)?;
+    let latest = now.with_timezone(&Utc) + Duration::days(MAX_AHEAD_DAYS);
+    if at > latest {
+        return Err(format!(
+            "'{}' is too far ahead; reminders can be at most {MAX_AHEAD_DAYS} days out.",
+            raw.trim()
+        ));
+    }
+    Ok(at)
+}
+
+fn resolve_when<Tz: TimeZone>(raw: &str, now: &DateTime<Tz>) -> Result<DateTime<Utc>, String> {
+    let trimmed = raw.trim().trim_end_matches('.');
+    if trimmed.is_empty() {
+        return Err("`when` is empty".to_string());
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    for format in ABSOLUTE_FORMATS {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(trimmed, format) {
+            return local_to_utc(&now.timezone(), naive)
+                .ok_or_else(|| format!("'{trimmed}' does not exist in the local timezone"));
+        }
+    }
+
+    let text = trimmed.to_ascii_lowercase();
+    if let Some(delay) = parse_delay(text.strip_prefix("in ").unwrap_or(&text)) {
+        return now
+            .with_timezone(&Utc)
+            .checked_add_signed(delay)
+            .ok_or_else(|| format!("'{trimmed}' is too far ahead"));
+    }
+    parse_day_time(&text, now).ok_or_else(|| {
+        format!(
+            "couldn't understand '{trimmed}'. Try 'in 2 hours', 'tomorrow at 9am', or '2026-05-01 18:00'."
+        )
+    })
+}
+
+fn local_to_utc<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// `2 hours`, `an hour and 15 minutes`, `1h30m`, `90s`.
+fn parse_delay(text: &str) -> Option<Duration> {
+    let mut tokens: Vec<String> = Vec::new();
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        let mut current = String::new();
+        for c in word.chars() {
+            let boundary = current
+                .chars()
+                .last()
+                .is_some_and(|last| last.is_ascii_digit() != c.is_ascii_digit());
+            if boundary {
+                tokens.push(std::mem::take(&mut current));
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens.retain(|token| token != "and");
+    if tokens.is_empty() || !tokens.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let mut total = 0i64;
+    for pair in tokens.chunks(2) {
+        let count = match pair[0].as_str() {
+            "a" | "an" => 1,
+            digits => digits.parse::<i64>().ok()?,
+        };
+        let unit = match pair[1].as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+            "d" | "day" | "days" => 86_400,
+            "w" | "week" | "weeks" => 604_800,
+            _ => return None,
+        };
+        total = total.checked_add(count.checked_mul(unit)?)?;
+    }
+    if total <= 0 {
+        return None;
+    }
+    TimeDelta::try_seconds(total)
+}
+
+/// `9am`, `at 17:30`, `tomorrow at 9:15 pm`, `tonight`, `noon`.
+fn parse_day_time<Tz: TimeZone>(text: &str, now: &DateTime<Tz>) -> Option<DateTime<Utc>> {
+    let mut day_offset = None;
+    let mut default_time = None;
+    let mut clock = String::new();
+    for word in text.split_whitespace() {
+        match word {
+            "today" => day_offset = Some(0),
+            "tonight" => {
+                day_offset = Some(0);
+                default_time = NaiveTime::from_hms_opt(20, 0, 0);
+            }
+            "tomorrow" => {
+                day_offset = Some(1);
+                default_time = NaiveTime::from_hms_opt(9, 0, 0);
+            }
+            "at" | "on" => {}
+            other => clock.push_str(other),
+        }
+    }
+    let time = if clock.is_empty() {
+        default_time?
+    } else {
+        parse_clock(&clock)?
+    };
+
+    let today: NaiveDate = now.date_naive();
+    let date = today + Duration::days(day_offset.unwrap_or(0));
+    let at = local_to_utc(&now.timezone(), date.and_time(time))?;
+    if day_offset.is_none() && at <= now.with_timezone(&Utc) {
+        return local_to_utc(&now.timezone(), (date + Duration::days(1)).and_time(time));
+    }
+    Some(at)
+}
+
+fn parse_clock(raw: &str) -> Option<NaiveTime> {
+    match raw {
+        "noon" | "midday" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+    let (body, meridiem) = if let Some(body) = raw.strip_suffix("am") {
+        (body, Some(false))
+    } else if let Some(body) = raw.strip_suffix("pm") {
+        (body, Some(true))
+    } else {
+        (raw, None)
+    };
+    let (hour, minute) = match body.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (body.parse::<u32>().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+impl Tool for RemindTool {
+    const NAME: &'static str = "remind";
+    type Args = RemindArgs;
+    type Output = String;
+    type Error = ToolError;
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        async {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Schedule a one-time reminder in this chat. `when` accepts relative times ('in 20 minutes', 'in 1h30m'), clock times ('at 17:30', 'tomorrow at 9am', 'tonight'), or absolute dates ('2026-05-01 18:00'). Returns the resolved fire time; repeat it to the user. Use manage_cron for recurring schedules.".to_string(),
+                parameters: serde_json::to_value(schemars::schema_for!(RemindArgs)).unwrap(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let service = self.service.clone();
+        let channels = self.channels.clone();
+        async move {
+            let message = args.message.trim().to_string();
+            if message.is_empty() {
+                return Err(ToolError::msg("Missing required field: message"));
+            }
+            let ctx = context::current()
+                .ok_or_else(|| ToolError::msg("remind is only available inside a chat"))?;
+            let now = Utc::now();
+            let parsed = match channels.timezone_for(&ctx.channel, &ctx.chat_id) {
+                Some(tz) => parse_when(&args.when, &now.with_timezone(&tz)),
+                None => parse_when(&args.when, &now.with_timezone(&Local)),
+            };
+            let at = match parsed {
+                Ok(at) => at,
+                Err(err) => return Ok(format!("Error: {err}")),
+            };
+            let shown = channels
+                .chat_time(&ctx.channel, &ctx.chat_id, at)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string();
+            if at <= now {
+                return Ok(format!(
+                    "Error: {shown} has already passed; pick a future time."
+                ));
+            }
+
+            let name = format!("reminder: {}", message.chars().take(40).collect::<String>());
+            let mention = Some(ctx.sender_id.clone()).filter(|id| id != "cron" && !id.is_empty());
+            let job = service
+                .add_one_shot(
+                    name,
+                    at,
+                    format!("Reminder for the user: {message}"),
+                    Some(ctx.channel),
+                    Some(ctx.chat_id),
+                    mention,
+                )
+                .await
+                .map_err(|e| ToolError::msg(e.to_string()))?;
+            Ok(format!(
+                "Reminder set for {shown} ({}). Job id: {}.",
+                at.to_rfc3339(),
+                job.id
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+    use crate::config::AppConfig;
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_relative_and_absolute_times() {
+        let now = utc(2026, 3, 10, 15, 0);
+        let cases = [
+            ("in 2 hours", utc(2026, 3, 10, 17, 0)),
+            ("in 1h30m", utc(2026, 3, 10, 16, 30)),
+            ("in an hour and 15 minutes", utc(2026, 3, 10, 16, 15)),
+            ("90s", Utc.with_ymd_and_hms(2026, 3, 10, 15, 1, 30).unwrap()),
+            ("tomorrow at 9am", utc(2026, 3, 11, 9, 0)),
+            ("at 17:30", utc(2026, 3, 10, 17, 30)),
+            ("9am", utc(2026, 3, 11, 9, 0)),
+            ("tonight", utc(2026, 3, 10, 20, 0)),
+            ("2026-04-01 08:00", utc(2026, 4, 1, 8, 0)),
+            ("2026-04-01T08:00:00+02:00", utc(2026, 4, 1, 6, 0)),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(parse_when(raw, &now), Ok(expected), "{raw}");
+        }
+
+        for bad in [
+            "",
+            "whenever",
+            "in 0 minutes",
+            "13pm",
+            "in 5 fortnights",
+            "in 99999999999 days",
+            "in 9223372036854775807 seconds",
+            "in 2000 days",
+            "9999-01-01 00:00",
+        ] {
+            assert!(parse_when(bad, &now).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reminder_is_a_one_shot_job_for_the_current_chat() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-remind-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.data_dir = dir.clone();
+        cfg.channels
+            .timezones
+            .insert("telegram:100".to_string(), "Asia/Tokyo".to_string());
+        let service = CronService::new(&cfg, MessageBus::new());
+        let tool = RemindTool::new(service.clone(), cfg.channels.clone());
+        let ctx = context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "100".to_string(),
+            sender_id: "7".to_string(),
+            ..Default::default()
+        };
+
+        let args = RemindArgs {
+            when: "in 2 hours".to_string(),
+            message: "check the oven".to_string(),
+        };
+        let reply = context::scope(ctx.clone(), tool.call(args)).await.unwrap();
+        assert!(reply.starts_with("Reminder set for"), "{reply}");
+        assert!(reply.contains("+09:00"), "{reply}");
+
+        let jobs = service.list_jobs().await.unwrap();
+        assert_eq!(jobs.len(), 1);
+        let job = &jobs[0];
+        assert_eq!(job.schedule.kind, "at");
+        assert!(job.schedule.every_ms.is_none() && job.schedule.expr.is_none());
+        assert!(job.delete_after_run);
+        assert_eq!(job.payload.channel.as_deref(), Some("telegram"));
+        assert_eq!(job.payload.to.as_deref(), Some("100"));
+        assert_eq!(job.payload.mention_user.as_deref(), Some("7"));
+        assert!(job.payload.message.contains("check the oven"));
+        assert!(reply.contains(&job.id));
+
+        let args = RemindArgs {
+            when: "2099-01-01 09:00".to_string(),
+            message: "too far".to_string(),
+        };
+        let reply = context::scope(ctx, tool.call(args)).await.unwrap();
+        assert!(reply.starts_with("Error:"), "{reply}");
+        assert_eq!(service.list_jobs().await.unwrap().len(), 1);
+
+        // Clock times are read on the chat's wall clock, not the server's:
+        // 15:00 UTC is already midnight in Tokyo.
+        let in_tokyo = parse_when(
+            "tomorrow at 9am",
+            &utc(2026, 3, 10, 15, 0).with_timezone(&chrono_tz::Asia::Tokyo),
+        );
+        assert_eq!(in_tokyo, Ok(utc(2026, 3, 12, 0, 0)));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,111 @@
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+
+pub const SAFE_MODE_REFUSAL: &str =
+    "Error: safe mode: action not permitted. This assistant is read-only; tell the user the change cannot be made here.";
+
+/// A mutating tool that refuses to act while `tools.safe_mode` is on.
+///
+/// The tool stays registered so the model learns why the action failed instead
+/// of hallucinating a missing capability.
+#[derive(Clone)]
+pub struct SafeMode<T: Tool> {
+    inner: T,
+    blocked: bool,
+    /// Calls that only read and so run even in safe mode.
+    read_only: Option<fn(&T::Args) -> bool>,
+}
+
+impl<T: Tool> SafeMode<T> {
+    pub fn new(inner: T, blocked: bool) -> Self {
+        Self {
+            inner,
+            blocked,
+            read_only: None,
+        }
+    }
+
+    /// Let calls matching `read_only` through while safe mode is on.
+    pub fn allowing(mut self, read_only: fn(&T::Args) -> bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Tool<Output = String>> Tool for SafeMode<T> {
+    const NAME: &'static str = T::NAME;
+    type Args = T::Args;
+    type Output = String;
+    type Error = T::Error;
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        let definition = self.inner.definition(prompt);
+        let blocked = self.blocked;
+        let partial = self.read_only.is_some();
+        async move {
+            let mut definition = definition.await;
+            if blocked {
+                definition.description.push_str(if partial {
+                    " (Safe mode is on: only read-only actions work.)"
+                } else {
+                    " (Disabled: safe mode is on.)"
+                });
+            }
+            definition
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let allowed = !self.blocked || self.read_only.is_some_and(|read_only| read_only(&args));
+        let call = allowed.then(|| self.inner.call(args));
+        async move {
+            match call {
+                Some(call) => call.await,
+                None => Ok(SAFE_MODE_REFUSAL.to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::fs::{WriteFileArgs, WriteFileTool};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn safe_mode_refuses_writes_without_touching_disk() {
+        let dir = std::env::temp_dir().join(format!("lightclaw-safe-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("note.txt");
+        let args = || WriteFileArgs {
+            path: target.display().to_string(),
+            content: "hello".to_string(),
+        };
+
+        let guarded = SafeMode::new(WriteFileTool::new(Some(dir.clone())), true);
+        let out = guarded
+            .call(args())
+            .await
+            .expect("refusal is not a tool error");
+        assert_eq!(out, SAFE_MODE_REFUSAL);
+        assert!(!target.exists());
+
+        let open = SafeMode::new(WriteFileTool::new(Some(dir.clone())), false);
+        open.call(args()).await.expect("write succeeds");
+        assert!(target.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
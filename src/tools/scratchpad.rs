@@ -0,0 +1,286 @@
+use crate::tools::context;
+use crate::tools::ToolError;
+use dashmap::DashMap;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Largest scratchpad a session may hold.
+const MAX_SCRATCH_CHARS: usize = 16_000;
+
+/// A session whose notes go unread and unwritten this long has ended; its
+/// notes are dropped.
+const SCRATCH_IDLE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Per-session working notes. Held in memory only: never written to
+/// MEMORY.md or the vector store, cleared by `/compact` and `/reset`, dropped
+/// once the session goes idle, gone on restart.
+#[derive(Clone, Default)]
+pub struct Scratchpad {
+    notes: Arc<DashMap<String, Notes>>,
+}
+
+struct Notes {
+    text: String,
+    touched: Instant,
+}
+
+impl Notes {
+    fn expired(&self) -> bool {
+        self.touched.elapsed() >= SCRATCH_IDLE_TTL
+    }
+}
+
+impl Scratchpad {
+    pub fn clear(&self, session_key: &str) {
+        self.notes.remove(session_key);
+    }
+
+    /// Drop the notes of sessions that have gone idle.
+    fn expire(&self) {
+        self.notes.retain(|_, notes| !notes.expired());
+    }
+
+    fn session_key() -> Result<String, ToolError> {
+        let ctx = context::current()
+            .ok_or_else(|| ToolError::msg("the scratchpad is only available inside a chat"))?;
+        Ok(format!("{}:{}", ctx.channel, ctx.chat_id))
+    }
+}
+
+#[derive(Clone)]
+pub struct ScratchWriteTool {
+    pad: Scratchpad,
+}
+
+impl ScratchWriteTool {
+    pub fn new(pad: Scratchpad) -> Self {
+        Self { pad }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct ScratchWriteArgs {
+    /// Notes to store
+    pub content: String,
+    /// Add to the end of the existing notes instead of replacing them
+    #[serde(default)]
+    pub append: bool,
+}
+
+impl Tool for ScratchWriteTool {
+    const NAME: &'static str = "scratch_write";
+    type Args = ScratchWriteArgs;
+    type Output = String;
+    type Error = ToolError;
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        async {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Save working notes for the current task (intermediate results, plans, partial computations). The scratchpad belongs to this chat, is never added to long-term memory, and is cleared by /compact and /reset. Replaces the notes unless append is true.".to_string(),
+                parameters: serde_json::to_value(schemars::schema_for!(ScratchWriteArgs)).unwrap(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let pad = self.pad.clone();
+        async move {
+            let key = Scratchpad::session_key()?;
+            pad.expire();
+            let mut notes = pad.notes.entry(key).or_insert_with(|| Notes {
+                text: String::new(),
+                touched: Instant::now(),
+            });
+            let updated = if args.append && !notes.text.is_empty() {
+                format!("{}\n{}", notes.text, args.content)
+            } else {
+                args.content
+            };
+            let chars = updated.chars().count();
+            if chars > MAX_SCRATCH_CHARS {
+                return Ok(format!(
+                    "Error: scratchpad would hold {chars} chars (limit {MAX_SCRATCH_CHARS}); shorten or replace the notes."
+                ));
+            }
+            notes.text = updated;
+            notes.touched = Instant::now();
+            Ok(format!("Scratchpad saved ({chars} chars)."))
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ScratchReadTool {
+    pad: Scratchpad,
+}
+
+impl ScratchReadTool {
+    pub fn new(pad: Scratchpad) -> Self {
+        Self { pad }
+    }
+}
+
+#[derive(Deserialize, schemars::JsonSchema)]
+pub struct ScratchReadArgs {}
+
+impl Tool for ScratchReadTool {
+    const NAME: &'static str = "scratch_read";
+    type Args = ScratchReadArgs;
+    type Output = String;
+    type Error = ToolError;
+
+    fn definition(
+        &self,
+        _prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        async {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: "Read the working notes saved with scratch_write in this chat."
+                    .to_string(),
+                parameters: serde_json::to_value(schemars::schema_for!(ScratchReadArgs)).unwrap(),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        _args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let pad = self.pad.clone();
+        async move {
+            let key = Scratchpad::session_key()?;
+            pad.expire();
+            Ok(pad
+                .notes
+                .get_mut(&key)
+                .map(|mut notes| {
+                    notes.touched = Instant::now();
+                    notes.text.clone()
+                })
+                .filter(|text| !text.is_empty())
+                .unwrap_or_else(|| "The scratchpad is empty.".to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::MessageBus;
+    use crate::config::{AppConfig, MemoryMode};
+    use crate::cron::CronService;
+    use crate::memory::simple::file_store::MemoryStore;
+    use crate::memory::smart::vector_store::VectorMemoryStore;
+    use crate::tools::ToolRegistry;
+
+    fn chat(chat_id: &str) -> context::ToolContext {
+        context::ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: chat_id.to_string(),
+            namespace: format!("telegram_{chat_id}"),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn scratch_notes_are_session_scoped_and_never_persisted() {
+        let root = std::env::temp_dir().join(format!("lightclaw-scratch-{}", uuid::Uuid::new_v4()));
+        let mut cfg = AppConfig::defaults();
+        cfg.memory.mode = MemoryMode::Smart;
+        cfg.workspace_dir = root.join("workspace");
+        cfg.data_dir = root.join("data");
+        let file = MemoryStore::new(cfg.workspace_dir.clone());
+        let vectors = VectorMemoryStore::for_tests(root.join("vectors.db"));
+        let bus = MessageBus::new();
+        // The same stores the registry's memory tools write to.
+        let registry = ToolRegistry::new(
+            cfg.clone(),
+            CronService::new(&cfg, bus.clone()),
+            bus,
+            file.clone(),
+            Some(vectors.clone()),
+        );
+        let (write, read) = (&registry.scratch_write, &registry.scratch_read);
+        let note = |content: &str, append| ScratchWriteArgs {
+            content: content.to_string(),
+            append,
+        };
+
+        context::scope(chat("1"), async {
+            write.call(note("subtotal = 4200", false)).await.unwrap();
+            write.call(note("tax = 840", true)).await.unwrap();
+        })
+        .await;
+        let first = context::scope(chat("1"), read.call(ScratchReadArgs {}))
+            .await
+            .unwrap();
+        assert_eq!(first, "subtotal = 4200\ntax = 840");
+        let other = context::scope(chat("2"), read.call(ScratchReadArgs {}))
+            .await
+            .unwrap();
+        assert_eq!(other, "The scratchpad is empty.");
+        assert!(read.call(ScratchReadArgs {}).await.is_err());
+
+        let too_long = context::scope(
+            chat("1"),
+            write.call(note(&"x".repeat(MAX_SCRATCH_CHARS + 1), false)),
+        )
+        .await
+        .unwrap();
+        assert!(too_long.starts_with("Error:"), "{too_long}");
+
+        assert!(!file.read_long_term().contains("subtotal"));
+        assert!(vectors.export_all(false).await.unwrap().is_empty());
+
+        registry.scratchpad.clear("telegram:1");
+        let cleared = context::scope(chat("1"), read.call(ScratchReadArgs {}))
+            .await
+            .unwrap();
+        assert_eq!(cleared, "The scratchpad is empty.");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn idle_sessions_lose_their_notes() {
+        let pad = Scratchpad::default();
+        let (write, read) = (
+            ScratchWriteTool::new(pad.clone()),
+            ScratchReadTool::new(pad.clone()),
+        );
+        for chat_id in ["1", "2"] {
+            let args = ScratchWriteArgs {
+                content: format!("notes for {chat_id}"),
+                append: false,
+            };
+            context::scope(chat(chat_id), write.call(args))
+                .await
+                .unwrap();
+        }
+        let Some(stale) = Instant::now().checked_sub(SCRATCH_IDLE_TTL) else {
+            return;
+        };
+        pad.notes.get_mut("telegram:1").unwrap().touched = stale;
+
+        let expired = context::scope(chat("1"), read.call(ScratchReadArgs {}))
+            .await
+            .unwrap();
+        assert_eq!(expired, "The scratchpad is empty.");
+        assert!(!pad.notes.contains_key("telegram:1"));
+        let live = context::scope(chat("2"), read.call(ScratchReadArgs {}))
+            .await
+            .unwrap();
+        assert_eq!(live, "notes for 2");
+    }
+}
@@ -1,18 +1,81 @@
-use crate::bus::{MessageBus, OutboundMessage};
+use crate::bus::{new_handle, MessageBus, OutboundAction, OutboundMessage, OutboundSource};
+use crate::channels;
+use crate::tools::context;
+use crate::tools::fs::resolve_path;
 use crate::tools::ToolError;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Channels the outbound forwarders know how to deliver to. Extra bots are
+/// addressed as `<platform>.<name>`, e.g. `telegram.work`.
+const KNOWN_CHANNELS: &[&str] = &["telegram", "discord", "tui"];
 
 #[derive(Clone)]
 pub struct SendMessageTool {
     bus: MessageBus,
+    safe_mode: bool,
+    /// Allow sends to chats other than the one that triggered the turn.
+    allow_cross_session: bool,
+    /// Attachments must resolve under this directory when set.
+    allowed_dir: Option<PathBuf>,
 }
 
 impl SendMessageTool {
-    pub fn new(bus: MessageBus) -> Self {
-        Self { bus }
+    pub fn new(
+        bus: MessageBus,
+        safe_mode: bool,
+        allow_cross_session: bool,
+        allowed_dir: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            bus,
+            safe_mode,
+            allow_cross_session,
+            allowed_dir,
+        }
+    }
+}
+
+/// Resolve each attachment to an existing file, honoring the workspace
+/// restriction.
+fn resolve_attachments(raw: &[String], allowed_dir: Option<&Path>) -> Result<Vec<PathBuf>, String> {
+    raw.iter()
+        .map(|path| path.trim())
+        .filter(|path| !path.is_empty())
+        .map(|path| {
+            let resolved = resolve_path(path, allowed_dir, false)
+                .map_err(|err| format!("attachment '{path}': {err}"))?;
+            if !resolved.is_file() {
+                return Err(format!("attachment '{path}' is not a file"));
+            }
+            Ok(resolved)
+        })
+        .collect()
+}
+
+/// Reject targets that no forwarder could deliver to.
+fn validate_target(channel: &str, chat_id: &str) -> Result<(), String> {
+    let platform = channels::platform(channel);
+    if !KNOWN_CHANNELS.contains(&platform) || (platform == "tui" && platform != channel) {
+        return Err(format!(
+            "unknown channel '{channel}'. Use one of: {}.",
+            KNOWN_CHANNELS.join(", ")
+        ));
+    }
+    let numeric = chat_id
+        .strip_prefix('-')
+        .unwrap_or(chat_id)
+        .chars()
+        .all(|c| c.is_ascii_digit());
+    if matches!(platform, "telegram" | "discord") && !numeric {
+        return Err(format!(
+            "invalid {channel} chat_id '{chat_id}': expected a numeric id."
+        ));
     }
+    Ok(())
 }
 
 #[derive(Deserialize, schemars::JsonSchema)]
@@ -21,8 +84,20 @@ pub struct SendMessageArgs {
     pub channel: String,
     /// Destination chat id (e.g. Telegram chat id)
     pub chat_id: String,
-    /// Message text to send
+    /// Message text to send, or the replacement text for edit
+    #[serde(default)]
     pub content: String,
+    /// One of: send (default), edit, delete
+    #[serde(default)]
+    pub action: Option<String>,
+    /// For edit/delete: message_id returned by an earlier send, or "last" for
+    /// the most recent message sent to this chat
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// For send: workspace file paths to deliver as files after the text,
+    /// e.g. a path returned by output_to_file
+    #[serde(default)]
+    pub attachments: Vec<String>,
 }
 
 impl Tool for SendMessageTool {
@@ -38,7 +113,7 @@ impl Tool for SendMessageTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Send a message to a specific channel/chat. This is the delivery path for proactive notifications; in cron-triggered turns, call this tool whenever a user-visible notification should be sent.".to_string(),
+                description: "Send a message to a specific channel/chat. This is the delivery path for proactive notifications; in cron-triggered turns, call this tool whenever a user-visible notification should be sent. Set attachments to workspace file paths (such as one saved by output_to_file) to deliver them as files. Sends return a message_id; to correct a mistake, use action=edit (with new content) or action=delete with that message_id, or message_id=\"last\" for your latest message in the chat.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(SendMessageArgs)).unwrap(),
             }
         }
@@ -49,10 +124,18 @@ impl Tool for SendMessageTool {
         args: Self::Args,
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         let bus = self.bus.clone();
+        let safe_mode = self.safe_mode;
+        let allow_cross_session = self.allow_cross_session;
+        let allowed_dir = self.allowed_dir.clone();
         async move {
             let channel = args.channel.trim().to_string();
             let chat_id = args.chat_id.trim().to_string();
             let content = args.content.trim().to_string();
+            let action = args
+                .action
+                .as_deref()
+                .map(|a| a.trim().to_ascii_lowercase())
+                .unwrap_or_else(|| "send".to_string());
 
             if channel.is_empty() {
                 return Err(ToolError::msg("Missing required field: channel"));
@@ -60,18 +143,371 @@ impl Tool for SendMessageTool {
             if chat_id.is_empty() {
                 return Err(ToolError::msg("Missing required field: chat_id"));
             }
-            if content.is_empty() {
+            let has_attachments = args.attachments.iter().any(|a| !a.trim().is_empty());
+            if content.is_empty() && action != "delete" && !(action == "send" && has_attachments) {
                 return Err(ToolError::msg("Missing required field: content"));
             }
+            if let Err(err) = validate_target(&channel, &chat_id) {
+                return Ok(format!("Error: {err}"));
+            }
+            if let Some(ctx) = context::current() {
+                let cross_session = ctx.channel != channel || ctx.chat_id != chat_id;
+                if cross_session && safe_mode {
+                    return Ok(format!(
+                        "Error: safe mode: action not permitted. Messages can only be sent to the current chat ({}:{}).",
+                        ctx.channel, ctx.chat_id
+                    ));
+                }
+                // Scheduled turns deliver wherever their job says; jobs made
+                // before this check (or from the CLI) run in `cron:direct`.
+                let scheduled = ctx.sender_id == "cron";
+                if cross_session && !allow_cross_session && !scheduled {
+                    warn!(
+                        "send_message: rejected cross-session target {channel}:{chat_id} from {}:{}",
+                        ctx.channel, ctx.chat_id
+                    );
+                    return Ok(format!(
+                        "Error: messages can only be sent to the current chat ({}:{}). \
+                         Sending elsewhere requires tools.send.allow_cross_session.",
+                        ctx.channel, ctx.chat_id
+                    ));
+                }
+            }
+
+            let outbound_action = match action.as_str() {
+                "send" => OutboundAction::Send,
+                "edit" => OutboundAction::Edit,
+                "delete" => OutboundAction::Delete,
+                _ => {
+                    return Ok("Error: invalid action. Use: send, edit, delete.".to_string());
+                }
+            };
+
+            // Sends made during a scheduled turn count as cron traffic.
+            let source = context::current().map_or(OutboundSource::Tool, |ctx| {
+                OutboundSource::for_turn(&ctx.sender_id, OutboundSource::Tool)
+            });
+
+            if outbound_action == OutboundAction::Send {
+                let attachments =
+                    match resolve_attachments(&args.attachments, allowed_dir.as_deref()) {
+                        Ok(attachments) => attachments,
+                        Err(err) => return Ok(format!("Error: {err}")),
+                    };
+                // The forwarder renders the ping in the platform's markup.
+                let mention = context::current()
+                    .filter(|ctx| ctx.channel == channel)
+                    .and_then(|ctx| ctx.mention);
+                let handle = new_handle();
+                bus.publish_outbound(OutboundMessage {
+                    channel,
+                    chat_id,
+                    content,
+                    reply_to: None,
+                    handle: Some(handle.clone()),
+                    action: OutboundAction::Send,
+                    source,
+                    attachments,
+                    mention,
+                })
+                .await;
+                return Ok(format!("Message sent (message_id: {handle})."));
+            }
+
+            let Some(requested) = args.message_id.as_deref().map(str::trim) else {
+                return Err(ToolError::msg("Missing required field: message_id"));
+            };
+            let sent = bus.sent_messages();
+            let handle = if requested.eq_ignore_ascii_case("last") {
+                sent.last(&channel, &chat_id)
+            } else {
+                Some(requested.to_string())
+            };
+            let Some((handle, record)) =
+                handle.and_then(|h| sent.lookup(&h).map(|record| (h, record)))
+            else {
+                return Ok(format!(
+                    "Error: unknown message_id '{requested}'. Only messages sent recently can be edited or deleted."
+                ));
+            };
+            if record.channel != channel || record.chat_id != chat_id {
+                return Ok(format!(
+                    "Error: message_id '{handle}' was sent to {}:{}, not {channel}:{chat_id}.",
+                    record.channel, record.chat_id
+                ));
+            }
 
             bus.publish_outbound(OutboundMessage {
                 channel,
                 chat_id,
                 content,
+                reply_to: None,
+                handle: Some(handle),
+                action: outbound_action.clone(),
+                source,
+                attachments: Vec::new(),
+                mention: None,
             })
             .await;
 
-            Ok("Message sent.".to_string())
+            Ok(match outbound_action {
+                OutboundAction::Delete => "Message deleted.".to_string(),
+                _ => "Message edited.".to_string(),
+            })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cron::types::CronPayload;
+    use crate::tools::context::ToolContext;
+    use std::time::Duration;
+
+    async fn send_as_cron_turn(channel: &str, payload: &CronPayload) -> OutboundMessage {
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus, false, false, None);
+        let ctx = ToolContext {
+            channel: channel.to_string(),
+            chat_id: "100".to_string(),
+            sender_id: "cron".to_string(),
+            mention: payload.mention_user.clone(),
+            ..Default::default()
+        };
+        let args = SendMessageArgs {
+            channel: channel.to_string(),
+            chat_id: "100".to_string(),
+            content: payload.message.clone(),
+            action: None,
+            message_id: None,
+            attachments: Vec::new(),
+        };
+        context::scope(ctx, tool.call(args)).await.unwrap();
+        let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("outbound published")
+            .unwrap();
+        assert_eq!(sent.source, OutboundSource::Cron);
+        sent
+    }
+
+    #[tokio::test]
+    async fn cron_notifications_mention_the_scheduling_user() {
+        let payload = CronPayload {
+            kind: "agent_turn".to_string(),
+            message: "Stand-up in 5 minutes".to_string(),
+            deliver: false,
+            channel: None,
+            to: None,
+            model: None,
+            mention_user: Some("4242".to_string()),
+            idle_after_ms: None,
+        };
+
+        let discord = send_as_cron_turn("discord", &payload).await;
+        assert_eq!(discord.content, "Stand-up in 5 minutes");
+        assert_eq!(discord.mention.as_deref(), Some("4242"));
+        assert_eq!(
+            channels::outbound_text(&discord),
+            "<@4242> Stand-up in 5 minutes"
+        );
+
+        let telegram = send_as_cron_turn("telegram", &payload).await;
+        assert!(channels::outbound_text(&telegram).starts_with("[@user](tg://user?id=4242) "));
+
+        let quiet = CronPayload {
+            mention_user: None,
+            ..payload
+        };
+        let quiet = send_as_cron_turn("discord", &quiet).await;
+        assert_eq!(quiet.mention, None);
+        assert_eq!(channels::outbound_text(&quiet), "Stand-up in 5 minutes");
+    }
+
+    #[tokio::test]
+    async fn edit_and_delete_route_to_the_recorded_message() {
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus.clone(), false, false, None);
+        let args =
+            |action: Option<&str>, message_id: Option<&str>, content: &str| SendMessageArgs {
+                channel: "discord".to_string(),
+                chat_id: "42".to_string(),
+                content: content.to_string(),
+                action: action.map(str::to_string),
+                message_id: message_id.map(str::to_string),
+                attachments: Vec::new(),
+            };
+
+        let reply = tool
+            .call(args(None, None, "The meeting is at 3pm"))
+            .await
+            .unwrap();
+        let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let handle = sent.handle.clone().expect("send assigns a handle");
+        assert!(reply.contains(&handle));
+        assert_eq!(sent.action, OutboundAction::Send);
+
+        // Unknown until the forwarder records the platform id.
+        let reply = tool
+            .call(args(Some("edit"), Some(&handle), "The meeting is at 4pm"))
+            .await
+            .unwrap();
+        assert!(reply.starts_with("Error: unknown message_id"), "{reply}");
+
+        bus.sent_messages()
+            .record(&handle, "discord", "42", "9001".to_string());
+        let reply = tool
+            .call(args(Some("edit"), Some("last"), "The meeting is at 4pm"))
+            .await
+            .unwrap();
+        assert_eq!(reply, "Message edited.");
+        let edit = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(edit.action, OutboundAction::Edit);
+        assert_eq!(edit.handle.as_deref(), Some(handle.as_str()));
+        assert_eq!(edit.content, "The meeting is at 4pm");
+
+        let wrong_chat = SendMessageArgs {
+            chat_id: "43".to_string(),
+            ..args(Some("delete"), Some(&handle), "")
+        };
+        let reply = tool.call(wrong_chat).await.unwrap();
+        assert!(reply.contains("was sent to discord:42"), "{reply}");
+
+        let reply = tool
+            .call(args(Some("delete"), Some(&handle), ""))
+            .await
+            .unwrap();
+        assert_eq!(reply, "Message deleted.");
+    }
+
+    #[tokio::test]
+    async fn cross_session_sends_are_rejected_unless_allowed() {
+        let ctx = ToolContext {
+            channel: "telegram".to_string(),
+            chat_id: "100".to_string(),
+            sender_id: "7".to_string(),
+            ..Default::default()
+        };
+        let args = |channel: &str, chat_id: &str| SendMessageArgs {
+            channel: channel.to_string(),
+            chat_id: chat_id.to_string(),
+            content: "hello".to_string(),
+            action: None,
+            message_id: None,
+            attachments: Vec::new(),
+        };
+
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus, false, false, None);
+        for (channel, chat_id) in [("telegram", "200"), ("discord", "100")] {
+            let reply = context::scope(ctx.clone(), tool.call(args(channel, chat_id)))
+                .await
+                .unwrap();
+            assert!(reply.contains("allow_cross_session"), "{reply}");
+        }
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), rx.recv())
+                .await
+                .is_err(),
+            "nothing should be published"
+        );
+
+        let reply = context::scope(ctx.clone(), tool.call(args("telegram", "@me")))
+            .await
+            .unwrap();
+        assert!(reply.contains("expected a numeric id"), "{reply}");
+
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus, false, true, None);
+        let reply = context::scope(ctx, tool.call(args("telegram", "200")))
+            .await
+            .unwrap();
+        assert!(reply.starts_with("Message sent"), "{reply}");
+        let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sent.chat_id, "200");
+        assert_eq!(sent.source, OutboundSource::Tool);
+    }
+
+    #[tokio::test]
+    async fn scheduled_turns_may_send_to_other_chats() {
+        // A job without a target runs its turn in `cron:direct`.
+        let ctx = ToolContext {
+            channel: "cron".to_string(),
+            chat_id: "direct".to_string(),
+            sender_id: "cron".to_string(),
+            ..Default::default()
+        };
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus, false, false, None);
+        let args = SendMessageArgs {
+            channel: "telegram".to_string(),
+            chat_id: "200".to_string(),
+            content: "daily digest".to_string(),
+            action: None,
+            message_id: None,
+            attachments: Vec::new(),
+        };
+        let reply = context::scope(ctx, tool.call(args)).await.unwrap();
+        assert!(reply.starts_with("Message sent"), "{reply}");
+        let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sent.chat_id, "200");
+        assert_eq!(sent.source, OutboundSource::Cron);
+    }
+
+    #[tokio::test]
+    async fn attachments_must_be_workspace_files() {
+        let workspace =
+            std::env::temp_dir().join(format!("lightclaw-send-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(workspace.join("outputs")).unwrap();
+        let report = workspace.join("outputs").join("report.txt");
+        std::fs::write(&report, "full report").unwrap();
+        let bus = MessageBus::new();
+        let mut rx = bus.subscribe_outbound();
+        let tool = SendMessageTool::new(bus, false, false, Some(workspace.clone()));
+        let args = |paths: &[&str]| SendMessageArgs {
+            channel: "telegram".to_string(),
+            chat_id: "100".to_string(),
+            content: String::new(),
+            action: None,
+            message_id: None,
+            attachments: paths.iter().map(|p| p.to_string()).collect(),
+        };
+
+        let reply = tool.call(args(&["outputs/report.txt"])).await.unwrap();
+        assert!(reply.starts_with("Message sent"), "{reply}");
+        let sent = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sent.attachments, vec![report.canonicalize().unwrap()]);
+
+        let reply = tool.call(args(&["outputs/missing.txt"])).await.unwrap();
+        assert!(reply.starts_with("Error: attachment"), "{reply}");
+        let reply = tool.call(args(&["/etc/hostname"])).await.unwrap();
+        assert!(reply.starts_with("Error: attachment"), "{reply}");
+        assert!(
+            tool.call(args(&[])).await.is_err(),
+            "text or a file is required"
+        );
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+}
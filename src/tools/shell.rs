@@ -1,11 +1,28 @@
+use crate::bus::{MessageBus, OutboundAction, OutboundMessage, OutboundSource};
+use crate::tools::context;
 use crate::tools::fs;
+use crate::tools::output::OutputSink;
 use crate::tools::ToolError;
 use regex::Regex;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
 use serde::Deserialize;
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
-use tokio::process::Command;
+use std::process::ExitStatus;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Minimum gap between streamed progress messages for one command.
+const STREAM_INTERVAL: Duration = Duration::from_secs(3);
+/// Lines kept per streamed message; older pending lines are counted as skipped.
+const STREAM_MAX_LINES: usize = 20;
+/// Output lines repeated in the final streamed message.
+const STREAM_TAIL_LINES: usize = 20;
 
 #[derive(Clone)]
 pub struct ShellGuard {
@@ -67,44 +84,351 @@ impl ShellGuard {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::ShellGuard;
+/// Commands matching these patterns only run when the caller passes
+/// `confirm: true`, so the model has to check with the user first.
+#[derive(Clone)]
+pub struct ConfirmGate {
+    patterns: Vec<Regex>,
+}
 
-    #[test]
-    fn guard_allows_url_query_format_param() {
-        let guard = ShellGuard::new();
-        let cmd = r#"curl -s "wttr.in/Goiania?format=%l:+%c+%t+%h+%w""#;
-        assert!(guard.check(cmd).is_ok());
+impl ConfirmGate {
+    pub fn new(patterns: &[String]) -> Self {
+        let patterns = patterns
+            .iter()
+            .filter_map(|raw| match Regex::new(raw) {
+                Ok(re) => Some(re),
+                Err(err) => {
+                    warn!("ignoring invalid tools.exec.confirm_patterns entry '{raw}': {err}");
+                    None
+                }
+            })
+            .collect();
+        Self { patterns }
     }
 
-    #[test]
-    fn guard_blocks_windows_format_command() {
-        let guard = ShellGuard::new();
-        assert!(guard.check("format c:").is_err());
+    pub fn check(&self, cmd: &str, confirmed: bool) -> Result<(), String> {
+        if confirmed {
+            return Ok(());
+        }
+        let lower = normalize_rm_flags(&cmd.to_lowercase());
+        match self.patterns.iter().find(|re| re.is_match(&lower)) {
+            Some(re) => Err(format!(
+                "confirmation required: this command matches the destructive pattern `{}`. \
+                 Explain to the user exactly what it will do, ask them to approve, and only \
+                 then call exec again with \"confirm\": true.",
+                re.as_str()
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rewrite each `rm` invocation's flags into one short cluster right after
+/// `rm`, wherever they appeared, so `rm -r -f`, `rm --recursive --force` and
+/// `rm build -rf` all read as `rm -rf` to the confirm patterns.
+fn normalize_rm_flags(cmd: &str) -> String {
+    let tokens: Vec<&str> = cmd.split_whitespace().collect();
+    let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        out.push(token.to_string());
+        i += 1;
+        if token != "rm" && !token.ends_with("/rm") {
+            continue;
+        }
+        let mut letters = String::new();
+        let mut long = Vec::new();
+        let mut operands = Vec::new();
+        let mut flags_ended = false;
+        while i < tokens.len() && !is_command_separator(tokens[i]) {
+            let arg = tokens[i];
+            i += 1;
+            if flags_ended || arg == "-" || !arg.starts_with('-') {
+                operands.push(arg.to_string());
+                // `rm a; ls -r` ends the invocation at the `;`.
+                if arg.ends_with(';') {
+                    break;
+                }
+                continue;
+            }
+            match arg.strip_prefix("--") {
+                Some("") => {
+                    flags_ended = true;
+                    operands.push(arg.to_string());
+                }
+                Some("recursive") => letters.push('r'),
+                Some("force") => letters.push('f'),
+                Some("dir") => letters.push('d'),
+                Some("verbose") => letters.push('v'),
+                Some(_) => long.push(arg.to_string()),
+                None => letters.push_str(&arg[1..]),
+            }
+        }
+        if !letters.is_empty() {
+            out.push(format!("-{letters}"));
+        }
+        out.extend(long);
+        out.extend(operands);
+    }
+    out.join(" ")
+}
+
+fn is_command_separator(token: &str) -> bool {
+    matches!(token, ";" | "&&" | "||" | "|" | "&")
+}
+
+/// Batches command output into at most one progress message per interval,
+/// keeping only the newest `max_lines` lines of each batch.
+struct LineThrottle {
+    interval: Duration,
+    max_lines: usize,
+    pending: VecDeque<String>,
+    skipped: usize,
+    last_flush: Option<Instant>,
+}
+
+impl LineThrottle {
+    fn new(interval: Duration, max_lines: usize) -> Self {
+        Self {
+            interval,
+            max_lines: max_lines.max(1),
+            pending: VecDeque::new(),
+            skipped: 0,
+            last_flush: None,
+        }
+    }
+
+    /// Buffer `line` and return a batch if one is due.
+    fn push(&mut self, line: String, now: Instant) -> Option<String> {
+        self.pending.push_back(line);
+        if self.pending.len() > self.max_lines {
+            self.pending.pop_front();
+            self.skipped += 1;
+        }
+        self.poll(now)
+    }
+
+    /// Return the buffered batch if the interval since the last one has passed.
+    fn poll(&mut self, now: Instant) -> Option<String> {
+        let due = match self.last_flush {
+            Some(last) => now.saturating_duration_since(last) >= self.interval,
+            None => true,
+        };
+        if !due || self.pending.is_empty() {
+            return None;
+        }
+        self.last_flush = Some(now);
+        self.take()
+    }
+
+    /// Drain whatever is still buffered, regardless of timing.
+    fn finish(&mut self) -> Option<String> {
+        self.take()
+    }
+
+    fn take(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let mut batch = String::new();
+        if self.skipped > 0 {
+            batch.push_str(&format!("... ({} lines skipped)\n", self.skipped));
+            self.skipped = 0;
+        }
+        let lines: Vec<String> = self.pending.drain(..).collect();
+        batch.push_str(&lines.join("\n"));
+        Some(batch)
     }
 }
 
 #[derive(Clone)]
 pub struct ExecTool {
     guard: ShellGuard,
+    confirm_gate: ConfirmGate,
     timeout_secs: u64,
     working_dir: PathBuf,
     /// When set, working_dir arg must resolve to a path under this directory.
     allowed_dir: Option<PathBuf>,
+    outputs: OutputSink,
+    /// Where streamed progress for `stream: true` runs is published.
+    bus: MessageBus,
 }
 
 impl ExecTool {
-    pub fn new(timeout_secs: u64, working_dir: PathBuf, allowed_dir: Option<PathBuf>) -> Self {
+    pub fn new(
+        timeout_secs: u64,
+        working_dir: PathBuf,
+        allowed_dir: Option<PathBuf>,
+        confirm_gate: ConfirmGate,
+        outputs: OutputSink,
+        bus: MessageBus,
+    ) -> Self {
         Self {
             guard: ShellGuard::new(),
+            confirm_gate,
             timeout_secs,
             working_dir,
             allowed_dir,
+            outputs,
+            bus,
         }
     }
 }
 
+/// Forward each line from `reader` to `tx`, tagged with its stream.
+async fn forward_lines<R: AsyncRead + Unpin>(
+    reader: R,
+    is_stderr: bool,
+    tx: mpsc::Sender<(bool, String)>,
+) {
+    let mut segments = BufReader::new(reader).split(b'\n');
+    while let Ok(Some(segment)) = segments.next_segment().await {
+        let line = String::from_utf8_lossy(&segment)
+            .trim_end_matches('\r')
+            .to_string();
+        if tx.send((is_stderr, line)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Run `child` to completion while posting throttled output to the chat the
+/// turn came from. Returns `None` on timeout, after killing the child.
+async fn run_streaming(
+    mut child: Child,
+    timeout: Duration,
+    bus: &MessageBus,
+    channel: &str,
+    chat_id: &str,
+    source: OutboundSource,
+) -> Result<Option<(Vec<u8>, Vec<u8>, ExitStatus)>, ToolError> {
+    let notify = move |content: String| {
+        bus.publish_outbound(OutboundMessage {
+            channel: channel.to_string(),
+            chat_id: chat_id.to_string(),
+            content,
+            reply_to: None,
+            handle: None,
+            action: OutboundAction::Send,
+            source,
+            attachments: Vec::new(),
+            mention: None,
+        })
+    };
+
+    let (tx, mut rx) = mpsc::channel(256);
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(forward_lines(stdout, false, tx.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(forward_lines(stderr, true, tx.clone()));
+    }
+    drop(tx);
+
+    let deadline = tokio::time::sleep(timeout);
+    tokio::pin!(deadline);
+    let mut ticker = tokio::time::interval(STREAM_INTERVAL);
+    let mut throttle = LineThrottle::new(STREAM_INTERVAL, STREAM_MAX_LINES);
+    let mut tail = VecDeque::with_capacity(STREAM_TAIL_LINES);
+    let (mut out_buf, mut err_buf) = (Vec::new(), Vec::new());
+
+    let status = loop {
+        tokio::select! {
+            line = rx.recv() => match line {
+                Some((is_stderr, line)) => {
+                    let buf = if is_stderr { &mut err_buf } else { &mut out_buf };
+                    buf.extend_from_slice(line.as_bytes());
+                    buf.push(b'\n');
+                    if tail.len() == STREAM_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line.clone());
+                    if let Some(batch) = throttle.push(line, Instant::now()) {
+                        notify(batch).await;
+                    }
+                }
+                // Both pipes closed: the process is exiting.
+                None => {
+                    tokio::select! {
+                        status = child.wait() => {
+                            break status.map_err(|e| ToolError::msg(e.to_string()))?;
+                        }
+                        _ = &mut deadline => {}
+                    }
+                    let _ = child.kill().await;
+                    notify("exec timed out".to_string()).await;
+                    return Ok(None);
+                }
+            },
+            _ = ticker.tick() => {
+                if let Some(batch) = throttle.poll(Instant::now()) {
+                    notify(batch).await;
+                }
+            }
+            _ = &mut deadline => {
+                let _ = child.kill().await;
+                notify("exec timed out".to_string()).await;
+                return Ok(None);
+            }
+        }
+    };
+
+    if let Some(batch) = throttle.finish() {
+        notify(batch).await;
+    }
+    let lines: Vec<String> = tail.into_iter().collect();
+    let mut summary = format!("exec finished (exit code {})", status.code().unwrap_or(-1));
+    if !lines.is_empty() {
+        summary.push_str(&format!(
+            "\nLast {} lines:\n{}",
+            lines.len(),
+            lines.join("\n")
+        ));
+    }
+    notify(summary).await;
+
+    Ok(Some((out_buf, err_buf, status)))
+}
+
+/// Read both pipes to the end while waiting for `child`. Returns `None` on
+/// timeout, after killing the child.
+async fn run_buffered(
+    mut child: Child,
+    timeout: Duration,
+) -> Result<Option<(Vec<u8>, Vec<u8>, ExitStatus)>, ToolError> {
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+
+    let read_stdout = async move {
+        let mut buf = Vec::new();
+        if let Some(mut s) = stdout.take() {
+            use tokio::io::AsyncReadExt;
+            let _ = s.read_to_end(&mut buf).await;
+        }
+        buf
+    };
+    let read_stderr = async move {
+        let mut buf = Vec::new();
+        if let Some(mut s) = stderr.take() {
+            use tokio::io::AsyncReadExt;
+            let _ = s.read_to_end(&mut buf).await;
+        }
+        buf
+    };
+
+    let output_status = tokio::select! {
+        status = child.wait() => status.map_err(|e| ToolError::msg(e.to_string()))?,
+        _ = tokio::time::sleep(timeout) => {
+            let _ = child.kill().await;
+            return Ok(None);
+        }
+    };
+
+    let (out_buf, err_buf) = tokio::join!(read_stdout, read_stderr);
+    Ok(Some((out_buf, err_buf, output_status)))
+}
+
 #[cfg(target_os = "windows")]
 fn build_shell_command(command: &str, cwd: &Path) -> Result<(Command, Option<Command>), ToolError> {
     let mut primary = if let Some(comspec) = std::env::var_os("ComSpec") {
@@ -152,6 +476,15 @@ pub struct ExecArgs {
     pub command: String,
     /// Optional working directory for the command
     pub working_dir: Option<String>,
+    /// Set only after the user explicitly approved a destructive command
+    #[serde(default)]
+    pub confirm: bool,
+    /// Save the full output to workspace outputs/ and return only a preview plus the path
+    #[serde(default)]
+    pub output_to_file: bool,
+    /// Post output lines to the current chat while the command runs (for long builds/tests)
+    #[serde(default)]
+    pub stream: bool,
 }
 
 impl Tool for ExecTool {
@@ -167,8 +500,7 @@ impl Tool for ExecTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Execute a shell command and return its output. Use with caution."
-                    .to_string(),
+                description: "Execute a shell command and return its output. Use with caution. Destructive commands are refused until re-run with confirm=true after the user approves. Set stream=true for long-running commands so the user sees progress in the chat.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(ExecArgs)).unwrap(),
             }
         }
@@ -179,6 +511,9 @@ impl Tool for ExecTool {
         args: Self::Args,
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         async move {
+            self.confirm_gate
+                .check(&args.command, args.confirm)
+                .map_err(ToolError::msg)?;
             self.guard.check(&args.command).map_err(ToolError::msg)?;
 
             let cwd = match args.working_dir.as_deref() {
@@ -189,7 +524,7 @@ impl Tool for ExecTool {
 
             let (mut cmd, fallback) = build_shell_command(&args.command, &cwd)?;
 
-            let mut child = match cmd.spawn() {
+            let child = match cmd.spawn() {
                 Ok(child) => child,
                 Err(err) => {
                     if let Some(mut retry) = fallback {
@@ -205,41 +540,31 @@ impl Tool for ExecTool {
                     }
                 }
             };
-            let timeout = tokio::time::Duration::from_secs(self.timeout_secs);
-
-            let mut stdout = child.stdout.take();
-            let mut stderr = child.stderr.take();
-
-            let read_stdout = async move {
-                let mut buf = Vec::new();
-                if let Some(mut s) = stdout.take() {
-                    use tokio::io::AsyncReadExt;
-                    let _ = s.read_to_end(&mut buf).await;
-                }
-                buf
-            };
-            let read_stderr = async move {
-                let mut buf = Vec::new();
-                if let Some(mut s) = stderr.take() {
-                    use tokio::io::AsyncReadExt;
-                    let _ = s.read_to_end(&mut buf).await;
+            let timeout = Duration::from_secs(self.timeout_secs);
+            let stream_to = context::current()
+                .filter(|ctx| args.stream && !ctx.channel.is_empty() && !ctx.chat_id.is_empty());
+            let finished = match stream_to {
+                Some(ctx) => {
+                    let source = OutboundSource::for_turn(&ctx.sender_id, OutboundSource::Tool);
+                    run_streaming(
+                        child,
+                        timeout,
+                        &self.bus,
+                        &ctx.channel,
+                        &ctx.chat_id,
+                        source,
+                    )
+                    .await?
                 }
-                buf
+                None => run_buffered(child, timeout).await?,
             };
-
-            let output_status = tokio::select! {
-                status = child.wait() => status.map_err(|e| ToolError::msg(e.to_string()))?,
-                _ = tokio::time::sleep(timeout) => {
-                    let _ = child.kill().await;
-                    return Ok(format!(
-                        "Error: Command timed out after {} seconds",
-                        self.timeout_secs
-                    ));
-                }
+            let Some((out_buf, err_buf, output_status)) = finished else {
+                return Ok(format!(
+                    "Error: Command timed out after {} seconds",
+                    self.timeout_secs
+                ));
             };
 
-            let (out_buf, err_buf) = tokio::join!(read_stdout, read_stderr);
-
             let mut parts = Vec::new();
             if !out_buf.is_empty() {
                 parts.push(String::from_utf8_lossy(&out_buf).to_string());
@@ -262,6 +587,9 @@ impl Tool for ExecTool {
             } else {
                 parts.join("\n")
             };
+            if args.output_to_file {
+                return Ok(self.outputs.spill(Self::NAME, &result).await);
+            }
 
             let max_len = 10000;
             if result.len() > max_len {
@@ -274,3 +602,114 @@ impl Tool for ExecTool {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_rm_flags, ConfirmGate, LineThrottle, ShellGuard};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn default_gate() -> ConfirmGate {
+        ConfirmGate::new(
+            &crate::config::AppConfig::defaults()
+                .tools
+                .exec_confirm_patterns,
+        )
+    }
+
+    #[test]
+    fn confirm_gate_blocks_destructive_commands_until_confirmed() {
+        let gate = default_gate();
+        for cmd in [
+            "git push --force origin main",
+            "git push -f",
+            "git push origin +feature",
+            "git push origin +HEAD:main",
+            "rm -rf build/",
+            "rm -r -f build/",
+            "rm -f -r build/",
+            "rm --recursive --force build/",
+            "rm --force -r build/",
+            "rm build/ -rf",
+            "rm -r build/ -f",
+            "rm build/ --recursive --force",
+            "cd /tmp && rm -R -f cache",
+            "dd if=/dev/zero of=disk.img bs=1M count=1",
+        ] {
+            let err = gate.check(cmd, false).expect_err(cmd);
+            assert!(err.contains("\"confirm\": true"), "{cmd}: {err}");
+            assert!(gate.check(cmd, true).is_ok(), "{cmd}");
+        }
+    }
+
+    #[test]
+    fn confirm_gate_ignores_ordinary_commands() {
+        let gate = default_gate();
+        assert!(gate.check("git push origin feature", false).is_ok());
+        assert!(gate.check("git add -A && ls -la", false).is_ok());
+        assert!(gate.check("rm notes.txt", false).is_ok());
+        assert!(gate.check("rm -r empty_dir", false).is_ok());
+        assert!(gate.check("rm --force notes.txt", false).is_ok());
+        assert!(gate.check("rm -r old && ls -f", false).is_ok());
+        assert!(gate
+            .check("git push origin feature && echo +1", false)
+            .is_ok());
+    }
+
+    #[test]
+    fn rm_flags_are_collapsed_into_one_cluster() {
+        assert_eq!(normalize_rm_flags("rm -r -f a b"), "rm -rf a b");
+        assert_eq!(normalize_rm_flags("rm --recursive --force a"), "rm -rf a");
+        assert_eq!(normalize_rm_flags("rm -v -- -r"), "rm -v -- -r");
+        assert_eq!(normalize_rm_flags("rm a -r b -f"), "rm -rf a b");
+        assert_eq!(normalize_rm_flags("rm a; ls -f"), "rm a; ls -f");
+        assert_eq!(
+            normalize_rm_flags("ls -la && /bin/rm --force -i x"),
+            "ls -la && /bin/rm -fi x"
+        );
+    }
+
+    #[test]
+    fn guard_allows_url_query_format_param() {
+        let guard = ShellGuard::new();
+        let cmd = r#"curl -s "wttr.in/Goiania?format=%l:+%c+%t+%h+%w""#;
+        assert!(guard.check(cmd).is_ok());
+    }
+
+    #[test]
+    fn guard_blocks_windows_format_command() {
+        let guard = ShellGuard::new();
+        assert!(guard.check("format c:").is_err());
+    }
+
+    #[test]
+    fn line_throttle_batches_lines_per_interval() {
+        let interval = Duration::from_secs(3);
+        let mut throttle = LineThrottle::new(interval, 3);
+        let start = Instant::now();
+
+        // The first line goes out right away so the user sees the command started.
+        assert_eq!(
+            throttle.push("compiling a".into(), start).as_deref(),
+            Some("compiling a")
+        );
+        for (i, line) in ["b", "c", "d", "e"].iter().enumerate() {
+            let now = start + Duration::from_millis(100 * (i as u64 + 1));
+            assert_eq!(throttle.push(line.to_string(), now), None);
+        }
+        // Nothing is due until the interval has passed.
+        assert_eq!(throttle.poll(start + Duration::from_secs(1)), None);
+
+        // Only the newest lines are kept; the rest are reported as skipped.
+        let batch = throttle.poll(start + interval).unwrap();
+        assert_eq!(batch, "... (1 lines skipped)\nc\nd\ne");
+        assert_eq!(throttle.poll(start + interval * 2), None);
+
+        assert_eq!(
+            throttle.push("f".into(), start + interval + Duration::from_secs(1)),
+            None
+        );
+        assert_eq!(throttle.finish().as_deref(), Some("f"));
+        assert_eq!(throttle.finish(), None);
+    }
+}
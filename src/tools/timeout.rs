@@ -0,0 +1,160 @@
+use crate::config::ToolsConfig;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+use std::time::Duration;
+
+/// Extra time `exec` gets over its own timeout, so its handler can kill the
+/// command and report first.
+const EXEC_GRACE: Duration = Duration::from_secs(5);
+
+/// How long each tool may run, from `tools.*timeout_secs`.
+#[derive(Clone, Copy, Debug)]
+pub struct ToolTimeouts {
+    default_secs: u64,
+    web_secs: Option<u64>,
+    web_fetch_secs: Option<u64>,
+    exec_secs: u64,
+}
+
+impl ToolTimeouts {
+    pub fn new(tools: &ToolsConfig) -> Self {
+        Self {
+            default_secs: tools.timeout_secs,
+            web_secs: tools.web_timeout_secs,
+            web_fetch_secs: tools.web_fetch_timeout_secs,
+            exec_secs: tools.exec_timeout_secs,
+        }
+    }
+
+    /// Bound for the tool named `name`; `None` lets it run unbounded.
+    pub fn for_tool(&self, name: &str) -> Option<Duration> {
+        let secs = match name {
+            "exec" => return Some(Duration::from_secs(self.exec_secs) + EXEC_GRACE),
+            "web_search" => self.web_secs.unwrap_or(self.default_secs),
+            "web_fetch" => self
+                .web_fetch_secs
+                .or(self.web_secs)
+                .unwrap_or(self.default_secs),
+            _ => self.default_secs,
+        };
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+}
+
+/// Wraps a tool so a call that runs past its limit returns an error message
+/// to the model instead of stalling the turn.
+#[derive(Clone)]
+pub struct Timed<T> {
+    inner: T,
+    limit: Option<Duration>,
+}
+
+impl<T> Timed<T> {
+    pub fn new(inner: T, limit: Option<Duration>) -> Self {
+        Self { inner, limit }
+    }
+}
+
+impl<T: Tool<Output = String>> Tool for Timed<T> {
+    const NAME: &'static str = T::NAME;
+    type Args = T::Args;
+    type Output = String;
+    type Error = T::Error;
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        self.inner.definition(prompt)
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        let call = self.inner.call(args);
+        let limit = self.limit;
+        async move {
+            let Some(limit) = limit else {
+                return call.await;
+            };
+            match tokio::time::timeout(limit, call).await {
+                Ok(result) => result,
+                Err(_) => Ok(format!(
+                    "Error: {} timed out after {}s. Try a narrower request or another approach.",
+                    T::NAME,
+                    limit.as_secs()
+                )),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::tools::ToolError;
+    use serde::Deserialize;
+
+    #[derive(Clone)]
+    struct Hang;
+
+    #[derive(Deserialize)]
+    struct NoArgs {}
+
+    impl Tool for Hang {
+        const NAME: &'static str = "hang";
+        type Args = NoArgs;
+        type Output = String;
+        type Error = ToolError;
+
+        async fn definition(&self, _prompt: String) -> ToolDefinition {
+            ToolDefinition {
+                name: Self::NAME.to_string(),
+                description: String::new(),
+                parameters: serde_json::json!({}),
+            }
+        }
+
+        async fn call(&self, _args: NoArgs) -> Result<String, ToolError> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn a_tool_past_its_timeout_returns_an_error_instead_of_hanging() {
+        let tool = Timed::new(Hang, Some(Duration::from_millis(20)));
+        let out = tokio::time::timeout(Duration::from_secs(5), tool.call(NoArgs {}))
+            .await
+            .expect("the wrapper bounds the call")
+            .unwrap();
+        assert!(out.starts_with("Error: hang timed out"), "{out}");
+    }
+
+    #[test]
+    fn web_and_exec_tools_get_their_own_bounds() {
+        let mut cfg = AppConfig::defaults();
+        cfg.tools.timeout_secs = 120;
+        cfg.tools.web_timeout_secs = Some(30);
+        cfg.tools.web_fetch_timeout_secs = Some(45);
+        cfg.tools.exec_timeout_secs = 60;
+        let timeouts = ToolTimeouts::new(&cfg.tools);
+        assert_eq!(
+            timeouts.for_tool("web_search"),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            timeouts.for_tool("web_fetch"),
+            Some(Duration::from_secs(45))
+        );
+        assert_eq!(timeouts.for_tool("exec"), Some(Duration::from_secs(65)));
+        assert_eq!(
+            timeouts.for_tool("read_file"),
+            Some(Duration::from_secs(120))
+        );
+
+        cfg.tools.timeout_secs = 0;
+        assert_eq!(ToolTimeouts::new(&cfg.tools).for_tool("read_file"), None);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::tools::context;
+use rig::completion::request::ToolDefinition;
+use rig::tool::Tool;
+
+/// Wraps a tool so each invocation is reported as turn progress.
+#[derive(Clone)]
+pub struct Tracked<T>(pub T);
+
+impl<T: Tool> Tool for Tracked<T> {
+    const NAME: &'static str = T::NAME;
+    type Args = T::Args;
+    type Output = T::Output;
+    type Error = T::Error;
+
+    fn definition(
+        &self,
+        prompt: String,
+    ) -> impl std::future::Future<Output = ToolDefinition> + Send {
+        self.0.definition(prompt)
+    }
+
+    fn call(
+        &self,
+        args: Self::Args,
+    ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
+        context::report_tool_call(T::NAME);
+        self.0.call(args)
+    }
+}
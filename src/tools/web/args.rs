@@ -36,7 +36,8 @@ pub struct WebSearchArgs {
 pub struct WebFetchArgs {
     /// URL to fetch
     pub url: String,
-    /// Extract mode: "markdown" or "text"
+    /// Extract mode: "markdown" or "text"; "links" returns the page's links
+    /// (absolute URLs) with title and description, "metadata" only the latter
     #[serde(default, alias = "extractMode")]
     pub extract_mode: Option<String>,
     /// Maximum characters to return (minimum 100)
@@ -62,6 +63,9 @@ pub struct WebFetchArgs {
     /// Firecrawl storeInCache option
     #[serde(default, alias = "storeInCache")]
     pub store_in_cache: Option<bool>,
+    /// Save the full result to workspace outputs/ and return only a preview plus the path
+    #[serde(default, alias = "outputToFile")]
+    pub output_to_file: bool,
 }
 
 fn de_optional_u8<'de, D>(deserializer: D) -> Result<Option<u8>, D::Error>
@@ -217,6 +221,8 @@ pub(crate) fn resolved_firecrawl_formats(args: &WebFetchArgs, extract_mode: &str
         "markdown" => vec!["markdown".to_string()],
         "summary" => vec!["summary".to_string()],
         "json" => vec!["json".to_string()],
+        // Parsed locally so both providers return the same shape.
+        "links" | "metadata" => vec!["rawHtml".to_string()],
         _ => vec!["markdown".to_string()],
     }
 }
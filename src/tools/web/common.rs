@@ -1,4 +1,6 @@
-use url::Url;
+use serde_json::Value;
+use std::net::{IpAddr, ToSocketAddrs};
+use url::{Host, Url};
 
 pub(crate) fn first_nonempty<'a>(a: Option<&'a str>, b: Option<&'a str>) -> Option<&'a str> {
     match a.map(str::trim).filter(|s| !s.is_empty()) {
@@ -14,3 +16,219 @@ pub(crate) fn validate_url(raw: &str) -> Result<(), String> {
         other => Err(format!("only http/https allowed, got '{other}'")),
     }
 }
+
+/// Whether `ip` only reaches this machine or its network: loopback, private
+/// (RFC 1918, IPv6 unique-local), link-local (which includes the cloud
+/// metadata address 169.254.169.254), carrier-grade NAT, unspecified or
+/// broadcast.
+pub(crate) fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || a == 0
+                || (a == 100 && b & 0xc0 == 64)
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_internal_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || first & 0xfe00 == 0xfc00
+                || first & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+/// Resolve `url`'s host and refuse it if any address it resolves to is
+/// internal, so fetches can't reach local services, the LAN or cloud
+/// metadata. Blocks on DNS.
+pub(crate) fn check_public_host(url: &Url) -> Result<(), String> {
+    let host = url.host().ok_or("URL has no host")?;
+    let addrs: Vec<IpAddr> = match &host {
+        Host::Ipv4(ip) => vec![IpAddr::V4(*ip)],
+        Host::Ipv6(ip) => vec![IpAddr::V6(*ip)],
+        Host::Domain(name) => {
+            let port = url.port_or_known_default().unwrap_or(80);
+            (*name, port)
+                .to_socket_addrs()
+                .map_err(|e| format!("can't resolve {name}: {e}"))?
+                .map(|addr| addr.ip())
+                .collect()
+        }
+    };
+    match addrs.into_iter().find(|ip| is_internal_ip(*ip)) {
+        Some(ip) => Err(format!(
+            "{host} is an internal address ({ip}); only public hosts can be fetched"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Turn a provider's non-2xx response into a message that tells the model
+/// whether to fix its arguments, retry later, or report a configuration
+/// problem. Understands Brave's `error.meta.errors` and Firecrawl's
+/// `details` validation lists.
+pub(crate) fn describe_provider_error(
+    provider: &str,
+    action: &str,
+    status: u16,
+    body: &str,
+) -> String {
+    let parsed: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+    let detail = first_nonempty(
+        first_nonempty(
+            parsed.pointer("/error/detail").and_then(Value::as_str),
+            parsed.get("error").and_then(Value::as_str),
+        ),
+        parsed.get("message").and_then(Value::as_str),
+    );
+    let with_detail = |text: String| match detail {
+        Some(detail) => format!("{text} ({detail})"),
+        None => text,
+    };
+    match status {
+        401 | 403 => with_detail(format!(
+            "Error: {provider} API key unauthorized — check configuration; retrying will not help"
+        )),
+        402 => with_detail(format!(
+            "Error: {provider} plan quota or credits exhausted — check the account; retrying will not help"
+        )),
+        429 => with_detail(format!(
+            "Error: {provider} rate limit exceeded — wait before retrying"
+        )),
+        400 | 422 => match invalid_param(&parsed) {
+            Some((param, msg)) => {
+                format!("Error: invalid {param} param: {msg} — fix the arguments and retry")
+            }
+            None => with_detail(format!(
+                "Error: {provider} rejected the {action} request — fix the arguments and retry"
+            )),
+        },
+        500..=599 => format!(
+            "Error: {provider} {action} is unavailable (status {status}) — try again later"
+        ),
+        _ => with_detail(format!(
+            "Error: {provider} {action} failed with status {status}"
+        )),
+    }
+}
+
+/// Whether a failed status is the provider's problem (credentials, quota,
+/// rate limit, outage) rather than the request's, so another provider may
+/// succeed.
+pub(crate) fn provider_unavailable(status: u16) -> bool {
+    matches!(status, 401..=403 | 429 | 500..=599)
+}
+
+/// First rejected parameter and its message, named as the tool argument.
+fn invalid_param(body: &Value) -> Option<(String, String)> {
+    let (loc, msg) = if let Some(err) = body
+        .pointer("/error/meta/errors")
+        .and_then(Value::as_array)
+        .and_then(|errors| errors.first())
+    {
+        (err.get("loc"), err.get("msg"))
+    } else {
+        let err = body
+            .get("details")
+            .and_then(Value::as_array)
+            .and_then(|details| details.first())?;
+        (err.get("path"), err.get("message"))
+    };
+    let param = loc?.as_array()?.last()?.as_str()?;
+    let param = match param {
+        "q" => "query",
+        "limit" => "count",
+        other => other,
+    };
+    let msg = msg.and_then(Value::as_str).unwrap_or("rejected");
+    Some((param.to_string(), msg.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_public_host, describe_provider_error, is_internal_ip};
+    use url::Url;
+
+    #[test]
+    fn internal_hosts_are_refused() {
+        for internal in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_internal_ip(internal.parse().unwrap()), "{internal}");
+        }
+        for public in ["93.184.216.34", "1.1.1.1", "2606:4700::1111"] {
+            assert!(!is_internal_ip(public.parse().unwrap()), "{public}");
+        }
+
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://localhost:8080/admin",
+            "http://[::1]/",
+            "https://10.0.0.5/",
+        ] {
+            let err = check_public_host(&Url::parse(url).unwrap()).unwrap_err();
+            assert!(err.contains("internal address"), "{url}: {err}");
+        }
+        assert!(check_public_host(&Url::parse("https://93.184.216.34/").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn brave_error_bodies_map_to_actionable_messages() {
+        let bad_count = r#"{"type":"ErrorResponse","error":{"id":"1","status":422,"code":"VALIDATION","detail":"Unable to validate request parameter(s)","meta":{"errors":[{"type":"less_than_equal","loc":["query","count"],"msg":"Input should be less than or equal to 20","input":"50"}]}}}"#;
+        assert_eq!(
+            describe_provider_error("Brave", "search", 422, bad_count),
+            "Error: invalid count param: Input should be less than or equal to 20 — fix the arguments and retry"
+        );
+
+        let bad_key = r#"{"type":"ErrorResponse","error":{"id":"2","status":401,"code":"SUBSCRIPTION_TOKEN_INVALID","detail":"The provided subscription token is invalid.","meta":{"component":"authentication"}}}"#;
+        assert_eq!(
+            describe_provider_error("Brave", "search", 401, bad_key),
+            "Error: Brave API key unauthorized — check configuration; retrying will not help (The provided subscription token is invalid.)"
+        );
+
+        let limited = r#"{"type":"ErrorResponse","error":{"id":"3","status":429,"code":"RATE_LIMITED","detail":"Request rate limit exceeded for plan."}}"#;
+        assert!(describe_provider_error("Brave", "search", 429, limited)
+            .starts_with("Error: Brave rate limit exceeded — wait before retrying"));
+
+        // Non-JSON bodies still get a status-based class.
+        assert_eq!(
+            describe_provider_error("Brave", "search", 503, "<html>"),
+            "Error: Brave search is unavailable (status 503) — try again later"
+        );
+    }
+
+    #[test]
+    fn firecrawl_error_bodies_map_to_actionable_messages() {
+        let too_many = r#"{"success":false,"error":"Bad Request","details":[{"code":"too_big","path":["limit"],"message":"Number must be less than or equal to 100"}]}"#;
+        assert_eq!(
+            describe_provider_error("Firecrawl", "search", 400, too_many),
+            "Error: invalid count param: Number must be less than or equal to 100 — fix the arguments and retry"
+        );
+
+        let no_credits =
+            r#"{"success":false,"error":"Insufficient credits to perform this request."}"#;
+        assert!(
+            describe_provider_error("Firecrawl", "scrape", 402, no_credits)
+                .contains("credits exhausted")
+        );
+    }
+}
@@ -1,18 +1,37 @@
 use crate::config::WebFetchProvider;
+use crate::tools::output::OutputSink;
 use crate::tools::ToolError;
 use html2text::from_read;
 use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use scraper::{Html, Selector};
 use serde_json::{json, Value};
+use url::Url;
 
 use super::args::{resolved_firecrawl_formats, WebFetchArgs};
-use super::common::{first_nonempty, validate_url};
+use super::common::{check_public_host, describe_provider_error, first_nonempty, validate_url};
 
 const DEFAULT_UA: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_7_2) AppleWebKit/537.36";
-const MAX_REDIRECTS: usize = 5;
+/// Links returned by `extract_mode: "links"`; the rest are counted but dropped.
+const MAX_PAGE_LINKS: usize = 100;
+/// Anchor text longer than this is cut.
+const MAX_LINK_TEXT_CHARS: usize = 120;
+
+/// Transport limits for direct HTTP fetches.
+#[derive(Clone, Copy, Debug)]
+pub struct FetchLimits {
+    /// Stop reading the response body after this many bytes.
+    pub max_download_bytes: usize,
+    pub max_redirects: usize,
+    /// Allow loopback, private and link-local hosts, which are refused by
+    /// default on the first request and on every redirect.
+    pub allow_internal_hosts: bool,
+}
 
 pub(crate) async fn run_fetch(
     provider: WebFetchProvider,
     firecrawl_api_key: Option<String>,
+    limits: FetchLimits,
+    outputs: &OutputSink,
     args: WebFetchArgs,
 ) -> Result<String, ToolError> {
     if let Err(err) = validate_url(&args.url) {
@@ -27,10 +46,17 @@ pub(crate) async fn run_fetch(
         .as_deref()
         .map(|m| m.trim().to_ascii_lowercase())
         .unwrap_or_else(|| "text".to_string());
-    let max_chars = args.max_chars.unwrap_or(50_000);
+    // Results saved to a file are only bounded by the download limit.
+    let max_chars = args.max_chars.unwrap_or(if args.output_to_file {
+        usize::MAX
+    } else {
+        50_000
+    });
 
     match provider {
-        WebFetchProvider::Native => fetch_direct_http(args.url, extract_mode, max_chars).await,
+        WebFetchProvider::Native => {
+            fetch_direct_http(args.url, extract_mode, max_chars, limits, outputs).await
+        }
         WebFetchProvider::Firecrawl => {
             let Some(api_key) = firecrawl_api_key else {
                 return Ok("Error: FIRECRAWL_API_KEY not configured".to_string());
@@ -44,19 +70,34 @@ async fn fetch_direct_http(
     url: String,
     extract_mode: String,
     max_chars: usize,
+    limits: FetchLimits,
+    outputs: &OutputSink,
 ) -> Result<String, ToolError> {
+    if !limits.allow_internal_hosts {
+        let parsed = Url::parse(&url).map_err(|e| ToolError::msg(e.to_string()))?;
+        let checked = tokio::task::spawn_blocking(move || check_public_host(&parsed))
+            .await
+            .map_err(|e| ToolError::msg(e.to_string()))?;
+        if let Err(err) = checked {
+            return Ok(
+                json!({ "error": format!("URL validation failed: {err}"), "url": url }).to_string(),
+            );
+        }
+    }
     let mut headers = HeaderMap::new();
     headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_UA));
     let client = reqwest::Client::builder()
         .default_headers(headers)
-        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .redirect(redirect_policy(limits))
         .build()
         .map_err(|e| ToolError::msg(e.to_string()))?;
-    let res = client
-        .get(&url)
-        .send()
-        .await
-        .map_err(|e| ToolError::msg(e.to_string()))?;
+    let res = client.get(&url).send().await.map_err(|e| {
+        // A refused redirect only says why in its source.
+        match std::error::Error::source(&e) {
+            Some(cause) if e.is_redirect() => ToolError::msg(format!("{e}: {cause}")),
+            _ => ToolError::msg(e.to_string()),
+        }
+    })?;
     let status = res.status();
     let final_url = res.url().to_string();
     let ctype = res
@@ -65,10 +106,71 @@ async fn fetch_direct_http(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("")
         .to_string();
-    let text = res
-        .text()
+    let (body, download_truncated) = read_capped(res, limits.max_download_bytes)
         .await
         .map_err(|e| ToolError::msg(e.to_string()))?;
+    if let Some(extension) = binary_extension(&ctype) {
+        if download_truncated {
+            return Ok(json!({
+                "error": format!(
+                    "'{ctype}' body is larger than the {} byte download limit; not saved",
+                    limits.max_download_bytes
+                ),
+                "url": url,
+                "finalUrl": final_url,
+                "status": status.as_u16(),
+                "contentType": ctype,
+            })
+            .to_string());
+        }
+        if outputs.safe_mode() {
+            return Ok(json!({
+                "error": format!("'{ctype}' body not saved: safe mode is on"),
+                "url": url,
+                "finalUrl": final_url,
+                "status": status.as_u16(),
+                "contentType": ctype,
+            })
+            .to_string());
+        }
+        let path = outputs
+            .save_bytes("web_fetch", extension, &body)
+            .await
+            .map_err(|e| ToolError::msg(format!("failed to save download: {e}")))?;
+        return Ok(json!({
+            "url": url,
+            "finalUrl": final_url,
+            "status": status.as_u16(),
+            "extractor": "file",
+            "contentType": ctype,
+            "bytes": body.len(),
+            "path": path.display().to_string(),
+        })
+        .to_string());
+    }
+    let text = String::from_utf8_lossy(&body).into_owned();
+    let is_html = ctype.contains("text/html")
+        || text.to_ascii_lowercase().starts_with("<!doctype")
+        || text.to_ascii_lowercase().starts_with("<html");
+    if is_structured_mode(&extract_mode) {
+        if !is_html {
+            return Ok(json!({
+                "error": format!("extract_mode '{extract_mode}' needs an HTML page, got '{ctype}'"),
+                "url": url,
+                "finalUrl": final_url,
+                "status": status.as_u16(),
+            })
+            .to_string());
+        }
+        let mut out = page_structure(&text, &final_url, extract_mode == "links");
+        out["url"] = json!(url);
+        out["finalUrl"] = json!(final_url);
+        out["status"] = json!(status.as_u16());
+        out["extractor"] = json!("html-parser");
+        out["extractMode"] = json!(extract_mode);
+        out["truncated"] = json!(download_truncated || out["linksTruncated"] == true);
+        return Ok(out.to_string());
+    }
     let mut extractor = "raw";
     let mut out_text = text.clone();
     if extract_mode == "raw" {
@@ -78,17 +180,15 @@ async fn fetch_direct_http(
             out_text = serde_json::to_string_pretty(&val).unwrap_or(text);
             extractor = "json";
         }
-    } else if ctype.contains("text/html")
-        || text.to_ascii_lowercase().starts_with("<!doctype")
-        || text.to_ascii_lowercase().starts_with("<html")
-    {
+    } else if is_html {
         let rendered = from_read(text.as_bytes(), 100);
         out_text = rendered;
         extractor = "html2text";
     }
-    let truncated = out_text.len() > max_chars;
-    if truncated {
+    let mut truncated = download_truncated;
+    if out_text.len() > max_chars {
         out_text.truncate(max_chars);
+        truncated = true;
     }
     Ok(json!({
         "url": url,
@@ -103,6 +203,73 @@ async fn fetch_direct_http(
     .to_string())
 }
 
+/// Follow up to `max_redirects` hops, each to a public host unless
+/// `allow_internal_hosts`. Hops are resolved here, so a redirect to a name
+/// blocks on DNS.
+fn redirect_policy(limits: FetchLimits) -> reqwest::redirect::Policy {
+    reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() > limits.max_redirects {
+            let max = limits.max_redirects;
+            return attempt.error(format!("too many redirects (limit {max})"));
+        }
+        if !limits.allow_internal_hosts {
+            if let Err(err) = check_public_host(attempt.url()) {
+                return attempt.error(err);
+            }
+        }
+        attempt.follow()
+    })
+}
+
+/// File extension for content types that shouldn't be decoded as text, or
+/// `None` for text-like ones (including SVG, which is XML).
+fn binary_extension(content_type: &str) -> Option<&'static str> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let (kind, subtype) = mime.split_once('/')?;
+    let ext = match (kind, subtype) {
+        ("image", "svg+xml") => return None,
+        ("image", "png") => "png",
+        ("image", "jpeg" | "jpg") => "jpg",
+        ("image", "gif") => "gif",
+        ("image", "webp") => "webp",
+        ("audio", "mpeg" | "mp3") => "mp3",
+        ("audio", "ogg" | "opus") => "ogg",
+        ("audio", "wav" | "x-wav" | "wave") => "wav",
+        ("audio", "mp4" | "m4a" | "x-m4a") => "m4a",
+        ("video", "mp4") => "mp4",
+        ("video", "webm") => "webm",
+        ("application", "pdf") => "pdf",
+        ("application", "zip") => "zip",
+        ("application", "octet-stream") => "bin",
+        ("image" | "audio" | "video", _) => "bin",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+/// Read the body chunk by chunk, stopping once `cap` bytes have been
+/// received. Returns the bytes read and whether the body was cut short.
+async fn read_capped(
+    mut res: reqwest::Response,
+    cap: usize,
+) -> Result<(Vec<u8>, bool), reqwest::Error> {
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await? {
+        let remaining = cap.saturating_sub(body.len());
+        if chunk.len() > remaining {
+            body.extend_from_slice(&chunk[..remaining]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
+
 async fn fetch_via_firecrawl(
     api_key: &str,
     args: WebFetchArgs,
@@ -136,8 +303,12 @@ async fn fetch_via_firecrawl(
         .map_err(|e| ToolError::msg(e.to_string()))?;
     let status = res.status();
     if !status.is_success() {
-        return Ok(format!(
-            "Error: Firecrawl scrape failed with status {status}"
+        let body = res.text().await.unwrap_or_default();
+        return Ok(describe_provider_error(
+            "Firecrawl",
+            "scrape",
+            status.as_u16(),
+            &body,
         ));
     }
     let body: Value = res
@@ -156,6 +327,25 @@ async fn fetch_via_firecrawl(
         return Ok("Error: Firecrawl scrape response missing data".to_string());
     };
 
+    if is_structured_mode(&extract_mode) {
+        let Some(html) = data.get("rawHtml").and_then(Value::as_str) else {
+            return Ok("Error: Firecrawl scrape response missing rawHtml".to_string());
+        };
+        let final_url = data
+            .get("metadata")
+            .and_then(|m| m.get("sourceURL"))
+            .and_then(Value::as_str)
+            .unwrap_or(&args.url)
+            .to_string();
+        let mut out = page_structure(html, &final_url, extract_mode == "links");
+        out["url"] = json!(args.url);
+        out["finalUrl"] = json!(final_url);
+        out["extractor"] = json!("firecrawl-rawHtml");
+        out["extractMode"] = json!(extract_mode);
+        out["truncated"] = out["linksTruncated"].clone();
+        return Ok(out.to_string());
+    }
+
     let (extractor, mut out_text) = select_firecrawl_text(data, &extract_mode);
     let truncated = out_text.len() > max_chars;
     if truncated {
@@ -238,6 +428,89 @@ fn select_firecrawl_text(data: &Value, extract_mode: &str) -> (&'static str, Str
     ("firecrawl-empty", String::new())
 }
 
+fn is_structured_mode(extract_mode: &str) -> bool {
+    matches!(extract_mode, "links" | "metadata")
+}
+
+/// Title, meta description and (with `include_links`) the page's http(s)
+/// links resolved against `base_url` (or the page's `<base href>`), deduped
+/// and capped at `MAX_PAGE_LINKS`.
+fn page_structure(html: &str, base_url: &str, include_links: bool) -> Value {
+    let doc = Html::parse_document(html);
+    let select = |css: &str| Selector::parse(css).expect("static selector");
+    let collapse = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let meta = |css: &str| {
+        doc.select(&select(css))
+            .filter_map(|el| el.value().attr("content"))
+            .map(collapse)
+            .find(|content| !content.is_empty())
+    };
+
+    let title = doc
+        .select(&select("title"))
+        .next()
+        .map(|el| collapse(&el.text().collect::<String>()))
+        .filter(|title| !title.is_empty())
+        .or_else(|| meta(r#"meta[property="og:title"]"#));
+    let description =
+        meta(r#"meta[name="description"]"#).or_else(|| meta(r#"meta[property="og:description"]"#));
+    let mut out = json!({ "title": title, "description": description });
+    if !include_links {
+        return out;
+    }
+
+    let page_base = Url::parse(base_url).ok();
+    let base = doc
+        .select(&select("base[href]"))
+        .filter_map(|el| el.value().attr("href"))
+        .find_map(|href| match &page_base {
+            Some(page) => page.join(href).ok(),
+            None => Url::parse(href).ok(),
+        })
+        .or(page_base);
+    let mut links = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    let mut total = 0usize;
+    for anchor in doc.select(&select("a[href]")) {
+        let href = anchor.value().attr("href").unwrap_or_default().trim();
+        let resolved = match &base {
+            Some(base) => base.join(href),
+            None => Url::parse(href),
+        };
+        let Ok(mut resolved) = resolved else {
+            continue;
+        };
+        if !matches!(resolved.scheme(), "http" | "https") {
+            continue;
+        }
+        resolved.set_fragment(None);
+        if !seen.insert(resolved.to_string()) {
+            continue;
+        }
+        total += 1;
+        if links.len() >= MAX_PAGE_LINKS {
+            continue;
+        }
+        let mut text = collapse(&anchor.text().collect::<String>());
+        if text.is_empty() {
+            text = anchor
+                .value()
+                .attr("title")
+                .or_else(|| anchor.value().attr("aria-label"))
+                .map(collapse)
+                .unwrap_or_default();
+        }
+        if text.chars().count() > MAX_LINK_TEXT_CHARS {
+            text = text.chars().take(MAX_LINK_TEXT_CHARS).collect();
+        }
+        links.push(json!({ "text": text, "href": resolved.to_string() }));
+    }
+    out["linkCount"] = json!(total);
+    out["linksTruncated"] = json!(total > links.len());
+    out["links"] = Value::Array(links);
+    out
+}
+
 fn firecrawl_extras(data: &Value) -> Value {
     let mut out = serde_json::Map::new();
     let Some(obj) = data.as_object() else {
@@ -257,3 +530,186 @@ fn firecrawl_extras(data: &Value) -> Value {
     }
     Value::Object(out)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn links_mode_returns_absolute_links_and_page_metadata() {
+        let html = r##"<!doctype html>
+<html><head>
+  <title>  Rust   Releases </title>
+  <meta name="description" content="Release notes for Rust.">
+</head><body>
+  <a href="/blog/1.80">Rust 1.80</a>
+  <a href="notes.html#top"><span>Notes</span></a>
+  <a href="https://crates.io/">crates.io</a>
+  <a href="/blog/1.80">duplicate</a>
+  <a href="mailto:team@example.com">mail</a>
+  <a href="#section">jump</a>
+  <a href="javascript:void(0)">js</a>
+  <a href="/icon" aria-label="Home"><img src="x.png"></a>
+</body></html>"##;
+
+        let out = page_structure(html, "https://blog.example.com/posts/index.html", true);
+        assert_eq!(out["title"], "Rust Releases");
+        assert_eq!(out["description"], "Release notes for Rust.");
+        assert_eq!(
+            out["links"],
+            json!([
+                { "text": "Rust 1.80", "href": "https://blog.example.com/blog/1.80" },
+                { "text": "Notes", "href": "https://blog.example.com/posts/notes.html" },
+                { "text": "crates.io", "href": "https://crates.io/" },
+                { "text": "jump", "href": "https://blog.example.com/posts/index.html" },
+                { "text": "Home", "href": "https://blog.example.com/icon" },
+            ])
+        );
+        assert_eq!(out["linksTruncated"], false);
+
+        let meta_only = page_structure(html, "https://blog.example.com/", false);
+        assert!(meta_only.get("links").is_none());
+
+        let many: String = (0..MAX_PAGE_LINKS + 5)
+            .map(|i| format!(r#"<a href="/p/{i}">{i}</a>"#))
+            .collect();
+        let capped = page_structure(&many, "https://example.com/", true);
+        assert_eq!(capped["links"].as_array().unwrap().len(), MAX_PAGE_LINKS);
+        assert_eq!(capped["linkCount"], MAX_PAGE_LINKS + 5);
+        assert_eq!(capped["linksTruncated"], true);
+    }
+
+    #[test]
+    fn direct_fetch_stops_reading_at_download_cap() {
+        const TOTAL: usize = 64 * 1024 * 1024;
+        const CAP: usize = 64 * 1024;
+
+        let rt = tokio::runtime::Runtime::new().expect("runtime");
+        rt.block_on(async {
+            let (listener, addr) = test_http::listen().await;
+            let written = Arc::new(AtomicUsize::new(0));
+            let server_written = written.clone();
+            // Streams far more than the cap, counting what got written.
+            let server = tokio::spawn(async move {
+                let (mut sock, _) = listener.accept().await.expect("accept");
+                test_http::read_request(&mut sock).await;
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {TOTAL}\r\n\r\n"
+                );
+                if sock.write_all(header.as_bytes()).await.is_err() {
+                    return;
+                }
+                let chunk = vec![b'a'; 64 * 1024];
+                while server_written.load(Ordering::SeqCst) < TOTAL {
+                    if sock.write_all(&chunk).await.is_err() {
+                        break;
+                    }
+                    server_written.fetch_add(chunk.len(), Ordering::SeqCst);
+                }
+            });
+
+            let limits = FetchLimits {
+                max_download_bytes: CAP,
+                max_redirects: 0,
+                allow_internal_hosts: true,
+            };
+            let outputs = OutputSink::new(&std::env::temp_dir(), false);
+            let out = fetch_direct_http(
+                format!("http://{addr}/big"),
+                "raw".to_string(),
+                usize::MAX,
+                limits,
+                &outputs,
+            )
+            .await
+            .expect("fetch");
+            let parsed: Value = serde_json::from_str(&out).expect("json");
+            assert_eq!(parsed["truncated"], true);
+            assert_eq!(parsed["length"].as_u64(), Some(CAP as u64));
+
+            tokio::time::timeout(std::time::Duration::from_secs(10), server)
+                .await
+                .expect("server finished after client hung up")
+                .expect("server task");
+            assert!(written.load(Ordering::SeqCst) < TOTAL);
+        });
+    }
+
+    #[tokio::test]
+    async fn image_responses_are_saved_to_a_workspace_file() {
+        let png: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xff\xfe";
+        let (addr, _) = test_http::serve(vec![test_http::response(
+            "200 OK",
+            &[("Content-Type", "image/png")],
+            png,
+        )])
+        .await;
+
+        let workspace =
+            std::env::temp_dir().join(format!("lightclaw-fetch-{}", uuid::Uuid::new_v4()));
+        let limits = FetchLimits {
+            max_download_bytes: 1024,
+            max_redirects: 0,
+            allow_internal_hosts: true,
+        };
+        let out = fetch_direct_http(
+            format!("http://{addr}/cat.png"),
+            "text".to_string(),
+            50_000,
+            limits,
+            &OutputSink::new(&workspace, false),
+        )
+        .await
+        .expect("fetch");
+
+        let parsed: Value = serde_json::from_str(&out).expect("json");
+        assert!(parsed.get("text").is_none(), "{out}");
+        assert_eq!(parsed["contentType"], "image/png");
+        assert_eq!(parsed["bytes"], png.len());
+        let path = parsed["path"].as_str().expect("saved path");
+        assert!(path.starts_with(workspace.join("outputs").to_str().unwrap()));
+        assert!(path.ends_with(".png"));
+        assert_eq!(std::fs::read(path).unwrap(), png);
+
+        assert_eq!(binary_extension("image/svg+xml"), None);
+        assert_eq!(binary_extension("text/html; charset=utf-8"), None);
+        assert_eq!(binary_extension("audio/mpeg"), Some("mp3"));
+
+        let _ = std::fs::remove_dir_all(workspace);
+    }
+
+    #[tokio::test]
+    async fn downloads_from_internal_hosts_are_never_saved() {
+        let workspace =
+            std::env::temp_dir().join(format!("lightclaw-fetch-{}", uuid::Uuid::new_v4()));
+        let limits = FetchLimits {
+            max_download_bytes: 1024,
+            max_redirects: 5,
+            allow_internal_hosts: false,
+        };
+        for url in [
+            "http://127.0.0.1:9/cat.png",
+            "http://169.254.169.254/latest/user-data",
+        ] {
+            let out = fetch_direct_http(
+                url.to_string(),
+                "text".to_string(),
+                50_000,
+                limits,
+                &OutputSink::new(&workspace, false),
+            )
+            .await
+            .expect("fetch");
+            let parsed: Value = serde_json::from_str(&out).expect("json");
+            assert!(parsed["error"]
+                .as_str()
+                .is_some_and(|err| err.contains("internal address")));
+            assert!(parsed.get("path").is_none(), "{out}");
+        }
+        assert!(!workspace.join("outputs").exists());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::{WebFetchProvider, WebSearchProvider};
+use crate::tools::output::OutputSink;
 use crate::tools::ToolError;
 use rig::completion::request::ToolDefinition;
 use rig::tool::Tool;
@@ -9,24 +10,29 @@ mod fetch;
 mod search;
 
 pub use args::{WebFetchArgs, WebSearchArgs};
+pub use fetch::FetchLimits;
 
 #[derive(Clone)]
 pub struct WebSearchTool {
-    provider: WebSearchProvider,
+    /// Primary provider first, then the fallbacks.
+    providers: Vec<WebSearchProvider>,
     brave_api_key: Option<String>,
     firecrawl_api_key: Option<String>,
+    searxng_base_url: Option<String>,
 }
 
 impl WebSearchTool {
     pub fn new(
-        provider: WebSearchProvider,
+        providers: Vec<WebSearchProvider>,
         brave_api_key: Option<String>,
         firecrawl_api_key: Option<String>,
+        searxng_base_url: Option<String>,
     ) -> Self {
         Self {
-            provider,
+            providers,
             brave_api_key,
             firecrawl_api_key,
+            searxng_base_url,
         }
     }
 }
@@ -54,11 +60,21 @@ impl Tool for WebSearchTool {
         &self,
         args: Self::Args,
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
-        let provider = self.provider.clone();
+        let providers = self.providers.clone();
         let brave_api_key = self.brave_api_key.clone();
         let firecrawl_api_key = self.firecrawl_api_key.clone();
-
-        async move { search::run_search(provider, brave_api_key, firecrawl_api_key, args).await }
+        let searxng_base_url = self.searxng_base_url.clone();
+
+        async move {
+            search::run_search(
+                &providers,
+                brave_api_key,
+                firecrawl_api_key,
+                searxng_base_url,
+                args,
+            )
+            .await
+        }
     }
 }
 
@@ -66,13 +82,22 @@ impl Tool for WebSearchTool {
 pub struct WebFetchTool {
     provider: WebFetchProvider,
     firecrawl_api_key: Option<String>,
+    limits: FetchLimits,
+    outputs: OutputSink,
 }
 
 impl WebFetchTool {
-    pub fn new(provider: WebFetchProvider, firecrawl_api_key: Option<String>) -> Self {
+    pub fn new(
+        provider: WebFetchProvider,
+        firecrawl_api_key: Option<String>,
+        limits: FetchLimits,
+        outputs: OutputSink,
+    ) -> Self {
         Self {
             provider,
             firecrawl_api_key,
+            limits,
+            outputs,
         }
     }
 }
@@ -90,7 +115,7 @@ impl Tool for WebFetchTool {
         async {
             ToolDefinition {
                 name: Self::NAME.to_string(),
-                description: "Fetch URL and extract readable content (provider-configurable: direct HTTP or Firecrawl scrape).".to_string(),
+                description: "Fetch URL and extract readable content (provider-configurable: direct HTTP or Firecrawl scrape). Use extract_mode \"links\" for the page's links plus title/description, or \"metadata\" for title/description only. Images, audio, video and PDFs are saved to a workspace file and the result gives its path and content type.".to_string(),
                 parameters: serde_json::to_value(schemars::schema_for!(WebFetchArgs)).unwrap(),
             }
         }
@@ -102,8 +127,18 @@ impl Tool for WebFetchTool {
     ) -> impl std::future::Future<Output = Result<Self::Output, Self::Error>> + Send {
         let provider = self.provider.clone();
         let firecrawl_api_key = self.firecrawl_api_key.clone();
-
-        async move { fetch::run_fetch(provider, firecrawl_api_key, args).await }
+        let limits = self.limits;
+        let outputs = self.outputs.clone();
+
+        async move {
+            let output_to_file = args.output_to_file;
+            let result =
+                fetch::run_fetch(provider, firecrawl_api_key, limits, &outputs, args).await?;
+            if output_to_file {
+                return Ok(outputs.spill(Self::NAME, &result).await);
+            }
+            Ok(result)
+        }
     }
 }
 
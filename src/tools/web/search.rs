@@ -1,106 +1,251 @@
 use crate::config::WebSearchProvider;
 use crate::tools::ToolError;
 use serde_json::{json, Value};
+use tracing::warn;
 
 use super::args::{normalize_list, normalize_optional_str, WebSearchArgs};
-use super::common::first_nonempty;
+use super::common::{describe_provider_error, first_nonempty, provider_unavailable};
 
+/// A provider's failed search: the message for the model, and whether the
+/// next provider in the fallback chain should be tried.
+#[derive(Debug)]
+pub(crate) struct SearchFailure {
+    message: String,
+    fall_through: bool,
+}
+
+impl SearchFailure {
+    /// An outage, quota or credential problem on this provider; another
+    /// provider may still answer.
+    fn provider(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            fall_through: true,
+        }
+    }
+
+    fn from_status(provider: &str, status: u16, body: &str) -> Self {
+        Self {
+            message: describe_provider_error(provider, "search", status, body),
+            fall_through: provider_unavailable(status),
+        }
+    }
+}
+
+/// Search with each provider in order until one answers. Failures that
+/// another provider could avoid (outages, quota, credentials) fall through;
+/// a rejected query is returned as is, since every provider would reject it.
 pub(crate) async fn run_search(
-    provider: WebSearchProvider,
+    providers: &[WebSearchProvider],
     brave_api_key: Option<String>,
     firecrawl_api_key: Option<String>,
+    searxng_base_url: Option<String>,
     args: WebSearchArgs,
 ) -> Result<String, ToolError> {
-    match provider {
-        WebSearchProvider::Brave => {
-            let n = args.count.unwrap_or(5).clamp(1, 10);
-            let Some(api_key) = brave_api_key else {
-                return Ok("Error: BRAVE_API_KEY not configured".to_string());
-            };
-            let client = reqwest::Client::new();
-            let res = client
-                .get("https://api.search.brave.com/res/v1/web/search")
-                .query(&[("q", &args.query), ("count", &n.to_string())])
-                .header(reqwest::header::ACCEPT, "application/json")
-                .header("X-Subscription-Token", api_key)
-                .send()
-                .await
-                .map_err(|e| ToolError::msg(e.to_string()))?;
-            let status = res.status();
-            if !status.is_success() {
-                return Ok(format!("Error: Brave search failed with status {status}"));
-            }
-            let body: Value = res
-                .json()
-                .await
-                .map_err(|e| ToolError::msg(e.to_string()))?;
-            let results = body
-                .get("web")
-                .and_then(|w| w.get("results"))
-                .and_then(|r| r.as_array())
-                .cloned()
-                .unwrap_or_default();
-            if results.is_empty() {
-                return Ok(format!("No results for: {}", args.query));
-            }
-            Ok(format_result_block(&args.query, None, &results, n as usize))
+    let brave_api_key = brave_api_key.as_deref();
+    let firecrawl_api_key = firecrawl_api_key.as_deref();
+    let searxng_base_url = searxng_base_url.as_deref();
+    let args = &args;
+    Ok(first_answer(providers, |provider| async move {
+        match provider {
+            WebSearchProvider::Brave => search_brave(brave_api_key, args).await,
+            WebSearchProvider::Firecrawl => search_firecrawl(firecrawl_api_key, args).await,
+            WebSearchProvider::Searxng => search_searxng(searxng_base_url, args).await,
         }
-        WebSearchProvider::Firecrawl => {
-            let n = args.count.unwrap_or(5).clamp(1, 100);
-            let Some(api_key) = firecrawl_api_key else {
-                return Ok("Error: FIRECRAWL_API_KEY not configured".to_string());
-            };
-            let client = reqwest::Client::new();
-            let mut payload = json!({
-                "query": args.query,
-                "limit": n,
-            });
-            if let Some(sources) = normalize_list(args.sources) {
-                payload["sources"] = json!(sources);
-            }
-            if let Some(categories) = normalize_list(args.categories) {
-                payload["categories"] = json!(categories);
-            }
-            if let Some(location) = normalize_optional_str(args.location) {
-                payload["location"] = json!(location);
-            }
-            if let Some(tbs) = normalize_optional_str(args.tbs) {
-                payload["tbs"] = json!(tbs);
-            }
-            let scrape_enabled = args.scrape.unwrap_or(false) || args.scrape_formats.is_some();
-            if scrape_enabled {
-                let formats = normalize_list(args.scrape_formats)
-                    .unwrap_or_else(|| vec!["markdown".to_string()]);
-                payload["scrapeOptions"] = json!({ "formats": formats });
-            }
-            let res = client
-                .post("https://api.firecrawl.dev/v2/search")
-                .bearer_auth(api_key)
-                .json(&payload)
-                .send()
-                .await
-                .map_err(|e| ToolError::msg(e.to_string()))?;
-            let status = res.status();
-            if !status.is_success() {
-                return Ok(format!(
-                    "Error: Firecrawl search failed with status {status}"
-                ));
-            }
-            let body: Value = res
-                .json()
-                .await
-                .map_err(|e| ToolError::msg(e.to_string()))?;
-            if body.get("success").and_then(Value::as_bool) == Some(false) {
-                let msg = first_nonempty(
-                    body.get("error").and_then(Value::as_str),
-                    body.get("message").and_then(Value::as_str),
-                )
-                .unwrap_or("unknown Firecrawl API error");
-                return Ok(format!("Error: Firecrawl search failed: {msg}"));
+    })
+    .await)
+}
+
+/// Try `attempt` for each provider and return the first answer, or the
+/// failures joined when every provider falls through.
+async fn first_answer<F, Fut>(providers: &[WebSearchProvider], mut attempt: F) -> String
+where
+    F: FnMut(WebSearchProvider) -> Fut,
+    Fut: std::future::Future<Output = Result<String, SearchFailure>>,
+{
+    let mut failures = Vec::new();
+    for provider in providers {
+        match attempt(provider.clone()).await {
+            Ok(text) => return text,
+            Err(failure) if failure.fall_through => {
+                warn!(
+                    "web_search provider {} failed, trying the next: {}",
+                    provider.as_str(),
+                    failure.message
+                );
+                failures.push(failure.message);
             }
-            Ok(format_firecrawl_response(&body, n as usize))
+            Err(failure) => return failure.message,
         }
     }
+    if failures.is_empty() {
+        "Error: no web search provider configured".to_string()
+    } else {
+        failures.join("\n")
+    }
+}
+
+async fn search_brave(
+    api_key: Option<&str>,
+    args: &WebSearchArgs,
+) -> Result<String, SearchFailure> {
+    let n = args.count.unwrap_or(5).clamp(1, 10);
+    let Some(api_key) = api_key else {
+        return Err(SearchFailure::provider(
+            "Error: BRAVE_API_KEY not configured",
+        ));
+    };
+    let client = reqwest::Client::new();
+    let res = client
+        .get("https://api.search.brave.com/res/v1/web/search")
+        .query(&[("q", &args.query), ("count", &n.to_string())])
+        .header(reqwest::header::ACCEPT, "application/json")
+        .header("X-Subscription-Token", api_key)
+        .send()
+        .await
+        .map_err(|e| SearchFailure::provider(format!("Error: Brave search failed: {e}")))?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        return Err(SearchFailure::from_status("Brave", status.as_u16(), &body));
+    }
+    let body: Value = res.json().await.map_err(|e| {
+        SearchFailure::provider(format!("Error: Brave returned an unreadable response: {e}"))
+    })?;
+    let results = body
+        .get("web")
+        .and_then(|w| w.get("results"))
+        .and_then(|r| r.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if results.is_empty() {
+        return Ok(format!("No results for: {}", args.query));
+    }
+    Ok(format_result_block(&args.query, None, &results, n as usize))
+}
+
+async fn search_firecrawl(
+    api_key: Option<&str>,
+    args: &WebSearchArgs,
+) -> Result<String, SearchFailure> {
+    let n = args.count.unwrap_or(5).clamp(1, 100);
+    let Some(api_key) = api_key else {
+        return Err(SearchFailure::provider(
+            "Error: FIRECRAWL_API_KEY not configured",
+        ));
+    };
+    let client = reqwest::Client::new();
+    let mut payload = json!({
+        "query": args.query,
+        "limit": n,
+    });
+    if let Some(sources) = normalize_list(args.sources.clone()) {
+        payload["sources"] = json!(sources);
+    }
+    if let Some(categories) = normalize_list(args.categories.clone()) {
+        payload["categories"] = json!(categories);
+    }
+    if let Some(location) = normalize_optional_str(args.location.clone()) {
+        payload["location"] = json!(location);
+    }
+    if let Some(tbs) = normalize_optional_str(args.tbs.clone()) {
+        payload["tbs"] = json!(tbs);
+    }
+    let scrape_enabled = args.scrape.unwrap_or(false) || args.scrape_formats.is_some();
+    if scrape_enabled {
+        let formats = normalize_list(args.scrape_formats.clone())
+            .unwrap_or_else(|| vec!["markdown".to_string()]);
+        payload["scrapeOptions"] = json!({ "formats": formats });
+    }
+    let res = client
+        .post("https://api.firecrawl.dev/v2/search")
+        .bearer_auth(api_key)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| SearchFailure::provider(format!("Error: Firecrawl search failed: {e}")))?;
+    let status = res.status();
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        return Err(SearchFailure::from_status(
+            "Firecrawl",
+            status.as_u16(),
+            &body,
+        ));
+    }
+    let body: Value = res.json().await.map_err(|e| {
+        SearchFailure::provider(format!(
+            "Error: Firecrawl returned an unreadable response: {e}"
+        ))
+    })?;
+    if body.get("success").and_then(Value::as_bool) == Some(false) {
+        let msg = first_nonempty(
+            body.get("error").and_then(Value::as_str),
+            body.get("message").and_then(Value::as_str),
+        )
+        .unwrap_or("unknown Firecrawl API error");
+        return Err(SearchFailure::provider(format!(
+            "Error: Firecrawl search failed: {msg}"
+        )));
+    }
+    Ok(format_firecrawl_response(&body, n as usize))
+}
+
+async fn search_searxng(
+    base_url: Option<&str>,
+    args: &WebSearchArgs,
+) -> Result<String, SearchFailure> {
+    let n = args.count.unwrap_or(5).clamp(1, 20);
+    let Some(base_url) = base_url else {
+        return Err(SearchFailure::provider(
+            "Error: tools.web.search.searxng_base_url not configured",
+        ));
+    };
+    let mut query = vec![("q", args.query.clone()), ("format", "json".to_string())];
+    if let Some(categories) = normalize_list(args.categories.clone()) {
+        query.push(("categories", categories.join(",")));
+    }
+    let client = reqwest::Client::new();
+    let res = client
+        .get(format!("{}/search", base_url.trim_end_matches('/')))
+        .query(&query)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .map_err(|e| SearchFailure::provider(format!("Error: SearXNG search failed: {e}")))?;
+    let status = res.status();
+    if status.as_u16() == 403 {
+        // Instances answer 403 when the JSON output format is not enabled.
+        return Err(SearchFailure::provider(
+            "Error: SearXNG refused the JSON request (status 403) — enable `json` under search.formats in the instance's settings.yml",
+        ));
+    }
+    if !status.is_success() {
+        let body = res.text().await.unwrap_or_default();
+        return Err(SearchFailure::from_status(
+            "SearXNG",
+            status.as_u16(),
+            &body,
+        ));
+    }
+    let body: Value = res.json().await.map_err(|e| {
+        SearchFailure::provider(format!(
+            "Error: SearXNG returned an unreadable response: {e}"
+        ))
+    })?;
+    Ok(format_searxng_response(&args.query, &body, n as usize))
+}
+
+fn format_searxng_response(query: &str, body: &Value, limit: usize) -> String {
+    let results = body
+        .get("results")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    if results.is_empty() {
+        return format!("No results for: {query}");
+    }
+    format_result_block(query, None, &results, limit)
 }
 
 fn format_firecrawl_response(body: &Value, limit: usize) -> String {
@@ -196,8 +341,12 @@ fn extract_url(item: &Value) -> Option<&str> {
 
 fn extract_description(item: &Value) -> Option<String> {
     if let Some(text) = first_nonempty(
-        item.get("description").and_then(Value::as_str),
-        item.get("snippet").and_then(Value::as_str),
+        first_nonempty(
+            item.get("description").and_then(Value::as_str),
+            item.get("snippet").and_then(Value::as_str),
+        ),
+        // SearXNG
+        item.get("content").and_then(Value::as_str),
     ) {
         return Some(text.to_string());
     }
@@ -213,3 +362,71 @@ fn extract_description(item: &Value) -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn failing_primary_falls_through_to_the_next_provider() {
+        let chain = [WebSearchProvider::Firecrawl, WebSearchProvider::Brave];
+        let out = first_answer(&chain, |provider| async move {
+            match provider {
+                WebSearchProvider::Firecrawl => Err(SearchFailure::from_status(
+                    "Firecrawl",
+                    402,
+                    r#"{"success":false,"error":"Insufficient credits"}"#,
+                )),
+                _ => Ok("Results for: rust\n1. The Rust Book".to_string()),
+            }
+        })
+        .await;
+        assert!(out.contains("The Rust Book"), "{out}");
+
+        // A malformed query is the caller's to fix; the chain stops there.
+        let mut tried = Vec::new();
+        let out = first_answer(&chain, |provider| {
+            tried.push(provider);
+            async {
+                Err(SearchFailure::from_status(
+                    "Firecrawl",
+                    400,
+                    r#"{"success":false,"error":"Bad Request","details":[{"path":["limit"],"message":"too big"}]}"#,
+                ))
+            }
+        })
+        .await;
+        assert!(out.starts_with("Error: invalid count param"), "{out}");
+        assert_eq!(tried, vec![WebSearchProvider::Firecrawl]);
+    }
+
+    #[test]
+    fn searxng_results_map_to_standard_lines() {
+        let body: Value = serde_json::from_str(
+            r#"{
+                "query": "rust async",
+                "number_of_results": 0,
+                "results": [
+                    {"url": "https://rust-lang.github.io/async-book/", "title": "Asynchronous Programming in Rust", "content": "A book about async Rust.", "engine": "duckduckgo", "engines": ["duckduckgo", "bing"], "score": 2.0, "category": "general"},
+                    {"url": "https://tokio.rs/", "title": "Tokio", "content": "", "engine": "bing", "score": 1.0},
+                    {"url": "https://example.com/3", "title": "Third", "content": "cut by the limit"}
+                ],
+                "answers": [],
+                "suggestions": ["rust async await"],
+                "unresponsive_engines": []
+            }"#,
+        )
+        .unwrap();
+        let out = format_searxng_response("rust async", &body, 2);
+        assert_eq!(
+            out,
+            "Results for: rust async\n\n1. Asynchronous Programming in Rust\n   https://rust-lang.github.io/async-book/\n   A book about async Rust.\n2. Tokio\n   https://tokio.rs/"
+        );
+
+        let empty: Value = serde_json::json!({"query": "zzz", "results": []});
+        assert_eq!(
+            format_searxng_response("zzz", &empty, 5),
+            "No results for: zzz"
+        );
+    }
+}
@@ -1,10 +1,12 @@
 use crate::config::AppConfig;
-use anyhow::{anyhow, Context, Result};
+use crate::retry::{self, Attempt, RetryPolicy};
+use anyhow::{anyhow, Result};
 use reqwest::multipart;
 use rig::prelude::TranscriptionClient;
 use rig::providers::openai;
-use rig::transcription::TranscriptionModel;
+use rig::transcription::{TranscriptionError, TranscriptionModel};
 use serde_json::Value;
+use std::time::Duration;
 use tracing::warn;
 
 #[derive(Clone)]
@@ -26,6 +28,9 @@ pub struct Transcriber {
     model: String,
     language: Option<String>,
     max_bytes: usize,
+    max_duration_secs: u64,
+    timeout: Duration,
+    retry: RetryPolicy,
 }
 
 impl Transcriber {
@@ -79,6 +84,9 @@ impl Transcriber {
             model: cfg.transcription.model.clone(),
             language: cfg.transcription.language.clone(),
             max_bytes: cfg.transcription.max_bytes.max(1),
+            max_duration_secs: cfg.transcription.max_duration_secs,
+            timeout: Duration::from_secs(cfg.transcription.timeout_secs.max(1)),
+            retry: RetryPolicy::new(cfg.transcription.max_retries),
         })
     }
 
@@ -86,6 +94,19 @@ impl Transcriber {
         self.max_bytes
     }
 
+    /// Reject clips longer than `max_duration_secs`. Unknown durations pass.
+    pub fn check_duration(&self, duration_secs: Option<f64>) -> Result<(), AudioTooLong> {
+        match duration_secs {
+            Some(secs) if self.max_duration_secs > 0 && secs > self.max_duration_secs as f64 => {
+                Err(AudioTooLong {
+                    duration_secs: secs.ceil() as u64,
+                    max_secs: self.max_duration_secs,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub async fn transcribe_bytes(&self, filename: String, data: Vec<u8>) -> Result<String> {
         if data.is_empty() {
             return Err(anyhow!("audio payload is empty"));
@@ -98,6 +119,17 @@ impl Transcriber {
             ));
         }
 
+        if self.max_duration_secs > 0 {
+            self.check_duration(probe_duration_secs(&data).await)?;
+        }
+
+        retry::run(&self.retry, "transcription", || {
+            self.transcribe_once(filename.clone(), data.clone())
+        })
+        .await
+    }
+
+    async fn transcribe_once(&self, filename: String, data: Vec<u8>) -> Attempt<String> {
         match &self.backend {
             Backend::OpenAI(client) => {
                 let model = client.transcription_model(self.model.clone());
@@ -108,11 +140,31 @@ impl Transcriber {
                 if let Some(language) = &self.language {
                     request = request.language(language.clone());
                 }
-                let response = request
-                    .send()
-                    .await
-                    .context("OpenAI transcription request failed")?;
-                Ok(response.text.trim().to_string())
+                match tokio::time::timeout(self.timeout, request.send()).await {
+                    Ok(Ok(response)) => Attempt::Done(response.text.trim().to_string()),
+                    Ok(Err(err)) => {
+                        let transient = match &err {
+                            TranscriptionError::HttpError(_) => true,
+                            TranscriptionError::ProviderError(body) => {
+                                is_transient_provider_error(body)
+                            }
+                            _ => false,
+                        };
+                        let error = anyhow!("OpenAI transcription request failed: {err}");
+                        if transient {
+                            Attempt::Retry { error, after: None }
+                        } else {
+                            Attempt::Fail(error)
+                        }
+                    }
+                    Err(_) => Attempt::Retry {
+                        error: anyhow!(
+                            "OpenAI transcription timed out after {}s",
+                            self.timeout.as_secs()
+                        ),
+                        after: None,
+                    },
+                }
             }
             Backend::Mistral {
                 http,
@@ -144,29 +196,137 @@ impl Transcriber {
                 }
 
                 let endpoint = format!("{}/audio/transcriptions", base_url.trim_end_matches('/'));
-                let response = http
+                let response = match http
                     .post(endpoint)
                     .bearer_auth(api_key)
+                    .timeout(self.timeout)
                     .multipart(form)
                     .send()
                     .await
-                    .context("Mistral transcription request failed")?
-                    .error_for_status()
-                    .context("Mistral transcription request returned non-success status")?;
-                let body: Value = response
-                    .json()
-                    .await
-                    .context("failed to decode Mistral transcription response")?;
-                extract_text_from_response(&body).ok_or_else(|| {
-                    anyhow!(
+                {
+                    Ok(response) => response,
+                    Err(err) if retry::is_retryable_error(&err) => {
+                        return Attempt::Retry {
+                            error: anyhow!("Mistral transcription request failed: {err}"),
+                            after: None,
+                        }
+                    }
+                    Err(err) => {
+                        return Attempt::Fail(anyhow!(
+                            "Mistral transcription request failed: {err}"
+                        ))
+                    }
+                };
+                let status = response.status();
+                if !status.is_success() {
+                    let error = anyhow!("Mistral transcription returned status {status}");
+                    return if retry::is_retryable_status(status) {
+                        Attempt::Retry {
+                            error,
+                            after: retry::retry_after(response.headers()),
+                        }
+                    } else {
+                        Attempt::Fail(error)
+                    };
+                }
+                let body: Value = match response.json().await {
+                    Ok(body) => body,
+                    Err(err) => {
+                        return Attempt::Fail(anyhow!(
+                            "failed to decode Mistral transcription response: {err}"
+                        ))
+                    }
+                };
+                let diarized = if *diarize {
+                    format_diarized_transcript(&body, !timestamp_granularities.is_empty())
+                } else {
+                    None
+                };
+                match diarized.or_else(|| extract_text_from_response(&body)) {
+                    Some(text) => Attempt::Done(text),
+                    None => Attempt::Fail(anyhow!(
                         "Mistral transcription response did not include a recognized text field"
-                    )
-                })
+                    )),
+                }
             }
         }
     }
 }
 
+/// Returned when a clip exceeds `transcription.max_duration_secs`.
+#[derive(Debug)]
+pub struct AudioTooLong {
+    pub duration_secs: u64,
+    pub max_secs: u64,
+}
+
+impl AudioTooLong {
+    /// Message suitable for sending back to the user.
+    pub fn user_message(&self) -> String {
+        format!(
+            "That audio is too long to transcribe ({}s). The maximum is {}s; please send a shorter clip.",
+            self.duration_secs, self.max_secs
+        )
+    }
+}
+
+impl std::fmt::Display for AudioTooLong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "audio too long: {}s (max {}s)",
+            self.duration_secs, self.max_secs
+        )
+    }
+}
+
+impl std::error::Error for AudioTooLong {}
+
+/// Probe the clip duration with `ffprobe`. Returns `None` when ffprobe is not
+/// installed or cannot read the container, so the limit is best-effort.
+async fn probe_duration_secs(data: &[u8]) -> Option<f64> {
+    use std::process::Stdio;
+    use tokio::io::AsyncWriteExt;
+
+    let mut child = tokio::process::Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "format=duration",
+            "-of",
+            "default=noprint_wrappers=1:nokey=1",
+            "-i",
+            "pipe:0",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .ok()?;
+
+    let mut stdin = child.stdin.take()?;
+    let payload = data.to_vec();
+    // ffprobe may stop reading once it has the header; a broken pipe is fine.
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&payload).await;
+    });
+    let output = tokio::time::timeout(Duration::from_secs(10), child.wait_with_output())
+        .await
+        .ok()?
+        .ok()?;
+    let _ = writer.await;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite())
+}
+
 fn build_openai_client(
     api_key: &str,
     base_url: &str,
@@ -175,6 +335,98 @@ fn build_openai_client(
     crate::providers::build_openai_client(api_key, base_url, extra_headers)
 }
 
+/// Render diarized segments as `Speaker N: ...` turns, merging consecutive
+/// segments from the same speaker. Speakers are numbered by first appearance.
+/// Returns `None` when the response carries no speaker information.
+fn format_diarized_transcript(body: &Value, with_timestamps: bool) -> Option<String> {
+    let segments = body.get("segments").and_then(Value::as_array)?;
+    let mut speakers: Vec<String> = Vec::new();
+    let mut turns: Vec<(usize, Option<f64>, String)> = Vec::new();
+
+    for segment in segments {
+        let text = segment
+            .get("text")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .unwrap_or_default();
+        if text.is_empty() {
+            continue;
+        }
+        let speaker = ["speaker_id", "speaker"]
+            .iter()
+            .find_map(|key| segment.get(*key))
+            .and_then(|value| match value {
+                Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })?;
+        let index = match speakers.iter().position(|known| *known == speaker) {
+            Some(idx) => idx,
+            None => {
+                speakers.push(speaker);
+                speakers.len() - 1
+            }
+        };
+        match turns.last_mut() {
+            Some((last, _, merged)) if *last == index => {
+                merged.push(' ');
+                merged.push_str(text);
+            }
+            _ => {
+                let start = segment.get("start").and_then(Value::as_f64);
+                turns.push((index, start, text.to_string()));
+            }
+        }
+    }
+
+    if turns.is_empty() {
+        return None;
+    }
+    let lines = turns
+        .into_iter()
+        .map(
+            |(index, start, text)| match start.filter(|_| with_timestamps) {
+                Some(secs) => format!("[{}] Speaker {}: {text}", format_timestamp(secs), index + 1),
+                None => format!("Speaker {}: {text}", index + 1),
+            },
+        )
+        .collect::<Vec<_>>();
+    Some(lines.join("\n"))
+}
+
+fn format_timestamp(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// rig reports a non-success status as `ProviderError` with only the response
+/// body, so tell rate limits and server failures apart from request, auth and
+/// quota errors by the error type OpenAI puts in the body, or by the status
+/// line a gateway echoes into its error page.
+fn is_transient_provider_error(body: &str) -> bool {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        let error = value.get("error").unwrap_or(&value);
+        return ["type", "code"]
+            .iter()
+            .filter_map(|key| error.get(key).and_then(Value::as_str))
+            .any(|kind| {
+                kind == "server_error"
+                    || kind == "rate_limit_exceeded"
+                    || kind.contains("overloaded")
+            });
+    }
+    let body = body.to_ascii_lowercase();
+    [
+        "too many requests",
+        "internal server error",
+        "bad gateway",
+        "service unavailable",
+        "gateway timeout",
+    ]
+    .iter()
+    .any(|marker| body.contains(marker))
+}
+
 fn extract_text_from_response(body: &Value) -> Option<String> {
     if let Some(text) = body.get("text").and_then(Value::as_str) {
         let trimmed = text.trim();
@@ -198,3 +450,124 @@ fn extract_text_from_response(body: &Value) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http;
+
+    fn mistral_transcriber(base_url: String) -> Transcriber {
+        Transcriber {
+            backend: Backend::Mistral {
+                http: reqwest::Client::new(),
+                api_key: "test-key".to_string(),
+                base_url,
+                diarize: false,
+                context_bias: None,
+                timestamp_granularities: Vec::new(),
+            },
+            model: "voxtral-mini-latest".to_string(),
+            language: None,
+            max_bytes: 1024,
+            max_duration_secs: 0,
+            timeout: Duration::from_secs(5),
+            retry: RetryPolicy {
+                max_retries: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(10),
+            },
+        }
+    }
+
+    #[test]
+    fn duration_limit_rejects_long_clips_only() {
+        let mut transcriber = mistral_transcriber("http://127.0.0.1:9".to_string());
+        transcriber.max_duration_secs = 120;
+
+        assert!(transcriber.check_duration(Some(12.5)).is_ok());
+        assert!(transcriber.check_duration(None).is_ok());
+        let err = transcriber
+            .check_duration(Some(3600.2))
+            .expect_err("hour-long clip rejected");
+        assert_eq!(err.duration_secs, 3601);
+        assert!(err.user_message().contains("maximum is 120s"));
+
+        transcriber.max_duration_secs = 0;
+        assert!(transcriber.check_duration(Some(3600.0)).is_ok());
+    }
+
+    #[test]
+    fn diarized_segments_are_formatted_as_speaker_turns() {
+        let body = serde_json::json!({
+            "text": "Hi there. Hello! How are you? Good, thanks.",
+            "segments": [
+                {"text": " Hi there.", "start": 0.0, "end": 1.2, "speaker_id": "speaker_0"},
+                {"text": "Hello!", "start": 1.4, "end": 2.0, "speaker_id": "speaker_1"},
+                {"text": "How are you?", "start": 2.1, "end": 3.0, "speaker_id": "speaker_1"},
+                {"text": "Good, thanks.", "start": 65.5, "end": 67.0, "speaker_id": "speaker_0"}
+            ]
+        });
+
+        assert_eq!(
+            format_diarized_transcript(&body, false).as_deref(),
+            Some("Speaker 1: Hi there.\nSpeaker 2: Hello! How are you?\nSpeaker 1: Good, thanks.")
+        );
+        assert_eq!(
+            format_diarized_transcript(&body, true).as_deref(),
+            Some(
+                "[00:00] Speaker 1: Hi there.\n[00:01] Speaker 2: Hello! How are you?\n[01:05] Speaker 1: Good, thanks."
+            )
+        );
+
+        let plain = serde_json::json!({"segments": [{"text": "no speakers here"}]});
+        assert_eq!(format_diarized_transcript(&plain, true), None);
+        assert_eq!(
+            extract_text_from_response(&plain).as_deref(),
+            Some("no speakers here")
+        );
+    }
+
+    #[test]
+    fn only_rate_limits_and_server_failures_count_as_transient() {
+        for body in [
+            r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#,
+            r#"{"error":{"message":"The server had an error","type":"server_error","code":null}}"#,
+            r#"{"error":{"message":"busy","type":"engine_overloaded"}}"#,
+            "<html><head><title>502 Bad Gateway</title></head></html>",
+            "503 Service Unavailable",
+        ] {
+            assert!(is_transient_provider_error(body), "{body}");
+        }
+        for body in [
+            r#"{"error":{"message":"Incorrect API key provided","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+            r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota","code":"insufficient_quota"}}"#,
+            r#"{"error":{"message":"Invalid file format.","type":"invalid_request_error","code":null}}"#,
+            "<html><title>404 Not Found</title></html>",
+            "",
+        ] {
+            assert!(!is_transient_provider_error(body), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried_until_transcript_arrives() {
+        let (addr, mut requests) = test_http::serve(vec![
+            test_http::response("503 Service Unavailable", &[], b""),
+            test_http::response("429 Too Many Requests", &[("Retry-After", "0")], b""),
+            test_http::json(r#"{"text":"  hello from the voice note  "}"#),
+        ])
+        .await;
+
+        let transcriber = mistral_transcriber(format!("http://{addr}/v1"));
+        let transcript = transcriber
+            .transcribe_bytes("note.ogg".to_string(), vec![1, 2, 3])
+            .await
+            .expect("transcript after retries");
+        assert_eq!(transcript, "hello from the voice note");
+        let mut hits = 0;
+        while requests.try_recv().is_ok() {
+            hits += 1;
+        }
+        assert_eq!(hits, 3);
+    }
+}